@@ -0,0 +1,617 @@
+pub mod scan;
+pub mod toolchain;
+
+use oj_shared::screening::{self, ScreeningRules};
+use oj_shared::{
+    EnvPolicy, ErrorInfo, HintRules, JudgeResult, JudgeStatus, JudgeTask, LanguageRegistry,
+    ProgrammingLanguage, SubmissionPolicy,
+};
+use sandbox::trusted::{self, ResourceLimits};
+use scan::{ScanVerdict, SourceScanner};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use toolchain::{ProbeError, ToolchainInfo};
+
+/// Data root used when `JUDGER_DATA_ROOT` is not set
+pub const DEFAULT_DATA_ROOT: &str = "/data";
+
+/// Long-lived judger state shared by every polling iteration
+pub struct Judger {
+    /// Toolchain configuration per language
+    pub registry: LanguageRegistry,
+    /// Bounds re-checked defensively before a sandbox is created
+    pub policy: SubmissionPolicy,
+    /// Environment variables tasks may set in the run sandbox
+    pub env_policy: EnvPolicy,
+    /// Static screening rules applied before the scanner
+    pub screening: ScreeningRules,
+    /// Optional pre-judge source scanner
+    pub scanner: Option<SourceScanner>,
+    /// Toolchains detected at startup; languages missing from the map are assumed available
+    pub toolchains: HashMap<ProgrammingLanguage, Result<ToolchainInfo, ProbeError>>,
+    /// Whether tasks may set `use_sandbox: false` to run on the host with rlimits
+    /// only; enable it only on judgers that serve trusted internal jobs
+    pub allow_unsandboxed: bool,
+    /// Explanations attached to compile and runtime errors
+    pub hints: HintRules,
+    /// Directory that test data file references are resolved against
+    pub data_root: PathBuf,
+}
+
+impl Judger {
+    /// Runs the checks that must pass before a task reaches the sandbox
+    ///
+    /// Returns the final result when the task is rejected, `None` when it may be judged.
+    /// Test data file references are resolved against the data root in place.
+    pub async fn precheck(&self, task: &mut JudgeTask) -> Option<JudgeResult> {
+        if let Err(e) = task
+            .validate_test_data()
+            .and_then(|()| task.resolve_test_data(&self.data_root))
+        {
+            return Some(system_error(task, e.to_string()));
+        }
+
+        let task = &*task;
+        let submission = &task.submission;
+
+        if let Err(e) = submission.validate_language() {
+            return Some(system_error(task, e.to_string()));
+        }
+
+        if !task.use_sandbox && !self.allow_unsandboxed {
+            return Some(system_error(
+                task,
+                "Task disables the sandbox, which this judger does not allow".to_string(),
+            ));
+        }
+
+        if let Err(e) = task.checker.validate() {
+            return Some(system_error(task, e.to_string()));
+        }
+
+        if let Err(e) = task.validate_comparisons() {
+            return Some(system_error(task, e.to_string()));
+        }
+
+        match self.env_policy.validate_task(task) {
+            Ok(()) => {}
+            Err((None, e)) => return Some(system_error(task, e.to_string())),
+            Err((Some(id), e)) => {
+                return Some(system_error(task, format!("test case {}: {}", id, e)));
+            }
+        }
+
+        // Hashing every test file can take a while; keep it off the async workers
+        let owned = task.clone();
+        let verified = match tokio::task::spawn_blocking(move || owned.verify_test_data()).await {
+            Ok(verified) => verified,
+            Err(e) => {
+                return Some(system_error(
+                    task,
+                    format!("Test data verification failed: {}", e),
+                ));
+            }
+        };
+        if let Err(failures) = verified {
+            let reasons: Vec<String> = failures
+                .iter()
+                .map(|(id, e)| format!("test case {}: {}", id, e))
+                .collect();
+            return Some(system_error(
+                task,
+                format!("Corrupted test data: {}", reasons.join("; ")),
+            ));
+        }
+
+        if let Err(e) = task.validate_limit_policy() {
+            return Some(system_error(task, e.to_string()));
+        }
+
+        if let Err(e) = task.validate_subtasks() {
+            return Some(system_error(task, e.to_string()));
+        }
+
+        if let Err(e) = task.validate_execution_mode() {
+            return Some(system_error(task, e.to_string()));
+        }
+
+        if let Err(e) = task.validate_generators() {
+            return Some(system_error(task, e.to_string()));
+        }
+
+        if let Some(Err(e)) = task
+            .execution_mode
+            .multi_case()
+            .map(|spec| spec.validate_for(task))
+        {
+            return Some(system_error(task, e.to_string()));
+        }
+
+        if let Err(errors) = submission.validate(&self.policy) {
+            let reasons: Vec<String> = errors.iter().map(ToString::to_string).collect();
+            return Some(system_error(
+                task,
+                format!("Invalid submission: {}", reasons.join("; ")),
+            ));
+        }
+
+        if let Some(Err(e)) = self.toolchains.get(&submission.language) {
+            return Some(system_error(task, e.to_string()));
+        }
+
+        if !task.skip_screening {
+            let (blocking, warnings): (Vec<_>, Vec<_>) = self
+                .screening
+                .screen(submission)
+                .into_iter()
+                .partition(|v| v.is_blocking());
+            for warning in &warnings {
+                tracing::warn!(
+                    "Submission {} matched warn-only screening rule {} on line {}: {}",
+                    submission.id,
+                    warning.rule,
+                    warning.line,
+                    warning.reason
+                );
+            }
+            if !blocking.is_empty() {
+                return Some(
+                    JudgeResult::with_error(
+                        JudgeStatus::RestrictedOperation,
+                        0,
+                        0,
+                        screening::violations_error(&blocking),
+                        submission.id,
+                        submission.problem_id,
+                        submission.user_id,
+                    )
+                    .with_attempt(submission.attempt),
+                );
+            }
+        }
+
+        if let Some(scanner) = &self.scanner
+            && let ScanVerdict::Vetoed(reason) = scanner.scan(submission).await
+        {
+            return Some(scan::vetoed_result(submission, &reason));
+        }
+
+        None
+    }
+
+    /// Runs the task's precompile step in `workdir`, which must contain the source
+    ///
+    /// The step runs on the host, so it gets the task's compile memory and time limits
+    /// as rlimits and a clean environment whose home is `workdir`. Returns a
+    /// [`JudgeStatus::CompileError`] result with the interpreter's stderr when the step
+    /// fails or exceeds the task's compile limits, `None` when it succeeds or the task
+    /// doesn't request it. Languages whose toolchain comes from an image are skipped,
+    /// since the host may not have the interpreter; the run in the image still catches
+    /// syntax errors.
+    pub async fn precompile(
+        &self,
+        task: &JudgeTask,
+        workdir: &Path,
+    ) -> std::io::Result<Option<JudgeResult>> {
+        let submission = &task.submission;
+        if !task.precompile || self.registry.get(&submission.language).image.is_some() {
+            return Ok(None);
+        }
+        let Some(argv) = self
+            .registry
+            .precompile_command(&submission.language, &submission.filename())
+        else {
+            return Ok(None);
+        };
+
+        let limit = task.effective_compile_time_limit();
+        let mut command = tokio::process::Command::new(&argv[0]);
+        command
+            .args(&argv[1..])
+            .env_clear()
+            .env("PATH", PRECOMPILE_PATH)
+            .env("HOME", workdir)
+            .env("TMPDIR", workdir)
+            .envs(&self.registry.get(&submission.language).env)
+            .current_dir(workdir)
+            .stdin(Stdio::null())
+            .kill_on_drop(true);
+        trusted::limit_command(
+            command.as_std_mut(),
+            &ResourceLimits {
+                // The wall clock timeout below is the real bound; this catches a
+                // step that somehow outlives it
+                cpu_time_secs: Some(limit.div_ceil(1000) + 1),
+                memory_bytes: Some(task.effective_compile_memory_limit().saturating_mul(1024)),
+                ..ResourceLimits::default()
+            },
+        );
+        let child = command.output();
+        let output = match tokio::time::timeout(Duration::from_millis(limit), child).await {
+            Ok(output) => output?,
+            Err(_) => {
+                return Ok(Some(
+                    JudgeResult::with_error(
+                        JudgeStatus::CompileError,
+                        0,
+                        0,
+                        ErrorInfo::compile_time_limit_exceeded(limit),
+                        submission.id,
+                        submission.problem_id,
+                        submission.user_id,
+                    )
+                    .with_attempt(submission.attempt),
+                ));
+            }
+        };
+        if output.status.success() {
+            return Ok(None);
+        }
+        Ok(Some(self.compile_failed(task, &output)))
+    }
+
+    /// Builds the result of a compile or precompile step that exited unsuccessfully
+    pub fn compile_failed(&self, task: &JudgeTask, output: &std::process::Output) -> JudgeResult {
+        let submission = &task.submission;
+        let mut error = submission.compile_error(&String::from_utf8_lossy(&output.stderr));
+        error.exit_code = output.status.code();
+        let mut result = JudgeResult::with_error(
+            JudgeStatus::CompileError,
+            0,
+            0,
+            error,
+            submission.id,
+            submission.problem_id,
+            submission.user_id,
+        )
+        .with_attempt(submission.attempt);
+        result.attach_hints(&self.hints, submission.language);
+        result
+    }
+}
+
+/// `PATH` of the precompile step, which must not inherit the judger's
+const PRECOMPILE_PATH: &str = "/usr/local/bin:/usr/bin:/bin";
+
+fn system_error(task: &JudgeTask, message: String) -> JudgeResult {
+    let submission = &task.submission;
+    JudgeResult::with_error(
+        JudgeStatus::SystemError,
+        0,
+        0,
+        ErrorInfo::new(message),
+        submission.id,
+        submission.problem_id,
+        submission.user_id,
+    )
+    .with_attempt(submission.attempt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oj_shared::testing;
+    use oj_shared::{LimitPolicy, LimitScaling, TestCase, TestData};
+    use uuid::Uuid;
+
+    fn judger() -> Judger {
+        Judger {
+            registry: LanguageRegistry::builtin(),
+            policy: SubmissionPolicy::default(),
+            env_policy: EnvPolicy::default(),
+            screening: ScreeningRules::builtin(),
+            scanner: None,
+            toolchains: HashMap::new(),
+            allow_unsandboxed: false,
+            hints: HintRules::builtin(),
+            data_root: PathBuf::from(DEFAULT_DATA_ROOT),
+        }
+    }
+
+    fn task(language: ProgrammingLanguage, source: &str) -> JudgeTask {
+        JudgeTask::new(testing::submission(language, source), vec![])
+    }
+
+    #[tokio::test]
+    async fn test_precheck_rejects_unavailable_language() {
+        let mut judger = judger();
+        judger.toolchains.insert(
+            ProgrammingLanguage::Cpp17,
+            Err(ProbeError::NotFound {
+                binary: "g++".to_string(),
+            }),
+        );
+
+        let result = judger
+            .precheck(&mut task(ProgrammingLanguage::Cpp17, "int main() {}"))
+            .await
+            .unwrap();
+        assert_eq!(result.status, JudgeStatus::SystemError);
+        assert_eq!(result.error_info.unwrap().message, "g++ not found in PATH");
+
+        assert!(
+            judger
+                .precheck(&mut task(ProgrammingLanguage::C, "int main() {}"))
+                .await
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_precheck_validates_submission() {
+        let judger = judger();
+        let mut task = task(ProgrammingLanguage::C, "int main() {}");
+        task.submission.time_limit = 0;
+
+        let result = judger.precheck(&mut task).await.unwrap();
+        assert_eq!(result.status, JudgeStatus::SystemError);
+        assert!(
+            result
+                .error_info
+                .unwrap()
+                .message
+                .starts_with("Invalid submission: time limit 0ms")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_precheck_checks_env() {
+        let judger = judger();
+        let mut task = task(ProgrammingLanguage::Java, "public class Main {}");
+        task.env = vec![("JAVA_TOOL_OPTIONS".to_string(), "-Xss64m".to_string())];
+        assert!(judger.precheck(&mut task).await.is_none());
+
+        task.env
+            .push(("LD_PRELOAD".to_string(), "/tmp/hook.so".to_string()));
+        let result = judger.precheck(&mut task).await.unwrap();
+        assert_eq!(result.status, JudgeStatus::SystemError);
+        assert_eq!(
+            result.error_info.unwrap().message,
+            "environment variable LD_PRELOAD is not allowed"
+        );
+
+        task.env.pop();
+        let mut test_case = TestCase::new("3".to_string(), "", "");
+        test_case.env = Some(vec![("PATH".to_string(), "/tmp".to_string())]);
+        task.test_cases.push(test_case);
+        let result = judger.precheck(&mut task).await.unwrap();
+        assert_eq!(
+            result.error_info.unwrap().message,
+            "test case 3: environment variable PATH is not allowed"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_precheck_verifies_test_data() {
+        let judger = judger();
+        let mut task = task(ProgrammingLanguage::C, "int main() {}");
+        let mut test_case = TestCase::new("1".to_string(), "1 2\n", "3\n");
+        test_case.compute_checksums().unwrap();
+        task.test_cases.push(test_case);
+        assert!(judger.precheck(&mut task).await.is_none());
+
+        task.test_cases[0].expected_output = "4\n".into();
+        let result = judger.precheck(&mut task).await.unwrap();
+        assert_eq!(result.status, JudgeStatus::SystemError);
+        assert!(
+            result
+                .error_info
+                .unwrap()
+                .message
+                .starts_with("Corrupted test data: test case 1:")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_precheck_confines_test_data_to_data_root() {
+        let judger = judger();
+        let mut task = task(ProgrammingLanguage::C, "int main() {}");
+        let mut test_case = TestCase::new("1".to_string(), "", "");
+        test_case.input = TestData::File("../etc/passwd".into());
+        task.test_cases.push(test_case);
+
+        let result = judger.precheck(&mut task).await.unwrap();
+        assert_eq!(result.status, JudgeStatus::SystemError);
+        assert_eq!(
+            result.error_info.unwrap().message,
+            "test data file ../etc/passwd is outside the data root"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_precheck_rejects_invalid_limit_policy() {
+        let judger = judger();
+        let mut task = task(ProgrammingLanguage::C, "int main() {}");
+        let mut policy = LimitPolicy::default();
+        policy.overrides.insert(
+            ProgrammingLanguage::C,
+            LimitScaling {
+                time_multiplier: f64::NAN,
+                memory_bonus_kb: 0,
+            },
+        );
+        task.limit_policy = Some(policy);
+
+        let result = judger.precheck(&mut task).await.unwrap();
+        assert_eq!(result.status, JudgeStatus::SystemError);
+        assert_eq!(
+            result.error_info.unwrap().message,
+            "time multiplier NaN for C must be a finite number of at least 1.0"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_precheck_gates_unsandboxed_tasks() {
+        let mut judger = judger();
+        let mut task = task(ProgrammingLanguage::C, "int main() {}");
+        task.use_sandbox = false;
+
+        let result = judger.precheck(&mut task).await.unwrap();
+        assert_eq!(result.status, JudgeStatus::SystemError);
+        assert_eq!(
+            result.error_info.unwrap().message,
+            "Task disables the sandbox, which this judger does not allow"
+        );
+
+        judger.allow_unsandboxed = true;
+        assert!(judger.precheck(&mut task).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_precheck_screens_source() {
+        let judger = judger();
+        let mut task = task(
+            ProgrammingLanguage::Cpp17,
+            "#include <sys/ptrace.h>\nint main() {}\n",
+        );
+
+        let result = judger.precheck(&mut task).await.unwrap();
+        assert_eq!(result.status, JudgeStatus::RestrictedOperation);
+        assert!(
+            result
+                .error_info
+                .unwrap()
+                .message
+                .contains("ptrace is not allowed")
+        );
+
+        task.skip_screening = true;
+        assert!(judger.precheck(&mut task).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_precheck_allows_warn_only_matches() {
+        let mut judger = judger();
+        judger.screening = ScreeningRules::from_toml_str(
+            "[[rules]]\nid = \"goto\"\npattern = \"goto\"\nreason = \"goto is discouraged\"\nwarn_only = true\n",
+        )
+        .unwrap();
+        assert!(
+            judger
+                .precheck(&mut task(
+                    ProgrammingLanguage::C,
+                    "int main() { goto end; end:; }"
+                ))
+                .await
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_precompile_reports_syntax_errors() {
+        let judger = judger();
+        let workdir = std::env::temp_dir().join(format!("oj-precompile-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&workdir).unwrap();
+
+        let mut bad = task(ProgrammingLanguage::Python3, "print(\"unterminated)\n");
+        std::fs::write(
+            workdir.join(bad.submission.filename()),
+            &bad.submission.source_code,
+        )
+        .unwrap();
+        assert!(judger.precompile(&bad, &workdir).await.unwrap().is_none());
+
+        bad.precompile = true;
+        let result = judger.precompile(&bad, &workdir).await.unwrap().unwrap();
+        assert_eq!(result.status, JudgeStatus::CompileError);
+        assert!(
+            result
+                .error_info
+                .unwrap()
+                .stderr
+                .unwrap()
+                .contains("SyntaxError")
+        );
+
+        let mut good = task(ProgrammingLanguage::Python3, "print(1)\n");
+        good.precompile = true;
+        std::fs::write(
+            workdir.join(good.submission.filename()),
+            &good.submission.source_code,
+        )
+        .unwrap();
+        assert!(judger.precompile(&good, &workdir).await.unwrap().is_none());
+
+        // A byte-compile that outlives the compile time limit is a compile error, not TLE
+        good.compile_time_limit = Some(0);
+        let result = judger.precompile(&good, &workdir).await.unwrap().unwrap();
+        assert_eq!(result.status, JudgeStatus::CompileError);
+        assert_eq!(
+            result.error_info.unwrap().code.as_deref(),
+            Some("COMPILE_TIME_LIMIT_EXCEEDED")
+        );
+
+        std::fs::remove_dir_all(&workdir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_precompile_skips_image_toolchains() {
+        let mut judger = judger();
+        // Only the image has this interpreter; running it on the host would fail
+        judger.registry = LanguageRegistry::from_toml_str(
+            "[Python3]\nruntime = \"python3-only-in-image\"\nimage = \"axon/toolchain-python:3.12\"\n",
+        )
+        .unwrap();
+        let workdir = std::env::temp_dir().join(format!("oj-precompile-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&workdir).unwrap();
+
+        let mut task = task(ProgrammingLanguage::Python3, "print(1)\n");
+        task.precompile = true;
+        std::fs::write(
+            workdir.join(task.submission.filename()),
+            &task.submission.source_code,
+        )
+        .unwrap();
+        assert!(judger.precompile(&task, &workdir).await.unwrap().is_none());
+
+        std::fs::remove_dir_all(&workdir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_compile_failed_explains_missing_public_class() {
+        let judger = judger();
+        let workdir = std::env::temp_dir().join(format!("oj-compile-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&workdir).unwrap();
+        let task = task(ProgrammingLanguage::Java, "class Solution { int x }\n");
+        let source = workdir.join(task.submission.filename());
+        std::fs::write(&source, &task.submission.source_code).unwrap();
+
+        let output = tokio::process::Command::new("javac")
+            .arg(&source)
+            .output()
+            .await
+            .unwrap();
+        assert!(!output.status.success());
+        let result = judger.compile_failed(&task, &output);
+        assert_eq!(result.status, JudgeStatus::CompileError);
+        let error = result.error_info.unwrap();
+        assert_eq!(error.code.as_deref(), Some("JAVA_NO_PUBLIC_CLASS"));
+        assert!(error.stderr.unwrap().contains("error"));
+        std::fs::remove_dir_all(&workdir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_precompile_enforces_memory_limit() {
+        let judger = judger();
+        let workdir = std::env::temp_dir().join(format!("oj-precompile-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&workdir).unwrap();
+
+        let mut task = task(ProgrammingLanguage::Python3, "print(1)\n");
+        task.precompile = true;
+        std::fs::write(
+            workdir.join(task.submission.filename()),
+            &task.submission.source_code,
+        )
+        .unwrap();
+        assert!(judger.precompile(&task, &workdir).await.unwrap().is_none());
+
+        // The interpreter can't even start within 1 MiB of address space
+        task.compile_memory_limit = Some(1024);
+        let result = judger.precompile(&task, &workdir).await.unwrap().unwrap();
+        assert_eq!(result.status, JudgeStatus::CompileError);
+
+        std::fs::remove_dir_all(&workdir).unwrap();
+    }
+}
@@ -1,13 +1,44 @@
+use oj_judger::Judger;
+use oj_judger::scan::SourceScanner;
+use oj_judger::toolchain::{self, ProbeError, ToolchainInfo};
+use oj_shared::{
+    EnvPolicy, HintRules, LanguageRegistry, ProgrammingLanguage, ScreeningRules, SubmissionPolicy,
+};
+use std::collections::HashMap;
+use std::path::PathBuf;
 use tokio::time::{Duration, sleep};
 
 #[tokio::main]
-async fn main() {
+async fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt::init();
 
+    let registry = load_language_registry()?;
+    let image_store = image_store();
+    let toolchains = toolchain::probe_all(&registry, &image_store).await;
+    log_toolchains(&toolchains);
+
+    let judger = Judger {
+        registry,
+        policy: SubmissionPolicy::default(),
+        env_policy: EnvPolicy::default(),
+        screening: load_screening_rules()?,
+        scanner: SourceScanner::from_env()?,
+        toolchains,
+        allow_unsandboxed: std::env::var("JUDGER_ALLOW_UNSANDBOXED").is_ok_and(|v| v == "1"),
+        hints: load_hint_rules()?,
+        data_root: data_root(),
+    };
+    if judger.scanner.is_some() {
+        tracing::info!("Pre-judge source scanning enabled");
+    }
+    if judger.allow_unsandboxed {
+        tracing::warn!("Trusted tasks may run without the sandbox");
+    }
+
     tracing::info!("Judger service started");
 
     loop {
-        match check_for_submissions().await {
+        match check_for_submissions(&judger).await {
             Ok(_) => {}
             Err(e) => tracing::error!("Error checking submissions: {}", e),
         }
@@ -16,7 +47,77 @@ async fn main() {
     }
 }
 
-async fn check_for_submissions() -> anyhow::Result<()> {
+/// Loads the language registry from `JUDGER_LANGUAGES_CONFIG`, falling back to the built-in toolchains
+fn load_language_registry() -> anyhow::Result<LanguageRegistry> {
+    match std::env::var("JUDGER_LANGUAGES_CONFIG") {
+        Ok(path) if !path.is_empty() => {
+            tracing::info!("Loading language registry from {}", path);
+            Ok(LanguageRegistry::from_file(&path)?)
+        }
+        _ => Ok(LanguageRegistry::builtin()),
+    }
+}
+
+/// Returns the directory of unpacked toolchain images, `JUDGER_IMAGE_STORE` if set
+fn image_store() -> PathBuf {
+    match std::env::var("JUDGER_IMAGE_STORE") {
+        Ok(path) if !path.is_empty() => PathBuf::from(path),
+        _ => PathBuf::from(toolchain::DEFAULT_IMAGE_STORE),
+    }
+}
+
+/// Returns the directory test data files are read from, `JUDGER_DATA_ROOT` if set
+fn data_root() -> PathBuf {
+    match std::env::var("JUDGER_DATA_ROOT") {
+        Ok(path) if !path.is_empty() => PathBuf::from(path),
+        _ => PathBuf::from(oj_judger::DEFAULT_DATA_ROOT),
+    }
+}
+
+fn load_screening_rules() -> anyhow::Result<ScreeningRules> {
+    match std::env::var("JUDGER_SCREENING_RULES") {
+        Ok(path) if !path.is_empty() => {
+            tracing::info!("Loading screening rules from {}", path);
+            Ok(ScreeningRules::from_file(&path)?)
+        }
+        _ => Ok(ScreeningRules::builtin()),
+    }
+}
+
+/// Loads the built-in hint rules, extended by `JUDGER_HINT_RULES` if set
+fn load_hint_rules() -> anyhow::Result<HintRules> {
+    let builtin = HintRules::builtin();
+    match std::env::var("JUDGER_HINT_RULES") {
+        Ok(path) if !path.is_empty() => {
+            tracing::info!("Loading hint rules from {}", path);
+            Ok(builtin.with_overrides(HintRules::from_file(&path)?))
+        }
+        _ => Ok(builtin),
+    }
+}
+
+/// Logs a table of available and missing toolchains
+fn log_toolchains(toolchains: &HashMap<ProgrammingLanguage, Result<ToolchainInfo, ProbeError>>) {
+    tracing::info!("Detected toolchains:");
+    for language in ProgrammingLanguage::ALL {
+        match &toolchains[&language] {
+            Ok(ToolchainInfo {
+                image: Some(image), ..
+            }) => tracing::info!("  {:<12} image {}", language.as_str(), image),
+            Ok(info) => {
+                let versions: Vec<String> = info
+                    .tools
+                    .iter()
+                    .map(|tool| format!("{} {}", tool.binary, tool.version))
+                    .collect();
+                tracing::info!("  {:<12} {}", language.as_str(), versions.join(", "));
+            }
+            Err(e) => tracing::warn!("  {:<12} unavailable: {}", language.as_str(), e),
+        }
+    }
+}
+
+async fn check_for_submissions(_judger: &Judger) -> anyhow::Result<()> {
     tracing::debug!("Checking for new submissions...");
     Ok(())
 }
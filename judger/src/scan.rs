@@ -0,0 +1,341 @@
+//! Pre-judge source scanning hook
+//!
+//! Submissions can be handed to an external scanner (a local command or an HTTP
+//! service) before they reach the sandbox. The scanner may veto a submission;
+//! scanner failures and timeouts are resolved by the configured [`FailurePolicy`].
+
+use oj_shared::{ErrorInfo, JudgeResult, JudgeStatus, Submission};
+use serde::{Deserialize, Serialize};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::time::{Duration, timeout};
+
+/// What to do when the scanner errors or times out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailurePolicy {
+    /// Let the submission through
+    FailOpen,
+    /// Reject the submission
+    FailClosed,
+}
+
+/// Where submissions are sent for scanning
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanBackend {
+    /// Runs a command with the source on stdin.
+    ///
+    /// Exit code 0 allows the submission, 1 vetoes it with stdout as the reason,
+    /// anything else is treated as a scanner failure.
+    Command { program: String, args: Vec<String> },
+    /// POSTs a [`ScanRequest`] as JSON and expects a [`ScanResponse`]
+    Http { url: String },
+}
+
+/// Body sent to an HTTP scanner
+#[derive(Debug, Serialize)]
+pub struct ScanRequest<'a> {
+    pub submission_id: String,
+    pub language: &'a str,
+    pub filename: String,
+    pub source_code: &'a str,
+}
+
+/// Body expected from an HTTP scanner
+#[derive(Debug, Deserialize)]
+pub struct ScanResponse {
+    pub allowed: bool,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Outcome of scanning a submission
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanVerdict {
+    /// The submission may be judged
+    Allowed,
+    /// The submission must not reach the sandbox
+    Vetoed(String),
+}
+
+/// Configured scanning hook
+#[derive(Debug, Clone)]
+pub struct SourceScanner {
+    backend: ScanBackend,
+    timeout: Duration,
+    policy: FailurePolicy,
+    client: reqwest::Client,
+}
+
+impl SourceScanner {
+    /// Creates a scanner for a backend
+    pub fn new(backend: ScanBackend, timeout: Duration, policy: FailurePolicy) -> Self {
+        Self {
+            backend,
+            timeout,
+            policy,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Builds a scanner from `JUDGER_SCAN_COMMAND` or `JUDGER_SCAN_URL`
+    ///
+    /// `JUDGER_SCAN_COMMAND` is a JSON argv list such as `["/opt/scan", "--strict"]`,
+    /// so the program and its arguments may contain spaces.
+    /// `JUDGER_SCAN_TIMEOUT_MS` (default 5000) bounds each scan, and
+    /// `JUDGER_SCAN_POLICY` is `fail-open` or `fail-closed` (default).
+    /// Returns `None` when no scanner is configured.
+    pub fn from_env() -> anyhow::Result<Option<Self>> {
+        let non_empty = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+
+        let backend = if let Some(command) = non_empty("JUDGER_SCAN_COMMAND") {
+            parse_command(&command)?
+        } else if let Some(url) = non_empty("JUDGER_SCAN_URL") {
+            ScanBackend::Http { url }
+        } else {
+            return Ok(None);
+        };
+
+        let timeout_ms = match non_empty("JUDGER_SCAN_TIMEOUT_MS") {
+            Some(value) => value.parse()?,
+            None => 5000,
+        };
+        let policy = match non_empty("JUDGER_SCAN_POLICY").as_deref() {
+            None | Some("fail-closed") => FailurePolicy::FailClosed,
+            Some("fail-open") => FailurePolicy::FailOpen,
+            Some(other) => anyhow::bail!("invalid JUDGER_SCAN_POLICY: {}", other),
+        };
+
+        Ok(Some(Self::new(
+            backend,
+            Duration::from_millis(timeout_ms),
+            policy,
+        )))
+    }
+
+    /// Scans a submission, applying the failure policy to scanner errors
+    pub async fn scan(&self, submission: &Submission) -> ScanVerdict {
+        let outcome = match timeout(self.timeout, self.run_backend(submission)).await {
+            Ok(result) => result,
+            Err(_) => Err(anyhow::anyhow!(
+                "scanner timed out after {}ms",
+                self.timeout.as_millis()
+            )),
+        };
+
+        match outcome {
+            Ok(verdict) => verdict,
+            Err(e) => {
+                tracing::warn!("Source scan failed for {}: {}", submission.id, e);
+                match self.policy {
+                    FailurePolicy::FailOpen => ScanVerdict::Allowed,
+                    FailurePolicy::FailClosed => {
+                        ScanVerdict::Vetoed("Source scanning is unavailable".to_string())
+                    }
+                }
+            }
+        }
+    }
+
+    async fn run_backend(&self, submission: &Submission) -> anyhow::Result<ScanVerdict> {
+        match &self.backend {
+            ScanBackend::Command { program, args } => {
+                let mut child = Command::new(program)
+                    .args(args)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::null())
+                    .kill_on_drop(true)
+                    .spawn()?;
+
+                // A scanner may decide without reading all of stdin, so the source is
+                // written concurrently and a closed pipe is not a failure
+                let writer = child.stdin.take().map(|mut stdin| {
+                    let source = submission.source_code.clone();
+                    tokio::spawn(async move { stdin.write_all(source.as_bytes()).await })
+                });
+                let output = child.wait_with_output().await?;
+                if let Some(writer) = writer {
+                    match writer.await? {
+                        Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => {
+                            return Err(e.into());
+                        }
+                        _ => {}
+                    }
+                }
+
+                match output.status.code() {
+                    Some(0) => Ok(ScanVerdict::Allowed),
+                    Some(1) => Ok(ScanVerdict::Vetoed(
+                        String::from_utf8_lossy(&output.stdout).trim().to_string(),
+                    )),
+                    _ => anyhow::bail!("scanner exited with {}", output.status),
+                }
+            }
+            ScanBackend::Http { url } => {
+                let request = ScanRequest {
+                    submission_id: submission.id.to_string(),
+                    language: submission.language.as_str(),
+                    filename: submission.filename(),
+                    source_code: &submission.source_code,
+                };
+                let response: ScanResponse = self
+                    .client
+                    .post(url)
+                    .json(&request)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+
+                if response.allowed {
+                    Ok(ScanVerdict::Allowed)
+                } else {
+                    Ok(ScanVerdict::Vetoed(response.reason.unwrap_or_default()))
+                }
+            }
+        }
+    }
+}
+
+/// Parses a JSON argv list into a command backend
+fn parse_command(argv: &str) -> anyhow::Result<ScanBackend> {
+    let mut argv: Vec<String> = serde_json::from_str(argv).map_err(|e| {
+        anyhow::anyhow!("JUDGER_SCAN_COMMAND must be a JSON array of strings: {}", e)
+    })?;
+    if argv.is_empty() || argv[0].is_empty() {
+        anyhow::bail!("JUDGER_SCAN_COMMAND has no program");
+    }
+    let program = argv.remove(0);
+    Ok(ScanBackend::Command {
+        program,
+        args: argv,
+    })
+}
+
+/// Builds the result reported for a vetoed submission
+pub fn vetoed_result(submission: &Submission, reason: &str) -> JudgeResult {
+    let message = if reason.is_empty() {
+        "Submission rejected by source scanner".to_string()
+    } else {
+        format!("Submission rejected by source scanner: {}", reason)
+    };
+    JudgeResult::with_error(
+        JudgeStatus::RestrictedOperation,
+        0,
+        0,
+        ErrorInfo::new(message),
+        submission.id,
+        submission.problem_id,
+        submission.user_id,
+    )
+    .with_attempt(submission.attempt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oj_shared::ProgrammingLanguage;
+    use oj_shared::testing;
+
+    fn submission(source: &str) -> Submission {
+        testing::submission(ProgrammingLanguage::Cpp17, source)
+    }
+
+    fn shell_scanner(script: &str, policy: FailurePolicy) -> SourceScanner {
+        SourceScanner::new(
+            ScanBackend::Command {
+                program: "sh".to_string(),
+                args: vec!["-c".to_string(), script.to_string()],
+            },
+            Duration::from_millis(500),
+            policy,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_command_allows_and_vetoes() {
+        let scanner = shell_scanner(
+            "if grep -q ptrace; then echo 'ptrace is banned'; exit 1; fi",
+            FailurePolicy::FailClosed,
+        );
+        assert_eq!(
+            scanner.scan(&submission("int main() {}")).await,
+            ScanVerdict::Allowed
+        );
+        assert_eq!(
+            scanner
+                .scan(&submission("#include <sys/ptrace.h>\nint main() {}"))
+                .await,
+            ScanVerdict::Vetoed("ptrace is banned".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_early_veto_of_large_source() {
+        // Exits after reading one byte, long before a 256 KiB source fits in the pipe
+        let scanner = shell_scanner(
+            "head -c 1 >/dev/null; echo 'too large'; exit 1",
+            FailurePolicy::FailOpen,
+        );
+        let source = "x".repeat(256 * 1024);
+        assert_eq!(
+            scanner.scan(&submission(&source)).await,
+            ScanVerdict::Vetoed("too large".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failure_policy() {
+        let open = shell_scanner("exit 3", FailurePolicy::FailOpen);
+        assert_eq!(open.scan(&submission("")).await, ScanVerdict::Allowed);
+
+        let closed = shell_scanner("exit 3", FailurePolicy::FailClosed);
+        assert!(matches!(
+            closed.scan(&submission("")).await,
+            ScanVerdict::Vetoed(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_timeout_uses_policy() {
+        let open = shell_scanner("sleep 5", FailurePolicy::FailOpen);
+        assert_eq!(open.scan(&submission("")).await, ScanVerdict::Allowed);
+
+        let closed = shell_scanner("sleep 5", FailurePolicy::FailClosed);
+        assert!(matches!(
+            closed.scan(&submission("")).await,
+            ScanVerdict::Vetoed(_)
+        ));
+    }
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(
+            parse_command(r#"["/opt/scan tools/scan", "--rules", "/etc/my rules"]"#).unwrap(),
+            ScanBackend::Command {
+                program: "/opt/scan tools/scan".to_string(),
+                args: vec!["--rules".to_string(), "/etc/my rules".to_string()],
+            }
+        );
+        assert!(parse_command("/opt/scan --strict").is_err());
+        assert!(parse_command("[]").is_err());
+        assert!(parse_command(r#"[""]"#).is_err());
+    }
+
+    #[test]
+    fn test_vetoed_result() {
+        let submission = submission("");
+        let result = vetoed_result(&submission, "ptrace is banned");
+        assert_eq!(result.status, JudgeStatus::RestrictedOperation);
+        assert_eq!(result.submission_id, submission.id);
+        assert!(
+            result
+                .error_info
+                .unwrap()
+                .message
+                .ends_with("ptrace is banned")
+        );
+    }
+}
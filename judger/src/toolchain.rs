@@ -0,0 +1,391 @@
+//! Detection of installed compilers and runtimes
+//!
+//! Languages whose toolchain comes from an OCI image are not probed on the host;
+//! their image must be unpacked in the image store instead.
+
+use futures::future::join_all;
+use oj_shared::{ImageRef, LanguageRegistry, LanguageSpec, ProgrammingLanguage};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command;
+use tokio::time::{Duration, timeout};
+
+/// How long a single `--version` invocation may take (JVM-based tools are slow to start)
+const PROBE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Directory holding unpacked toolchain images unless `JUDGER_IMAGE_STORE` is set
+pub const DEFAULT_IMAGE_STORE: &str = "/var/lib/axon/images";
+
+/// Version of one binary used by a language
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolVersion {
+    /// Binary as configured in the language registry
+    pub binary: String,
+    /// Version string parsed from the binary's output
+    pub version: String,
+}
+
+/// Installed toolchain for a language
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolchainInfo {
+    pub language: ProgrammingLanguage,
+    /// Compiler and/or runtime versions, compiler first; empty for image toolchains
+    pub tools: Vec<ToolVersion>,
+    /// Image providing the toolchain, `None` for host binaries
+    pub image: Option<ImageRef>,
+}
+
+/// Why a toolchain could not be probed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProbeError {
+    /// The binary is not installed
+    NotFound { binary: String },
+    /// The binary could not be run or timed out
+    Failed { binary: String, message: String },
+    /// The binary ran but printed no recognizable version
+    UnparsableVersion { binary: String, output: String },
+    /// The toolchain image is not unpacked in the image store
+    ImageNotFound { image: ImageRef, path: PathBuf },
+}
+
+impl fmt::Display for ProbeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProbeError::NotFound { binary } => write!(f, "{} not found in PATH", binary),
+            ProbeError::Failed { binary, message } => {
+                write!(f, "failed to run {}: {}", binary, message)
+            }
+            ProbeError::UnparsableVersion { binary, .. } => {
+                write!(f, "could not determine the version of {}", binary)
+            }
+            ProbeError::ImageNotFound { image, path } => {
+                write!(f, "image {} not found at {}", image, path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProbeError {}
+
+/// Probes the compiler and runtime configured for a language
+///
+/// For an image toolchain this only checks that the image is in `image_store`.
+pub async fn probe(
+    language: ProgrammingLanguage,
+    spec: &LanguageSpec,
+    image_store: &Path,
+) -> Result<ToolchainInfo, ProbeError> {
+    if let Some(image) = &spec.image {
+        let path = image_store.join(image.store_path());
+        if !path.is_dir() {
+            return Err(ProbeError::ImageNotFound {
+                image: image.clone(),
+                path,
+            });
+        }
+        return Ok(ToolchainInfo {
+            language,
+            tools: Vec::new(),
+            image: Some(image.clone()),
+        });
+    }
+    let mut tools = Vec::new();
+    for binary in binaries_for(spec) {
+        let version = probe_binary(binary).await?;
+        tools.push(ToolVersion {
+            binary: binary.to_string(),
+            version,
+        });
+    }
+    Ok(ToolchainInfo {
+        language,
+        tools,
+        image: None,
+    })
+}
+
+/// Probes every language in the registry concurrently
+pub async fn probe_all(
+    registry: &LanguageRegistry,
+    image_store: &Path,
+) -> HashMap<ProgrammingLanguage, Result<ToolchainInfo, ProbeError>> {
+    let probes = ProgrammingLanguage::ALL.map(|language| async move {
+        (
+            language,
+            probe(language, registry.get(&language), image_store).await,
+        )
+    });
+    join_all(probes).await.into_iter().collect()
+}
+
+/// Returns the binaries that must be installed for a language
+///
+/// Runtimes that point at the compiled artifact (`./a.out`) are skipped, and an
+/// interpreter used for both roles is only listed once.
+fn binaries_for(spec: &LanguageSpec) -> Vec<&str> {
+    let mut binaries = Vec::new();
+    if spec.needs_compilation {
+        binaries.push(spec.compiler.as_str());
+    }
+    let runtime = spec.runtime.as_str();
+    if !runtime.starts_with("./") && !binaries.contains(&runtime) {
+        binaries.push(runtime);
+    }
+    binaries
+}
+
+async fn probe_binary(binary: &str) -> Result<String, ProbeError> {
+    let output = Command::new(binary)
+        .args(version_args(binary))
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+
+    let output = match timeout(PROBE_TIMEOUT, output).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(ProbeError::NotFound {
+                binary: binary.to_string(),
+            });
+        }
+        Ok(Err(e)) => {
+            return Err(ProbeError::Failed {
+                binary: binary.to_string(),
+                message: e.to_string(),
+            });
+        }
+        Err(_) => {
+            return Err(ProbeError::Failed {
+                binary: binary.to_string(),
+                message: "timed out".to_string(),
+            });
+        }
+    };
+
+    // Several tools (python2, older JDKs) print their version on stderr
+    let text = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    parse_version(&text).ok_or_else(|| ProbeError::UnparsableVersion {
+        binary: binary.to_string(),
+        output: text.trim().to_string(),
+    })
+}
+
+fn version_args(binary: &str) -> &'static [&'static str] {
+    let name = Path::new(binary)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(binary);
+    match name {
+        "go" => &["version"],
+        // JDK 8 only understands the single-dash form, and prints it on stderr
+        "java" | "javac" | "kotlinc" | "kotlinc-jvm" => &["-version"],
+        _ => &["--version"],
+    }
+}
+
+/// Extracts the version from `--version` style output
+///
+/// Handles the usual formats: `gcc (Debian 12.2.0-14) 12.2.0`, `clang version 17.0.6`,
+/// `Python 3.11.7`, `v20.11.0`, `openjdk version "17.0.8"`, `go version go1.21.5 linux/amd64`
+/// and `rustc 1.75.0 (82e1608df 2023-12-21)`.
+pub fn parse_version(output: &str) -> Option<String> {
+    output.lines().find_map(parse_version_line)
+}
+
+fn parse_version_line(line: &str) -> Option<String> {
+    let line = strip_parenthesized(line);
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+
+    // Prefer the token following a literal "version" keyword
+    let after_keyword = tokens
+        .iter()
+        .position(|t| t.eq_ignore_ascii_case("version"))
+        .and_then(|i| tokens.get(i + 1))
+        .and_then(|t| as_version(t));
+
+    after_keyword.or_else(|| tokens.iter().find_map(|t| as_version(t)))
+}
+
+/// Returns the token as a version if it looks like one (`1.2`, `v1.2`, `go1.2`, `"1.2"`)
+fn as_version(token: &str) -> Option<String> {
+    let token = token.trim_matches(|c| c == '"' || c == ',' || c == ';');
+    let token = token
+        .strip_prefix("go")
+        .or_else(|| token.strip_prefix('v'))
+        .unwrap_or(token);
+    let starts_with_digit = token.chars().next().is_some_and(|c| c.is_ascii_digit());
+    (starts_with_digit && token.contains('.')).then(|| token.to_string())
+}
+
+fn strip_parenthesized(line: &str) -> String {
+    let mut depth = 0usize;
+    line.chars()
+        .filter(|&c| match c {
+            '(' => {
+                depth += 1;
+                false
+            }
+            ')' => {
+                depth = depth.saturating_sub(1);
+                false
+            }
+            _ => depth == 0,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gcc() {
+        let sample = "gcc (Debian 12.2.0-14+deb12u1) 12.2.0\nCopyright (C) 2022 Free Software Foundation, Inc.\n";
+        assert_eq!(parse_version(sample).as_deref(), Some("12.2.0"));
+        let sample = "g++ (GCC) 13.2.1 20230801\n";
+        assert_eq!(parse_version(sample).as_deref(), Some("13.2.1"));
+    }
+
+    #[test]
+    fn test_parse_clang() {
+        let sample = "Ubuntu clang version 14.0.0-1ubuntu1.1\nTarget: x86_64-pc-linux-gnu\n";
+        assert_eq!(parse_version(sample).as_deref(), Some("14.0.0-1ubuntu1.1"));
+        let sample = "Apple clang version 15.0.0 (clang-1500.1.0.2.5)\n";
+        assert_eq!(parse_version(sample).as_deref(), Some("15.0.0"));
+    }
+
+    #[test]
+    fn test_parse_python() {
+        assert_eq!(parse_version("Python 3.11.7\n").as_deref(), Some("3.11.7"));
+        assert_eq!(
+            parse_version("\nPython 2.7.18\n").as_deref(),
+            Some("2.7.18")
+        );
+    }
+
+    #[test]
+    fn test_parse_node() {
+        assert_eq!(parse_version("v20.11.0\n").as_deref(), Some("20.11.0"));
+    }
+
+    #[test]
+    fn test_parse_java() {
+        let sample = "openjdk 17.0.15 2025-04-15\nOpenJDK Runtime Environment (build 17.0.15+6-Debian-1deb12u1)\n";
+        assert_eq!(parse_version(sample).as_deref(), Some("17.0.15"));
+        let sample = "openjdk version \"1.8.0_392\"\nOpenJDK Runtime Environment\n";
+        assert_eq!(parse_version(sample).as_deref(), Some("1.8.0_392"));
+        assert_eq!(parse_version("javac 17.0.15\n").as_deref(), Some("17.0.15"));
+        assert_eq!(
+            parse_version("javac 1.8.0_392\n").as_deref(),
+            Some("1.8.0_392")
+        );
+    }
+
+    #[test]
+    fn test_version_args() {
+        assert_eq!(version_args("java"), &["-version"]);
+        assert_eq!(version_args("/usr/lib/jvm/java-8/bin/javac"), &["-version"]);
+        assert_eq!(version_args("go"), &["version"]);
+        assert_eq!(version_args("g++"), &["--version"]);
+    }
+
+    #[tokio::test]
+    async fn test_probe_reads_stderr() {
+        // Stands in for JDK 8's `java`, which rejects `--version` and prints on stderr
+        let dir = std::env::temp_dir().join(format!("oj-probe-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let java = dir.join("java");
+        std::fs::write(
+            &java,
+            "#!/bin/sh\n[ \"$1\" = -version ] || exit 2\necho 'openjdk version \"1.8.0_392\"' >&2\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&java, std::os::unix::fs::PermissionsExt::from_mode(0o755))
+            .unwrap();
+
+        let version = probe_binary(java.to_str().unwrap()).await.unwrap();
+        assert_eq!(version, "1.8.0_392");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_go() {
+        let sample = "go version go1.21.5 linux/amd64\n";
+        assert_eq!(parse_version(sample).as_deref(), Some("1.21.5"));
+    }
+
+    #[test]
+    fn test_parse_rustc() {
+        let sample = "rustc 1.75.0 (82e1608df 2023-12-21)\n";
+        assert_eq!(parse_version(sample).as_deref(), Some("1.75.0"));
+    }
+
+    #[test]
+    fn test_parse_unrecognized() {
+        assert_eq!(parse_version("usage: foo [options]\n"), None);
+        assert_eq!(parse_version(""), None);
+    }
+
+    #[test]
+    fn test_binaries_for() {
+        let cpp = LanguageSpec::builtin(ProgrammingLanguage::Cpp17);
+        assert_eq!(binaries_for(&cpp), vec!["g++"]);
+
+        let python = LanguageSpec::builtin(ProgrammingLanguage::Python3);
+        assert_eq!(binaries_for(&python), vec!["python3"]);
+
+        let java = LanguageSpec::builtin(ProgrammingLanguage::Java);
+        assert_eq!(binaries_for(&java), vec!["javac", "java"]);
+    }
+
+    #[tokio::test]
+    async fn test_probe_missing_binary() {
+        let mut spec = LanguageSpec::builtin(ProgrammingLanguage::Cpp17);
+        spec.compiler = "definitely-not-a-real-compiler".to_string();
+        let err = probe(
+            ProgrammingLanguage::Cpp17,
+            &spec,
+            Path::new(DEFAULT_IMAGE_STORE),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "definitely-not-a-real-compiler not found in PATH"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_probe_image_toolchain() {
+        let store = std::env::temp_dir().join(format!("axon-images-{}", std::process::id()));
+        let mut spec = LanguageSpec::builtin(ProgrammingLanguage::Cpp20);
+        // Not installed on the host, but never run by the probe
+        spec.compiler = "definitely-not-a-real-compiler".to_string();
+        spec.image = Some("axon/toolchain-gcc13:1.0".parse().unwrap());
+
+        let err = probe(ProgrammingLanguage::Cpp20, &spec, &store)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "image axon/toolchain-gcc13:1.0 not found at {}",
+                store.join("axon/toolchain-gcc13/1.0").display()
+            )
+        );
+
+        std::fs::create_dir_all(store.join("axon/toolchain-gcc13/1.0")).unwrap();
+        let info = probe(ProgrammingLanguage::Cpp20, &spec, &store)
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(&store).unwrap();
+        assert!(info.tools.is_empty());
+        assert_eq!(info.image, spec.image);
+    }
+}
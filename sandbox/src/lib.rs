@@ -1,9 +1,72 @@
-use std::fs;
-use std::process::{Command, Stdio};
+pub mod trusted;
+
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+
+/// Host files the standard streams of a sandboxed process are redirected to
+///
+/// The files are opened by the judger and handed to runc, which passes them to
+/// the container process as fds 0-2 when no terminal is requested, so no data is
+/// copied through pipes. Keep them outside the rootfs: the process only sees the
+/// open fds, never the paths.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IoFiles {
+    /// Read as standard input
+    pub stdin: PathBuf,
+    /// Created or truncated for standard output
+    pub stdout: PathBuf,
+    /// Created or truncated for standard error
+    pub stderr: PathBuf,
+}
+
+/// Limits of a redirected run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IoLimits {
+    /// Bytes the process may write to stdout (and, separately, to stderr)
+    pub output_bytes: u64,
+    /// Files the process may have open besides its three standard streams
+    pub open_files: u64,
+}
+
+/// Result of a redirected run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunOutcome {
+    /// Exit status of runc, which mirrors the container process
+    pub status: ExitStatus,
+    /// Size of the stdout file after the run
+    pub stdout_bytes: u64,
+    /// Size of the stderr file after the run
+    pub stderr_bytes: u64,
+    /// Whether stdout or stderr went over [`IoLimits::output_bytes`]
+    pub output_limit_exceeded: bool,
+}
+
+/// Environment every sandboxed process starts with; extra variables can't replace it
+const BASE_ENV: &[(&str, &str)] = &[
+    ("PATH", "/bin:/usr/bin:/usr/local/bin"),
+    ("HOME", "/root"),
+    ("TERM", "xterm"),
+];
+
+/// Host directories bind-mounted read-only to provide toolchains, as
+/// (destination, source)
+const HOST_TOOLCHAIN_BINDS: &[(&str, &str)] = &[
+    ("/bin", "/usr/bin"),
+    ("/usr/bin", "/usr/bin"),
+    ("/lib", "/lib"),
+    ("/lib64", "/lib64"),
+    ("/usr/lib", "/usr/lib"),
+    ("/usr/lib64", "/usr/lib64"),
+];
+
+/// Directories of a toolchain image bind-mounted read-only, if the image has them
+const IMAGE_TOOLCHAIN_DIRS: &[&str] = &["/bin", "/lib", "/lib64", "/usr", "/etc", "/opt"];
 
 pub struct ContainerSandbox {
     container_id: String,
     rootfs: String,
+    toolchain_root: Option<PathBuf>,
 }
 
 impl ContainerSandbox {
@@ -27,6 +90,7 @@ impl ContainerSandbox {
         Ok(Self {
             container_id: container_id.to_string(),
             rootfs: rootfs.to_string(),
+            toolchain_root: None,
         })
     }
 
@@ -46,7 +110,7 @@ impl ContainerSandbox {
 
         // Use runc to run the command
         let output = Command::new("runc")
-            .args(&["run", "--bundle", &self.rootfs, &self.container_id])
+            .args(["run", "--bundle", &self.rootfs, &self.container_id])
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .output()?;
@@ -61,21 +125,96 @@ impl ContainerSandbox {
         }
     }
 
+    /// Runs a command with its standard streams redirected to files
+    ///
+    /// The output limit is enforced with `RLIMIT_FSIZE` one byte above the limit:
+    /// a process writing more is killed by `SIGXFSZ`, and either way the file ends
+    /// up larger than the limit, which is what [`RunOutcome::output_limit_exceeded`]
+    /// checks. The limit also applies to files the process writes in its workspace.
+    ///
+    /// `env` is added to the process environment as given, so filter it with the
+    /// judger's allowlist first. Names of the base environment (`PATH`, `HOME`,
+    /// `TERM`) are ignored.
+    pub fn run_redirected(
+        &self,
+        command: &str,
+        args: &[&str],
+        env: &[(String, String)],
+        io: &IoFiles,
+        limits: &IoLimits,
+    ) -> anyhow::Result<RunOutcome> {
+        let config = self.container_config(command, args, env, Some(limits));
+        fs::write(format!("{}/config.json", self.rootfs), config.to_string())?;
+
+        let status = Command::new("runc")
+            .args(["run", "--bundle", &self.rootfs, &self.container_id])
+            .stdin(File::open(&io.stdin)?)
+            .stdout(File::create(&io.stdout)?)
+            .stderr(File::create(&io.stderr)?)
+            .status()?;
+
+        let stdout_bytes = fs::metadata(&io.stdout)?.len();
+        let stderr_bytes = fs::metadata(&io.stderr)?.len();
+        Ok(RunOutcome {
+            status,
+            stdout_bytes,
+            stderr_bytes,
+            output_limit_exceeded: stdout_bytes.max(stderr_bytes) > limits.output_bytes,
+        })
+    }
+
     fn create_container_config(&self, command: &str, args: &[&str]) -> anyhow::Result<()> {
+        let config = self.container_config(command, args, &[], None);
+        fs::write(format!("{}/config.json", self.rootfs), config.to_string())?;
+        Ok(())
+    }
+
+    fn container_config(
+        &self,
+        command: &str,
+        args: &[&str],
+        env: &[(String, String)],
+        limits: Option<&IoLimits>,
+    ) -> serde_json::Value {
         let mut full_args = vec![command];
         full_args.extend_from_slice(args);
+        let mut mounts = vec![
+            serde_json::json!({
+                "destination": "/proc",
+                "type": "proc",
+                "source": "proc"
+            }),
+            serde_json::json!({
+                "destination": "/dev",
+                "type": "tmpfs",
+                "source": "tmpfs",
+                "options": ["nosuid", "strictatime", "mode=755", "size=65536k"]
+            }),
+        ];
+        mounts.extend(self.toolchain_mounts());
+        mounts.push(serde_json::json!({
+            "destination": "/workspace",
+            "type": "tmpfs",
+            "source": "tmpfs",
+            "options": ["rw", "nosuid", "nodev", "size=1048576k"]
+        }));
+        let full_env: Vec<String> = BASE_ENV
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .chain(
+                env.iter()
+                    .filter(|(name, _)| !BASE_ENV.iter().any(|(base, _)| base == name))
+                    .map(|(name, value)| format!("{}={}", name, value)),
+            )
+            .collect();
 
-        let config = serde_json::json!({
+        let mut config = serde_json::json!({
             "ociVersion": "1.0.0",
             "process": {
                 "terminal": false,
                 "user": {"uid": 0, "gid": 0},
                 "args": full_args,
-                "env": [
-                    "PATH=/bin:/usr/bin:/usr/local/bin",
-                    "HOME=/root",
-                    "TERM=xterm"
-                ],
+                "env": full_env,
                 "cwd": "/workspace",
                 "capabilities": {
                     "bounding": [],
@@ -87,61 +226,7 @@ impl ContainerSandbox {
             },
             "root": {"path": &self.rootfs,"readonly":false},
             "hostname": "sandbox",
-            "mounts": [
-                {
-                    "destination": "/proc",
-                    "type": "proc",
-                    "source": "proc"
-                },
-                {
-                    "destination": "/dev",
-                    "type": "tmpfs",
-                    "source": "tmpfs",
-                    "options": ["nosuid", "strictatime", "mode=755", "size=65536k"]
-                },
-                  {
-                    "destination": "/bin",
-                    "type": "bind",
-                    "source": "/usr/bin",
-                    "options": ["rbind","ro", "nosuid", "nodev"]
-                },
-                {
-                    "destination": "/usr/bin",
-                    "type": "bind",
-                    "source": "/usr/bin",
-                    "options": ["rbind","ro", "nosuid", "nodev"]
-                },
-                {
-                    "destination": "/lib",
-                    "type": "bind",
-                    "source": "/lib",
-                    "options": ["rbind","ro", "nosuid", "nodev"]
-                },
-                {
-                    "destination": "/lib64",
-                    "type": "bind",
-                    "source": "/lib64",
-                    "options": ["rbind","ro", "nosuid", "nodev"]
-                },
-                {
-                    "destination": "/usr/lib",
-                    "type": "bind",
-                    "source": "/usr/lib",
-                    "options": ["rbind","ro", "nosuid", "nodev"]
-                },
-                {
-                    "destination": "/usr/lib64",
-                    "type": "bind",
-                    "source": "/usr/lib64",
-                    "options": ["rbind","ro", "nosuid", "nodev"]
-                },
-                {
-                    "destination": "/workspace",
-                    "type": "tmpfs",
-                    "source": "tmpfs",
-                    "options": ["rw", "nosuid", "nodev", "size=1048576k"]
-                }
-            ],
+            "mounts": mounts,
             "linux": {
                 "resources": {
                     "devices": [{"allow": false, "access": "rwm"}]
@@ -163,19 +248,66 @@ impl ContainerSandbox {
             }
         });
 
-        fs::write(format!("{}/config.json", self.rootfs), config.to_string())?;
-        Ok(())
+        if let Some(limits) = limits {
+            // RLIMIT_NOFILE caps the highest fd number, and fds 0-2 are the streams
+            let open_files = limits.open_files.saturating_add(3);
+            let output_bytes = limits.output_bytes.saturating_add(1);
+            config["process"]["rlimits"] = serde_json::json!([
+                {"type": "RLIMIT_FSIZE", "hard": output_bytes, "soft": output_bytes},
+                {"type": "RLIMIT_NOFILE", "hard": open_files, "soft": open_files}
+            ]);
+        }
+        config
     }
 
     pub fn cleanup(&self) -> anyhow::Result<()> {
         // Delete the container
         let _output = Command::new("runc")
-            .args(&["delete", &self.container_id])
+            .args(["delete", &self.container_id])
             .output()?;
 
         Ok(())
     }
 
+    /// Takes compilers and runtimes from an unpacked toolchain image instead of the host
+    ///
+    /// `root` is the image's root filesystem. Its `/bin`, `/lib`, `/lib64`, `/usr`,
+    /// `/etc` and `/opt` are mounted read-only in place of the host directories;
+    /// the workspace and the rest of the sandbox stay as they are.
+    pub fn with_toolchain_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.toolchain_root = Some(root.into());
+        self
+    }
+
+    /// Read-only binds that provide compilers, runtimes and their libraries
+    fn toolchain_mounts(&self) -> Vec<serde_json::Value> {
+        let binds: Vec<(String, String)> = match &self.toolchain_root {
+            None => HOST_TOOLCHAIN_BINDS
+                .iter()
+                .map(|(destination, source)| (destination.to_string(), source.to_string()))
+                .collect(),
+            Some(root) => IMAGE_TOOLCHAIN_DIRS
+                .iter()
+                .map(|dir| {
+                    let source = root.join(dir.trim_start_matches('/'));
+                    (dir.to_string(), source.to_string_lossy().into_owned())
+                })
+                .filter(|(_, source)| Path::new(source).is_dir())
+                .collect(),
+        };
+        binds
+            .into_iter()
+            .map(|(destination, source)| {
+                serde_json::json!({
+                    "destination": destination,
+                    "type": "bind",
+                    "source": source,
+                    "options": ["rbind", "ro", "nosuid", "nodev"]
+                })
+            })
+            .collect()
+    }
+
     /// Clean up rootfs directory
     pub fn cleanup_rootfs(&self) -> anyhow::Result<()> {
         if std::path::Path::new(&self.rootfs).exists() {
@@ -184,3 +316,121 @@ impl ContainerSandbox {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sandbox() -> ContainerSandbox {
+        ContainerSandbox {
+            container_id: "test".to_string(),
+            rootfs: "/tmp/axon-rootfs-test".to_string(),
+            toolchain_root: None,
+        }
+    }
+
+    fn mounts(config: &serde_json::Value) -> Vec<(String, String)> {
+        config["mounts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|mount| {
+                (
+                    mount["destination"].as_str().unwrap().to_string(),
+                    mount["source"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_toolchain_mounts() {
+        let config = sandbox().container_config("./main", &[], &[], None);
+        let host = mounts(&config);
+        assert_eq!(host.first().unwrap().0, "/proc");
+        assert_eq!(host.last().unwrap().0, "/workspace");
+        assert!(host.contains(&("/bin".to_string(), "/usr/bin".to_string())));
+        assert!(host.contains(&("/usr/lib64".to_string(), "/usr/lib64".to_string())));
+
+        let root = std::env::temp_dir().join(format!("axon-toolchain-{}", std::process::id()));
+        for dir in ["bin", "lib", "usr"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        let config =
+            sandbox()
+                .with_toolchain_root(&root)
+                .container_config("./main", &[], &[], None);
+        fs::remove_dir_all(&root).unwrap();
+        let image: Vec<(String, String)> = mounts(&config)
+            .into_iter()
+            .filter(|(destination, _)| {
+                !["/proc", "/dev", "/workspace"].contains(&destination.as_str())
+            })
+            .collect();
+        assert_eq!(
+            image,
+            ["bin", "lib", "usr"]
+                .iter()
+                .map(|dir| (
+                    format!("/{}", dir),
+                    root.join(dir).to_string_lossy().into_owned()
+                ))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(mounts(&config).last().unwrap().0, "/workspace");
+    }
+
+    #[test]
+    fn test_redirected_config_rlimits() {
+        let sandbox = sandbox();
+        let config = sandbox.container_config("./main", &[], &[], None);
+        assert!(config["process"].get("rlimits").is_none());
+        assert_eq!(config["process"]["terminal"], false);
+
+        let limits = IoLimits {
+            output_bytes: 1024,
+            open_files: 16,
+        };
+        let config = sandbox.container_config("./main", &["--fast"], &[], Some(&limits));
+        assert_eq!(
+            config["process"]["args"],
+            serde_json::json!(["./main", "--fast"])
+        );
+        assert_eq!(
+            config["process"]["rlimits"],
+            serde_json::json!([
+                {"type": "RLIMIT_FSIZE", "hard": 1025, "soft": 1025},
+                {"type": "RLIMIT_NOFILE", "hard": 19, "soft": 19}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_config_env() {
+        let sandbox = sandbox();
+        let config = sandbox.container_config("./main", &[], &[], None);
+        assert_eq!(
+            config["process"]["env"],
+            serde_json::json!([
+                "PATH=/bin:/usr/bin:/usr/local/bin",
+                "HOME=/root",
+                "TERM=xterm"
+            ])
+        );
+
+        let env = [
+            ("OMP_NUM_THREADS".to_string(), "4".to_string()),
+            ("PATH".to_string(), "/tmp".to_string()),
+        ];
+        let config = sandbox.container_config("./main", &[], &env, None);
+        assert_eq!(
+            config["process"]["env"],
+            serde_json::json!([
+                "PATH=/bin:/usr/bin:/usr/local/bin",
+                "HOME=/root",
+                "TERM=xterm",
+                "OMP_NUM_THREADS=4"
+            ])
+        );
+    }
+}
@@ -0,0 +1,218 @@
+use nix::sys::resource::{Resource, setrlimit};
+use nix::sys::signal::{Signal, killpg};
+use nix::unistd::Pid;
+use std::io::{Read, Write};
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::time::{Duration, Instant};
+
+/// Resource limits applied to a trusted process
+///
+/// A `None` limit is left at the value inherited from the judger.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// CPU time in seconds
+    pub cpu_time_secs: Option<u64>,
+    /// Address space in bytes
+    pub memory_bytes: Option<u64>,
+    /// Largest file the process may write, in bytes
+    pub file_size_bytes: Option<u64>,
+    /// Stack size in bytes
+    pub stack_bytes: Option<u64>,
+    /// Wall clock time in milliseconds, enforced by [`run_trusted`] only
+    ///
+    /// Defaults to twice the CPU limit plus a second, so a program that sleeps or
+    /// blocks can't hold the judger forever.
+    pub wall_time_ms: Option<u64>,
+}
+
+impl ResourceLimits {
+    /// Returns the wall clock deadline, explicit or derived from the CPU limit
+    fn wall_time(&self) -> Option<Duration> {
+        self.wall_time_ms.map(Duration::from_millis).or_else(|| {
+            self.cpu_time_secs
+                .map(|secs| Duration::from_secs(secs.saturating_mul(2).saturating_add(1)))
+        })
+    }
+}
+
+/// Runs a trusted program directly on the host, without a container
+///
+/// This is the fast path for setter-provided programs (reference solutions,
+/// generators) where starting a container dominates the turnaround. The process
+/// only gets rlimits: it shares the judger's filesystem, network and user, so it
+/// must never be used for user submissions.
+pub fn run_trusted(
+    command: &str,
+    args: &[&str],
+    workdir: &Path,
+    stdin: &[u8],
+    limits: &ResourceLimits,
+) -> anyhow::Result<Output> {
+    let mut cmd = Command::new(command);
+    cmd.args(args)
+        .current_dir(workdir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // Its own process group, so the deadline also kills anything it spawned
+        .process_group(0);
+    limit_command(&mut cmd, limits);
+
+    let mut child = cmd.spawn()?;
+    let mut child_stdin = child.stdin.take().expect("stdin is piped");
+    let input = stdin.to_vec();
+    // Write and read on separate threads so a child filling a pipe can't deadlock us
+    let writer = std::thread::spawn(move || {
+        // The child may exit without reading its input
+        let _ = child_stdin.write_all(&input);
+    });
+    let stdout = read_to_end(child.stdout.take().expect("stdout is piped"));
+    let stderr = read_to_end(child.stderr.take().expect("stderr is piped"));
+
+    let status = match limits.wall_time() {
+        Some(limit) => wait_with_deadline(&mut child, limit)?,
+        None => Some(child.wait()?),
+    };
+    let _ = writer.join();
+    let stdout = stdout.join().expect("reader thread panicked")?;
+    let stderr = stderr.join().expect("reader thread panicked")?;
+    match status {
+        Some(status) => Ok(Output {
+            status,
+            stdout,
+            stderr,
+        }),
+        None => anyhow::bail!(
+            "{} exceeded the wall time limit of {}ms",
+            command,
+            limits.wall_time().unwrap_or_default().as_millis()
+        ),
+    }
+}
+
+fn read_to_end(
+    mut pipe: impl Read + Send + 'static,
+) -> std::thread::JoinHandle<std::io::Result<Vec<u8>>> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        pipe.read_to_end(&mut buf)?;
+        Ok(buf)
+    })
+}
+
+/// Waits for the child, killing its process group at the deadline
+///
+/// Returns `None` when the child was killed.
+fn wait_with_deadline(child: &mut Child, limit: Duration) -> std::io::Result<Option<ExitStatus>> {
+    let deadline = Instant::now() + limit;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            let _ = killpg(Pid::from_raw(child.id() as i32), Signal::SIGKILL);
+            child.wait()?;
+            return Ok(None);
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Makes a command apply `limits` to its process when it is spawned
+///
+/// Also used for host-side steps of untrusted submissions that can't run in the
+/// container, such as the precompile syntax check; there the rlimits are the only
+/// bound on what the step may use.
+pub fn limit_command(cmd: &mut Command, limits: &ResourceLimits) {
+    let limits = limits.clone();
+    // SAFETY: the closure runs between fork and exec and only calls setrlimit,
+    // which is async-signal-safe, without allocating.
+    unsafe {
+        cmd.pre_exec(move || apply_limits(&limits).map_err(std::io::Error::from));
+    }
+}
+
+fn apply_limits(limits: &ResourceLimits) -> nix::Result<()> {
+    let resources = [
+        (Resource::RLIMIT_CPU, limits.cpu_time_secs),
+        (Resource::RLIMIT_AS, limits.memory_bytes),
+        (Resource::RLIMIT_FSIZE, limits.file_size_bytes),
+        (Resource::RLIMIT_STACK, limits.stack_bytes),
+    ];
+    for (resource, limit) in resources {
+        if let Some(limit) = limit {
+            setrlimit(resource, limit, limit)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_trusted_pipes_io() {
+        let output = run_trusted(
+            "sh",
+            &["-c", "read a b; echo $((a + b))"],
+            Path::new("/tmp"),
+            b"1 2\n",
+            &ResourceLimits::default(),
+        )
+        .unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"3\n");
+    }
+
+    #[test]
+    fn test_wall_time_limit() {
+        let limits = ResourceLimits {
+            wall_time_ms: Some(200),
+            ..ResourceLimits::default()
+        };
+        let start = Instant::now();
+        let err = run_trusted(
+            "sh",
+            &["-c", "sleep 10; echo done"],
+            Path::new("/tmp"),
+            b"",
+            &limits,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("wall time limit of 200ms"));
+        // The orphaned sleep must not keep the output pipes open
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        let limits = ResourceLimits {
+            cpu_time_secs: Some(1),
+            ..ResourceLimits::default()
+        };
+        assert_eq!(limits.wall_time(), Some(Duration::from_secs(3)));
+        assert_eq!(ResourceLimits::default().wall_time(), None);
+    }
+
+    #[test]
+    fn test_file_size_limit() {
+        let workdir = std::env::temp_dir().join(format!("axon-trusted-{}", std::process::id()));
+        std::fs::create_dir_all(&workdir).unwrap();
+        let limits = ResourceLimits {
+            file_size_bytes: Some(4),
+            ..ResourceLimits::default()
+        };
+        let output = run_trusted(
+            "sh",
+            &["-c", "printf 0123456789 > out"],
+            &workdir,
+            b"",
+            &limits,
+        )
+        .unwrap();
+        // The shell is killed by SIGXFSZ or reports the failed write
+        assert!(!output.status.success());
+        assert!(std::fs::metadata(workdir.join("out")).unwrap().len() <= 4);
+        std::fs::remove_dir_all(&workdir).unwrap();
+    }
+}
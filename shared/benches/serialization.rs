@@ -0,0 +1,82 @@
+//! JSON vs binary encoding of a large judge result
+//!
+//! Run with `cargo bench -p oj-shared --features binary`.
+
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use oj_shared::{
+    JudgeMode, JudgeResult, JudgeStatus, ProgrammingLanguage, Submission, TestCaseResult,
+};
+use uuid::Uuid;
+
+const TEST_CASES: usize = 200;
+const IO_BYTES: usize = 8 * 1024;
+
+/// A result with 200 test cases carrying their input, expected and actual output
+fn synthetic_result() -> JudgeResult {
+    let submission = Submission::new(
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+        ProgrammingLanguage::Cpp17,
+        "int main() {}".to_string(),
+        1000,
+        262144,
+    );
+    let io = |seed: usize| -> String {
+        (0..)
+            .map(|i| ((seed * 31 + i * 7) % 1_000_000).to_string())
+            .scan(0, |len, token| {
+                *len += token.len() + 1;
+                (*len <= IO_BYTES).then(|| token + if *len % 80 < 8 { "\n" } else { " " })
+            })
+            .collect()
+    };
+    let test_cases = (0..TEST_CASES)
+        .map(|i| TestCaseResult {
+            id: (i + 1).to_string(),
+            status: JudgeStatus::Accepted,
+            time_used: (i as u64 * 13) % 1000,
+            memory_used: 1024 + i as u64 * 64,
+            input: Some(io(i)),
+            expected_output: Some(io(i + TEST_CASES)),
+            actual_output: Some(io(i + TEST_CASES)),
+            error_info: None,
+            is_hidden: i % 2 == 0,
+            diff: None,
+        })
+        .collect();
+    JudgeResult::from_test_cases(
+        &submission,
+        test_cases,
+        &[1.0; TEST_CASES],
+        JudgeMode::OiPartial,
+    )
+}
+
+fn bench_serialization(c: &mut Criterion) {
+    let result = synthetic_result();
+    let json = serde_json::to_vec(&result).unwrap();
+    let binary = result.to_bytes();
+    println!(
+        "encoded size: json {} bytes, binary {} bytes",
+        json.len(),
+        binary.len()
+    );
+
+    let mut group = c.benchmark_group("judge_result");
+    group.bench_function("json_encode", |b| {
+        b.iter(|| serde_json::to_vec(black_box(&result)).unwrap())
+    });
+    group.bench_function("binary_encode", |b| {
+        b.iter(|| black_box(&result).to_bytes())
+    });
+    group.bench_function("json_decode", |b| {
+        b.iter(|| serde_json::from_slice::<JudgeResult>(black_box(&json)).unwrap())
+    });
+    group.bench_function("binary_decode", |b| {
+        b.iter(|| JudgeResult::from_bytes(black_box(&binary)).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_serialization);
+criterion_main!(benches);
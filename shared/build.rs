@@ -0,0 +1,40 @@
+fn main() {
+    #[cfg(feature = "proto")]
+    proto::generate();
+}
+
+/// Generates the `proto` feature's Rust types
+///
+/// The `.proto` files are parsed by the pure Rust `protobuf-parse` instead of
+/// `protoc`, so building doesn't need a protobuf toolchain. The parsed descriptors
+/// are re-encoded as prost descriptors and handed to prost-build.
+#[cfg(feature = "proto")]
+mod proto {
+    use prost::Message as _;
+    use protobuf::Message as _;
+
+    const PROTO_DIR: &str = "proto";
+    const PROTO_FILES: &[&str] = &["proto/axon/judge/v1/judge.proto"];
+
+    pub fn generate() {
+        println!("cargo:rerun-if-changed={}", PROTO_DIR);
+        let parsed = protobuf_parse::Parser::new()
+            .pure()
+            .include(PROTO_DIR)
+            .inputs(PROTO_FILES)
+            .parse_and_typecheck()
+            .expect("failed to parse proto files");
+
+        let mut descriptors = prost_types::FileDescriptorSet::default();
+        for file in parsed.file_descriptors {
+            let bytes = file.write_to_bytes().expect("failed to encode descriptor");
+            descriptors.file.push(
+                prost_types::FileDescriptorProto::decode(bytes.as_slice())
+                    .expect("failed to decode descriptor"),
+            );
+        }
+        prost_build::Config::new()
+            .compile_fds(descriptors)
+            .expect("failed to generate protobuf types");
+    }
+}
@@ -0,0 +1,494 @@
+//! Splitting a task across judger hosts
+//!
+//! A task with many test cases can be split into batches that share the submission
+//! and are judged independently; [`JudgeResult::merge`] recombines their results.
+//! Every batch compiles the submission again, which costs less than running hundreds
+//! of test cases on a single host.
+//!
+//! Test cases of a subtask always stay in the same batch, and so do subtasks that
+//! depend on each other, so every batch can score its subtasks on its own.
+
+use crate::{JudgeMode, JudgeResult, JudgeStatus, JudgeTask, Subtask};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use uuid::Uuid;
+
+/// Position of a batch within its split task
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchInfo {
+    /// 0-based index of this batch
+    pub index: usize,
+    /// Number of batches the task was split into
+    pub total: usize,
+    /// Id of the split task; the submission id, shared by every batch
+    pub parent_task_id: Uuid,
+    /// Fraction of the task's total score this batch carries
+    pub score_share: f64,
+    /// Judge mode of the split task
+    pub judge_mode: JudgeMode,
+}
+
+/// Why batch results can't be merged
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeError {
+    /// No results were given
+    Empty,
+    /// A result doesn't belong to a batch
+    NotBatched(Uuid),
+    /// Results belong to different tasks or disagree on the batch count
+    Mismatched,
+    /// Two results claim the same batch
+    DuplicateBatch(usize),
+    /// A batch has no result
+    MissingBatch(usize),
+    /// A result's batch index is not below the batch count
+    BatchOutOfRange { index: usize, total: usize },
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeError::Empty => write!(f, "no batch results to merge"),
+            MergeError::NotBatched(id) => {
+                write!(f, "result for submission {} is not a batch result", id)
+            }
+            MergeError::Mismatched => write!(f, "batch results belong to different tasks"),
+            MergeError::DuplicateBatch(index) => write!(f, "batch {} reported twice", index),
+            MergeError::MissingBatch(index) => write!(f, "batch {} has no result", index),
+            MergeError::BatchOutOfRange { index, total } => {
+                write!(f, "batch {} is out of range for {} batches", index, total)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MergeError {}
+
+impl JudgeTask {
+    /// Splits the task into at most `n` batches of test cases
+    ///
+    /// Batches are as even as possible and keep the declared test order within each
+    /// batch. A task with fewer groups of test cases than `n` yields fewer batches.
+    pub fn split_into_batches(&self, n: usize) -> Vec<JudgeTask> {
+        let groups = self.batch_groups();
+        let count = n.clamp(1, groups.len().max(1));
+        let (base, extra) = (groups.len() / count, groups.len() % count);
+
+        let mut groups = groups.into_iter();
+        let chunks: Vec<Vec<usize>> = (0..count)
+            .map(|i| {
+                let mut indices: Vec<usize> = groups
+                    .by_ref()
+                    .take(base + usize::from(i < extra))
+                    .flatten()
+                    .collect();
+                indices.sort_unstable();
+                indices
+            })
+            .collect();
+
+        chunks
+            .iter()
+            .enumerate()
+            .map(|(index, indices)| {
+                let mut batch = self.clone();
+                batch.test_cases = indices
+                    .iter()
+                    .map(|&i| self.test_cases[i].clone())
+                    .collect();
+                batch.subtasks = self
+                    .subtasks
+                    .iter()
+                    .filter(|s| s.test_case_ids.iter().any(|id| batch.has_test_case(id)))
+                    .cloned()
+                    .collect();
+                batch.batch = Some(BatchInfo {
+                    index,
+                    total: count,
+                    parent_task_id: self.submission.id,
+                    score_share: self.score_share(&batch),
+                    judge_mode: self.judge_mode,
+                });
+                batch
+            })
+            .collect()
+    }
+
+    fn has_test_case(&self, id: &str) -> bool {
+        self.test_cases.iter().any(|tc| tc.id == id)
+    }
+
+    /// Groups test case indices that must be judged together, in declared order
+    fn batch_groups(&self) -> Vec<Vec<usize>> {
+        // Union-find over test cases joined by subtasks and subtask dependencies
+        let mut parent: Vec<usize> = (0..self.test_cases.len()).collect();
+        fn find(parent: &mut [usize], i: usize) -> usize {
+            let mut root = i;
+            while parent[root] != root {
+                root = parent[root];
+            }
+            parent[i] = root;
+            root
+        }
+        let mut union = |a: usize, b: usize| {
+            let (a, b) = (find(&mut parent, a), find(&mut parent, b));
+            parent[a.max(b)] = a.min(b);
+        };
+
+        let index_of = |id: &str| self.test_cases.iter().position(|tc| tc.id == id);
+        let subtask_root =
+            |subtask: &Subtask| subtask.test_case_ids.iter().find_map(|id| index_of(id));
+        for subtask in &self.subtasks {
+            let Some(root) = subtask_root(subtask) else {
+                continue;
+            };
+            for i in subtask.test_case_ids.iter().filter_map(|id| index_of(id)) {
+                union(root, i);
+            }
+            for dependency in &subtask.depends_on {
+                if let Some(other) = self
+                    .subtasks
+                    .iter()
+                    .find(|s| &s.id == dependency)
+                    .and_then(subtask_root)
+                {
+                    union(root, other);
+                }
+            }
+        }
+
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        let mut group_of_root: Vec<Option<usize>> = vec![None; self.test_cases.len()];
+        for i in 0..self.test_cases.len() {
+            let root = find(&mut parent, i);
+            match group_of_root[root] {
+                Some(group) => groups[group].push(i),
+                None => {
+                    group_of_root[root] = Some(groups.len());
+                    groups.push(vec![i]);
+                }
+            }
+        }
+        groups
+    }
+
+    /// Returns the fraction of this task's score carried by a batch
+    fn score_share(&self, batch: &JudgeTask) -> f64 {
+        let (part, total) = if !self.subtasks.is_empty() {
+            (batch.subtask_score_total(), self.subtask_score_total())
+        } else {
+            (batch.total_weight(), self.total_weight())
+        };
+        if total > 0.0 {
+            part / total
+        } else if self.test_cases.is_empty() {
+            1.0
+        } else {
+            batch.test_cases.len() as f64 / self.test_cases.len() as f64
+        }
+    }
+}
+
+impl JudgeResult {
+    /// Recombines the results of every batch of a split task
+    ///
+    /// Test case results are concatenated by batch index, whatever order `results`
+    /// come in, and keep the declared order within each batch. Subtasks may put test
+    /// cases that aren't adjacent into one batch, so the merged order can differ from
+    /// the declared one. The overall status and error are those of the batch with the
+    /// most severe status (see [`JudgeStatus::severity`]), ties going to the lower
+    /// batch index, as [`JudgeResult::from_test_cases`] would pick them from all test
+    /// cases at once; time and memory are the maxima.
+    pub fn merge(results: Vec<JudgeResult>) -> Result<JudgeResult, MergeError> {
+        let mut batched = Vec::with_capacity(results.len());
+        for result in results {
+            let info = result
+                .batch
+                .clone()
+                .ok_or(MergeError::NotBatched(result.submission_id))?;
+            batched.push((info, result));
+        }
+        let Some((first, _)) = batched.first() else {
+            return Err(MergeError::Empty);
+        };
+        let (parent, total, mode) = (first.parent_task_id, first.total, first.judge_mode);
+        if batched
+            .iter()
+            .any(|(info, _)| info.parent_task_id != parent || info.total != total)
+        {
+            return Err(MergeError::Mismatched);
+        }
+        if let Some((info, _)) = batched.iter().find(|(info, _)| info.index >= total) {
+            return Err(MergeError::BatchOutOfRange {
+                index: info.index,
+                total,
+            });
+        }
+
+        batched.sort_by_key(|(info, _)| info.index);
+        for (expected, (info, _)) in batched.iter().enumerate() {
+            if info.index < expected {
+                return Err(MergeError::DuplicateBatch(info.index));
+            }
+            if info.index > expected {
+                return Err(MergeError::MissingBatch(expected));
+            }
+        }
+        if batched.len() < total {
+            return Err(MergeError::MissingBatch(batched.len()));
+        }
+
+        let all_accepted = batched.iter().all(|(_, r)| r.status.is_accepted());
+        let score = if !mode.scores_partially() {
+            if all_accepted { 100.0 } else { 0.0 }
+        } else {
+            batched
+                .iter()
+                .map(|(info, r)| info.score_share * r.score)
+                .sum()
+        };
+
+        let mut batched = batched.into_iter().map(|(_, r)| r);
+        let mut merged = batched.next().expect("at least one batch");
+        for result in batched {
+            merged.time_used = merged.time_used.max(result.time_used);
+            merged.memory_used = merged.memory_used.max(result.memory_used);
+            merged.judged_at = merged.judged_at.max(result.judged_at);
+            if result.status.severity() > merged.status.severity() {
+                merged.status = result.status;
+                merged.error_info = result.error_info;
+            }
+            merged.test_cases.extend(result.test_cases);
+        }
+        if all_accepted {
+            merged.status = JudgeStatus::Accepted;
+        }
+        merged.score = score;
+        merged.batch = None;
+        Ok(merged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use crate::{RuntimeErrorType, TestCaseResult};
+
+    fn task(count: usize) -> JudgeTask {
+        testing::task(testing::blank_test_cases(1..=count))
+    }
+
+    fn ids(task: &JudgeTask) -> Vec<&str> {
+        task.test_cases.iter().map(|tc| tc.id.as_str()).collect()
+    }
+
+    fn run(batch: &JudgeTask, failing: &[&str]) -> JudgeResult {
+        let submission = &batch.submission;
+        let mut result = JudgeResult::accepted(
+            10,
+            100,
+            submission.id,
+            submission.problem_id,
+            submission.user_id,
+        );
+        for tc in &batch.test_cases {
+            let status = if failing.contains(&tc.id.as_str()) {
+                JudgeStatus::RuntimeError(RuntimeErrorType::SegmentationFault)
+            } else {
+                JudgeStatus::Accepted
+            };
+            result.add_test_case(TestCaseResult {
+                id: tc.id.clone(),
+                status,
+                time_used: 0,
+                memory_used: 0,
+                input: None,
+                expected_output: None,
+                actual_output: None,
+                error_info: None,
+                is_hidden: false,
+                diff: None,
+            });
+            if !status.is_accepted() && result.status.is_accepted() {
+                result.status = status;
+            }
+        }
+        result.score = result.score_for(batch);
+        result.batch = batch.batch.clone();
+        result
+    }
+
+    #[test]
+    fn test_uneven_split() {
+        let task = task(7);
+        let batches = task.split_into_batches(3);
+        assert_eq!(batches.len(), 3);
+        assert_eq!(ids(&batches[0]), vec!["1", "2", "3"]);
+        assert_eq!(ids(&batches[1]), vec!["4", "5"]);
+        assert_eq!(ids(&batches[2]), vec!["6", "7"]);
+        for (i, batch) in batches.iter().enumerate() {
+            let info = batch.batch.as_ref().unwrap();
+            assert_eq!((info.index, info.total), (i, 3));
+            assert_eq!(info.parent_task_id, task.submission.id);
+            assert_eq!(batch.submission, task.submission);
+        }
+        assert_eq!(batches[0].batch.as_ref().unwrap().score_share, 3.0 / 7.0);
+    }
+
+    #[test]
+    fn test_single_test_case() {
+        let task = task(1);
+        let batches = task.split_into_batches(4);
+        assert_eq!(batches.len(), 1);
+        assert_eq!(ids(&batches[0]), vec!["1"]);
+        assert_eq!(task.split_into_batches(0).len(), 1);
+
+        let merged = JudgeResult::merge(vec![run(&batches[0], &[])]).unwrap();
+        assert_eq!(merged.status, JudgeStatus::Accepted);
+        assert_eq!(merged.score, 100.0);
+        assert_eq!(merged.batch, None);
+    }
+
+    #[test]
+    fn test_split_respects_subtasks() {
+        let mut task = task(5);
+        task.subtasks = vec![
+            Subtask {
+                id: "a".to_string(),
+                score: 30.0,
+                test_case_ids: vec!["1".to_string(), "4".to_string()],
+                depends_on: vec![],
+            },
+            Subtask {
+                id: "b".to_string(),
+                score: 70.0,
+                test_case_ids: vec!["3".to_string()],
+                depends_on: vec!["c".to_string()],
+            },
+            Subtask {
+                id: "c".to_string(),
+                score: 0.0,
+                test_case_ids: vec!["5".to_string()],
+                depends_on: vec![],
+            },
+        ];
+        let batches = task.split_into_batches(3);
+        assert_eq!(ids(&batches[0]), vec!["1", "4"]);
+        assert_eq!(ids(&batches[1]), vec!["2"]);
+        assert_eq!(ids(&batches[2]), vec!["3", "5"]);
+        assert_eq!(batches[2].subtasks.len(), 2);
+        assert_eq!(batches[2].batch.as_ref().unwrap().score_share, 0.7);
+
+        let merged =
+            JudgeResult::merge(batches.iter().rev().map(|batch| run(batch, &[])).collect())
+                .unwrap();
+        let ids: Vec<&str> = merged.test_cases.iter().map(|tc| tc.id.as_str()).collect();
+        assert_eq!(ids, vec!["1", "4", "2", "3", "5"]);
+    }
+
+    #[test]
+    fn test_merge_out_of_order() {
+        let mut task = task(6);
+        task.judge_mode = JudgeMode::OiPartial;
+        let batches = task.split_into_batches(3);
+        let results = vec![
+            run(&batches[2], &[]),
+            run(&batches[0], &[]),
+            run(&batches[1], &["4"]),
+        ];
+        let merged = JudgeResult::merge(results).unwrap();
+        let ids: Vec<&str> = merged.test_cases.iter().map(|tc| tc.id.as_str()).collect();
+        assert_eq!(ids, vec!["1", "2", "3", "4", "5", "6"]);
+        assert_eq!(
+            merged.status,
+            JudgeStatus::RuntimeError(RuntimeErrorType::SegmentationFault)
+        );
+        assert!((merged.score - 500.0 / 6.0).abs() < 1e-9);
+
+        // ACM scoring is all-or-nothing across batches
+        let mut acm = task.clone();
+        acm.judge_mode = JudgeMode::AcmIcpc;
+        let batches = acm.split_into_batches(2);
+        let merged =
+            JudgeResult::merge(vec![run(&batches[0], &[]), run(&batches[1], &["6"])]).unwrap();
+        assert_eq!(merged.score, 0.0);
+    }
+
+    #[test]
+    fn test_merge_picks_most_severe_status() {
+        let task = task(4);
+        let batches = task.split_into_batches(2);
+        let with_status = |batch: &JudgeTask, status: JudgeStatus| {
+            let mut result = run(batch, &[]);
+            result.test_cases[0].status = status;
+            result.status = status;
+            result.error_info = Some(crate::ErrorInfo::new(format!("{:?}", status)));
+            result
+        };
+        let wrong = JudgeStatus::WrongAnswer;
+        let crashed = JudgeStatus::RuntimeError(RuntimeErrorType::SegmentationFault);
+        for (first, second) in [(wrong, crashed), (crashed, wrong)] {
+            let cases = [
+                with_status(&batches[0], first),
+                with_status(&batches[1], second),
+            ];
+            let merged = JudgeResult::merge(cases.to_vec()).unwrap();
+            assert_eq!(merged.status, crashed);
+            assert_eq!(merged.error_info.unwrap().message, format!("{:?}", crashed));
+            let all: Vec<TestCaseResult> = cases.into_iter().flat_map(|r| r.test_cases).collect();
+            let direct =
+                JudgeResult::from_test_cases(&task.submission, all, &[], JudgeMode::AcmIcpc);
+            assert_eq!(direct.status, crashed);
+        }
+    }
+
+    #[test]
+    fn test_merge_errors() {
+        let task = task(4);
+        let batches = task.split_into_batches(3);
+        assert_eq!(
+            JudgeResult::merge(vec![run(&batches[0], &[]), run(&batches[2], &[])]),
+            Err(MergeError::MissingBatch(1))
+        );
+        assert_eq!(
+            JudgeResult::merge(vec![run(&batches[0], &[]), run(&batches[1], &[])]),
+            Err(MergeError::MissingBatch(2))
+        );
+        assert_eq!(
+            JudgeResult::merge(vec![
+                run(&batches[0], &[]),
+                run(&batches[0], &[]),
+                run(&batches[1], &[]),
+            ]),
+            Err(MergeError::DuplicateBatch(0))
+        );
+        assert_eq!(JudgeResult::merge(vec![]), Err(MergeError::Empty));
+
+        // Three results for two batches
+        let two = task.split_into_batches(2);
+        let mut extra = run(&two[1], &[]);
+        extra.batch.as_mut().unwrap().index = 2;
+        assert_eq!(
+            JudgeResult::merge(vec![run(&two[0], &[]), run(&two[1], &[]), extra]),
+            Err(MergeError::BatchOutOfRange { index: 2, total: 2 })
+        );
+
+        let mut unbatched = run(&batches[0], &[]);
+        unbatched.batch = None;
+        assert!(matches!(
+            JudgeResult::merge(vec![unbatched]),
+            Err(MergeError::NotBatched(_))
+        ));
+
+        let other = self::task(4).split_into_batches(3);
+        assert_eq!(
+            JudgeResult::merge(vec![
+                run(&batches[0], &[]),
+                run(&other[1], &[]),
+                run(&batches[2], &[]),
+            ]),
+            Err(MergeError::Mismatched)
+        );
+    }
+}
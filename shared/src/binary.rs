@@ -0,0 +1,286 @@
+//! Compact binary encoding of judge tasks and results
+//!
+//! Enabled by the `binary` feature. JSON is the default wire format, but a result
+//! with hundreds of test cases and their I/O is large and slow to encode; this is
+//! the alternative for peers that negotiate [`CONTENT_TYPE`].
+//!
+//! A payload is one header byte holding the format version followed by the body:
+//!
+//! * 1: MessagePack with named fields
+//!
+//! Fields are named so the compatibility rules of [`compat`](crate::compat) carry
+//! over unchanged: defaulted fields may be missing, unknown fields are ignored, and
+//! a too new `schema_version` is refused before the rest is decoded. UUIDs are
+//! encoded as 16 raw bytes and timestamps as RFC 3339 strings with nanoseconds, so
+//! both round-trip exactly.
+//!
+//! ```
+//! use oj_shared::JudgeResult;
+//! use uuid::Uuid;
+//!
+//! let result = JudgeResult::accepted(12, 1024, Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+//! let bytes = result.to_bytes();
+//! assert_eq!(JudgeResult::from_bytes(&bytes).unwrap(), result);
+//! ```
+
+use crate::compat::{SCHEMA_VERSION, check_compatibility, default_schema_version};
+use crate::{JudgeResult, JudgeTask};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Media type of binary payloads
+pub const CONTENT_TYPE: &str = "application/vnd.axon.judge+msgpack";
+
+/// Format version written in the header byte by this build
+pub const FORMAT_VERSION: u8 = 1;
+
+/// Why a binary payload can't be decoded
+#[derive(Debug)]
+pub enum BinaryError {
+    /// The payload has no header byte
+    Empty,
+    /// The header names a format this build can't read
+    UnsupportedFormat(u8),
+    /// The payload's schema version can't be read by this build
+    Incompatible { local: u32, remote: u32 },
+    /// The body is malformed
+    Decode(rmp_serde::decode::Error),
+}
+
+impl fmt::Display for BinaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BinaryError::Empty => write!(f, "binary payload is empty"),
+            BinaryError::UnsupportedFormat(version) => write!(
+                f,
+                "binary format version {} is not supported (this build reads {})",
+                version, FORMAT_VERSION
+            ),
+            BinaryError::Incompatible { local, remote } => write!(
+                f,
+                "payload schema version {} is not supported (this build reads up to {})",
+                remote, local
+            ),
+            BinaryError::Decode(e) => write!(f, "invalid binary payload: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for BinaryError {}
+
+/// Just the version of a payload; every other field is skipped
+#[derive(Deserialize)]
+struct SchemaVersion {
+    #[serde(default = "default_schema_version")]
+    schema_version: u32,
+}
+
+fn to_bytes<T: Serialize>(value: &T) -> Vec<u8> {
+    let mut bytes = vec![FORMAT_VERSION];
+    value
+        .serialize(&mut rmp_serde::Serializer::new(&mut bytes).with_struct_map())
+        .expect("judge payloads serialize to MessagePack");
+    bytes
+}
+
+fn from_bytes<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, BinaryError> {
+    let (&format, body) = bytes.split_first().ok_or(BinaryError::Empty)?;
+    if format != FORMAT_VERSION {
+        return Err(BinaryError::UnsupportedFormat(format));
+    }
+    let remote = rmp_serde::from_slice::<SchemaVersion>(body)
+        .map_err(BinaryError::Decode)?
+        .schema_version;
+    if !check_compatibility(SCHEMA_VERSION, remote).is_compatible() {
+        return Err(BinaryError::Incompatible {
+            local: SCHEMA_VERSION,
+            remote,
+        });
+    }
+    rmp_serde::from_slice(body).map_err(BinaryError::Decode)
+}
+
+impl JudgeTask {
+    /// Encodes the task as a binary payload
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+
+    /// Decodes a binary payload, checking its format and schema version first
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BinaryError> {
+        from_bytes(bytes)
+    }
+}
+
+impl JudgeResult {
+    /// Encodes the result as a binary payload
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(self)
+    }
+
+    /// Decodes a binary payload, checking its format and schema version first
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BinaryError> {
+        from_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use crate::{
+        ErrorInfo, JudgeMode, JudgeStatus, ProgrammingLanguage, RuntimeErrorType, Submission,
+        TestCase, TestCaseResult, TestData,
+    };
+    use chrono::{DateTime, TimeZone, Utc};
+    use std::path::PathBuf;
+    use uuid::Uuid;
+
+    fn submission() -> Submission {
+        let mut submission = testing::submission(ProgrammingLanguage::Cpp17, "int main() {}");
+        submission.created_at = Utc.timestamp_opt(1_700_000_000, 123_456_789).unwrap();
+        submission.contest_id = Some(Uuid::new_v4());
+        submission
+    }
+
+    #[test]
+    fn test_task_round_trip() {
+        let mut task = JudgeTask::new(
+            submission(),
+            vec![
+                TestCase::new("1".to_string(), "1 2\n", "3\n"),
+                TestCase::new(
+                    "2".to_string(),
+                    TestData::File(PathBuf::from("data/2.in")),
+                    TestData::Remote {
+                        url: "https://data.example.com/2.out".to_string(),
+                        sha256: "ab".repeat(32),
+                        size: 4,
+                    },
+                ),
+            ],
+        );
+        task.env = vec![("LANG".to_string(), "C".to_string())];
+        let bytes = task.to_bytes();
+        assert_eq!(bytes[0], FORMAT_VERSION);
+        let decoded = JudgeTask::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, task);
+        // Nanoseconds and UUIDs survive exactly
+        assert_eq!(decoded.submission.created_at, task.submission.created_at);
+        assert_eq!(decoded.submission.contest_id, task.submission.contest_id);
+    }
+
+    #[test]
+    fn test_result_round_trip() {
+        let submission = submission();
+        let mut error = ErrorInfo::killed_by_signal(11, Some("stack trace".to_string()));
+        error.hint = Some("Check array bounds".to_string());
+        let cases = vec![
+            TestCaseResult {
+                id: "1".to_string(),
+                status: JudgeStatus::Accepted,
+                time_used: 10,
+                memory_used: 1024,
+                input: Some("1 2\n".to_string()),
+                expected_output: Some("3\n".to_string()),
+                actual_output: Some("3\n".to_string()),
+                error_info: None,
+                is_hidden: false,
+                diff: None,
+            },
+            TestCaseResult {
+                id: "2".to_string(),
+                status: JudgeStatus::RuntimeError(RuntimeErrorType::SegmentationFault),
+                time_used: 20,
+                memory_used: 2048,
+                input: None,
+                expected_output: None,
+                actual_output: None,
+                error_info: Some(error),
+                is_hidden: true,
+                diff: None,
+            },
+        ];
+        let mut result =
+            JudgeResult::from_test_cases(&submission, cases, &[1.0, 1.0], JudgeMode::OiPartial);
+        result.judged_at = Utc.timestamp_opt(1_700_000_123, 999_999_999).unwrap();
+        let decoded = JudgeResult::from_bytes(&result.to_bytes()).unwrap();
+        assert_eq!(decoded, result);
+        assert_eq!(decoded.judged_at, result.judged_at);
+        assert_eq!(decoded.submission_id, submission.id);
+    }
+
+    #[test]
+    fn test_fields_missing_or_unknown() {
+        // A result as written before `schema_version`, `attempt` and `batch`
+        // existed, by a peer that also sends a field this build doesn't know
+        #[derive(Serialize)]
+        struct OldResult {
+            status: JudgeStatus,
+            time_used: u64,
+            memory_used: u64,
+            error_info: Option<ErrorInfo>,
+            test_cases: Vec<TestCaseResult>,
+            submission_id: Uuid,
+            problem_id: Uuid,
+            user_id: Uuid,
+            judged_at: DateTime<Utc>,
+            score: f64,
+            added_later: Vec<u32>,
+        }
+        let old = OldResult {
+            status: JudgeStatus::Accepted,
+            time_used: 12,
+            memory_used: 1024,
+            error_info: None,
+            test_cases: Vec::new(),
+            submission_id: Uuid::new_v4(),
+            problem_id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            judged_at: Utc.timestamp_opt(1_700_000_000, 1).unwrap(),
+            score: 100.0,
+            added_later: vec![1, 2, 3],
+        };
+        let result = JudgeResult::from_bytes(&to_bytes(&old)).unwrap();
+        assert_eq!(result.schema_version, 1);
+        assert_eq!(result.attempt, 1);
+        assert_eq!(result.batch, None);
+        assert_eq!(result.submission_id, old.submission_id);
+        assert_eq!(result.judged_at, old.judged_at);
+    }
+
+    #[test]
+    fn test_invalid_payloads() {
+        assert!(matches!(
+            JudgeTask::from_bytes(&[]),
+            Err(BinaryError::Empty)
+        ));
+
+        let mut bytes =
+            JudgeResult::accepted(1, 1, Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()).to_bytes();
+        bytes[0] = FORMAT_VERSION + 1;
+        assert!(matches!(
+            JudgeResult::from_bytes(&bytes),
+            Err(BinaryError::UnsupportedFormat(2))
+        ));
+
+        let mut result =
+            JudgeResult::accepted(1, 1, Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        result.schema_version = SCHEMA_VERSION + 1;
+        let error = JudgeResult::from_bytes(&result.to_bytes()).unwrap_err();
+        assert!(matches!(
+            error,
+            BinaryError::Incompatible {
+                local: SCHEMA_VERSION,
+                ..
+            }
+        ));
+        assert!(error.to_string().contains("not supported"));
+
+        assert!(matches!(
+            JudgeResult::from_bytes(&[FORMAT_VERSION, 0xc1]),
+            Err(BinaryError::Decode(_))
+        ));
+    }
+}
@@ -0,0 +1,290 @@
+use crate::{CustomLanguage, CustomLanguageError, ProgrammingLanguage, Submission};
+use chrono::{DateTime, Utc};
+use std::fmt;
+use uuid::Uuid;
+
+/// Time limit used when none is given, in milliseconds
+pub const DEFAULT_TIME_LIMIT_MS: u64 = 1000;
+/// Memory limit used when none is given, in kilobytes
+pub const DEFAULT_MEMORY_LIMIT_KB: u64 = 256 * 1024;
+/// Priority given to contest submissions unless set explicitly
+pub const CONTEST_PRIORITY: i32 = 10;
+/// Priority of rejudges, below all live traffic
+pub const REJUDGE_PRIORITY: i32 = -10;
+
+/// Invariant violated when building a [`Submission`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    /// The source code is empty or only whitespace
+    EmptySource,
+    /// The time limit is zero
+    ZeroTimeLimit,
+    /// The memory limit is zero
+    ZeroMemoryLimit,
+    /// The custom language spec is missing or invalid
+    InvalidCustomLanguage(CustomLanguageError),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::EmptySource => write!(f, "source code must not be empty"),
+            BuildError::ZeroTimeLimit => write!(f, "time limit must be positive"),
+            BuildError::ZeroMemoryLimit => write!(f, "memory limit must be positive"),
+            BuildError::InvalidCustomLanguage(e) => write!(f, "invalid custom language: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// Fluent builder for [`Submission`]
+///
+/// ```
+/// use oj_shared::{ProgrammingLanguage, SubmissionBuilder};
+/// use uuid::Uuid;
+///
+/// let submission = SubmissionBuilder::new(
+///     Uuid::new_v4(),
+///     Uuid::new_v4(),
+///     ProgrammingLanguage::Cpp17,
+///     "int main() {}",
+/// )
+/// .time_limit(2000)
+/// .priority(5)
+/// .build()
+/// .unwrap();
+/// assert_eq!(submission.priority, 5);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SubmissionBuilder {
+    id: Option<Uuid>,
+    problem_id: Uuid,
+    user_id: Uuid,
+    language: ProgrammingLanguage,
+    source_code: String,
+    created_at: Option<DateTime<Utc>>,
+    time_limit: u64,
+    memory_limit: u64,
+    priority: Option<i32>,
+    contest_id: Option<Uuid>,
+    custom_language: Option<CustomLanguage>,
+    output_limit: Option<u64>,
+    stack_limit: Option<u64>,
+}
+
+impl SubmissionBuilder {
+    /// Starts a submission with default limits and priority
+    pub fn new(
+        problem_id: Uuid,
+        user_id: Uuid,
+        language: ProgrammingLanguage,
+        source_code: impl Into<String>,
+    ) -> Self {
+        Self {
+            id: None,
+            problem_id,
+            user_id,
+            language,
+            source_code: source_code.into(),
+            created_at: None,
+            time_limit: DEFAULT_TIME_LIMIT_MS,
+            memory_limit: DEFAULT_MEMORY_LIMIT_KB,
+            priority: None,
+            contest_id: None,
+            custom_language: None,
+            output_limit: None,
+            stack_limit: None,
+        }
+    }
+
+    /// Uses a fixed id, e.g. when replaying a submission (random by default)
+    pub fn id(mut self, id: Uuid) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    /// Uses a fixed creation time, e.g. when importing historical data (now by default)
+    pub fn created_at(mut self, created_at: DateTime<Utc>) -> Self {
+        self.created_at = Some(created_at);
+        self
+    }
+
+    /// Sets the time limit in milliseconds
+    pub fn time_limit(mut self, time_limit: u64) -> Self {
+        self.time_limit = time_limit;
+        self
+    }
+
+    /// Sets the memory limit in kilobytes
+    pub fn memory_limit(mut self, memory_limit: u64) -> Self {
+        self.memory_limit = memory_limit;
+        self
+    }
+
+    /// Sets the output limit in bytes
+    pub fn output_limit(mut self, output_limit: u64) -> Self {
+        self.output_limit = Some(output_limit);
+        self
+    }
+
+    /// Sets the stack limit in kilobytes
+    pub fn stack_limit(mut self, stack_limit: u64) -> Self {
+        self.stack_limit = Some(stack_limit);
+        self
+    }
+
+    /// Sets the judge priority (higher numbers get processed first)
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Marks this as a contest submission
+    ///
+    /// Contest submissions get [`CONTEST_PRIORITY`] unless a priority is set explicitly.
+    pub fn contest(mut self, contest_id: Uuid) -> Self {
+        self.contest_id = Some(contest_id);
+        self
+    }
+
+    /// Uses a custom language toolchain, switching the language to [`ProgrammingLanguage::Custom`]
+    pub fn custom_language(mut self, spec: CustomLanguage) -> Self {
+        self.language = ProgrammingLanguage::Custom;
+        self.custom_language = Some(spec);
+        self
+    }
+
+    /// Checks the invariants and builds the submission
+    pub fn build(self) -> Result<Submission, BuildError> {
+        if self.source_code.trim().is_empty() {
+            return Err(BuildError::EmptySource);
+        }
+        if self.time_limit == 0 {
+            return Err(BuildError::ZeroTimeLimit);
+        }
+        if self.memory_limit == 0 {
+            return Err(BuildError::ZeroMemoryLimit);
+        }
+        let submission = self.build_unchecked();
+        submission
+            .validate_language()
+            .map_err(BuildError::InvalidCustomLanguage)?;
+        Ok(submission)
+    }
+
+    /// Builds the submission without checking invariants
+    ///
+    /// Only for the legacy [`Submission::new`] and [`Submission::for_contest`] constructors.
+    pub(crate) fn build_unchecked(self) -> Submission {
+        let default_priority = if self.contest_id.is_some() {
+            CONTEST_PRIORITY
+        } else {
+            0
+        };
+        Submission {
+            id: self.id.unwrap_or_else(Uuid::new_v4),
+            problem_id: self.problem_id,
+            user_id: self.user_id,
+            language: self.language,
+            source_code: self.source_code,
+            created_at: self.created_at.unwrap_or_else(Utc::now),
+            time_limit: self.time_limit,
+            memory_limit: self.memory_limit,
+            priority: self.priority.unwrap_or(default_priority),
+            contest_id: self.contest_id,
+            custom_language: self.custom_language,
+            rejudge_of: None,
+            attempt: 1,
+            output_limit: self.output_limit,
+            stack_limit: self.stack_limit,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn builder(source: &str) -> SubmissionBuilder {
+        SubmissionBuilder::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            ProgrammingLanguage::Python3,
+            source,
+        )
+    }
+
+    #[test]
+    fn test_defaults() {
+        let submission = builder("print(1)").build().unwrap();
+        assert_eq!(submission.time_limit, DEFAULT_TIME_LIMIT_MS);
+        assert_eq!(submission.memory_limit, DEFAULT_MEMORY_LIMIT_KB);
+        assert_eq!(submission.priority, 0);
+        assert_eq!(submission.contest_id, None);
+    }
+
+    #[test]
+    fn test_overrides() {
+        let id = Uuid::new_v4();
+        let contest_id = Uuid::new_v4();
+        let created_at = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+
+        let submission = builder("print(1)")
+            .id(id)
+            .created_at(created_at)
+            .contest(contest_id)
+            .build()
+            .unwrap();
+        assert_eq!(submission.id, id);
+        assert_eq!(submission.created_at, created_at);
+        assert_eq!(submission.contest_id, Some(contest_id));
+        assert_eq!(submission.priority, CONTEST_PRIORITY);
+
+        let submission = builder("print(1)")
+            .contest(contest_id)
+            .priority(-1)
+            .build()
+            .unwrap();
+        assert_eq!(submission.priority, -1);
+    }
+
+    #[test]
+    fn test_invariants() {
+        assert_eq!(builder("  \n").build(), Err(BuildError::EmptySource));
+        assert_eq!(
+            builder("print(1)").time_limit(0).build(),
+            Err(BuildError::ZeroTimeLimit)
+        );
+        assert_eq!(
+            builder("print(1)").memory_limit(0).build(),
+            Err(BuildError::ZeroMemoryLimit)
+        );
+
+        let mut submission = builder("print(1)");
+        submission.language = ProgrammingLanguage::Custom;
+        assert_eq!(
+            submission.build(),
+            Err(BuildError::InvalidCustomLanguage(
+                CustomLanguageError::MissingSpec
+            ))
+        );
+    }
+
+    #[test]
+    fn test_legacy_constructors_use_builder_defaults() {
+        let contest_id = Uuid::new_v4();
+        let submission = Submission::for_contest(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            contest_id,
+            ProgrammingLanguage::C,
+            String::new(),
+            1000,
+            65536,
+        );
+        assert_eq!(submission.priority, CONTEST_PRIORITY);
+        assert_eq!(submission.contest_id, Some(contest_id));
+    }
+}
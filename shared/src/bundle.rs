@@ -0,0 +1,308 @@
+//! Test case bundles as zip archives
+//!
+//! A bundle uses the same `N.in` / `N.out` + `meta.toml` convention as
+//! [`load_from_dir`](crate::testcases::load_from_dir), so a problem's test data can be
+//! uploaded and downloaded as a single file. The API works on `Read` / `Write`
+//! streams rather than paths so callers can pass request bodies or buffers.
+//!
+//! Exports are deterministic: entries are written in test case order with fixed
+//! timestamps and permissions, so the same test cases always produce the same bytes
+//! and the archive can be hashed.
+
+use crate::testcases::{self, LoadError, META_FILE, Meta};
+use crate::{TestCase, TestData};
+use std::fmt;
+use std::io::{self, Read, Seek, Write};
+use std::path::Component;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, DateTime, ZipArchive, ZipWriter};
+
+/// Default cap on the total uncompressed size of an imported bundle
+pub const DEFAULT_MAX_UNCOMPRESSED_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Errors raised while importing or exporting a bundle
+#[derive(Debug)]
+pub enum BundleError {
+    /// The archive is malformed
+    Zip(String),
+    /// Reading or writing the underlying stream failed
+    Io(io::Error),
+    /// An entry name or test case id would escape the bundle root
+    UnsafePath(String),
+    /// The uncompressed contents exceed the configured limit
+    TooLarge { limit: u64 },
+    /// An entry is not valid UTF-8
+    NotUtf8(String),
+    /// The entries don't form valid test cases
+    Load(LoadError),
+    /// A test case references remote data, which must be fetched before exporting
+    RemoteData(String),
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BundleError::Zip(e) => write!(f, "invalid zip archive: {}", e),
+            BundleError::Io(e) => write!(f, "bundle I/O failed: {}", e),
+            BundleError::UnsafePath(name) => write!(f, "unsafe path in bundle: {:?}", name),
+            BundleError::TooLarge { limit } => {
+                write!(f, "bundle exceeds {} bytes uncompressed", limit)
+            }
+            BundleError::NotUtf8(name) => write!(f, "{} is not valid UTF-8", name),
+            BundleError::Load(e) => write!(f, "{}", e),
+            BundleError::RemoteData(id) => {
+                write!(f, "test case {:?} references remote data", id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BundleError {}
+
+impl From<zip::result::ZipError> for BundleError {
+    fn from(e: zip::result::ZipError) -> Self {
+        match e {
+            zip::result::ZipError::Io(e) => BundleError::Io(e),
+            e => BundleError::Zip(e.to_string()),
+        }
+    }
+}
+
+impl From<io::Error> for BundleError {
+    fn from(e: io::Error) -> Self {
+        BundleError::Io(e)
+    }
+}
+
+/// Imports and exports test case bundles
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestCaseBundle {
+    /// Maximum total uncompressed size accepted on import, in bytes
+    pub max_uncompressed_bytes: u64,
+}
+
+impl Default for TestCaseBundle {
+    fn default() -> Self {
+        Self {
+            max_uncompressed_bytes: DEFAULT_MAX_UNCOMPRESSED_BYTES,
+        }
+    }
+}
+
+impl TestCaseBundle {
+    /// Reads the test cases of a zip bundle
+    ///
+    /// Entries may sit in subdirectories; only their file names are used, so the same
+    /// file name in two directories is a [`LoadError::DuplicateInput`] or
+    /// [`LoadError::DuplicateOutput`]. Entries whose path would escape the archive root
+    /// are rejected, and the size limit is enforced on the bytes actually decompressed
+    /// rather than on the sizes the archive declares.
+    pub fn import_zip<R: Read + Seek>(&self, reader: R) -> Result<Vec<TestCase>, BundleError> {
+        let mut archive = ZipArchive::new(reader)?;
+        let mut remaining = self.max_uncompressed_bytes;
+        let mut files = Vec::new();
+        let mut meta = None;
+
+        for index in 0..archive.len() {
+            let entry = archive.by_index(index)?;
+            let raw_name = entry.name().to_string();
+            let path = entry
+                .enclosed_name()
+                .ok_or_else(|| BundleError::UnsafePath(raw_name.clone()))?;
+            if entry.is_dir() {
+                continue;
+            }
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                return Err(BundleError::UnsafePath(raw_name));
+            };
+            let name = name.to_string();
+
+            let mut bytes = Vec::new();
+            entry.take(remaining + 1).read_to_end(&mut bytes)?;
+            if bytes.len() as u64 > remaining {
+                return Err(BundleError::TooLarge {
+                    limit: self.max_uncompressed_bytes,
+                });
+            }
+            remaining -= bytes.len() as u64;
+
+            let text = String::from_utf8(bytes).map_err(|_| BundleError::NotUtf8(raw_name))?;
+            if name == META_FILE {
+                meta = Some(text);
+            } else {
+                files.push((name, TestData::Inline(text)));
+            }
+        }
+
+        testcases::assemble(files, meta.as_deref()).map_err(BundleError::Load)
+    }
+
+    /// Writes test cases as a zip bundle
+    ///
+    /// Inline and file-backed data are supported; remote data must be fetched first.
+    /// A `meta.toml` is only written when some test case differs from the defaults.
+    pub fn export_zip<W: Write + Seek>(
+        test_cases: &[TestCase],
+        writer: W,
+    ) -> Result<W, BundleError> {
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .last_modified_time(DateTime::default())
+            .unix_permissions(0o644);
+        let mut zip = ZipWriter::new(writer);
+
+        for test_case in test_cases {
+            if !is_plain_name(&test_case.id) {
+                return Err(BundleError::UnsafePath(test_case.id.clone()));
+            }
+            for (extension, data) in [
+                ("in", &test_case.input),
+                ("out", &test_case.expected_output),
+            ] {
+                if matches!(data, TestData::Remote { .. }) {
+                    return Err(BundleError::RemoteData(test_case.id.clone()));
+                }
+                zip.start_file(format!("{}.{}", test_case.id, extension), options)?;
+                io::copy(&mut data.open_reader()?, &mut zip)?;
+            }
+        }
+
+        let meta = Meta::from_test_cases(test_cases);
+        if !meta.cases.is_empty() {
+            let meta = toml::to_string(&meta).map_err(|e| BundleError::Zip(e.to_string()))?;
+            zip.start_file(META_FILE, options)?;
+            zip.write_all(meta.as_bytes())?;
+        }
+
+        Ok(zip.finish()?)
+    }
+}
+
+/// Returns whether a test case id can be used as a file name as-is
+fn is_plain_name(id: &str) -> bool {
+    let mut components = std::path::Path::new(id).components();
+    !id.contains(['/', '\\'])
+        && matches!(components.next(), Some(Component::Normal(_)))
+        && components.next().is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn test_cases() -> Vec<TestCase> {
+        let mut hidden = TestCase::hidden("2".to_string(), "2 3\n", "5\n");
+        hidden.weight = 2.5;
+        vec![TestCase::new("1".to_string(), "1 2\n", "3\n"), hidden]
+    }
+
+    fn export(test_cases: &[TestCase]) -> Vec<u8> {
+        TestCaseBundle::export_zip(test_cases, Cursor::new(Vec::new()))
+            .unwrap()
+            .into_inner()
+    }
+
+    fn raw_zip(entries: &[(&str, &str)]) -> Vec<u8> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, contents) in entries {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_round_trip_preserves_metadata() {
+        let bytes = export(&test_cases());
+        let imported = TestCaseBundle::default()
+            .import_zip(Cursor::new(bytes))
+            .unwrap();
+
+        assert_eq!(imported.len(), 2);
+        assert_eq!(imported[0].input.as_inline(), Some("1 2\n"));
+        assert_eq!(imported[0].expected_output.as_inline(), Some("3\n"));
+        assert!(!imported[0].is_hidden);
+        assert_eq!(imported[1].id, "2");
+        assert!(imported[1].is_hidden);
+        assert_eq!(imported[1].weight, 2.5);
+    }
+
+    #[test]
+    fn test_export_is_deterministic() {
+        assert_eq!(export(&test_cases()), export(&test_cases()));
+    }
+
+    #[test]
+    fn test_import_nested_entries() {
+        let bytes = raw_zip(&[("tests/", ""), ("tests/1.in", "x"), ("tests/1.ans", "y")]);
+        let imported = TestCaseBundle::default()
+            .import_zip(Cursor::new(bytes))
+            .unwrap();
+        assert_eq!(imported.len(), 1);
+        assert_eq!(imported[0].expected_output.as_inline(), Some("y"));
+
+        let bytes = raw_zip(&[
+            ("a/1.in", "x"),
+            ("a/1.out", "y"),
+            ("b/1.in", "z"),
+            ("b/1.out", "w"),
+        ]);
+        assert!(matches!(
+            TestCaseBundle::default().import_zip(Cursor::new(bytes)),
+            Err(BundleError::Load(LoadError::DuplicateInput(stem))) if stem == "1"
+        ));
+    }
+
+    #[test]
+    fn test_rejects_zip_slip() {
+        let bytes = raw_zip(&[("../1.in", "x"), ("1.out", "y")]);
+        assert!(matches!(
+            TestCaseBundle::default().import_zip(Cursor::new(bytes)),
+            Err(BundleError::UnsafePath(name)) if name == "../1.in"
+        ));
+
+        let unsafe_id = TestCase::new("../1".to_string(), "x", "y");
+        assert!(matches!(
+            TestCaseBundle::export_zip(&[unsafe_id], Cursor::new(Vec::new())),
+            Err(BundleError::UnsafePath(_))
+        ));
+    }
+
+    #[test]
+    fn test_size_limit() {
+        let bytes = raw_zip(&[("1.in", "12345"), ("1.out", "67890")]);
+        let bundle = TestCaseBundle {
+            max_uncompressed_bytes: 10,
+        };
+        assert!(bundle.import_zip(Cursor::new(bytes.clone())).is_ok());
+
+        let bundle = TestCaseBundle {
+            max_uncompressed_bytes: 9,
+        };
+        assert!(matches!(
+            bundle.import_zip(Cursor::new(bytes)),
+            Err(BundleError::TooLarge { limit: 9 })
+        ));
+    }
+
+    #[test]
+    fn test_load_errors_and_remote_data() {
+        let bytes = raw_zip(&[("1.in", "x")]);
+        assert!(matches!(
+            TestCaseBundle::default().import_zip(Cursor::new(bytes)),
+            Err(BundleError::Load(LoadError::MissingOutput(_)))
+        ));
+
+        let remote = TestData::Remote {
+            url: "https://example.com/1.in".to_string(),
+            sha256: "0".repeat(64),
+            size: 1,
+        };
+        let test_case = TestCase::new("1".to_string(), remote, "y");
+        assert!(matches!(
+            TestCaseBundle::export_zip(&[test_case], Cursor::new(Vec::new())),
+            Err(BundleError::RemoteData(id)) if id == "1"
+        ));
+    }
+}
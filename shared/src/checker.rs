@@ -0,0 +1,330 @@
+//! Output checkers (special judges)
+//!
+//! Problems with more than one correct answer can't be judged by string equality.
+//! A task names its [`Checker`]; custom checkers follow the testlib convention: they
+//! are run as `checker <input> <contestant output> <expected output>` and report the
+//! verdict through their exit code.
+//!
+//! Legacy datasets may list several acceptable outputs per test case. Output matching
+//! any of them (under the test case's comparison mode) is accepted before the checker
+//! is consulted.
+
+use crate::{ComparisonMode, JudgeStatus, OutputVerdict, ProgrammingLanguage};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How contestant output is checked against the expected output
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum Checker {
+    /// Exact comparison, ignoring trailing whitespace at line ends and end of file
+    #[default]
+    DefaultDiff,
+    /// Compares whitespace-separated tokens
+    TokenCompare,
+    /// Compares tokens, treating numbers within `epsilon` (absolute or relative) as equal
+    FloatCompare { epsilon: f64 },
+    /// A testlib-style checker program
+    Custom {
+        language: ProgrammingLanguage,
+        source: String,
+    },
+}
+
+/// Why a checker configuration is rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum CheckerError {
+    /// The custom checker has no source code
+    EmptySource,
+    /// Custom checkers must use a built-in language
+    UnsupportedLanguage(ProgrammingLanguage),
+    /// The float tolerance is negative or not finite
+    InvalidEpsilon(f64),
+}
+
+impl fmt::Display for CheckerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckerError::EmptySource => write!(f, "custom checker source must not be empty"),
+            CheckerError::UnsupportedLanguage(language) => {
+                write!(
+                    f,
+                    "custom checkers cannot be written in {}",
+                    language.as_str()
+                )
+            }
+            CheckerError::InvalidEpsilon(epsilon) => {
+                write!(
+                    f,
+                    "checker epsilon must be finite and non-negative, got {}",
+                    epsilon
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for CheckerError {}
+
+/// Outcome of checking an output without running a program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputCheck {
+    /// The output is correct
+    Accepted,
+    /// The output is wrong
+    WrongAnswer,
+    /// The output has the right tokens but differs in whitespace
+    PresentationError,
+    /// No accepted output matched; the custom checker must decide
+    NeedsChecker,
+}
+
+impl Checker {
+    /// Returns whether the checker is a program that must be compiled and run
+    pub fn is_custom(&self) -> bool {
+        matches!(self, Checker::Custom { .. })
+    }
+
+    /// Checks that the checker can be used
+    pub fn validate(&self) -> Result<(), CheckerError> {
+        match self {
+            Checker::DefaultDiff | Checker::TokenCompare => Ok(()),
+            Checker::FloatCompare { epsilon } => {
+                if epsilon.is_finite() && *epsilon >= 0.0 {
+                    Ok(())
+                } else {
+                    Err(CheckerError::InvalidEpsilon(*epsilon))
+                }
+            }
+            Checker::Custom { language, source } => {
+                if *language == ProgrammingLanguage::Custom {
+                    Err(CheckerError::UnsupportedLanguage(*language))
+                } else if source.trim().is_empty() {
+                    Err(CheckerError::EmptySource)
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    /// Returns the comparison mode of a built-in checker, `None` for [`Checker::Custom`]
+    pub fn comparison_mode(&self) -> Option<ComparisonMode> {
+        match *self {
+            Checker::DefaultDiff => Some(ComparisonMode::TrimWhitespace),
+            Checker::TokenCompare => Some(ComparisonMode::TokenEquality),
+            Checker::FloatCompare { epsilon } => Some(ComparisonMode::FloatEpsilon {
+                abs: epsilon,
+                rel: epsilon,
+            }),
+            Checker::Custom { .. } => None,
+        }
+    }
+
+    /// Compares an output with one expected output using a built-in checker
+    ///
+    /// Returns `None` for [`Checker::Custom`], which must be run instead.
+    pub fn compare(&self, expected: &str, actual: &str) -> Option<bool> {
+        self.comparison_mode()
+            .map(|mode| mode.matches(expected, actual))
+    }
+
+    /// Checks an output against the accepted outputs of a test case
+    ///
+    /// `expected` holds the primary expected output first, then the alternatives
+    /// (see [`TestCase::expected_outputs`](crate::TestCase::expected_outputs)), and
+    /// `mode` is the test case's resolved comparison mode (see
+    /// [`JudgeTask::comparison_for`](crate::JudgeTask::comparison_for)). A match with any
+    /// of them under `mode` accepts; otherwise a custom checker must decide. For
+    /// built-in checkers a rejected output with the tokens of any expected output is a
+    /// presentation error.
+    pub fn check_output(
+        &self,
+        mode: ComparisonMode,
+        expected: &[&str],
+        actual: &str,
+    ) -> OutputCheck {
+        let verdict = expected
+            .iter()
+            .map(|e| mode.classify(e, actual))
+            .min()
+            .unwrap_or_else(|| mode.classify("", actual));
+        match verdict {
+            OutputVerdict::Match => OutputCheck::Accepted,
+            _ if self.is_custom() => OutputCheck::NeedsChecker,
+            OutputVerdict::PresentationOnly => OutputCheck::PresentationError,
+            OutputVerdict::Mismatch => OutputCheck::WrongAnswer,
+        }
+    }
+
+    /// Maps a custom checker's exit code to a test case verdict
+    ///
+    /// Follows testlib: 0 accepts, 1 is a wrong answer and 2 a presentation error,
+    /// anything else means the checker itself failed.
+    pub fn verdict_from_exit_code(code: i32) -> JudgeStatus {
+        match code {
+            0 => JudgeStatus::Accepted,
+            1 => JudgeStatus::WrongAnswer,
+            2 => JudgeStatus::PresentationError,
+            _ => JudgeStatus::SystemError,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(Checker::default(), Checker::DefaultDiff);
+        assert_eq!(Checker::TokenCompare.validate(), Ok(()));
+        assert_eq!(Checker::FloatCompare { epsilon: 1e-6 }.validate(), Ok(()));
+        assert_eq!(
+            Checker::FloatCompare { epsilon: -1.0 }.validate(),
+            Err(CheckerError::InvalidEpsilon(-1.0))
+        );
+        assert!(
+            Checker::FloatCompare { epsilon: f64::NAN }
+                .validate()
+                .is_err()
+        );
+
+        let custom = Checker::Custom {
+            language: ProgrammingLanguage::Cpp17,
+            source: "#include \"testlib.h\"\nint main(int argc, char* argv[]) {}\n".to_string(),
+        };
+        assert!(custom.is_custom());
+        assert!(!Checker::DefaultDiff.is_custom());
+        assert_eq!(custom.validate(), Ok(()));
+
+        let empty = Checker::Custom {
+            language: ProgrammingLanguage::Cpp17,
+            source: "\n".to_string(),
+        };
+        assert_eq!(empty.validate(), Err(CheckerError::EmptySource));
+        let custom_language = Checker::Custom {
+            language: ProgrammingLanguage::Custom,
+            source: "x".to_string(),
+        };
+        assert_eq!(
+            custom_language.validate(),
+            Err(CheckerError::UnsupportedLanguage(
+                ProgrammingLanguage::Custom
+            ))
+        );
+    }
+
+    #[test]
+    fn test_builtin_compare() {
+        assert_eq!(
+            Checker::DefaultDiff.compare("1 2\n3\n", "1 2  \r\n3\n\n"),
+            Some(true)
+        );
+        assert_eq!(Checker::DefaultDiff.compare("1 2\n", "1  2\n"), Some(false));
+        assert_eq!(Checker::TokenCompare.compare("1 2\n", "1  2"), Some(true));
+        assert_eq!(Checker::TokenCompare.compare("1 2", "1 2 3"), Some(false));
+
+        let float = Checker::FloatCompare { epsilon: 1e-6 };
+        assert_eq!(float.compare("0.3333333 YES", "0.33333334 YES"), Some(true));
+        assert_eq!(float.compare("1000000", "1000000.5"), Some(true));
+        assert_eq!(float.compare("0.5", "0.51"), Some(false));
+        assert_eq!(float.compare("nan", "NaN"), Some(false));
+
+        let custom = Checker::Custom {
+            language: ProgrammingLanguage::Cpp17,
+            source: "int main() {}".to_string(),
+        };
+        assert_eq!(custom.compare("1", "1"), None);
+    }
+
+    #[test]
+    fn test_check_output_with_alternatives() {
+        let expected = ["1 2 3\n", "3 2 1\n"];
+        let trim = ComparisonMode::TrimWhitespace;
+        assert_eq!(
+            Checker::DefaultDiff.check_output(trim, &expected, "3 2 1\n"),
+            OutputCheck::Accepted
+        );
+        assert_eq!(
+            Checker::DefaultDiff.check_output(trim, &expected, "2 1 3\n"),
+            OutputCheck::WrongAnswer
+        );
+        // Right tokens of an alternative, wrong line breaks
+        assert_eq!(
+            Checker::DefaultDiff.check_output(trim, &expected, "3\n2\n1\n"),
+            OutputCheck::PresentationError
+        );
+        assert_eq!(
+            Checker::TokenCompare.check_output(ComparisonMode::TokenEquality, &expected, "3\n2\n1"),
+            OutputCheck::Accepted
+        );
+
+        let custom = Checker::Custom {
+            language: ProgrammingLanguage::Cpp17,
+            source: "int main() {}".to_string(),
+        };
+        assert_eq!(
+            custom.check_output(trim, &expected, "3 2 1"),
+            OutputCheck::Accepted
+        );
+        assert_eq!(
+            custom.check_output(trim, &expected, "2 1 3"),
+            OutputCheck::NeedsChecker
+        );
+        assert_eq!(
+            custom.check_output(trim, &expected, "3\n2\n1"),
+            OutputCheck::NeedsChecker
+        );
+    }
+
+    #[test]
+    fn test_check_output_respects_exact_mode() {
+        let expected = ["1 2 3\n", "3 2 1\n"];
+        for checker in [Checker::DefaultDiff, Checker::TokenCompare] {
+            assert_eq!(
+                checker.check_output(ComparisonMode::Exact, &expected, "3 2 1\n"),
+                OutputCheck::Accepted
+            );
+            // Would match after trimming, but the test case asks for exact output
+            assert_eq!(
+                checker.check_output(ComparisonMode::Exact, &expected, "3 2 1  \n"),
+                OutputCheck::PresentationError
+            );
+        }
+        let custom = Checker::Custom {
+            language: ProgrammingLanguage::Cpp17,
+            source: "int main() {}".to_string(),
+        };
+        assert_eq!(
+            custom.check_output(ComparisonMode::Exact, &expected, "1 2 3\n\n"),
+            OutputCheck::NeedsChecker
+        );
+    }
+
+    #[test]
+    fn test_verdict_from_exit_code() {
+        assert_eq!(Checker::verdict_from_exit_code(0), JudgeStatus::Accepted);
+        assert_eq!(Checker::verdict_from_exit_code(1), JudgeStatus::WrongAnswer);
+        assert_eq!(
+            Checker::verdict_from_exit_code(2),
+            JudgeStatus::PresentationError
+        );
+        assert_eq!(Checker::verdict_from_exit_code(3), JudgeStatus::SystemError);
+        assert_eq!(
+            Checker::verdict_from_exit_code(-1),
+            JudgeStatus::SystemError
+        );
+    }
+
+    #[test]
+    fn test_serialization() {
+        let checker = Checker::FloatCompare { epsilon: 1e-9 };
+        let json = serde_json::to_string(&checker).unwrap();
+        assert_eq!(json, r#"{"FloatCompare":{"epsilon":1e-9}}"#);
+        assert_eq!(serde_json::from_str::<Checker>(&json).unwrap(), checker);
+        assert_eq!(
+            serde_json::from_str::<Checker>(r#""TokenCompare""#).unwrap(),
+            Checker::TokenCompare
+        );
+    }
+}
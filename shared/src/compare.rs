@@ -0,0 +1,579 @@
+//! Per-test-case output comparison
+//!
+//! Most test cases tolerate trailing whitespace, some need the output byte for byte,
+//! and a few need a floating point tolerance. A [`TestCase`] may name its
+//! [`ComparisonMode`]; otherwise the task-level default applies, and failing that the
+//! mode implied by the task's built-in [`Checker`](crate::Checker).
+//!
+//! When an output doesn't match, [`first_difference`] locates where it goes wrong so
+//! a wrong answer can be reported as "expected `12`, got `13` at line 4", and
+//! [`ComparisonMode::classify`] tells a presentation error (right tokens, wrong
+//! spacing) from a wrong answer.
+
+use crate::{CheckerError, JudgeStatus, JudgeTask, TestCase};
+use serde::{Deserialize, Serialize};
+
+/// Default maximum length of an [`OutputDiff`] fragment in characters
+pub const DEFAULT_DIFF_FRAGMENT_CHARS: usize = 64;
+
+/// Where an output first differs from the expected output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputDiff {
+    /// 1-based line in the actual output
+    pub line: u64,
+    /// 1-based column (in characters) in the actual output
+    pub column: u64,
+    /// Expected text at that point
+    pub expected_fragment: String,
+    /// Actual text at that point, empty if the output ended early
+    pub actual_fragment: String,
+}
+
+/// How an output compares with the expected output
+///
+/// Ordered from best to worst, so the best verdict over several accepted outputs
+/// is the minimum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OutputVerdict {
+    /// The output matches
+    Match,
+    /// The tokens match but whitespace or line breaks differ
+    PresentationOnly,
+    /// The output is wrong
+    Mismatch,
+}
+
+impl OutputVerdict {
+    /// Returns the test case status for this verdict
+    pub fn status(&self) -> JudgeStatus {
+        match self {
+            OutputVerdict::Match => JudgeStatus::Accepted,
+            OutputVerdict::PresentationOnly => JudgeStatus::PresentationError,
+            OutputVerdict::Mismatch => JudgeStatus::WrongAnswer,
+        }
+    }
+}
+
+/// How the output of one test case is compared with the expected output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum ComparisonMode {
+    /// Byte-for-byte equality
+    Exact,
+    /// Equality after trimming whitespace at line ends and trailing blank lines
+    #[default]
+    TrimWhitespace,
+    /// Equality of the whitespace-separated tokens
+    TokenEquality,
+    /// Token equality, treating numbers within `abs` or `rel` (relative to the
+    /// expected value) as equal
+    FloatEpsilon { abs: f64, rel: f64 },
+}
+
+impl ComparisonMode {
+    /// Checks that the tolerances are usable
+    pub fn validate(&self) -> Result<(), CheckerError> {
+        if let ComparisonMode::FloatEpsilon { abs, rel } = *self {
+            for epsilon in [abs, rel] {
+                if !epsilon.is_finite() || epsilon < 0.0 {
+                    return Err(CheckerError::InvalidEpsilon(epsilon));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns whether `actual` matches `expected` under this mode
+    pub fn matches(&self, expected: &str, actual: &str) -> bool {
+        match *self {
+            ComparisonMode::Exact => expected == actual,
+            ComparisonMode::TrimWhitespace => trimmed_lines_eq(expected, actual),
+            ComparisonMode::TokenEquality => {
+                expected.split_whitespace().eq(actual.split_whitespace())
+            }
+            ComparisonMode::FloatEpsilon { abs, rel } => {
+                let mut expected = expected.split_whitespace();
+                let mut actual = actual.split_whitespace();
+                loop {
+                    match (expected.next(), actual.next()) {
+                        (None, None) => return true,
+                        (Some(e), Some(a)) if float_token_eq(e, a, abs, rel) => {}
+                        _ => return false,
+                    }
+                }
+            }
+        }
+    }
+
+    /// Classifies `actual` against `expected` under this mode
+    ///
+    /// An output that doesn't match but has the same whitespace-separated tokens is
+    /// [`OutputVerdict::PresentationOnly`]. Token-based modes ignore whitespace, so
+    /// they never report it.
+    pub fn classify(&self, expected: &str, actual: &str) -> OutputVerdict {
+        if self.matches(expected, actual) {
+            OutputVerdict::Match
+        } else if expected.split_whitespace().eq(actual.split_whitespace()) {
+            OutputVerdict::PresentationOnly
+        } else {
+            OutputVerdict::Mismatch
+        }
+    }
+}
+
+impl TestCase {
+    /// Returns the effective comparison mode (custom, task default, or
+    /// [`ComparisonMode::TrimWhitespace`])
+    pub fn effective_comparison(&self, task_default: Option<ComparisonMode>) -> ComparisonMode {
+        self.comparison.or(task_default).unwrap_or_default()
+    }
+}
+
+impl JudgeTask {
+    /// Returns the comparison mode of a test case
+    ///
+    /// Falls back to [`JudgeTask::default_comparison`], then to the mode of the task's
+    /// built-in checker. Custom checkers decide on their own; the mode only governs
+    /// the pre-check against the accepted outputs.
+    pub fn comparison_for(&self, test_case: &TestCase) -> ComparisonMode {
+        let task_default = self
+            .default_comparison
+            .or_else(|| self.checker.comparison_mode());
+        test_case.effective_comparison(task_default)
+    }
+
+    /// Checks the task default and every per-test-case comparison mode
+    pub fn validate_comparisons(&self) -> Result<(), CheckerError> {
+        self.default_comparison
+            .iter()
+            .chain(
+                self.test_cases
+                    .iter()
+                    .filter_map(|tc| tc.comparison.as_ref()),
+            )
+            .try_for_each(ComparisonMode::validate)
+    }
+}
+
+/// Classifies `actual` against `expected` under the default
+/// [`ComparisonMode::TrimWhitespace`]
+///
+/// Trailing whitespace at line ends and trailing blank lines are tolerated; any other
+/// whitespace difference is [`OutputVerdict::PresentationOnly`].
+pub fn classify(expected: &str, actual: &str) -> OutputVerdict {
+    ComparisonMode::default().classify(expected, actual)
+}
+
+/// Locates the first difference between `expected` and `actual` under `mode`
+///
+/// Returns `None` if the outputs match. Line-based modes report the rest of the
+/// first differing line from the token where it diverges; token-based modes report
+/// the first differing token. Fragments are cut to `max_fragment_chars` characters.
+pub fn first_difference(
+    expected: &str,
+    actual: &str,
+    mode: ComparisonMode,
+    max_fragment_chars: usize,
+) -> Option<OutputDiff> {
+    if mode.matches(expected, actual) {
+        return None;
+    }
+    let (line, column, expected_fragment, actual_fragment) = match mode {
+        ComparisonMode::Exact => {
+            let expected: Vec<&str> = expected.split_inclusive('\n').collect();
+            let actual: Vec<&str> = actual.split_inclusive('\n').collect();
+            line_difference(&expected, &actual)?
+        }
+        ComparisonMode::TrimWhitespace => {
+            line_difference(&trimmed_lines(expected), &trimmed_lines(actual))?
+        }
+        ComparisonMode::TokenEquality => token_difference(expected, actual, |e, a| e == a)?,
+        ComparisonMode::FloatEpsilon { abs, rel } => {
+            token_difference(expected, actual, |e, a| float_token_eq(e, a, abs, rel))?
+        }
+    };
+    Some(OutputDiff {
+        line,
+        column,
+        expected_fragment: cap_fragment(expected_fragment, max_fragment_chars),
+        actual_fragment: cap_fragment(actual_fragment, max_fragment_chars),
+    })
+}
+
+/// Finds the first differing line and the column where it diverges
+fn line_difference<'a>(
+    expected: &[&'a str],
+    actual: &[&'a str],
+) -> Option<(u64, u64, &'a str, &'a str)> {
+    let index =
+        (0..expected.len().max(actual.len())).find(|&i| expected.get(i) != actual.get(i))?;
+    let expected_line = expected.get(index).copied().unwrap_or_default();
+    let actual_line = actual.get(index).copied().unwrap_or_default();
+
+    let mut start = expected_line
+        .char_indices()
+        .zip(actual_line.chars())
+        .find(|((_, e), a)| e != a)
+        .map_or_else(
+            || expected_line.len().min(actual_line.len()),
+            |((i, _), _)| i,
+        );
+    // Back up to the start of the token, so "12" vs "13" reports both numbers
+    let starts_token = |line: &str| line[start..].starts_with(|c: char| !c.is_whitespace());
+    if starts_token(expected_line) && starts_token(actual_line) {
+        start = expected_line[..start]
+            .rfind(char::is_whitespace)
+            .map_or(0, |i| i + 1);
+    }
+    let column = expected_line[..start].chars().count() as u64 + 1;
+    Some((
+        index as u64 + 1,
+        column,
+        &expected_line[start..],
+        &actual_line[start..],
+    ))
+}
+
+/// Finds the first differing token, located in the actual output
+fn token_difference<'a>(
+    expected: &'a str,
+    actual: &'a str,
+    eq: impl Fn(&str, &str) -> bool,
+) -> Option<(u64, u64, &'a str, &'a str)> {
+    let mut expected_tokens = expected.split_whitespace();
+    let mut actual_tokens = tokens_with_position(actual);
+    loop {
+        match (expected_tokens.next(), actual_tokens.next()) {
+            (None, None) => return None,
+            (Some(e), Some((_, _, a))) if eq(e, a) => {}
+            (e, Some((line, column, a))) => {
+                return Some((line, column, e.unwrap_or_default(), a));
+            }
+            (Some(e), None) => {
+                let (line, column) = end_position(actual);
+                return Some((line, column, e, ""));
+            }
+        }
+    }
+}
+
+/// Yields the whitespace-separated tokens with their 1-based line and column
+fn tokens_with_position(text: &str) -> impl Iterator<Item = (u64, u64, &str)> {
+    text.lines().enumerate().flat_map(|(index, line)| {
+        let mut column = 0;
+        let mut rest = line;
+        std::iter::from_fn(move || {
+            let skipped = rest.len() - rest.trim_start().len();
+            column += rest[..skipped].chars().count();
+            rest = &rest[skipped..];
+            if rest.is_empty() {
+                return None;
+            }
+            let len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let token = &rest[..len];
+            let token_column = column as u64 + 1;
+            column += token.chars().count();
+            rest = &rest[len..];
+            Some((index as u64 + 1, token_column, token))
+        })
+    })
+}
+
+/// Position just after the last character of `text`
+fn end_position(text: &str) -> (u64, u64) {
+    let line = text.matches('\n').count() as u64 + 1;
+    let last_line = text.rsplit('\n').next().unwrap_or_default();
+    (line, last_line.chars().count() as u64 + 1)
+}
+
+fn cap_fragment(fragment: &str, max_chars: usize) -> String {
+    match fragment.char_indices().nth(max_chars) {
+        Some((cut, _)) => format!("{}…", &fragment[..cut]),
+        None => fragment.to_string(),
+    }
+}
+
+/// Lines without trailing whitespace, without trailing blank lines
+fn trimmed_lines(s: &str) -> Vec<&str> {
+    let mut lines: Vec<&str> = s.lines().map(str::trim_end).collect();
+    while lines.last() == Some(&"") {
+        lines.pop();
+    }
+    lines
+}
+
+/// Compares line by line, ignoring trailing whitespace and trailing blank lines
+pub(crate) fn trimmed_lines_eq(expected: &str, actual: &str) -> bool {
+    trimmed_lines(expected) == trimmed_lines(actual)
+}
+
+/// Compares tokens exactly, or as numbers within an absolute or relative tolerance
+fn float_token_eq(expected: &str, actual: &str, abs: f64, rel: f64) -> bool {
+    if expected == actual {
+        return true;
+    }
+    match (expected.parse::<f64>(), actual.parse::<f64>()) {
+        (Ok(e), Ok(a)) if e.is_finite() && a.is_finite() => {
+            let diff = (e - a).abs();
+            diff <= abs || diff <= rel * e.abs()
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Checker;
+    use crate::testing::task;
+
+    #[test]
+    fn test_matches() {
+        assert!(ComparisonMode::Exact.matches("1 2\n", "1 2\n"));
+        assert!(!ComparisonMode::Exact.matches("1 2\n", "1 2 \n"));
+        assert!(ComparisonMode::TrimWhitespace.matches("1 2\n3\n", "1 2  \r\n3\n\n"));
+        assert!(!ComparisonMode::TrimWhitespace.matches("1 2\n", "1  2\n"));
+        assert!(ComparisonMode::TokenEquality.matches("1 2\n", "1\n  2"));
+
+        let float = ComparisonMode::FloatEpsilon {
+            abs: 1e-6,
+            rel: 0.0,
+        };
+        assert!(float.matches("0.3333333 YES", "0.33333334 YES"));
+        assert!(!float.matches("1000000", "1000000.5"));
+        let float = ComparisonMode::FloatEpsilon {
+            abs: 0.0,
+            rel: 1e-6,
+        };
+        assert!(float.matches("1000000", "1000000.5"));
+        assert!(!float.matches("nan", "NaN"));
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify("1 2\n3\n", "1 2  \n3"), OutputVerdict::Match);
+        assert_eq!(
+            classify("1 2\n3\n", "1  2\n3\n"),
+            OutputVerdict::PresentationOnly
+        );
+        assert_eq!(
+            classify("1 2\n3\n", "1 2 3\n"),
+            OutputVerdict::PresentationOnly
+        );
+        assert_eq!(classify("1 2\n3\n", "1 2\n4\n"), OutputVerdict::Mismatch);
+        assert_eq!(classify("1 2\n", "12\n"), OutputVerdict::Mismatch);
+
+        // Exact output must keep even trailing whitespace
+        assert_eq!(
+            ComparisonMode::Exact.classify("1\n", "1"),
+            OutputVerdict::PresentationOnly
+        );
+        // Token modes don't care about whitespace at all
+        assert_eq!(
+            ComparisonMode::TokenEquality.classify("1 2\n", "1\n2"),
+            OutputVerdict::Match
+        );
+        let float = ComparisonMode::FloatEpsilon { abs: 0.1, rel: 0.0 };
+        assert_eq!(float.classify("1.0 2.0", "1.05\n2.0"), OutputVerdict::Match);
+        assert_eq!(
+            float.classify("1.0 2.0", "1.5 2.0"),
+            OutputVerdict::Mismatch
+        );
+
+        assert!(OutputVerdict::Match < OutputVerdict::PresentationOnly);
+        assert!(OutputVerdict::PresentationOnly < OutputVerdict::Mismatch);
+        assert_eq!(
+            OutputVerdict::PresentationOnly.status(),
+            JudgeStatus::PresentationError
+        );
+        assert_eq!(OutputVerdict::Mismatch.status(), JudgeStatus::WrongAnswer);
+    }
+
+    fn diff(expected: &str, actual: &str, mode: ComparisonMode) -> Option<OutputDiff> {
+        first_difference(expected, actual, mode, DEFAULT_DIFF_FRAGMENT_CHARS)
+    }
+
+    fn at(line: u64, column: u64, expected: &str, actual: &str) -> Option<OutputDiff> {
+        Some(OutputDiff {
+            line,
+            column,
+            expected_fragment: expected.to_string(),
+            actual_fragment: actual.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_first_difference_by_line() {
+        let expected = "1\n2\n3\n12 7\n";
+        assert_eq!(
+            diff(expected, "1\n2\n3\n13 7\n", ComparisonMode::TrimWhitespace),
+            at(4, 1, "12 7", "13 7")
+        );
+        assert_eq!(
+            diff(expected, "1\n2\n3\n12 8\n", ComparisonMode::Exact),
+            at(4, 4, "7\n", "8\n")
+        );
+        assert_eq!(
+            diff(expected, "1\n2\n", ComparisonMode::TrimWhitespace),
+            at(3, 1, "3", "")
+        );
+        assert_eq!(diff(expected, expected, ComparisonMode::Exact), None);
+    }
+
+    #[test]
+    fn test_first_difference_line_endings() {
+        // Only exact comparison cares about CRLF and the final newline
+        assert_eq!(
+            diff("1 2\n3\n", "1 2\r\n3\r\n", ComparisonMode::TrimWhitespace),
+            None
+        );
+        assert_eq!(
+            diff("1 2\n3\n", "1 2\r\n3\r\n", ComparisonMode::Exact),
+            at(1, 4, "\n", "\r\n")
+        );
+        assert_eq!(
+            diff("1 2\n3\n", "1 2\n3", ComparisonMode::TrimWhitespace),
+            None
+        );
+        assert_eq!(
+            diff("1 2\n3\n", "1 2\n3", ComparisonMode::Exact),
+            at(2, 2, "\n", "")
+        );
+    }
+
+    #[test]
+    fn test_first_difference_by_token() {
+        assert_eq!(
+            diff("1 2 3\n", "1\n  2 4\n", ComparisonMode::TokenEquality),
+            at(2, 5, "3", "4")
+        );
+        assert_eq!(diff("1 2\n", "1 2\n", ComparisonMode::TokenEquality), None);
+        assert_eq!(
+            diff("1 2 3", "1 2\n", ComparisonMode::TokenEquality),
+            at(2, 1, "3", "")
+        );
+        assert_eq!(
+            diff("1", "1 extra", ComparisonMode::TokenEquality),
+            at(1, 3, "", "extra")
+        );
+        let float = ComparisonMode::FloatEpsilon {
+            abs: 1e-6,
+            rel: 0.0,
+        };
+        assert_eq!(
+            diff("0.5 0.25", "0.5000001 0.3", float),
+            at(1, 11, "0.25", "0.3")
+        );
+    }
+
+    #[test]
+    fn test_fragments_are_capped() {
+        let expected = "a".repeat(100);
+        let actual = "b".repeat(100);
+        let found = first_difference(&expected, &actual, ComparisonMode::Exact, 8).unwrap();
+        assert_eq!(found.expected_fragment, "aaaaaaaa…");
+        assert_eq!(found.actual_fragment, "bbbbbbbb…");
+        let found = first_difference("é1", "é2", ComparisonMode::Exact, 1).unwrap();
+        assert_eq!((found.column, found.expected_fragment.as_str()), (1, "é…"));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(ComparisonMode::Exact.validate(), Ok(()));
+        assert_eq!(
+            ComparisonMode::FloatEpsilon {
+                abs: 1e-9,
+                rel: -1.0
+            }
+            .validate(),
+            Err(CheckerError::InvalidEpsilon(-1.0))
+        );
+
+        let mut task = task(vec![TestCase::new("1".to_string(), "", "")]);
+        assert_eq!(task.validate_comparisons(), Ok(()));
+        task.test_cases[0].comparison = Some(ComparisonMode::FloatEpsilon {
+            abs: f64::INFINITY,
+            rel: 0.0,
+        });
+        assert!(task.validate_comparisons().is_err());
+    }
+
+    #[test]
+    fn test_effective_comparison() {
+        let mut exact = TestCase::new("1".to_string(), "", "");
+        exact.comparison = Some(ComparisonMode::Exact);
+        let plain = TestCase::new("2".to_string(), "", "");
+
+        assert_eq!(
+            plain.effective_comparison(None),
+            ComparisonMode::TrimWhitespace
+        );
+        assert_eq!(
+            plain.effective_comparison(Some(ComparisonMode::TokenEquality)),
+            ComparisonMode::TokenEquality
+        );
+        assert_eq!(
+            exact.effective_comparison(Some(ComparisonMode::TokenEquality)),
+            ComparisonMode::Exact
+        );
+
+        let mut task = task(vec![exact, plain]);
+        task.checker = Checker::FloatCompare { epsilon: 1e-6 };
+        assert_eq!(
+            task.comparison_for(&task.test_cases[1]),
+            ComparisonMode::FloatEpsilon {
+                abs: 1e-6,
+                rel: 1e-6
+            }
+        );
+        task.default_comparison = Some(ComparisonMode::TokenEquality);
+        assert_eq!(
+            task.comparison_for(&task.test_cases[1]),
+            ComparisonMode::TokenEquality
+        );
+        assert_eq!(
+            task.comparison_for(&task.test_cases[0]),
+            ComparisonMode::Exact
+        );
+    }
+
+    #[test]
+    fn test_serde_compatibility() {
+        let json = r#"{"FloatEpsilon":{"abs":1e-9,"rel":1e-6}}"#;
+        let mode: ComparisonMode = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            mode,
+            ComparisonMode::FloatEpsilon {
+                abs: 1e-9,
+                rel: 1e-6
+            }
+        );
+        assert_eq!(serde_json::to_string(&mode).unwrap(), json);
+
+        // Payloads from before per-test-case comparison
+        let mut test_case = TestCase::new("1".to_string(), "1\n", "1\n");
+        test_case.comparison = Some(ComparisonMode::Exact);
+        let mut value = serde_json::to_value(&test_case).unwrap();
+        value.as_object_mut().unwrap().remove("comparison");
+        let old: TestCase = serde_json::from_value(value).unwrap();
+        assert_eq!(old.comparison, None);
+        assert_eq!(
+            old.effective_comparison(None),
+            ComparisonMode::TrimWhitespace
+        );
+
+        let mut task = task(vec![test_case]);
+        task.default_comparison = Some(ComparisonMode::TokenEquality);
+        let mut value = serde_json::to_value(&task).unwrap();
+        value.as_object_mut().unwrap().remove("default_comparison");
+        value["test_cases"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("comparison");
+        let old: JudgeTask = serde_json::from_value(value).unwrap();
+        assert_eq!(old.default_comparison, None);
+        assert_eq!(
+            old.comparison_for(&old.test_cases[0]),
+            ComparisonMode::TrimWhitespace
+        );
+    }
+}
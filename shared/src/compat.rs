@@ -0,0 +1,215 @@
+//! Wire format versioning
+//!
+//! Backend and judger are deployed separately, so a reader may see payloads written
+//! by an older or newer peer. [`JudgeTask`](crate::JudgeTask) and
+//! [`JudgeResult`](crate::JudgeResult) carry a `schema_version`:
+//!
+//! * fields added later are `#[serde(default)]`, so older payloads still read;
+//! * unknown fields are ignored, so a peer that only added fields is read fine;
+//! * a payload of a newer version may use enum variants the reader doesn't know
+//!   and is refused up front by [`from_json`] with a clear error instead of an
+//!   obscure serde one.
+//!
+//! Adding a defaulted field needs no bump, since older readers ignore it. Bump
+//! [`SCHEMA_VERSION`] when older readers can't understand a payload, e.g. when a wire
+//! enum gains a variant.
+
+use serde::de::DeserializeOwned;
+use std::fmt;
+
+/// Schema version written by this build
+///
+/// Changes that older readers can't understand:
+///
+/// * 2: `ExecutionMode::SingleRunMultiCase`
+/// * 3: flat `JudgeStatus` strings, see [`wire`](crate::wire)
+/// * 4: `JudgeStatus::PresentationError`
+/// * 5: `JudgeStatus::PartialAccepted` and `JudgeStatus::Skipped`
+/// * 6: `null` `stop_on_first_failure`, meaning the judge mode decides
+pub const SCHEMA_VERSION: u32 = 6;
+
+/// Serde default for `schema_version`: payloads without the field are version 1
+pub(crate) fn default_schema_version() -> u32 {
+    1
+}
+
+/// Whether a payload of version `remote` can be read by a reader of version `local`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compat {
+    /// Same version
+    Exact,
+    /// The remote is older; missing fields take their defaults
+    RemoteOlder,
+    /// The remote is newer (or not a valid version) and can't be read safely
+    ///
+    /// Additive changes don't bump the version, so every newer version may use
+    /// variants this build doesn't know.
+    Incompatible,
+}
+
+impl Compat {
+    /// Returns whether the payload can be read
+    pub fn is_compatible(&self) -> bool {
+        !matches!(self, Compat::Incompatible)
+    }
+}
+
+/// Compares a local and a remote schema version
+pub fn check_compatibility(local: u32, remote: u32) -> Compat {
+    if remote == 0 {
+        Compat::Incompatible
+    } else if remote == local {
+        Compat::Exact
+    } else if remote < local {
+        Compat::RemoteOlder
+    } else {
+        Compat::Incompatible
+    }
+}
+
+/// Errors raised while decoding a versioned payload
+#[derive(Debug)]
+pub enum CompatError {
+    /// The payload's schema version can't be read by this build
+    Incompatible { local: u32, remote: u32 },
+    /// The payload is malformed
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for CompatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompatError::Incompatible { local, remote } => write!(
+                f,
+                "payload schema version {} is not supported (this build reads up to {})",
+                remote, local
+            ),
+            CompatError::Json(e) => write!(f, "invalid payload: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CompatError {}
+
+/// Decodes a versioned payload, checking its `schema_version` before anything else
+pub fn from_json<T: DeserializeOwned>(json: &str) -> Result<T, CompatError> {
+    let value: serde_json::Value = serde_json::from_str(json).map_err(CompatError::Json)?;
+    let remote = match value.get("schema_version") {
+        None => default_schema_version(),
+        Some(version) => version
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .unwrap_or(0),
+    };
+    if !check_compatibility(SCHEMA_VERSION, remote).is_compatible() {
+        return Err(CompatError::Incompatible {
+            local: SCHEMA_VERSION,
+            remote,
+        });
+    }
+    serde_json::from_value(value).map_err(CompatError::Json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{JudgeResult, JudgeStatus, JudgeTask, ProgrammingLanguage, RuntimeErrorType};
+
+    const TASK_V1: &str = include_str!("../fixtures/judge_task_v1.json");
+    const RESULT_V1: &str = include_str!("../fixtures/judge_result_v1.json");
+
+    #[test]
+    fn test_check_compatibility() {
+        assert_eq!(check_compatibility(1, 1), Compat::Exact);
+        assert_eq!(check_compatibility(3, 1), Compat::RemoteOlder);
+        assert_eq!(check_compatibility(1, 2), Compat::Incompatible);
+        assert_eq!(check_compatibility(1, 3), Compat::Incompatible);
+        assert_eq!(check_compatibility(1, 0), Compat::Incompatible);
+        assert!(!Compat::Incompatible.is_compatible());
+        assert!(Compat::RemoteOlder.is_compatible());
+    }
+
+    #[test]
+    fn test_v1_task_fixture() {
+        let task: JudgeTask = from_json(TASK_V1).unwrap();
+        assert_eq!(task.schema_version, 1);
+        assert_eq!(task.submission.language, ProgrammingLanguage::Cpp17);
+        assert_eq!(task.submission.priority, 10);
+        assert_eq!(task.submission.attempt, 1);
+        assert_eq!(task.test_cases.len(), 2);
+        assert_eq!(
+            task.test_cases[1].expected_output.as_inline(),
+            Some("2000000000\n")
+        );
+        assert_eq!(task.test_cases[1].time_limit, Some(2000));
+        assert!(task.subtasks.is_empty());
+
+        // Writing and reading back loses nothing
+        let json = serde_json::to_string(&task).unwrap();
+        assert_eq!(from_json::<JudgeTask>(&json).unwrap(), task);
+    }
+
+    #[test]
+    fn test_v1_result_fixture() {
+        let result: JudgeResult = from_json(RESULT_V1).unwrap();
+        assert_eq!(result.schema_version, 1);
+        assert_eq!(result.attempt, 1);
+        assert_eq!(
+            result.status,
+            JudgeStatus::RuntimeError(RuntimeErrorType::SegmentationFault)
+        );
+        assert_eq!(result.passed_test_cases(), 1);
+        assert_eq!(result.error_info.as_ref().unwrap().signal, Some(11));
+
+        let json = serde_json::to_string(&result).unwrap();
+        assert_eq!(from_json::<JudgeResult>(&json).unwrap(), result);
+    }
+
+    #[test]
+    fn test_unknown_fields_are_ignored() {
+        // A peer of the same version that added fields without a bump
+        let mut value: serde_json::Value = serde_json::from_str(TASK_V1).unwrap();
+        value["schema_version"] = SCHEMA_VERSION.into();
+        value["added_later"] = serde_json::json!({"anything": [1, 2, 3]});
+        value["submission"]["also_new"] = true.into();
+        let task: JudgeTask = from_json(&value.to_string()).unwrap();
+        assert_eq!(task.schema_version, SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_next_version_is_refused() {
+        // The next version may use a variant this build doesn't know yet; it must be
+        // refused by version, not fail halfway through decoding
+        let mut value: serde_json::Value = serde_json::from_str(RESULT_V1).unwrap();
+        value["schema_version"] = (SCHEMA_VERSION + 1).into();
+        value["status"] = "XX".into();
+        assert!(matches!(
+            from_json::<JudgeResult>(&value.to_string()),
+            Err(CompatError::Incompatible {
+                local: SCHEMA_VERSION,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_too_new_payload_is_refused() {
+        let mut value: serde_json::Value = serde_json::from_str(RESULT_V1).unwrap();
+        value["schema_version"] = (SCHEMA_VERSION + 2).into();
+        value["status"] = "SomeFutureVerdict".into();
+        let error = from_json::<JudgeResult>(&value.to_string()).unwrap_err();
+        assert!(matches!(
+            error,
+            CompatError::Incompatible {
+                local: SCHEMA_VERSION,
+                ..
+            }
+        ));
+        assert!(error.to_string().contains("not supported"));
+
+        assert!(matches!(
+            from_json::<JudgeResult>("{"),
+            Err(CompatError::Json(_))
+        ));
+    }
+}
@@ -0,0 +1,392 @@
+//! Validation of caller-supplied compile flags
+//!
+//! Flags on a [`JudgeTask`](crate::JudgeTask) are passed straight to the compiler, so
+//! untrusted overrides are checked against a per-language allowlist before use.
+//! Flags that could redirect output, pull in host files or reach the linker are rejected.
+
+use crate::{BuildProfile, ProgrammingLanguage};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How much the caller supplying compile flags is trusted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FlagPolicy {
+    /// Overrides must pass the language allowlist
+    #[default]
+    Restricted,
+    /// Overrides are used as given (problem setters, administrators)
+    Trusted,
+}
+
+/// Why a compile flag was rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompileFlagsError {
+    /// `@file` response files can smuggle in arbitrary flags
+    ResponseFile(String),
+    /// The flag references an absolute or home-relative path
+    AbsolutePath(String),
+    /// The flag is not on the language's allowlist
+    NotAllowed {
+        language: ProgrammingLanguage,
+        flag: String,
+    },
+    /// The flag expects a value but none was given
+    MissingValue(String),
+}
+
+impl fmt::Display for CompileFlagsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompileFlagsError::ResponseFile(flag) => {
+                write!(f, "response files are not allowed: {}", flag)
+            }
+            CompileFlagsError::AbsolutePath(flag) => {
+                write!(f, "compile flags must not reference paths: {}", flag)
+            }
+            CompileFlagsError::NotAllowed { language, flag } => {
+                write!(
+                    f,
+                    "compile flag {} is not allowed for {}",
+                    flag,
+                    language.as_str()
+                )
+            }
+            CompileFlagsError::MissingValue(flag) => {
+                write!(f, "compile flag {} requires a value", flag)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CompileFlagsError {}
+
+/// Compile flags that passed validation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompileFlags(Vec<String>);
+
+impl CompileFlags {
+    /// Merges caller overrides into the language defaults for a build profile
+    ///
+    /// Defaults come first; overrides already present in the defaults are not repeated.
+    /// Under [`FlagPolicy::Restricted`] every override is checked against the allowlist.
+    pub fn merge(
+        language: ProgrammingLanguage,
+        profile: BuildProfile,
+        overrides: &[String],
+        policy: FlagPolicy,
+    ) -> Result<Self, CompileFlagsError> {
+        let mut flags = language.compile_flags_for(profile);
+        let defaults_len = flags.len();
+
+        if policy == FlagPolicy::Restricted {
+            validate(language, overrides)?;
+        }
+
+        let mut iter = overrides.iter();
+        while let Some(flag) = iter.next() {
+            match flag_rule(language, flag) {
+                // Options with a separate value are compared and kept as a pair
+                Some(FlagRule::WithValue(_)) => {
+                    let pair: Vec<String> =
+                        std::iter::once(flag).chain(iter.next()).cloned().collect();
+                    if !flags[..defaults_len]
+                        .windows(pair.len())
+                        .any(|w| w == pair.as_slice())
+                    {
+                        flags.extend(pair);
+                    }
+                }
+                _ if flags[..defaults_len].contains(flag) => {}
+                _ => flags.push(flag.clone()),
+            }
+        }
+        Ok(Self(flags))
+    }
+
+    /// Returns the flags as a slice
+    pub fn as_slice(&self) -> &[String] {
+        &self.0
+    }
+
+    /// Consumes the wrapper, returning the flags
+    pub fn into_vec(self) -> Vec<String> {
+        self.0
+    }
+}
+
+/// Checks a list of flags against the allowlist for a language
+pub fn validate(language: ProgrammingLanguage, flags: &[String]) -> Result<(), CompileFlagsError> {
+    let mut iter = flags.iter();
+    while let Some(flag) = iter.next() {
+        check_token(flag)?;
+        match flag_rule(language, flag) {
+            Some(FlagRule::WithValue(_)) => {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| CompileFlagsError::MissingValue(flag.clone()))?;
+                check_token(value)?;
+                if value.starts_with('-') {
+                    return Err(CompileFlagsError::MissingValue(flag.clone()));
+                }
+            }
+            Some(_) => {}
+            None => {
+                return Err(CompileFlagsError::NotAllowed {
+                    language,
+                    flag: flag.clone(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Shape of an allowed flag
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FlagRule {
+    /// The flag must match exactly
+    Exact(&'static str),
+    /// Any flag starting with the prefix
+    Prefix(&'static str),
+    /// The flag takes its value as the next argument
+    WithValue(&'static str),
+}
+
+impl FlagRule {
+    fn matches(&self, flag: &str) -> bool {
+        match self {
+            FlagRule::Exact(name) | FlagRule::WithValue(name) => flag == *name,
+            FlagRule::Prefix(prefix) => flag.starts_with(prefix),
+        }
+    }
+}
+
+const C_FAMILY_FLAGS: &[FlagRule] = &[
+    FlagRule::Exact("-O0"),
+    FlagRule::Exact("-O1"),
+    FlagRule::Exact("-O2"),
+    FlagRule::Exact("-O3"),
+    FlagRule::Exact("-Os"),
+    FlagRule::Exact("-Ofast"),
+    FlagRule::Exact("-g"),
+    FlagRule::Exact("-w"),
+    FlagRule::Exact("-pedantic"),
+    FlagRule::Exact("-lm"),
+    FlagRule::Exact("-pipe"),
+    FlagRule::Exact("-fno-omit-frame-pointer"),
+    FlagRule::Prefix("-std="),
+    FlagRule::Prefix("-D"),
+    FlagRule::Prefix("-fsanitize="),
+    FlagRule::Prefix("-march="),
+    FlagRule::Prefix("-W"),
+];
+
+const RUST_FLAGS: &[FlagRule] = &[
+    FlagRule::Exact("-O"),
+    FlagRule::Exact("-g"),
+    FlagRule::Prefix("--edition="),
+    FlagRule::Prefix("-Copt-level="),
+    FlagRule::Prefix("-Cdebuginfo="),
+    FlagRule::Prefix("-Coverflow-checks="),
+];
+
+const JAVA_FLAGS: &[FlagRule] = &[
+    FlagRule::Exact("-g"),
+    FlagRule::Exact("-nowarn"),
+    FlagRule::Prefix("-Xlint"),
+];
+
+const GO_FLAGS: &[FlagRule] = &[FlagRule::Exact("-trimpath")];
+
+const TYPESCRIPT_FLAGS: &[FlagRule] = &[
+    FlagRule::Exact("--strict"),
+    FlagRule::WithValue("--target"),
+    FlagRule::WithValue("--lib"),
+];
+
+const KOTLIN_FLAGS: &[FlagRule] = &[
+    FlagRule::Exact("-include-runtime"),
+    FlagRule::Exact("-nowarn"),
+    FlagRule::WithValue("-jvm-target"),
+];
+
+const CSHARP_FLAGS: &[FlagRule] = &[
+    FlagRule::Exact("-optimize+"),
+    FlagRule::Exact("-optimize-"),
+    FlagRule::Prefix("-langversion:"),
+    FlagRule::Prefix("-define:"),
+];
+
+fn allowlist(language: ProgrammingLanguage) -> &'static [FlagRule] {
+    match language {
+        ProgrammingLanguage::C
+        | ProgrammingLanguage::Cpp
+        | ProgrammingLanguage::Cpp11
+        | ProgrammingLanguage::Cpp14
+        | ProgrammingLanguage::Cpp17
+        | ProgrammingLanguage::Cpp20 => C_FAMILY_FLAGS,
+        ProgrammingLanguage::Rust => RUST_FLAGS,
+        ProgrammingLanguage::Java => JAVA_FLAGS,
+        ProgrammingLanguage::Go => GO_FLAGS,
+        ProgrammingLanguage::TypeScript => TYPESCRIPT_FLAGS,
+        ProgrammingLanguage::Kotlin => KOTLIN_FLAGS,
+        ProgrammingLanguage::CSharp => CSHARP_FLAGS,
+        ProgrammingLanguage::Python2
+        | ProgrammingLanguage::Python3
+        | ProgrammingLanguage::JavaScript
+        | ProgrammingLanguage::Ruby
+        | ProgrammingLanguage::PHP
+        | ProgrammingLanguage::Custom => &[],
+    }
+}
+
+fn flag_rule(language: ProgrammingLanguage, flag: &str) -> Option<FlagRule> {
+    // `-Wl,`, `-Wa,` and `-Wp,` pass arguments through to the linker, assembler and preprocessor
+    if ["-Wl,", "-Wa,", "-Wp,"]
+        .iter()
+        .any(|prefix| flag.starts_with(prefix))
+    {
+        return None;
+    }
+    allowlist(language)
+        .iter()
+        .copied()
+        .find(|rule| rule.matches(flag))
+}
+
+/// Rejects tokens that are response files or contain paths, whatever the language
+fn check_token(token: &str) -> Result<(), CompileFlagsError> {
+    if token.starts_with('@') {
+        return Err(CompileFlagsError::ResponseFile(token.to_string()));
+    }
+    let is_path = token
+        .split(['=', ':', ','])
+        .any(|part| part.starts_with('/') || part.starts_with('~') || part.contains(".."));
+    if is_path {
+        return Err(CompileFlagsError::AbsolutePath(token.to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flags(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_merge_keeps_defaults_and_dedups() {
+        let merged = CompileFlags::merge(
+            ProgrammingLanguage::Cpp17,
+            BuildProfile::Release,
+            &flags(&["-O2", "-DLOCAL", "-Wextra"]),
+            FlagPolicy::Restricted,
+        )
+        .unwrap();
+        assert_eq!(
+            merged.into_vec(),
+            flags(&["-O2", "-Wall", "-std=c++17", "-DLOCAL", "-Wextra"])
+        );
+    }
+
+    #[test]
+    fn test_merge_dedups_value_pairs() {
+        let merged = CompileFlags::merge(
+            ProgrammingLanguage::TypeScript,
+            BuildProfile::Release,
+            &flags(&["--target", "es2020", "--lib", "es2020"]),
+            FlagPolicy::Restricted,
+        )
+        .unwrap();
+        assert_eq!(
+            merged.into_vec(),
+            flags(&["--target", "es2020", "--lib", "es2020"])
+        );
+    }
+
+    #[test]
+    fn test_rejects_dangerous_flags() {
+        let cases = [
+            (flags(&["-o", "/etc/cron.d/x"]), "-o"),
+            (flags(&["-include", "secret.h"]), "-include"),
+            (flags(&["-Wl,--wrap=main"]), "-Wl,--wrap=main"),
+            (flags(&["-isystem", "include"]), "-isystem"),
+        ];
+        for (input, flag) in cases {
+            assert_eq!(
+                validate(ProgrammingLanguage::Cpp17, &input),
+                Err(CompileFlagsError::NotAllowed {
+                    language: ProgrammingLanguage::Cpp17,
+                    flag: flag.to_string(),
+                })
+            );
+        }
+
+        assert_eq!(
+            validate(ProgrammingLanguage::C, &flags(&["@flags.txt"])),
+            Err(CompileFlagsError::ResponseFile("@flags.txt".to_string()))
+        );
+        assert_eq!(
+            validate(ProgrammingLanguage::C, &flags(&["-DPATH=/etc/shadow"])),
+            Err(CompileFlagsError::AbsolutePath(
+                "-DPATH=/etc/shadow".to_string()
+            ))
+        );
+        assert!(matches!(
+            validate(ProgrammingLanguage::C, &flags(&["-DX=../../x"])),
+            Err(CompileFlagsError::AbsolutePath(_))
+        ));
+    }
+
+    #[test]
+    fn test_flags_with_values() {
+        assert!(
+            validate(
+                ProgrammingLanguage::TypeScript,
+                &flags(&["--target", "es2022"])
+            )
+            .is_ok()
+        );
+        assert_eq!(
+            validate(ProgrammingLanguage::TypeScript, &flags(&["--target"])),
+            Err(CompileFlagsError::MissingValue("--target".to_string()))
+        );
+        assert_eq!(
+            validate(
+                ProgrammingLanguage::TypeScript,
+                &flags(&["--target", "--outFile"])
+            ),
+            Err(CompileFlagsError::MissingValue("--target".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_interpreted_languages_allow_nothing() {
+        assert!(validate(ProgrammingLanguage::Python3, &[]).is_ok());
+        assert!(validate(ProgrammingLanguage::Python3, &flags(&["-O"])).is_err());
+    }
+
+    #[test]
+    fn test_trusted_policy_skips_allowlist() {
+        let merged = CompileFlags::merge(
+            ProgrammingLanguage::C,
+            BuildProfile::Release,
+            &flags(&["-I", "/opt/testlib"]),
+            FlagPolicy::Trusted,
+        )
+        .unwrap();
+        assert!(merged.as_slice().ends_with(&flags(&["-I", "/opt/testlib"])));
+    }
+
+    #[test]
+    fn test_defaults_pass_validation() {
+        for language in ProgrammingLanguage::ALL {
+            for profile in [BuildProfile::Release, BuildProfile::Sanitize] {
+                let defaults = language.compile_flags_for(profile);
+                assert_eq!(validate(language, &defaults), Ok(()), "{:?}", language);
+            }
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! Compile logs stored as artifacts separate from the result JSON
+//!
+//! Compiler output (long C++ template errors in particular) can be far larger than
+//! what belongs in [`ErrorInfo`](crate::ErrorInfo). The full log is kept as a
+//! [`CompileLog`] with terminal escape codes removed and its size capped, and is
+//! stored gzip-compressed.
+
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read, Write};
+
+/// Maximum size of a stored compile log in bytes
+pub const MAX_COMPILE_LOG_BYTES: usize = 256 * 1024;
+
+/// Marker appended to logs that were cut at the size cap
+pub const TRUNCATION_MARKER: &str = "\n[compile log truncated]\n";
+
+/// Cleaned-up compiler output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompileLog {
+    /// Log text without ANSI escape codes, at most the cap plus the truncation marker
+    pub content: String,
+    /// Size of the log in bytes after escape codes were removed, before capping
+    pub original_len: usize,
+    /// Whether the content was cut at the cap
+    pub truncated: bool,
+}
+
+impl CompileLog {
+    /// Builds a log from raw compiler output using [`MAX_COMPILE_LOG_BYTES`]
+    pub fn from_output(raw: &[u8]) -> Self {
+        Self::with_limit(raw, MAX_COMPILE_LOG_BYTES)
+    }
+
+    /// Builds a log from raw compiler output, keeping at most `limit` bytes
+    ///
+    /// Invalid UTF-8 is replaced rather than rejected, and truncation never splits a character.
+    pub fn with_limit(raw: &[u8], limit: usize) -> Self {
+        let mut content = strip_ansi(&String::from_utf8_lossy(raw));
+        let original_len = content.len();
+        let truncated = original_len > limit;
+        if truncated {
+            let mut end = limit;
+            while !content.is_char_boundary(end) {
+                end -= 1;
+            }
+            content.truncate(end);
+            content.push_str(TRUNCATION_MARKER);
+        }
+        Self {
+            content,
+            original_len,
+            truncated,
+        }
+    }
+
+    /// Compresses the log content for storage
+    pub fn to_gzip(&self) -> io::Result<Vec<u8>> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(self.content.as_bytes())?;
+        encoder.finish()
+    }
+
+    /// Decompresses log content previously produced by [`to_gzip`](Self::to_gzip)
+    ///
+    /// Content larger than a capped log could be is rejected with
+    /// [`io::ErrorKind::InvalidData`] instead of being decompressed in full.
+    pub fn content_from_gzip(bytes: &[u8]) -> io::Result<String> {
+        let limit = (MAX_COMPILE_LOG_BYTES + TRUNCATION_MARKER.len()) as u64;
+        let mut content = String::new();
+        GzDecoder::new(bytes)
+            .take(limit + 1)
+            .read_to_string(&mut content)?;
+        if content.len() as u64 > limit {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("decompressed compile log exceeds {} bytes", limit),
+            ));
+        }
+        Ok(content)
+    }
+}
+
+/// Removes ANSI escape sequences (colors, cursor movement, hyperlinks) from text
+pub fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            // CSI: parameters and intermediates up to a final byte in @..~
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC (e.g. gcc's hyperlinks): terminated by BEL or ESC \
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            // Two-character escapes
+            Some(_) | None => {}
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_ansi() {
+        let gcc = "\x1b[01m\x1b[Kmain.cpp:3:5:\x1b[m\x1b[K \x1b[01;31m\x1b[Kerror: \x1b[m\x1b[K'foo' was not declared";
+        assert_eq!(
+            strip_ansi(gcc),
+            "main.cpp:3:5: error: 'foo' was not declared"
+        );
+
+        let hyperlink = "see \x1b]8;;https://gcc.gnu.org/\x07-Wall\x1b]8;;\x07 and \x1b]8;;x\x1b\\link\x1b]8;;\x1b\\";
+        assert_eq!(strip_ansi(hyperlink), "see -Wall and link");
+        assert_eq!(strip_ansi("plain text\n"), "plain text\n");
+    }
+
+    #[test]
+    fn test_capped_at_char_boundary() {
+        let log = CompileLog::with_limit("ééé".as_bytes(), 3);
+        assert!(log.truncated);
+        assert_eq!(log.original_len, 6);
+        assert_eq!(log.content, format!("é{}", TRUNCATION_MARKER));
+
+        let log = CompileLog::with_limit(b"short", 10);
+        assert!(!log.truncated);
+        assert_eq!(log.content, "short");
+    }
+
+    #[test]
+    fn test_gzip_round_trip() {
+        let raw = "error: no matching function for call\n".repeat(1000);
+        let log = CompileLog::from_output(raw.as_bytes());
+        let compressed = log.to_gzip().unwrap();
+        assert!(compressed.len() < log.content.len());
+        assert_eq!(
+            CompileLog::content_from_gzip(&compressed).unwrap(),
+            log.content
+        );
+
+        let capped = CompileLog::from_output(&vec![b'x'; MAX_COMPILE_LOG_BYTES * 2]);
+        assert_eq!(
+            CompileLog::content_from_gzip(&capped.to_gzip().unwrap()).unwrap(),
+            capped.content
+        );
+    }
+
+    #[test]
+    fn test_gzip_bomb_is_rejected() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(&vec![b'x'; 16 * MAX_COMPILE_LOG_BYTES])
+            .unwrap();
+        let bomb = encoder.finish().unwrap();
+        assert_eq!(
+            CompileLog::content_from_gzip(&bomb).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
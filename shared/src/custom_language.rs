@@ -0,0 +1,244 @@
+//! User-supplied toolchains for languages without a built-in variant
+//!
+//! A [`CustomLanguage`] travels on the [`Submission`](crate::Submission) together with
+//! [`ProgrammingLanguage::Custom`](crate::ProgrammingLanguage::Custom) and takes precedence
+//! over the built-in defaults. Commands are argv templates in which `{source}` and
+//! `{artifact}` are replaced by the source file and the compile output.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Placeholder replaced by the source file name
+pub const SOURCE_PLACEHOLDER: &str = "{source}";
+/// Placeholder replaced by the compiled artifact name
+pub const ARTIFACT_PLACEHOLDER: &str = "{artifact}";
+
+/// Artifact name used for compiled custom languages
+const CUSTOM_ARTIFACT: &str = "main";
+
+/// Toolchain description for a custom language
+///
+/// ```
+/// use oj_shared::CustomLanguage;
+///
+/// let ocaml = CustomLanguage {
+///     name: "OCaml".to_string(),
+///     file_extension: "ml".to_string(),
+///     needs_compilation: true,
+///     compile_argv: vec![
+///         "ocamlfind".into(), "ocamlopt".into(), "{source}".into(), "-o".into(), "{artifact}".into(),
+///     ],
+///     run_argv: vec!["./{artifact}".into()],
+/// };
+/// assert!(ocaml.validate().is_ok());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomLanguage {
+    /// Display name, e.g. "OCaml"
+    pub name: String,
+    /// Source file extension without the dot
+    pub file_extension: String,
+    /// Whether `compile_argv` runs before execution
+    pub needs_compilation: bool,
+    /// Compile argv template; must reference `{source}` and `{artifact}`
+    #[serde(default)]
+    pub compile_argv: Vec<String>,
+    /// Run argv template; must reference `{artifact}` (compiled) or `{source}` (interpreted)
+    pub run_argv: Vec<String>,
+}
+
+/// Why a custom language spec was rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CustomLanguageError {
+    /// The submission uses `ProgrammingLanguage::Custom` but carries no spec
+    MissingSpec,
+    /// The file extension is empty or contains path separators
+    InvalidExtension(String),
+    /// A required argv template is empty
+    EmptyCommand(&'static str),
+    /// A template does not reference a required placeholder
+    MissingPlaceholder {
+        field: &'static str,
+        placeholder: &'static str,
+    },
+}
+
+impl fmt::Display for CustomLanguageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CustomLanguageError::MissingSpec => {
+                write!(f, "custom language submission without a language spec")
+            }
+            CustomLanguageError::InvalidExtension(ext) => {
+                write!(f, "invalid file extension: {:?}", ext)
+            }
+            CustomLanguageError::EmptyCommand(field) => write!(f, "{} must not be empty", field),
+            CustomLanguageError::MissingPlaceholder { field, placeholder } => {
+                write!(f, "{} must contain {}", field, placeholder)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CustomLanguageError {}
+
+impl CustomLanguage {
+    /// Checks that the spec is complete and its templates use the required placeholders
+    pub fn validate(&self) -> Result<(), CustomLanguageError> {
+        let ext = &self.file_extension;
+        if ext.is_empty() || ext.contains(['/', '\\', '.']) {
+            return Err(CustomLanguageError::InvalidExtension(ext.clone()));
+        }
+
+        if self.needs_compilation {
+            require(&self.compile_argv, "compile_argv", SOURCE_PLACEHOLDER)?;
+            require(&self.compile_argv, "compile_argv", ARTIFACT_PLACEHOLDER)?;
+            require(&self.run_argv, "run_argv", ARTIFACT_PLACEHOLDER)
+        } else {
+            require(&self.run_argv, "run_argv", SOURCE_PLACEHOLDER)
+        }
+    }
+
+    /// Returns the source file name, `main.<ext>`
+    pub fn filename(&self) -> String {
+        format!("main.{}", self.file_extension)
+    }
+
+    /// Returns the compile output name, or `None` if the language isn't compiled
+    pub fn artifact_name(&self) -> Option<&'static str> {
+        self.needs_compilation.then_some(CUSTOM_ARTIFACT)
+    }
+
+    /// Returns the compile argv with placeholders filled in
+    pub fn compile_command(&self, source: &str, artifact: &str) -> Option<Vec<String>> {
+        self.needs_compilation
+            .then(|| expand(&self.compile_argv, source, artifact))
+    }
+
+    /// Returns the run argv with placeholders filled in
+    pub fn run_command(&self, source: &str, artifact: &str) -> Vec<String> {
+        expand(&self.run_argv, source, artifact)
+    }
+}
+
+fn require(
+    argv: &[String],
+    field: &'static str,
+    placeholder: &'static str,
+) -> Result<(), CustomLanguageError> {
+    if argv.is_empty() || argv[0].trim().is_empty() {
+        return Err(CustomLanguageError::EmptyCommand(field));
+    }
+    if !argv.iter().any(|arg| arg.contains(placeholder)) {
+        return Err(CustomLanguageError::MissingPlaceholder { field, placeholder });
+    }
+    Ok(())
+}
+
+fn expand(template: &[String], source: &str, artifact: &str) -> Vec<String> {
+    template
+        .iter()
+        .map(|arg| {
+            arg.replace(SOURCE_PLACEHOLDER, source)
+                .replace(ARTIFACT_PLACEHOLDER, artifact)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(parts: &[&str]) -> Vec<String> {
+        parts.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn zig() -> CustomLanguage {
+        CustomLanguage {
+            name: "Zig".to_string(),
+            file_extension: "zig".to_string(),
+            needs_compilation: true,
+            compile_argv: argv(&[
+                "zig",
+                "build-exe",
+                "{source}",
+                "-O",
+                "ReleaseFast",
+                "-femit-bin={artifact}",
+            ]),
+            run_argv: argv(&["./{artifact}"]),
+        }
+    }
+
+    #[test]
+    fn test_compiled_templates() {
+        let zig = zig();
+        assert!(zig.validate().is_ok());
+        assert_eq!(zig.filename(), "main.zig");
+        assert_eq!(zig.artifact_name(), Some("main"));
+        assert_eq!(
+            zig.compile_command("main.zig", "main"),
+            Some(argv(&[
+                "zig",
+                "build-exe",
+                "main.zig",
+                "-O",
+                "ReleaseFast",
+                "-femit-bin=main"
+            ]))
+        );
+        assert_eq!(zig.run_command("main.zig", "main"), argv(&["./main"]));
+    }
+
+    #[test]
+    fn test_interpreted_templates() {
+        let lua = CustomLanguage {
+            name: "Lua".to_string(),
+            file_extension: "lua".to_string(),
+            needs_compilation: false,
+            compile_argv: vec![],
+            run_argv: argv(&["lua5.4", "{source}"]),
+        };
+        assert!(lua.validate().is_ok());
+        assert_eq!(lua.artifact_name(), None);
+        assert_eq!(lua.compile_command("main.lua", "main"), None);
+        assert_eq!(
+            lua.run_command("main.lua", "main"),
+            argv(&["lua5.4", "main.lua"])
+        );
+    }
+
+    #[test]
+    fn test_validation_errors() {
+        let mut spec = zig();
+        spec.compile_argv = argv(&["zig", "build-exe", "{source}"]);
+        assert_eq!(
+            spec.validate(),
+            Err(CustomLanguageError::MissingPlaceholder {
+                field: "compile_argv",
+                placeholder: ARTIFACT_PLACEHOLDER,
+            })
+        );
+
+        let mut spec = zig();
+        spec.run_argv = vec![];
+        assert_eq!(
+            spec.validate(),
+            Err(CustomLanguageError::EmptyCommand("run_argv"))
+        );
+
+        let mut spec = zig();
+        spec.file_extension = "../zig".to_string();
+        assert!(matches!(
+            spec.validate(),
+            Err(CustomLanguageError::InvalidExtension(_))
+        ));
+    }
+
+    #[test]
+    fn test_serialization() {
+        let json = serde_json::to_string(&zig()).unwrap();
+        let deserialized: CustomLanguage = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, zig());
+    }
+}
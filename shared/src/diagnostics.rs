@@ -0,0 +1,366 @@
+//! Parsing of compiler diagnostics
+//!
+//! Compilers report errors as text on stderr. [`parse_compiler_output`] extracts the
+//! location and message of each diagnostic so the frontend can highlight the
+//! offending line of a compile error. Supported formats:
+//!
+//! * gcc and clang (also kotlinc): `main.cpp:3:13: error: message`
+//! * rustc: `error[E0308]: message` followed by ` --> main.rs:2:18`
+//! * javac: `Main.java:3: error: message`, with the column taken from the caret line
+//! * Python: `SyntaxError` reports and tracebacks, located at the innermost frame
+//!
+//! Anything that isn't recognized is skipped.
+
+use crate::compile_log::strip_ansi;
+use crate::{ErrorInfo, ProgrammingLanguage};
+use serde::{Deserialize, Serialize};
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+/// A single compiler message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// How serious the message is
+    pub severity: Severity,
+    /// File the message refers to, as printed by the compiler
+    pub file: Option<String>,
+    /// 1-based line number
+    pub line: Option<u32>,
+    /// 1-based column number
+    pub column: Option<u32>,
+    /// Message text without the location and severity prefix
+    pub message: String,
+    /// Compiler error code, e.g. `E0308` for rustc
+    pub code: Option<String>,
+}
+
+impl Diagnostic {
+    /// Whether this diagnostic makes the compilation fail
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+/// Extracts the diagnostics from a compiler's stderr
+///
+/// ANSI color codes are ignored. Diagnostics are returned in the order printed.
+pub fn parse_compiler_output(language: ProgrammingLanguage, stderr: &str) -> Vec<Diagnostic> {
+    let stderr = strip_ansi(stderr);
+    match language {
+        ProgrammingLanguage::Rust => parse_rustc(&stderr),
+        ProgrammingLanguage::Java => parse_javac(&stderr),
+        ProgrammingLanguage::Python2 | ProgrammingLanguage::Python3 => parse_python(&stderr),
+        _ => stderr.lines().filter_map(parse_located_line).collect(),
+    }
+}
+
+impl ErrorInfo {
+    /// Creates error info for a compile error from the compiler's stderr
+    ///
+    /// Message, line, column and code come from the first error diagnostic; warnings
+    /// are never reported as the cause. The full stderr is kept.
+    pub fn from_compiler_output(language: ProgrammingLanguage, stderr: &str) -> Self {
+        let first_error = parse_compiler_output(language, stderr)
+            .into_iter()
+            .find(Diagnostic::is_error);
+        let mut error =
+            Self::compilation_error("Compilation failed".to_string(), Some(stderr.to_string()));
+        if let Some(diagnostic) = first_error {
+            error.message = diagnostic.message;
+            error.line = diagnostic.line;
+            error.column = diagnostic.column;
+            error.code = diagnostic.code;
+        }
+        error
+    }
+}
+
+/// Parses `file:line[:column]: severity: message`
+fn parse_located_line(line: &str) -> Option<Diagnostic> {
+    let mut parts = line.splitn(4, ':');
+    let file = parts.next()?;
+    let line_number: u32 = parts.next()?.parse().ok()?;
+    let third = parts.next()?;
+    let (column, rest) = match third.parse::<u32>() {
+        Ok(column) => (Some(column), parts.next()?.to_string()),
+        Err(_) => match parts.next() {
+            Some(rest) => (None, format!("{}:{}", third, rest)),
+            None => (None, third.to_string()),
+        },
+    };
+    let (severity, message) = split_severity(rest.trim_start())?;
+    if file.is_empty() || file.contains(char::is_whitespace) {
+        return None;
+    }
+    Some(Diagnostic {
+        severity,
+        file: Some(file.to_string()),
+        line: Some(line_number),
+        column,
+        message: message.trim().to_string(),
+        code: None,
+    })
+}
+
+fn split_severity(text: &str) -> Option<(Severity, &str)> {
+    const PREFIXES: [(&str, Severity); 4] = [
+        ("fatal error: ", Severity::Error),
+        ("error: ", Severity::Error),
+        ("warning: ", Severity::Warning),
+        ("note: ", Severity::Note),
+    ];
+    PREFIXES.iter().find_map(|(prefix, severity)| {
+        text.strip_prefix(prefix)
+            .map(|message| (*severity, message))
+    })
+}
+
+fn parse_rustc(stderr: &str) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    // Whether the last diagnostic may still receive its ` --> ` location
+    let mut awaiting_location = false;
+    for line in stderr.lines() {
+        if let Some(location) = line.trim_start().strip_prefix("--> ") {
+            if awaiting_location && let Some(last) = diagnostics.last_mut() {
+                let mut parts = location.rsplitn(3, ':');
+                last.column = parts.next().and_then(|c| c.parse().ok());
+                last.line = parts.next().and_then(|l| l.parse().ok());
+                last.file = parts.next().map(str::to_string);
+            }
+            awaiting_location = false;
+            continue;
+        }
+        // Top-level messages start in the first column; `help:` and `note:` there
+        // belong to the previous diagnostic
+        let Some((severity, code, message)) = split_rustc_header(line) else {
+            continue;
+        };
+        if is_rustc_summary(message) {
+            awaiting_location = false;
+            continue;
+        }
+        diagnostics.push(Diagnostic {
+            severity,
+            file: None,
+            line: None,
+            column: None,
+            message: message.trim().to_string(),
+            code,
+        });
+        awaiting_location = true;
+    }
+    diagnostics
+}
+
+/// Parses `error[E0308]: message`, `error: message` and `warning: message`
+fn split_rustc_header(line: &str) -> Option<(Severity, Option<String>, &str)> {
+    let (severity, rest) = if let Some(rest) = line.strip_prefix("error") {
+        (Severity::Error, rest)
+    } else if let Some(rest) = line.strip_prefix("warning") {
+        (Severity::Warning, rest)
+    } else {
+        return None;
+    };
+    if let Some(message) = rest.strip_prefix(": ") {
+        return Some((severity, None, message));
+    }
+    let (code, message) = rest.strip_prefix('[')?.split_once("]: ")?;
+    Some((severity, Some(code.to_string()), message))
+}
+
+fn is_rustc_summary(message: &str) -> bool {
+    message.starts_with("aborting due to")
+        || message.ends_with("warning emitted")
+        || message.ends_with("warnings emitted")
+}
+
+fn parse_javac(stderr: &str) -> Vec<Diagnostic> {
+    let lines: Vec<&str> = stderr.lines().collect();
+    let mut diagnostics = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let Some(mut diagnostic) = parse_located_line(line) else {
+            continue;
+        };
+        // The message is followed by the source line and a caret under the column
+        diagnostic.column = diagnostic.column.or_else(|| {
+            let caret = lines.get(i + 2)?;
+            let column = caret.find('^')?;
+            caret[..column]
+                .trim()
+                .is_empty()
+                .then_some(column as u32 + 1)
+        });
+        diagnostics.push(diagnostic);
+    }
+    diagnostics
+}
+
+/// Reports the exception of a traceback or syntax error at the innermost frame
+///
+/// Python re-indents the source line it quotes, so the column is not recoverable.
+fn parse_python(stderr: &str) -> Vec<Diagnostic> {
+    let mut location: Option<(String, u32)> = None;
+    let mut diagnostics = Vec::new();
+    for line in stderr.lines() {
+        if let Some(frame) = line.trim_start().strip_prefix("File \"") {
+            location = frame.split_once("\", line ").and_then(|(file, rest)| {
+                let number = rest.split(',').next()?.trim().parse().ok()?;
+                Some((file.to_string(), number))
+            });
+            continue;
+        }
+        if line.starts_with(char::is_whitespace) || !is_python_exception(line) {
+            continue;
+        }
+        let (file, line_number) = location.take().unzip();
+        diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            file,
+            line: line_number,
+            column: None,
+            message: line.trim().to_string(),
+            code: None,
+        });
+    }
+    diagnostics
+}
+
+/// Matches the final `SomeError: message` line of a traceback
+fn is_python_exception(line: &str) -> bool {
+    let name = line.split(':').next().unwrap_or_default();
+    let name = name.rsplit('.').next().unwrap_or_default();
+    line.contains(':')
+        && !name.is_empty()
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && (name.ends_with("Error") || name.ends_with("Exception") || name == "KeyboardInterrupt")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GCC_ERRORS: &str = include_str!("../fixtures/diagnostics/gcc_errors.txt");
+    const GCC_WARNINGS: &str = include_str!("../fixtures/diagnostics/gcc_warnings.txt");
+    const RUSTC_ERRORS: &str = include_str!("../fixtures/diagnostics/rustc_errors.txt");
+    const RUSTC_WARNINGS: &str = include_str!("../fixtures/diagnostics/rustc_warnings.txt");
+    const JAVAC_ERRORS: &str = include_str!("../fixtures/diagnostics/javac_errors.txt");
+    const PYTHON_SYNTAX: &str = include_str!("../fixtures/diagnostics/python_syntax.txt");
+    const PYTHON_TRACEBACK: &str = include_str!("../fixtures/diagnostics/python_traceback.txt");
+
+    fn locations(diagnostics: &[Diagnostic]) -> Vec<(Severity, Option<u32>, Option<u32>)> {
+        diagnostics
+            .iter()
+            .map(|d| (d.severity, d.line, d.column))
+            .collect()
+    }
+
+    #[test]
+    fn test_gcc() {
+        let diagnostics = parse_compiler_output(ProgrammingLanguage::Cpp17, GCC_ERRORS);
+        assert_eq!(
+            locations(&diagnostics),
+            vec![
+                (Severity::Error, Some(3), Some(13)),
+                (Severity::Error, Some(4), Some(18)),
+                (Severity::Warning, Some(3), Some(9)),
+            ]
+        );
+        assert_eq!(diagnostics[1].message, "'y' was not declared in this scope");
+        assert_eq!(diagnostics[0].file.as_deref(), Some("main.cpp"));
+
+        let error = ErrorInfo::from_compiler_output(ProgrammingLanguage::Cpp17, GCC_ERRORS);
+        assert_eq!(
+            error.message,
+            "invalid conversion from 'const char*' to 'int' [-fpermissive]"
+        );
+        assert_eq!((error.line, error.column), (Some(3), Some(13)));
+        assert_eq!(error.stderr.as_deref(), Some(GCC_ERRORS));
+    }
+
+    #[test]
+    fn test_gcc_colored() {
+        let colored = "\x1b[01m\x1b[Kmain.c:2:5:\x1b[m\x1b[K \x1b[01;31m\x1b[Kerror: \x1b[m\x1b[Kexpected ';'\n";
+        let diagnostics = parse_compiler_output(ProgrammingLanguage::C, colored);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "expected ';'");
+    }
+
+    #[test]
+    fn test_rustc() {
+        let diagnostics = parse_compiler_output(ProgrammingLanguage::Rust, RUSTC_ERRORS);
+        assert_eq!(
+            locations(&diagnostics),
+            vec![
+                (Severity::Error, Some(3), Some(20)),
+                (Severity::Error, Some(2), Some(18)),
+            ]
+        );
+        assert_eq!(diagnostics[1].code.as_deref(), Some("E0308"));
+        assert_eq!(diagnostics[1].file.as_deref(), Some("main.rs"));
+
+        let error = ErrorInfo::from_compiler_output(ProgrammingLanguage::Rust, RUSTC_ERRORS);
+        assert_eq!(error.message, "cannot find value `y` in this scope");
+        assert_eq!(error.code.as_deref(), Some("E0425"));
+        assert_eq!((error.line, error.column), (Some(3), Some(20)));
+    }
+
+    #[test]
+    fn test_javac() {
+        let diagnostics = parse_compiler_output(ProgrammingLanguage::Java, JAVAC_ERRORS);
+        assert_eq!(
+            locations(&diagnostics),
+            vec![
+                (Severity::Error, Some(3), Some(17)),
+                (Severity::Error, Some(4), Some(28)),
+            ]
+        );
+        assert_eq!(diagnostics[1].message, "cannot find symbol");
+    }
+
+    #[test]
+    fn test_python() {
+        let diagnostics = parse_compiler_output(ProgrammingLanguage::Python3, PYTHON_SYNTAX);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "SyntaxError: '(' was never closed");
+        assert_eq!(diagnostics[0].line, Some(2));
+
+        let error = ErrorInfo::from_compiler_output(ProgrammingLanguage::Python3, PYTHON_TRACEBACK);
+        assert_eq!(
+            error.message,
+            "ZeroDivisionError: integer division or modulo by zero"
+        );
+        assert_eq!((error.line, error.column), (Some(2), None));
+    }
+
+    #[test]
+    fn test_warnings_only() {
+        for (language, stderr) in [
+            (ProgrammingLanguage::Cpp17, GCC_WARNINGS),
+            (ProgrammingLanguage::Rust, RUSTC_WARNINGS),
+        ] {
+            let diagnostics = parse_compiler_output(language, stderr);
+            assert_eq!(diagnostics.len(), 1, "{}", stderr);
+            assert_eq!(diagnostics[0].severity, Severity::Warning);
+
+            let error = ErrorInfo::from_compiler_output(language, stderr);
+            assert_eq!(error.message, "Compilation failed");
+            assert_eq!(error.line, None);
+        }
+    }
+
+    #[test]
+    fn test_unrecognized_output() {
+        assert!(
+            parse_compiler_output(ProgrammingLanguage::Cpp17, "ld: cannot find -lfoo\n").is_empty()
+        );
+        assert!(parse_compiler_output(ProgrammingLanguage::Rust, "").is_empty());
+        assert!(parse_compiler_output(ProgrammingLanguage::Python3, "Killed\n").is_empty());
+    }
+}
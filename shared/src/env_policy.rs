@@ -0,0 +1,373 @@
+//! Allowlist for environment variables set by judge tasks
+//!
+//! Some problems need a variable in the run environment (a locale for formatted
+//! output, `JAVA_TOOL_OPTIONS` for a larger thread stack), but a task must never be
+//! able to change how the sandbox loads or finds programs. Only allowlisted names
+//! with short, printable values pass; `LD_PRELOAD`, `PATH` and the like never do.
+//! Variables read as command line options are checked option by option:
+//! `JAVA_TOOL_OPTIONS` may only set the thread stack size (`-Xss64m`), never load
+//! an agent or run a command on error.
+//!
+//! A test case may add variables of its own (a thread count, a dataset path) on top
+//! of the task's; [`JudgeTask::env_for`] merges the two.
+
+use crate::{JudgeTask, TestCase};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+
+/// Variable names allowed by default
+pub const DEFAULT_ALLOWED_NAMES: &[&str] = &[
+    "LANG",
+    "LANGUAGE",
+    "LC_ALL",
+    "LC_CTYPE",
+    "LC_NUMERIC",
+    "TZ",
+    "JAVA_TOOL_OPTIONS",
+    "PYTHONHASHSEED",
+    "PYTHONIOENCODING",
+    "RUST_BACKTRACE",
+];
+
+/// Maximum value length in bytes allowed by default
+pub const DEFAULT_MAX_VALUE_LEN: usize = 256;
+
+/// Which environment variables a task may set
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvPolicy {
+    /// Names that may be set
+    pub allowed_names: Vec<String>,
+    /// Maximum value length in bytes
+    pub max_value_len: usize,
+}
+
+impl Default for EnvPolicy {
+    fn default() -> Self {
+        Self {
+            allowed_names: DEFAULT_ALLOWED_NAMES
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            max_value_len: DEFAULT_MAX_VALUE_LEN,
+        }
+    }
+}
+
+/// Why a task's environment is rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvError {
+    /// The name is not on the allowlist
+    NotAllowed(String),
+    /// The value is longer than the policy allows
+    ValueTooLong {
+        name: String,
+        len: usize,
+        max: usize,
+    },
+    /// The value contains control characters
+    InvalidValue(String),
+    /// The name is set more than once
+    Duplicate(String),
+    /// The value passes an option that is not allowed
+    UnsafeOption { name: String, option: String },
+}
+
+impl fmt::Display for EnvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvError::NotAllowed(name) => {
+                write!(f, "environment variable {} is not allowed", name)
+            }
+            EnvError::ValueTooLong { name, len, max } => write!(
+                f,
+                "environment variable {} is {} bytes long, the maximum is {}",
+                name, len, max
+            ),
+            EnvError::InvalidValue(name) => write!(
+                f,
+                "environment variable {} contains control characters",
+                name
+            ),
+            EnvError::Duplicate(name) => {
+                write!(f, "environment variable {} is set more than once", name)
+            }
+            EnvError::UnsafeOption { name, option } => write!(
+                f,
+                "environment variable {} passes option {:?}, which is not allowed",
+                name, option
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EnvError {}
+
+impl EnvPolicy {
+    /// Checks a list of variables, reporting the first violation
+    pub fn validate(&self, env: &[(String, String)]) -> Result<(), EnvError> {
+        let mut seen = HashSet::new();
+        for (name, value) in env {
+            if !self.allowed_names.iter().any(|allowed| allowed == name) {
+                return Err(EnvError::NotAllowed(name.clone()));
+            }
+            if !seen.insert(name.as_str()) {
+                return Err(EnvError::Duplicate(name.clone()));
+            }
+            if value.len() > self.max_value_len {
+                return Err(EnvError::ValueTooLong {
+                    name: name.clone(),
+                    len: value.len(),
+                    max: self.max_value_len,
+                });
+            }
+            if value.chars().any(char::is_control) {
+                return Err(EnvError::InvalidValue(name.clone()));
+            }
+            if name == "JAVA_TOOL_OPTIONS"
+                && let Some(option) = value
+                    .split_whitespace()
+                    .find(|option| !is_allowed_java_option(option))
+            {
+                return Err(EnvError::UnsafeOption {
+                    name: name.clone(),
+                    option: option.to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the task environment and that of every test case
+    ///
+    /// Errors name the test case whose environment is rejected, if any.
+    pub fn validate_task(&self, task: &JudgeTask) -> Result<(), (Option<String>, EnvError)> {
+        self.validate(&task.env).map_err(|e| (None, e))?;
+        for test_case in &task.test_cases {
+            if let Some(env) = &test_case.env {
+                self.validate(env)
+                    .map_err(|e| (Some(test_case.id.clone()), e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Keeps the variables this policy allows, dropping the rest
+    ///
+    /// For environments that reach the sandbox without going through
+    /// [`EnvPolicy::validate`]. A name set more than once keeps its first value.
+    pub fn filter(&self, env: &[(String, String)]) -> Vec<(String, String)> {
+        let mut seen = HashSet::new();
+        env.iter()
+            .filter(|var| self.validate(std::slice::from_ref(*var)).is_ok() && seen.insert(&var.0))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Whether a JVM option may be passed through `JAVA_TOOL_OPTIONS`
+///
+/// Only `-Xss<size>` is, with an optional `k`, `m` or `g` suffix.
+fn is_allowed_java_option(option: &str) -> bool {
+    option.strip_prefix("-Xss").is_some_and(|size| {
+        let digits = size.trim_end_matches(['k', 'K', 'm', 'M', 'g', 'G']);
+        !digits.is_empty()
+            && size.len() - digits.len() <= 1
+            && digits.bytes().all(|b| b.is_ascii_digit())
+    })
+}
+
+impl JudgeTask {
+    /// Returns the environment of a test case's run
+    ///
+    /// The test case's variables override task variables of the same name; the
+    /// order is the task's, followed by names only the test case sets.
+    pub fn env_for(&self, test_case: &TestCase) -> Vec<(String, String)> {
+        let overrides = test_case.env.as_deref().unwrap_or_default();
+        let mut env: Vec<(String, String)> = self
+            .env
+            .iter()
+            .map(|(name, value)| {
+                let value = overrides
+                    .iter()
+                    .find(|(n, _)| n == name)
+                    .map_or(value, |(_, v)| v);
+                (name.clone(), value.clone())
+            })
+            .collect();
+        for (name, value) in overrides {
+            if !env.iter().any(|(n, _)| n == name) {
+                env.push((name.clone(), value.clone()));
+            }
+        }
+        env
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProgrammingLanguage;
+    use crate::testing;
+
+    fn env(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_allowed_variables() {
+        let policy = EnvPolicy::default();
+        assert_eq!(policy.validate(&[]), Ok(()));
+        assert_eq!(
+            policy.validate(&env(&[
+                ("LC_ALL", "de_DE.UTF-8"),
+                ("JAVA_TOOL_OPTIONS", "-Xss64m"),
+            ])),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_rejected_names() {
+        let policy = EnvPolicy::default();
+        for name in ["LD_PRELOAD", "PATH", "LD_LIBRARY_PATH", "HOME", "lc_all"] {
+            assert_eq!(
+                policy.validate(&env(&[(name, "x")])),
+                Err(EnvError::NotAllowed(name.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_oversized_and_invalid_values() {
+        let policy = EnvPolicy::default();
+        let long = "x".repeat(DEFAULT_MAX_VALUE_LEN + 1);
+        assert_eq!(
+            policy.validate(&env(&[("TZ", &long)])),
+            Err(EnvError::ValueTooLong {
+                name: "TZ".to_string(),
+                len: DEFAULT_MAX_VALUE_LEN + 1,
+                max: DEFAULT_MAX_VALUE_LEN,
+            })
+        );
+        assert_eq!(
+            policy.validate(&env(&[("TZ", &"x".repeat(DEFAULT_MAX_VALUE_LEN))])),
+            Ok(())
+        );
+        assert_eq!(
+            policy.validate(&env(&[("LANG", "C\nLD_PRELOAD=evil.so")])),
+            Err(EnvError::InvalidValue("LANG".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_java_tool_options() {
+        let policy = EnvPolicy::default();
+        for value in ["-Xss64m", "-Xss1024k -Xss2G", ""] {
+            assert_eq!(
+                policy.validate(&env(&[("JAVA_TOOL_OPTIONS", value)])),
+                Ok(()),
+                "{}",
+                value
+            );
+        }
+        for (value, option) in [
+            ("-javaagent:/tmp/agent.jar", "-javaagent:/tmp/agent.jar"),
+            ("-Xss64m -agentpath:/tmp/a.so", "-agentpath:/tmp/a.so"),
+            ("-XX:OnError=sh", "-XX:OnError=sh"),
+            ("-XX:OnOutOfMemoryError=sh", "-XX:OnOutOfMemoryError=sh"),
+            ("-Xmx8g", "-Xmx8g"),
+            ("-Xss", "-Xss"),
+            ("-Xss64mm", "-Xss64mm"),
+        ] {
+            assert_eq!(
+                policy.validate(&env(&[("JAVA_TOOL_OPTIONS", value)])),
+                Err(EnvError::UnsafeOption {
+                    name: "JAVA_TOOL_OPTIONS".to_string(),
+                    option: option.to_string(),
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_filter() {
+        let policy = EnvPolicy::default();
+        assert_eq!(
+            policy.filter(&env(&[
+                ("LD_PRELOAD", "evil.so"),
+                ("TZ", "UTC"),
+                ("LANG", "C\n"),
+                ("TZ", "Asia/Tokyo"),
+                ("LC_ALL", "C"),
+            ])),
+            env(&[("TZ", "UTC"), ("LC_ALL", "C")])
+        );
+    }
+
+    fn task() -> JudgeTask {
+        let mut task = JudgeTask::new(
+            testing::submission(ProgrammingLanguage::Python3, "print(1)"),
+            testing::blank_test_cases(["1", "2"]),
+        );
+        task.env = env(&[("LANG", "C.UTF-8"), ("TZ", "UTC")]);
+        task
+    }
+
+    #[test]
+    fn test_env_for_test_case() {
+        let mut task = task();
+        assert_eq!(task.env_for(&task.test_cases[0]), task.env);
+
+        task.test_cases[1].env = Some(env(&[("PYTHONHASHSEED", "0"), ("TZ", "Asia/Tokyo")]));
+        assert_eq!(
+            task.env_for(&task.test_cases[1]),
+            env(&[
+                ("LANG", "C.UTF-8"),
+                ("TZ", "Asia/Tokyo"),
+                ("PYTHONHASHSEED", "0")
+            ])
+        );
+    }
+
+    #[test]
+    fn test_validate_task() {
+        let policy = EnvPolicy::default();
+        let mut task = task();
+        assert_eq!(policy.validate_task(&task), Ok(()));
+
+        task.test_cases[1].env = Some(env(&[("LD_PRELOAD", "evil.so")]));
+        assert_eq!(
+            policy.validate_task(&task),
+            Err((
+                Some("2".to_string()),
+                EnvError::NotAllowed("LD_PRELOAD".to_string())
+            ))
+        );
+
+        task.env.push(("PATH".to_string(), "/tmp".to_string()));
+        assert_eq!(
+            policy.validate_task(&task),
+            Err((None, EnvError::NotAllowed("PATH".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_old_test_cases_have_no_env() {
+        let mut value = serde_json::to_value(TestCase::new("1".to_string(), "", "")).unwrap();
+        value.as_object_mut().unwrap().remove("env");
+        let test_case: TestCase = serde_json::from_value(value).unwrap();
+        assert_eq!(test_case.env, None);
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        assert_eq!(
+            EnvPolicy::default().validate(&env(&[("TZ", "UTC"), ("TZ", "Asia/Tokyo")])),
+            Err(EnvError::Duplicate("TZ".to_string()))
+        );
+    }
+}
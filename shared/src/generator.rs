@@ -0,0 +1,215 @@
+//! Generator-based test cases
+//!
+//! Stress-style problems ship a generator and a reference solution instead of static
+//! files. For a [`TestCaseSource::Generated`] test case the judger runs the
+//! generator in the sandbox with the case's arguments and seed to produce the input,
+//! then runs the reference solution on that input to produce the expected output,
+//! and only then judges the submission.
+
+use crate::{JudgeTask, ProgrammingLanguage, TestCase};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A helper program shipped with the problem
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProgramSpec {
+    /// Language the program is written in
+    pub language: ProgrammingLanguage,
+    /// Program source code
+    pub source: String,
+}
+
+/// Parameters of a generated test case
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GeneratedTestCase {
+    /// Arguments passed to the generator
+    #[serde(default)]
+    pub generator_args: Vec<String>,
+    /// Random seed passed to the generator, so the same case is generated every time
+    pub seed: u64,
+}
+
+/// Where a test case's input and expected output come from
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TestCaseSource {
+    /// The test case's `input` and `expected_output` fields
+    #[default]
+    Static,
+    /// Produced by the task's generator and reference solution
+    Generated(GeneratedTestCase),
+}
+
+/// Why a task's generator setup is rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GeneratorError {
+    /// A test case is generated but the task has no generator
+    MissingGenerator(String),
+    /// A test case is generated but the task has no reference solution
+    MissingReferenceSolution(String),
+    /// The generator or reference solution has no source code
+    EmptySource(&'static str),
+    /// Helper programs must use a built-in language
+    UnsupportedLanguage(&'static str),
+}
+
+impl fmt::Display for GeneratorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeneratorError::MissingGenerator(id) => {
+                write!(f, "test case {:?} is generated but no generator is set", id)
+            }
+            GeneratorError::MissingReferenceSolution(id) => write!(
+                f,
+                "test case {:?} is generated but no reference solution is set",
+                id
+            ),
+            GeneratorError::EmptySource(program) => {
+                write!(f, "{} source must not be empty", program)
+            }
+            GeneratorError::UnsupportedLanguage(program) => {
+                write!(f, "{} must use a built-in language", program)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GeneratorError {}
+
+impl ProgramSpec {
+    fn validate(&self, program: &'static str) -> Result<(), GeneratorError> {
+        if self.language == ProgrammingLanguage::Custom {
+            Err(GeneratorError::UnsupportedLanguage(program))
+        } else if self.source.trim().is_empty() {
+            Err(GeneratorError::EmptySource(program))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl TestCase {
+    /// Creates a test case produced by the task's generator
+    pub fn generated(id: String, generator_args: Vec<String>, seed: u64) -> Self {
+        let mut test_case = Self::new(id, "", "");
+        test_case.source = TestCaseSource::Generated(GeneratedTestCase {
+            generator_args,
+            seed,
+        });
+        test_case
+    }
+
+    /// Returns the generator parameters if the test case is generated
+    pub fn generated_params(&self) -> Option<&GeneratedTestCase> {
+        match &self.source {
+            TestCaseSource::Static => None,
+            TestCaseSource::Generated(params) => Some(params),
+        }
+    }
+}
+
+impl JudgeTask {
+    /// Checks that generated test cases have a generator and reference solution
+    pub fn validate_generators(&self) -> Result<(), GeneratorError> {
+        if let Some(generator) = &self.generator {
+            generator.validate("generator")?;
+        }
+        if let Some(reference) = &self.reference_solution {
+            reference.validate("reference solution")?;
+        }
+
+        let Some(generated) = self
+            .test_cases
+            .iter()
+            .find(|tc| tc.generated_params().is_some())
+        else {
+            return Ok(());
+        };
+        if self.generator.is_none() {
+            return Err(GeneratorError::MissingGenerator(generated.id.clone()));
+        }
+        if self.reference_solution.is_none() {
+            return Err(GeneratorError::MissingReferenceSolution(
+                generated.id.clone(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::task;
+
+    fn program(source: &str) -> ProgramSpec {
+        ProgramSpec {
+            language: ProgrammingLanguage::Cpp17,
+            source: source.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_generated_cases_need_programs() {
+        let generated =
+            TestCase::generated("big".to_string(), vec!["-n".into(), "100000".into()], 42);
+        assert_eq!(generated.generated_params().unwrap().seed, 42);
+
+        let mut task = task(vec![
+            TestCase::new("1".to_string(), "1\n", "1\n"),
+            generated,
+        ]);
+        assert_eq!(
+            task.validate_generators(),
+            Err(GeneratorError::MissingGenerator("big".to_string()))
+        );
+        task.generator = Some(program("int main() {}"));
+        assert_eq!(
+            task.validate_generators(),
+            Err(GeneratorError::MissingReferenceSolution("big".to_string()))
+        );
+        task.reference_solution = Some(program("int main() {}"));
+        assert_eq!(task.validate_generators(), Ok(()));
+
+        task.reference_solution = Some(program(" "));
+        assert_eq!(
+            task.validate_generators(),
+            Err(GeneratorError::EmptySource("reference solution"))
+        );
+        task.generator = Some(ProgramSpec {
+            language: ProgrammingLanguage::Custom,
+            source: "x".to_string(),
+        });
+        assert_eq!(
+            task.validate_generators(),
+            Err(GeneratorError::UnsupportedLanguage("generator"))
+        );
+    }
+
+    #[test]
+    fn test_static_tasks_need_nothing() {
+        let task = task(vec![TestCase::new("1".to_string(), "1\n", "1\n")]);
+        assert_eq!(task.validate_generators(), Ok(()));
+    }
+
+    #[test]
+    fn test_serde_defaults() {
+        let mut task = task(vec![TestCase::generated("1".to_string(), vec![], 7)]);
+        task.generator = Some(program("int main() {}"));
+        task.reference_solution = Some(program("int main() {}"));
+        let json = serde_json::to_string(&task).unwrap();
+        assert_eq!(serde_json::from_str::<JudgeTask>(&json).unwrap(), task);
+
+        let mut value = serde_json::to_value(&task).unwrap();
+        let object = value.as_object_mut().unwrap();
+        object.remove("generator");
+        object.remove("reference_solution");
+        value["test_cases"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("source");
+        let old: JudgeTask = serde_json::from_value(value).unwrap();
+        assert_eq!(old.generator, None);
+        assert_eq!(old.reference_solution, None);
+        assert_eq!(old.test_cases[0].source, TestCaseSource::Static);
+    }
+}
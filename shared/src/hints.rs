@@ -0,0 +1,450 @@
+//! Explanations of common beginner errors
+//!
+//! A compile or runtime error often has a well-known cause that the raw compiler or
+//! interpreter output hides from beginners, e.g. `NoClassDefFoundError: Main` means
+//! the public class isn't named `Main`. Hint rules map such errors to a short
+//! explanation stored in [`ErrorInfo::hint`]. Like screening rules, they are data;
+//! admins can add their own in a TOML file:
+//!
+//! ```toml
+//! [[rules]]
+//! id = "cpp-vla"
+//! languages = ["Cpp17"]
+//! verdicts = ["CompileError"]
+//! pattern = "ISO C++ forbids variable length array"
+//! hint = "Use std::vector instead of a variable length array"
+//! ```
+//!
+//! Patterns are substrings (or regexes with `regex = true`) matched against the
+//! error message and stderr. Hints are best effort and never change a verdict.
+
+use crate::{ErrorInfo, JudgeResult, JudgeStatus, ProgrammingLanguage};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+/// Verdicts a hint rule can explain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HintVerdict {
+    CompileError,
+    RuntimeError,
+}
+
+impl HintVerdict {
+    fn matches(&self, status: JudgeStatus) -> bool {
+        match self {
+            HintVerdict::CompileError => status == JudgeStatus::CompileError,
+            HintVerdict::RuntimeError => status.is_runtime_error(),
+        }
+    }
+}
+
+/// A hint rule as written in a rules file
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HintSpec {
+    /// Identifier of the rule, e.g. "java-main-class"
+    pub id: String,
+    /// Languages the rule applies to; empty means every language
+    #[serde(default)]
+    pub languages: Vec<ProgrammingLanguage>,
+    /// Verdicts the rule applies to; empty means compile and runtime errors
+    #[serde(default)]
+    pub verdicts: Vec<HintVerdict>,
+    /// Substring (or regex, see `regex`) searched in the error message and stderr
+    pub pattern: String,
+    /// Whether `pattern` is a regular expression
+    #[serde(default)]
+    pub regex: bool,
+    /// Explanation shown to the user
+    pub hint: String,
+}
+
+/// Errors raised while loading hint rules
+#[derive(Debug)]
+pub enum HintError {
+    /// The file could not be read
+    Io(std::io::Error),
+    /// The TOML document is malformed
+    Parse(String),
+    /// A regex rule does not compile
+    InvalidPattern { pattern: String, message: String },
+}
+
+impl fmt::Display for HintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HintError::Io(e) => write!(f, "failed to read hint rules: {}", e),
+            HintError::Parse(e) => write!(f, "invalid hint rules: {}", e),
+            HintError::InvalidPattern { pattern, message } => {
+                write!(f, "invalid hint pattern {:?}: {}", pattern, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for HintError {}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    spec: HintSpec,
+    regex: Option<Regex>,
+}
+
+impl Rule {
+    fn applies(&self, language: ProgrammingLanguage, status: JudgeStatus) -> bool {
+        let verdicts = &self.spec.verdicts;
+        let verdict_matches = if verdicts.is_empty() {
+            status == JudgeStatus::CompileError || status.is_runtime_error()
+        } else {
+            verdicts.iter().any(|v| v.matches(status))
+        };
+        verdict_matches
+            && (self.spec.languages.is_empty() || self.spec.languages.contains(&language))
+    }
+
+    fn is_match(&self, text: &str) -> bool {
+        match &self.regex {
+            Some(re) => re.is_match(text),
+            None => text.contains(self.spec.pattern.as_str()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    #[serde(default)]
+    rules: Vec<HintSpec>,
+}
+
+/// Compiled set of hint rules
+#[derive(Debug, Clone)]
+pub struct HintRules {
+    rules: Vec<Rule>,
+}
+
+const C_FAMILY: &[ProgrammingLanguage] = &[
+    ProgrammingLanguage::C,
+    ProgrammingLanguage::Cpp,
+    ProgrammingLanguage::Cpp11,
+    ProgrammingLanguage::Cpp14,
+    ProgrammingLanguage::Cpp17,
+    ProgrammingLanguage::Cpp20,
+];
+
+const PYTHON: &[ProgrammingLanguage] =
+    &[ProgrammingLanguage::Python2, ProgrammingLanguage::Python3];
+
+const JVM: &[ProgrammingLanguage] = &[ProgrammingLanguage::Java, ProgrammingLanguage::Kotlin];
+
+const CE: &[HintVerdict] = &[HintVerdict::CompileError];
+const RE: &[HintVerdict] = &[HintVerdict::RuntimeError];
+
+/// A built-in rule, compiled into a [`HintSpec`] by [`HintRules::builtin`]
+struct HintRule {
+    id: &'static str,
+    languages: &'static [ProgrammingLanguage],
+    verdicts: &'static [HintVerdict],
+    pattern: &'static str,
+    regex: bool,
+    hint: &'static str,
+}
+
+/// Built-in rules
+const BUILTIN_RULES: &[HintRule] = &[
+    HintRule {
+        id: "java-public-class-file",
+        languages: &[ProgrammingLanguage::Java],
+        verdicts: CE,
+        pattern: r"class \w+ is public, should be declared in a file named",
+        regex: true,
+        hint: "The public class must be named Main",
+    },
+    HintRule {
+        id: "java-main-class",
+        languages: JVM,
+        verdicts: RE,
+        pattern: r"NoClassDefFoundError|Could not find or load main class",
+        regex: true,
+        hint: "The public class must be named Main",
+    },
+    HintRule {
+        id: "jvm-stack-overflow",
+        languages: JVM,
+        verdicts: RE,
+        pattern: "StackOverflowError",
+        regex: false,
+        hint: "Recursion is too deep; use an iterative approach or reduce the recursion depth",
+    },
+    HintRule {
+        id: "c-missing-main",
+        languages: C_FAMILY,
+        verdicts: CE,
+        pattern: "undefined reference to `main'",
+        regex: false,
+        hint: "The program needs a main function",
+    },
+    HintRule {
+        id: "cpp-bits-header",
+        languages: C_FAMILY,
+        verdicts: CE,
+        pattern: "bits/stdc++.h",
+        regex: false,
+        hint: "<bits/stdc++.h> is not available with this compiler; include the standard headers you use",
+    },
+    HintRule {
+        id: "segmentation-fault",
+        languages: &[],
+        verdicts: RE,
+        pattern: "SIGSEGV",
+        regex: false,
+        hint: "Segmentation fault: check array bounds, null pointers and recursion depth",
+    },
+    HintRule {
+        id: "python3-print",
+        languages: &[ProgrammingLanguage::Python3],
+        verdicts: CE,
+        pattern: "Missing parentheses in call to 'print'",
+        regex: false,
+        hint: "This is Python 3, where print is a function: print(x)",
+    },
+    HintRule {
+        id: "python-recursion",
+        languages: PYTHON,
+        verdicts: RE,
+        pattern: "RecursionError",
+        regex: false,
+        hint: "Recursion is too deep; raise the limit with sys.setrecursionlimit or use an iterative approach",
+    },
+    HintRule {
+        id: "python-eof",
+        languages: PYTHON,
+        verdicts: RE,
+        pattern: "EOFError",
+        regex: false,
+        hint: "The program tried to read past the end of the input",
+    },
+    HintRule {
+        id: "python-module",
+        languages: PYTHON,
+        verdicts: RE,
+        pattern: "ModuleNotFoundError",
+        regex: false,
+        hint: "Only the standard library is available",
+    },
+];
+
+impl HintRules {
+    /// Returns the built-in rule set
+    pub fn builtin() -> Self {
+        let specs = BUILTIN_RULES
+            .iter()
+            .map(|rule| HintSpec {
+                id: rule.id.to_string(),
+                languages: rule.languages.to_vec(),
+                verdicts: rule.verdicts.to_vec(),
+                pattern: rule.pattern.to_string(),
+                regex: rule.regex,
+                hint: rule.hint.to_string(),
+            })
+            .collect();
+        Self::from_specs(specs).expect("built-in hint rules are valid")
+    }
+
+    /// Compiles rules from their specs
+    pub fn from_specs(specs: Vec<HintSpec>) -> Result<Self, HintError> {
+        let rules = specs
+            .into_iter()
+            .map(|spec| {
+                let regex = if spec.regex {
+                    Some(
+                        Regex::new(&spec.pattern).map_err(|e| HintError::InvalidPattern {
+                            pattern: spec.pattern.clone(),
+                            message: e.to_string(),
+                        })?,
+                    )
+                } else {
+                    None
+                };
+                Ok(Rule { spec, regex })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Parses rules from a TOML document
+    pub fn from_toml_str(s: &str) -> Result<Self, HintError> {
+        let file: RulesFile = toml::from_str(s).map_err(|e| HintError::Parse(e.to_string()))?;
+        Self::from_specs(file.rules)
+    }
+
+    /// Reads and parses rules from a TOML file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, HintError> {
+        let content = std::fs::read_to_string(path).map_err(HintError::Io)?;
+        Self::from_toml_str(&content)
+    }
+
+    /// Adds `overrides` in front of these rules, so they win when both match
+    pub fn with_overrides(mut self, overrides: HintRules) -> Self {
+        let mut rules = overrides.rules;
+        rules.append(&mut self.rules);
+        self.rules = rules;
+        self
+    }
+
+    /// Returns the hint of the first rule matching the error, if any
+    pub fn hint_for(
+        &self,
+        language: ProgrammingLanguage,
+        status: JudgeStatus,
+        error: &ErrorInfo,
+    ) -> Option<&str> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.applies(language, status))
+            .find(|rule| {
+                rule.is_match(&error.message)
+                    || error.stderr.as_deref().is_some_and(|s| rule.is_match(s))
+            })
+            .map(|rule| rule.spec.hint.as_str())
+    }
+}
+
+impl JudgeResult {
+    /// Fills [`ErrorInfo::hint`] of the result and its test cases from `rules`
+    pub fn attach_hints(&mut self, rules: &HintRules, language: ProgrammingLanguage) {
+        let attach = |status: JudgeStatus, error: &mut Option<ErrorInfo>| {
+            if let Some(error) = error
+                && let Some(hint) = rules.hint_for(language, status, error)
+            {
+                error.hint = Some(hint.to_string());
+            }
+        };
+        attach(self.status, &mut self.error_info);
+        for tc in &mut self.test_cases {
+            attach(tc.status, &mut tc.error_info);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RuntimeErrorType;
+    use uuid::Uuid;
+
+    const RE_OTHER: JudgeStatus = JudgeStatus::RuntimeError(RuntimeErrorType::Other);
+
+    fn stderr(text: &str) -> ErrorInfo {
+        ErrorInfo::compilation_error("Runtime error".to_string(), Some(text.to_string()))
+    }
+
+    #[test]
+    fn test_builtin_hints() {
+        let rules = HintRules::builtin();
+        let error = stderr(
+            "Error: Could not find or load main class Main\n\
+             Caused by: java.lang.NoClassDefFoundError: Main (wrong name: Solution)\n",
+        );
+        assert_eq!(
+            rules.hint_for(ProgrammingLanguage::Java, RE_OTHER, &error),
+            Some("The public class must be named Main")
+        );
+        // Only runtime errors, only JVM languages
+        assert_eq!(
+            rules.hint_for(ProgrammingLanguage::Java, JudgeStatus::WrongAnswer, &error),
+            None
+        );
+        assert_eq!(
+            rules.hint_for(ProgrammingLanguage::Cpp17, RE_OTHER, &error),
+            None
+        );
+
+        let error = ErrorInfo::killed_by_signal(11, None);
+        let status = JudgeStatus::runtime_error_from_signal(11);
+        assert!(
+            rules
+                .hint_for(ProgrammingLanguage::Rust, status, &error)
+                .unwrap()
+                .starts_with("Segmentation fault")
+        );
+
+        let error = stderr("/usr/bin/ld: undefined reference to `main'\n");
+        assert_eq!(
+            rules.hint_for(ProgrammingLanguage::C, JudgeStatus::CompileError, &error),
+            Some("The program needs a main function")
+        );
+    }
+
+    #[test]
+    fn test_admin_rules() {
+        let custom = HintRules::from_toml_str(
+            r#"
+            [[rules]]
+            id = "cpp-vla"
+            languages = ["Cpp17"]
+            verdicts = ["CompileError"]
+            pattern = 'ISO C\+\+ forbids variable length array'
+            regex = true
+            hint = "Use std::vector instead of a variable length array"
+
+            [[rules]]
+            id = "main-override"
+            pattern = "undefined reference to `main'"
+            hint = "Custom main hint"
+            "#,
+        )
+        .unwrap();
+        let rules = HintRules::builtin().with_overrides(custom);
+
+        let error = stderr("a.cpp:3:9: error: ISO C++ forbids variable length array 'a'\n");
+        assert_eq!(
+            rules.hint_for(
+                ProgrammingLanguage::Cpp17,
+                JudgeStatus::CompileError,
+                &error
+            ),
+            Some("Use std::vector instead of a variable length array")
+        );
+        let error = stderr("undefined reference to `main'\n");
+        assert_eq!(
+            rules.hint_for(ProgrammingLanguage::C, JudgeStatus::CompileError, &error),
+            Some("Custom main hint")
+        );
+
+        assert!(HintRules::from_toml_str("[[rules]]\nid = \"x\"\n").is_err());
+        let invalid = "[[rules]]\nid = \"x\"\npattern = \"(\"\nregex = true\nhint = \"h\"\n";
+        assert!(matches!(
+            HintRules::from_toml_str(invalid),
+            Err(HintError::InvalidPattern { .. })
+        ));
+    }
+
+    #[test]
+    fn test_attach_hints() {
+        let mut result = JudgeResult::with_error(
+            JudgeStatus::CompileError,
+            0,
+            0,
+            stderr(
+                "  File \"main.py\", line 1\nSyntaxError: Missing parentheses in call to 'print'. Did you mean print(...)?\n",
+            ),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        );
+        result.attach_hints(&HintRules::builtin(), ProgrammingLanguage::Python3);
+        assert_eq!(
+            result.error_info.unwrap().hint.as_deref(),
+            Some("This is Python 3, where print is a function: print(x)")
+        );
+
+        // Old payloads without a hint still parse
+        let mut value = serde_json::to_value(stderr("x")).unwrap();
+        value.as_object_mut().unwrap().remove("hint");
+        let old: ErrorInfo = serde_json::from_value(value).unwrap();
+        assert_eq!(old.hint, None);
+    }
+}
@@ -0,0 +1,345 @@
+//! References to OCI toolchain images
+//!
+//! A language may name an image (e.g. `axon/toolchain-gcc13`) whose filesystem
+//! provides its compiler and runtime, so adding a language doesn't mean installing
+//! it on every judger host. Images are pulled and unpacked out of band into the
+//! judger's image store; [`ImageRef::store_path`] is where a reference lives there.
+//!
+//! References follow the usual `[registry/]repository[:tag][@sha256:digest]` form.
+//! The first component is a registry if it contains a `.` or `:` or is `localhost`;
+//! it must be a `hostname[:port]`. Every part ends up as a path component of
+//! [`ImageRef::store_path`], so none of them can be `.` or `..`.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Tag assumed when a reference has neither tag nor digest
+pub const DEFAULT_TAG: &str = "latest";
+
+/// Parsed OCI image reference
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ImageRef {
+    /// Registry host (with port), `None` for the default registry
+    pub registry: Option<String>,
+    /// Slash-separated repository path, e.g. `axon/toolchain-gcc13`
+    pub repository: String,
+    /// Tag, e.g. `1.2`
+    pub tag: Option<String>,
+    /// Content digest as `sha256:<64 hex digits>`, pins the image exactly
+    pub digest: Option<String>,
+}
+
+impl ImageRef {
+    /// Returns the relative directory of the unpacked image in the image store
+    ///
+    /// `[registry/]repository/<tag>`, or `.../sha256-<hex>` for pinned images, so
+    /// a digest always wins over a tag. A registry port's `:` becomes `_`.
+    pub fn store_path(&self) -> PathBuf {
+        let mut path = PathBuf::new();
+        if let Some(registry) = &self.registry {
+            path.push(registry.replace(':', "_"));
+        }
+        path.push(&self.repository);
+        match (&self.digest, &self.tag) {
+            (Some(digest), _) => path.push(digest.replace(':', "-")),
+            (None, Some(tag)) => path.push(tag),
+            (None, None) => path.push(DEFAULT_TAG),
+        }
+        path
+    }
+}
+
+/// Why an image reference is rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseImageRefError {
+    /// The reference is empty
+    Empty,
+    /// A repository path component is not lowercase alphanumerics joined by `.`, `_`
+    /// or `-`
+    InvalidRepository(String),
+    /// The registry is not a `hostname[:port]`
+    InvalidRegistry(String),
+    /// The tag is empty, too long or has characters other than `[A-Za-z0-9_.-]`
+    InvalidTag(String),
+    /// The digest is not `sha256:` followed by 64 lowercase hex digits
+    InvalidDigest(String),
+}
+
+impl fmt::Display for ParseImageRefError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseImageRefError::Empty => write!(f, "image reference must not be empty"),
+            ParseImageRefError::InvalidRepository(repository) => {
+                write!(f, "invalid image repository {:?}", repository)
+            }
+            ParseImageRefError::InvalidRegistry(registry) => {
+                write!(f, "invalid image registry {:?}", registry)
+            }
+            ParseImageRefError::InvalidTag(tag) => write!(f, "invalid image tag {:?}", tag),
+            ParseImageRefError::InvalidDigest(digest) => {
+                write!(f, "invalid image digest {:?}", digest)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseImageRefError {}
+
+impl fmt::Display for ImageRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(registry) = &self.registry {
+            write!(f, "{}/", registry)?;
+        }
+        write!(f, "{}", self.repository)?;
+        if let Some(tag) = &self.tag {
+            write!(f, ":{}", tag)?;
+        }
+        if let Some(digest) = &self.digest {
+            write!(f, "@{}", digest)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ImageRef {
+    type Err = ParseImageRefError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(ParseImageRefError::Empty);
+        }
+        let (name, digest) = match s.split_once('@') {
+            Some((name, digest)) => {
+                if !is_valid_digest(digest) {
+                    return Err(ParseImageRefError::InvalidDigest(digest.to_string()));
+                }
+                (name, Some(digest.to_string()))
+            }
+            None => (s, None),
+        };
+        // A `:` after the last `/` starts the tag; earlier ones belong to a port
+        let last_slash = name.rfind('/').map_or(0, |i| i + 1);
+        let (name, tag) = match name[last_slash..].find(':') {
+            Some(i) => {
+                let tag = &name[last_slash + i + 1..];
+                if !is_valid_tag(tag) {
+                    return Err(ParseImageRefError::InvalidTag(tag.to_string()));
+                }
+                (&name[..last_slash + i], Some(tag.to_string()))
+            }
+            None => (name, None),
+        };
+        let (registry, repository) = match name.split_once('/') {
+            Some((host, rest))
+                if host.contains('.') || host.contains(':') || host == "localhost" =>
+            {
+                (Some(host.to_string()), rest)
+            }
+            _ => (None, name),
+        };
+        if let Some(host) = registry.as_deref().filter(|host| !is_valid_registry(host)) {
+            return Err(ParseImageRefError::InvalidRegistry(host.to_string()));
+        }
+        if repository.split('/').any(|c| !is_valid_component(c)) {
+            return Err(ParseImageRefError::InvalidRepository(name.to_string()));
+        }
+        Ok(Self {
+            registry,
+            repository: repository.to_string(),
+            tag,
+            digest,
+        })
+    }
+}
+
+fn is_valid_component(component: &str) -> bool {
+    let bytes = component.as_bytes();
+    let alnum = |b: &u8| b.is_ascii_lowercase() || b.is_ascii_digit();
+    bytes.first().is_some_and(alnum)
+        && bytes.last().is_some_and(alnum)
+        && bytes
+            .iter()
+            .all(|b| alnum(b) || matches!(b, b'.' | b'_' | b'-'))
+}
+
+/// `hostname[:port]`, with dot-separated labels of alphanumerics and inner `-`
+fn is_valid_registry(registry: &str) -> bool {
+    let (host, port) = match registry.split_once(':') {
+        Some((host, port)) => (host, Some(port)),
+        None => (registry, None),
+    };
+    let valid_label = |label: &str| {
+        let bytes = label.as_bytes();
+        bytes.first().is_some_and(u8::is_ascii_alphanumeric)
+            && bytes.last().is_some_and(u8::is_ascii_alphanumeric)
+            && bytes
+                .iter()
+                .all(|b| b.is_ascii_alphanumeric() || *b == b'-')
+    };
+    host.split('.').all(valid_label)
+        && port.is_none_or(|port| {
+            !port.is_empty() && port.len() <= 5 && port.bytes().all(|b| b.is_ascii_digit())
+        })
+}
+
+fn is_valid_tag(tag: &str) -> bool {
+    tag.len() <= 128
+        && tag.bytes().next().is_some_and(|b| b != b'.' && b != b'-')
+        && tag
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'-'))
+}
+
+fn is_valid_digest(digest: &str) -> bool {
+    digest.strip_prefix("sha256:").is_some_and(|hex| {
+        hex.len() == 64
+            && hex
+                .bytes()
+                .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+    })
+}
+
+impl Serialize for ImageRef {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ImageRef {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(s: &str) -> ImageRef {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            image("axon/toolchain-gcc13"),
+            ImageRef {
+                registry: None,
+                repository: "axon/toolchain-gcc13".to_string(),
+                tag: None,
+                digest: None,
+            }
+        );
+
+        let digest = format!("sha256:{}", "ab".repeat(32));
+        let pinned = image(&format!("registry.example.com:5000/axon/jdk:21@{}", digest));
+        assert_eq!(
+            pinned.registry.as_deref(),
+            Some("registry.example.com:5000")
+        );
+        assert_eq!(pinned.repository, "axon/jdk");
+        assert_eq!(pinned.tag.as_deref(), Some("21"));
+        assert_eq!(pinned.digest, Some(digest));
+
+        let local = image("localhost/python:3.12-slim");
+        assert_eq!(local.registry.as_deref(), Some("localhost"));
+        assert_eq!(local.repository, "python");
+        assert_eq!(local.tag.as_deref(), Some("3.12-slim"));
+
+        for s in [
+            "axon/toolchain-gcc13",
+            "ghcr.io/axon/rust:1.80",
+            "localhost:5000/go@sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef",
+        ] {
+            assert_eq!(image(s).to_string(), s);
+        }
+    }
+
+    #[test]
+    fn test_invalid_references() {
+        assert_eq!("".parse::<ImageRef>(), Err(ParseImageRefError::Empty));
+        for s in [
+            "Axon/gcc",
+            "axon//gcc",
+            "axon/gcc-",
+            "/gcc",
+            "axon gcc",
+            "axon/../gcc",
+            "axon/./gcc",
+        ] {
+            assert!(
+                matches!(
+                    s.parse::<ImageRef>(),
+                    Err(ParseImageRefError::InvalidRepository(_))
+                ),
+                "{}",
+                s
+            );
+        }
+        for s in ["gcc:", "gcc:-1", "gcc:ä", "gcc:v 1"] {
+            assert!(
+                matches!(
+                    s.parse::<ImageRef>(),
+                    Err(ParseImageRefError::InvalidTag(_))
+                ),
+                "{}",
+                s
+            );
+        }
+        for s in [
+            "../toolchain",
+            "./toolchain",
+            "a..b/gcc",
+            "host:/gcc",
+            "host:80a/gcc",
+            "-host.com/gcc",
+            "my host.com/gcc",
+        ] {
+            assert!(
+                matches!(
+                    s.parse::<ImageRef>(),
+                    Err(ParseImageRefError::InvalidRegistry(_))
+                ),
+                "{}",
+                s
+            );
+        }
+        assert!(matches!(
+            "gcc@sha256:abc".parse::<ImageRef>(),
+            Err(ParseImageRefError::InvalidDigest(_))
+        ));
+        assert!(matches!(
+            format!("gcc@md5:{}", "ab".repeat(32)).parse::<ImageRef>(),
+            Err(ParseImageRefError::InvalidDigest(_))
+        ));
+    }
+
+    #[test]
+    fn test_store_path() {
+        assert_eq!(
+            image("axon/toolchain-gcc13").store_path(),
+            PathBuf::from("axon/toolchain-gcc13/latest")
+        );
+        assert_eq!(
+            image("localhost:5000/axon/jdk:21").store_path(),
+            PathBuf::from("localhost_5000/axon/jdk/21")
+        );
+        let digest = "cd".repeat(32);
+        assert_eq!(
+            image(&format!("axon/go:1.22@sha256:{}", digest)).store_path(),
+            PathBuf::from(format!("axon/go/sha256-{}", digest))
+        );
+    }
+
+    #[test]
+    fn test_serde() {
+        let reference = image("ghcr.io/axon/rust:1.80");
+        let json = serde_json::to_string(&reference).unwrap();
+        assert_eq!(json, "\"ghcr.io/axon/rust:1.80\"");
+        assert_eq!(serde_json::from_str::<ImageRef>(&json).unwrap(), reference);
+        assert!(serde_json::from_str::<ImageRef>("\"Bad Image\"").is_err());
+    }
+}
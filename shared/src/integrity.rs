@@ -0,0 +1,259 @@
+//! Integrity checks for test data
+//!
+//! A botched sync can leave test data truncated or corrupted, and judging against it
+//! produces wrong verdicts without any error. Test cases may record the SHA-256 of
+//! their input and expected output when they are created; the judger re-hashes the
+//! data before judging and refuses the task if anything changed.
+
+use crate::{JudgeTask, TestCase, TestData};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::io;
+
+/// Which part of a test case a checksum covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataPart {
+    Input,
+    ExpectedOutput,
+}
+
+impl fmt::Display for DataPart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataPart::Input => write!(f, "input"),
+            DataPart::ExpectedOutput => write!(f, "expected output"),
+        }
+    }
+}
+
+/// Why test data failed verification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityError {
+    /// No checksum was recorded, so the data can't be verified
+    NoChecksum(DataPart),
+    /// The data doesn't hash to the recorded checksum
+    Mismatch {
+        part: DataPart,
+        expected: String,
+        actual: String,
+    },
+    /// The data could not be read
+    Unreadable { part: DataPart, reason: String },
+}
+
+impl fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrityError::NoChecksum(part) => write!(f, "no checksum recorded for {}", part),
+            IntegrityError::Mismatch {
+                part,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{} checksum mismatch: expected {}, got {}",
+                part, expected, actual
+            ),
+            IntegrityError::Unreadable { part, reason } => {
+                write!(f, "cannot read {}: {}", part, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for IntegrityError {}
+
+impl IntegrityError {
+    /// Returns whether the data was simply never checksummed
+    pub fn is_missing_checksum(&self) -> bool {
+        matches!(self, IntegrityError::NoChecksum(_))
+    }
+}
+
+impl TestData {
+    /// Returns the lowercase hex SHA-256 of the content
+    ///
+    /// Files are hashed in a streaming fashion. Remote data is not fetched: its
+    /// reference already carries the hash, which is checked when it is downloaded.
+    pub fn sha256(&self) -> io::Result<String> {
+        if let TestData::Remote { sha256, .. } = self {
+            return Ok(sha256.to_ascii_lowercase());
+        }
+        let mut hasher = Sha256::new();
+        io::copy(&mut self.open_reader()?, &mut hasher)?;
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+}
+
+impl TestCase {
+    /// Records the checksums of the input and expected output
+    pub fn compute_checksums(&mut self) -> io::Result<()> {
+        self.input_sha256 = Some(self.input.sha256()?);
+        self.output_sha256 = Some(self.expected_output.sha256()?);
+        Ok(())
+    }
+
+    /// Re-hashes the input and expected output and compares them with the recorded
+    /// checksums
+    ///
+    /// A part without a checksum is reported as [`IntegrityError::NoChecksum`] only
+    /// if the other part verifies, so a mismatch is never hidden behind it.
+    pub fn verify(&self) -> Result<(), IntegrityError> {
+        let input = verify_part(DataPart::Input, &self.input, self.input_sha256.as_deref());
+        let output = verify_part(
+            DataPart::ExpectedOutput,
+            &self.expected_output,
+            self.output_sha256.as_deref(),
+        );
+        match (input, output) {
+            (Err(e), _) | (_, Err(e)) if !e.is_missing_checksum() => Err(e),
+            (input, output) => input.and(output),
+        }
+    }
+}
+
+fn verify_part(
+    part: DataPart,
+    data: &TestData,
+    recorded: Option<&str>,
+) -> Result<(), IntegrityError> {
+    let expected = recorded.ok_or(IntegrityError::NoChecksum(part))?;
+    let actual = data.sha256().map_err(|e| IntegrityError::Unreadable {
+        part,
+        reason: e.to_string(),
+    })?;
+    if actual.eq_ignore_ascii_case(expected) {
+        Ok(())
+    } else {
+        Err(IntegrityError::Mismatch {
+            part,
+            expected: expected.to_string(),
+            actual,
+        })
+    }
+}
+
+impl JudgeTask {
+    /// Verifies the test data of every test case, returning the failures by test id
+    ///
+    /// Test cases without recorded checksums are not failures; they just can't be
+    /// checked.
+    pub fn verify_test_data(&self) -> Result<(), Vec<(String, IntegrityError)>> {
+        let failures: Vec<(String, IntegrityError)> = self
+            .test_cases
+            .iter()
+            .filter_map(|tc| match tc.verify() {
+                Err(e) if !e.is_missing_checksum() => Some((tc.id.clone(), e)),
+                _ => None,
+            })
+            .collect();
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(failures)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProgrammingLanguage;
+    use crate::testing;
+    use uuid::Uuid;
+
+    const EMPTY_SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    fn checksummed(id: &str, input: &str, output: &str) -> TestCase {
+        let mut test_case = TestCase::new(id.to_string(), input, output);
+        test_case.compute_checksums().unwrap();
+        test_case
+    }
+
+    #[test]
+    fn test_sha256() {
+        assert_eq!(TestData::from("").sha256().unwrap(), EMPTY_SHA256);
+
+        let path = std::env::temp_dir().join(format!("oj-integrity-{}", Uuid::new_v4()));
+        std::fs::write(&path, "").unwrap();
+        let file = TestData::File(path.clone());
+        assert_eq!(file.sha256().unwrap(), EMPTY_SHA256);
+        std::fs::remove_file(&path).unwrap();
+        assert!(file.sha256().is_err());
+
+        let remote = TestData::Remote {
+            url: "https://data.example.com/1.in".to_string(),
+            sha256: EMPTY_SHA256.to_ascii_uppercase(),
+            size: 0,
+        };
+        assert_eq!(remote.sha256().unwrap(), EMPTY_SHA256);
+    }
+
+    #[test]
+    fn test_verify() {
+        let mut test_case = TestCase::new("1".to_string(), "1 2\n", "3\n");
+        assert_eq!(
+            test_case.verify(),
+            Err(IntegrityError::NoChecksum(DataPart::Input))
+        );
+
+        test_case.compute_checksums().unwrap();
+        assert_eq!(test_case.verify(), Ok(()));
+
+        // A missing input checksum doesn't hide a corrupted output
+        test_case.input_sha256 = None;
+        test_case.output_sha256 = Some(EMPTY_SHA256.to_string());
+        assert!(matches!(
+            test_case.verify(),
+            Err(IntegrityError::Mismatch { .. })
+        ));
+        test_case.compute_checksums().unwrap();
+
+        test_case.expected_output = TestData::from("4\n");
+        assert!(matches!(
+            test_case.verify(),
+            Err(IntegrityError::Mismatch {
+                part: DataPart::ExpectedOutput,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_verify_task() {
+        let submission = testing::submission(ProgrammingLanguage::Cpp17, "int main() {}");
+        let mut corrupted = checksummed("2", "2\n", "4\n");
+        corrupted.input = TestData::from("2");
+        let unchecked = TestCase::new("3".to_string(), "3\n", "9\n");
+        let mut task = JudgeTask::new(
+            submission,
+            vec![checksummed("1", "1\n", "1\n"), corrupted, unchecked],
+        );
+
+        let failures = task.verify_test_data().unwrap_err();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, "2");
+        assert!(matches!(
+            failures[0].1,
+            IntegrityError::Mismatch {
+                part: DataPart::Input,
+                ..
+            }
+        ));
+
+        task.test_cases.remove(1);
+        assert_eq!(task.verify_test_data(), Ok(()));
+    }
+
+    #[test]
+    fn test_old_payloads_have_no_checksums() {
+        let test_case = checksummed("1", "1\n", "1\n");
+        let mut value = serde_json::to_value(&test_case).unwrap();
+        let object = value.as_object_mut().unwrap();
+        object.remove("input_sha256");
+        object.remove("output_sha256");
+        let old: TestCase = serde_json::from_value(value).unwrap();
+        assert_eq!(old.input_sha256, None);
+        assert_eq!(old.output_sha256, None);
+    }
+}
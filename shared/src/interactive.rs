@@ -0,0 +1,261 @@
+//! Interactive problems
+//!
+//! In an interactive problem the submission does not read a fixed input: it talks to
+//! an interactor program over stdin/stdout, and the interactor decides the verdict.
+//! The meaning of a [`TestCase`]'s fields changes accordingly, see [`TestIo`].
+
+use crate::{JudgeTask, MultiCaseSpec, ProgrammingLanguage, TestCase, TestData};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// How the submission is run against each test case
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExecutionMode {
+    /// The test input is piped to the submission and its output compared
+    #[default]
+    Batch,
+    /// The submission is connected to an interactor
+    Interactive(InteractorSpec),
+    /// All test cases are judged in one run, see [`multi_case`](crate::multi_case)
+    SingleRunMultiCase(MultiCaseSpec),
+}
+
+/// Where the interactor program comes from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InteractorProgram {
+    /// Source code compiled by the judger like a submission
+    Source(String),
+    /// Reference to a prebuilt binary in the problem's data directory
+    Binary(String),
+}
+
+/// Interactor of an interactive problem
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InteractorSpec {
+    /// Language of the interactor (for a binary, the language it was built from)
+    pub language: ProgrammingLanguage,
+    /// Interactor source or binary reference
+    pub program: InteractorProgram,
+    /// Extra interactor arguments by test case id
+    #[serde(default)]
+    pub test_args: HashMap<String, Vec<String>>,
+}
+
+/// Why an interactive task is malformed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InteractorError {
+    /// The interactor source or binary reference is empty
+    EmptyProgram,
+    /// `test_args` names a test case that isn't in the task
+    UnknownTestCase(String),
+}
+
+impl fmt::Display for InteractorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InteractorError::EmptyProgram => write!(f, "interactor program must not be empty"),
+            InteractorError::UnknownTestCase(id) => {
+                write!(
+                    f,
+                    "interactor arguments given for unknown test case {:?}",
+                    id
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for InteractorError {}
+
+impl InteractorSpec {
+    /// Returns the interactor arguments for a test case (empty if none are given)
+    pub fn args_for(&self, test_case_id: &str) -> &[String] {
+        self.test_args
+            .get(test_case_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+/// What a test case provides, depending on the execution mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestIo<'a> {
+    /// `input` is the submission's stdin, `expected_output` what it must print
+    Batch {
+        stdin: &'a TestData,
+        expected_output: &'a TestData,
+    },
+    /// `input` is given to the interactor, never to the submission; a non-empty
+    /// `expected_output` is passed to the interactor as its answer file
+    Interactive {
+        interactor_input: &'a TestData,
+        answer: Option<&'a TestData>,
+        args: &'a [String],
+    },
+}
+
+impl ExecutionMode {
+    /// Returns whether the submission runs against an interactor
+    pub fn is_interactive(&self) -> bool {
+        matches!(self, ExecutionMode::Interactive(_))
+    }
+
+    /// Returns the interactor spec in interactive mode
+    pub fn interactor(&self) -> Option<&InteractorSpec> {
+        match self {
+            ExecutionMode::Interactive(spec) => Some(spec),
+            _ => None,
+        }
+    }
+
+    /// Returns the multi-case spec when all test cases share one run
+    pub fn multi_case(&self) -> Option<&MultiCaseSpec> {
+        match self {
+            ExecutionMode::SingleRunMultiCase(spec) => Some(spec),
+            _ => None,
+        }
+    }
+
+    /// Interprets a test case's data for this mode
+    ///
+    /// In single-run mode each test case contributes its input to the combined stdin
+    /// and is checked against its own slice of the output.
+    pub fn test_io<'a>(&'a self, test_case: &'a TestCase) -> TestIo<'a> {
+        match self {
+            ExecutionMode::Batch | ExecutionMode::SingleRunMultiCase(_) => TestIo::Batch {
+                stdin: &test_case.input,
+                expected_output: &test_case.expected_output,
+            },
+            ExecutionMode::Interactive(spec) => TestIo::Interactive {
+                interactor_input: &test_case.input,
+                answer: Some(&test_case.expected_output).filter(|a| !a.is_empty_inline()),
+                args: spec.args_for(&test_case.id),
+            },
+        }
+    }
+}
+
+impl JudgeTask {
+    /// Checks that the interactor is usable with this task's test cases
+    pub fn validate_execution_mode(&self) -> Result<(), InteractorError> {
+        let Some(spec) = self.execution_mode.interactor() else {
+            return Ok(());
+        };
+        let (InteractorProgram::Source(program) | InteractorProgram::Binary(program)) =
+            &spec.program;
+        if program.trim().is_empty() {
+            return Err(InteractorError::EmptyProgram);
+        }
+        let mut ids: Vec<&String> = spec.test_args.keys().collect();
+        ids.sort();
+        match ids
+            .into_iter()
+            .find(|id| !self.test_cases.iter().any(|tc| &tc.id == *id))
+        {
+            Some(id) => Err(InteractorError::UnknownTestCase(id.clone())),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn guess_interactor() -> InteractorSpec {
+        InteractorSpec {
+            language: ProgrammingLanguage::Cpp17,
+            program: InteractorProgram::Source(
+                "#include \"testlib.h\"\nint main(int argc, char* argv[]) { registerInteraction(argc, argv); }\n"
+                    .to_string(),
+            ),
+            test_args: HashMap::from([("2".to_string(), vec!["--adaptive".to_string()])]),
+        }
+    }
+
+    fn task(mode: ExecutionMode) -> JudgeTask {
+        let mut task = JudgeTask::new(
+            testing::submission(ProgrammingLanguage::Python3, "print(50)\n"),
+            vec![
+                TestCase::new("1".to_string(), "42\n".to_string(), String::new()),
+                TestCase::new("2".to_string(), "1000\n".to_string(), "10\n".to_string()),
+            ],
+        );
+        task.execution_mode = mode;
+        task
+    }
+
+    #[test]
+    fn test_test_io_by_mode() {
+        let batch = task(ExecutionMode::Batch);
+        assert_eq!(
+            batch.execution_mode.test_io(&batch.test_cases[1]),
+            TestIo::Batch {
+                stdin: &TestData::from("1000\n"),
+                expected_output: &TestData::from("10\n"),
+            }
+        );
+
+        let interactive = task(ExecutionMode::Interactive(guess_interactor()));
+        assert!(interactive.execution_mode.is_interactive());
+        assert_eq!(
+            interactive
+                .execution_mode
+                .test_io(&interactive.test_cases[0]),
+            TestIo::Interactive {
+                interactor_input: &TestData::from("42\n"),
+                answer: None,
+                args: &[],
+            }
+        );
+        assert_eq!(
+            interactive
+                .execution_mode
+                .test_io(&interactive.test_cases[1]),
+            TestIo::Interactive {
+                interactor_input: &TestData::from("1000\n"),
+                answer: Some(&TestData::from("10\n")),
+                args: &["--adaptive".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_validate_execution_mode() {
+        assert_eq!(task(ExecutionMode::Batch).validate_execution_mode(), Ok(()));
+        assert_eq!(
+            task(ExecutionMode::Interactive(guess_interactor())).validate_execution_mode(),
+            Ok(())
+        );
+
+        let mut spec = guess_interactor();
+        spec.program = InteractorProgram::Binary(" ".to_string());
+        assert_eq!(
+            task(ExecutionMode::Interactive(spec)).validate_execution_mode(),
+            Err(InteractorError::EmptyProgram)
+        );
+
+        let mut spec = guess_interactor();
+        spec.test_args.insert("7".to_string(), vec![]);
+        assert_eq!(
+            task(ExecutionMode::Interactive(spec)).validate_execution_mode(),
+            Err(InteractorError::UnknownTestCase("7".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_execution_mode_serde() {
+        let interactive = task(ExecutionMode::Interactive(guess_interactor()));
+        let json = serde_json::to_string(&interactive).unwrap();
+        let deserialized: JudgeTask = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.execution_mode, interactive.execution_mode);
+
+        // Tasks serialized before interactive support are batch tasks
+        let mut value = serde_json::to_value(task(ExecutionMode::Batch)).unwrap();
+        value.as_object_mut().unwrap().remove("execution_mode");
+        let deserialized: JudgeTask = serde_json::from_value(value).unwrap();
+        assert_eq!(deserialized.execution_mode, ExecutionMode::Batch);
+    }
+}
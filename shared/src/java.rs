@@ -0,0 +1,235 @@
+//! Helpers for Java submissions, whose file name must match the public class
+
+use crate::ErrorInfo;
+
+/// Class name assumed when the source declares no public top-level class
+pub const DEFAULT_MAIN_CLASS: &str = "Main";
+
+/// Returns the name of the public top-level class (or interface, enum, record)
+///
+/// Comments, string and character literals are ignored, as are nested classes and
+/// annotation arguments. Returns `None` if no public top-level type is declared.
+pub fn main_class_name(source: &str) -> Option<String> {
+    let code = strip_comments_and_literals(source);
+
+    let mut brace_depth = 0usize;
+    let mut paren_depth = 0usize;
+    let mut is_public = false;
+    let mut expect_name = false;
+    let mut prev_char = ' ';
+
+    let mut chars = code.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if is_identifier_char(c) {
+            let mut end = start + c.len_utf8();
+            while let Some(&(i, next)) = chars.peek() {
+                if !is_identifier_char(next) {
+                    break;
+                }
+                end = i + next.len_utf8();
+                chars.next();
+            }
+            let word = &code[start..end];
+
+            if brace_depth == 0 && paren_depth == 0 && prev_char != '.' && prev_char != '@' {
+                if expect_name {
+                    return Some(word.to_string());
+                }
+                match word {
+                    "public" => is_public = true,
+                    "class" | "interface" | "enum" | "record" if is_public => expect_name = true,
+                    _ => {}
+                }
+            }
+            prev_char = 'a';
+            continue;
+        }
+
+        match c {
+            '{' => {
+                brace_depth += 1;
+                is_public = false;
+                expect_name = false;
+            }
+            '}' => {
+                brace_depth = brace_depth.saturating_sub(1);
+                is_public = false;
+            }
+            '(' => paren_depth += 1,
+            ')' => paren_depth = paren_depth.saturating_sub(1),
+            ';' if brace_depth == 0 => is_public = false,
+            _ => {}
+        }
+        if !c.is_whitespace() {
+            prev_char = c;
+        }
+    }
+
+    None
+}
+
+/// Returns the compile error reported when no public class could be found
+pub fn no_public_class_error() -> ErrorInfo {
+    let mut error = ErrorInfo::compilation_error(
+        format!(
+            "No public class found; the source was compiled as {}.java. \
+             Declare your entry point as `public class {}`.",
+            DEFAULT_MAIN_CLASS, DEFAULT_MAIN_CLASS
+        ),
+        None,
+    );
+    error.code = Some("JAVA_NO_PUBLIC_CLASS".to_string());
+    error
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+/// Replaces comments and string/char literals with spaces
+fn strip_comments_and_literals(source: &str) -> String {
+    enum State {
+        Code,
+        LineComment,
+        BlockComment,
+        String,
+        TextBlock,
+        Char,
+    }
+
+    let chars: Vec<char> = source.chars().collect();
+    let starts_text_block = |i: usize| chars[i..].starts_with(&['"', '"', '"']);
+    let mut out = String::with_capacity(source.len());
+    let mut state = State::Code;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        // Number of source chars consumed by this step
+        let mut consumed = 1;
+
+        match state {
+            State::Code => match (c, next) {
+                ('/', Some('/')) => {
+                    state = State::LineComment;
+                    consumed = 2;
+                }
+                ('/', Some('*')) => {
+                    state = State::BlockComment;
+                    consumed = 2;
+                }
+                ('"', _) if starts_text_block(i) => {
+                    state = State::TextBlock;
+                    consumed = 3;
+                }
+                ('"', _) => state = State::String,
+                ('\'', _) => state = State::Char,
+                _ => {
+                    out.push(c);
+                    i += 1;
+                    continue;
+                }
+            },
+            State::LineComment if c == '\n' => state = State::Code,
+            State::BlockComment if c == '*' && next == Some('/') => {
+                state = State::Code;
+                consumed = 2;
+            }
+            State::String | State::Char | State::TextBlock if c == '\\' => consumed = 2,
+            State::String if c == '"' || c == '\n' => state = State::Code,
+            State::Char if c == '\'' || c == '\n' => state = State::Code,
+            State::TextBlock if starts_text_block(i) => {
+                state = State::Code;
+                consumed = 3;
+            }
+            _ => {}
+        }
+
+        out.push(' ');
+        i += consumed;
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simple_public_class() {
+        let source = "import java.util.*;\n\npublic class Solution {\n    public static void main(String[] args) {}\n}\n";
+        assert_eq!(main_class_name(source), Some("Solution".to_string()));
+    }
+
+    #[test]
+    fn test_no_public_class() {
+        assert_eq!(main_class_name("class Main { }"), None);
+        assert_eq!(main_class_name(""), None);
+    }
+
+    #[test]
+    fn test_ignores_comments_and_strings() {
+        let source = r#"
+            // public class Commented {}
+            /* public class Blocked {} */
+            class Helper {
+                String s = "public class InString {}";
+                char c = '"';
+                String t = """
+                    public class InTextBlock {}
+                    """;
+            }
+            public class Real {}
+        "#;
+        assert_eq!(main_class_name(source), Some("Real".to_string()));
+    }
+
+    #[test]
+    fn test_multiple_top_level_classes() {
+        let source = "class A {}\nfinal class B { public static class Nested {} }\npublic final class C {}\n";
+        assert_eq!(main_class_name(source), Some("C".to_string()));
+    }
+
+    #[test]
+    fn test_annotations_and_modifiers() {
+        let source = r#"
+            @SuppressWarnings("unchecked")
+            @Ann(value = Foo.class)
+            public abstract class Annotated<T> extends Base implements Runnable {}
+        "#;
+        assert_eq!(main_class_name(source), Some("Annotated".to_string()));
+
+        let source = "public @Deprecated class WithInlineAnnotation {}";
+        assert_eq!(
+            main_class_name(source),
+            Some("WithInlineAnnotation".to_string())
+        );
+    }
+
+    #[test]
+    fn test_other_public_types() {
+        assert_eq!(
+            main_class_name("public enum Answer { YES }"),
+            Some("Answer".to_string())
+        );
+        assert_eq!(
+            main_class_name("public record Point(int x, int y) {}"),
+            Some("Point".to_string())
+        );
+    }
+
+    #[test]
+    fn test_public_member_of_non_public_class() {
+        let source = "class Main {\n    public static void main(String[] args) {}\n}\n";
+        assert_eq!(main_class_name(source), None);
+    }
+
+    #[test]
+    fn test_no_public_class_error() {
+        let error = no_public_class_error();
+        assert!(error.message.contains("Main.java"));
+        assert_eq!(error.code.as_deref(), Some("JAVA_NO_PUBLIC_CLASS"));
+    }
+}
@@ -0,0 +1,116 @@
+//! Serde adapter encoding a [`ProgrammingLanguage`] as its stable numeric id
+//!
+//! Use it on fields that feed systems expecting integer language codes:
+//!
+//! ```
+//! use oj_shared::ProgrammingLanguage;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct LegacySubmission {
+//!     #[serde(with = "oj_shared::language_id")]
+//!     language: ProgrammingLanguage,
+//! }
+//!
+//! let json = serde_json::to_string(&LegacySubmission {
+//!     language: ProgrammingLanguage::Cpp17,
+//! })
+//! .unwrap();
+//! assert_eq!(json, r#"{"language":5}"#);
+//! ```
+
+use crate::ProgrammingLanguage;
+use serde::{Deserialize, Deserializer, Serializer};
+
+/// Serializes a language as its numeric id
+pub fn serialize<S>(language: &ProgrammingLanguage, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_u32(language.id())
+}
+
+/// Deserializes a language from its numeric id
+pub fn deserialize<'de, D>(deserializer: D) -> Result<ProgrammingLanguage, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let id = u32::deserialize(deserializer)?;
+    ProgrammingLanguage::from_id(id)
+        .ok_or_else(|| serde::de::Error::custom(format!("unknown language id {}", id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Serialize;
+    use std::collections::HashSet;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Wire {
+        #[serde(with = "crate::language_id")]
+        language: ProgrammingLanguage,
+    }
+
+    #[test]
+    fn test_ids_are_stable() {
+        let expected = [
+            (ProgrammingLanguage::C, 1),
+            (ProgrammingLanguage::Cpp, 2),
+            (ProgrammingLanguage::Cpp11, 3),
+            (ProgrammingLanguage::Cpp14, 4),
+            (ProgrammingLanguage::Cpp17, 5),
+            (ProgrammingLanguage::Cpp20, 6),
+            (ProgrammingLanguage::Python2, 7),
+            (ProgrammingLanguage::Python3, 8),
+            (ProgrammingLanguage::Java, 9),
+            (ProgrammingLanguage::Rust, 10),
+            (ProgrammingLanguage::Go, 11),
+            (ProgrammingLanguage::JavaScript, 12),
+            (ProgrammingLanguage::TypeScript, 13),
+            (ProgrammingLanguage::Kotlin, 14),
+            (ProgrammingLanguage::CSharp, 15),
+            (ProgrammingLanguage::Ruby, 16),
+            (ProgrammingLanguage::PHP, 17),
+            (ProgrammingLanguage::Custom, 18),
+        ];
+        // A new variant must be added here with a fresh id
+        assert_eq!(expected.len(), ProgrammingLanguage::ALL.len() + 1);
+        for (language, id) in expected {
+            assert_eq!(language.id(), id, "{:?}", language);
+        }
+    }
+
+    #[test]
+    fn test_ids_round_trip_and_are_unique() {
+        let mut seen = HashSet::new();
+        for language in ProgrammingLanguage::ALL {
+            assert!(
+                seen.insert(language.id()),
+                "duplicate id for {:?}",
+                language
+            );
+            assert_eq!(ProgrammingLanguage::from_id(language.id()), Some(language));
+        }
+
+        for id in 0..1000 {
+            if let Some(language) = ProgrammingLanguage::from_id(id) {
+                assert_eq!(language.id(), id);
+            }
+        }
+        assert_eq!(ProgrammingLanguage::from_id(0), None);
+    }
+
+    #[test]
+    fn test_serde_with_numeric_id() {
+        let wire = Wire {
+            language: ProgrammingLanguage::Python3,
+        };
+        let json = serde_json::to_string(&wire).unwrap();
+        assert_eq!(json, r#"{"language":8}"#);
+        assert_eq!(serde_json::from_str::<Wire>(&json).unwrap(), wire);
+
+        assert!(serde_json::from_str::<Wire>(r#"{"language":999}"#).is_err());
+        assert!(serde_json::from_str::<Wire>(r#"{"language":"Python3"}"#).is_err());
+    }
+}
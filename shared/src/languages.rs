@@ -0,0 +1,503 @@
+use crate::{ImageRef, JudgeTask, ProgrammingLanguage, Submission, TestCase};
+use serde::de::IntoDeserializer;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+
+/// Toolchain configuration for a single language
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LanguageSpec {
+    /// Whether a compile step runs before execution
+    ///
+    /// Setting this to `false` runs the source directly with `runtime`, e.g. to
+    /// use `ts-node` instead of compiling TypeScript with `tsc`.
+    pub needs_compilation: bool,
+    /// Compiler (or interpreter) binary
+    pub compiler: String,
+    /// Flags passed to the compiler
+    pub compile_flags: Vec<String>,
+    /// Command used to run the program
+    pub runtime: String,
+    /// Extra environment variables for compile and run
+    pub env: BTreeMap<String, String>,
+    /// OCI image providing the toolchain, instead of the judger host's binaries
+    ///
+    /// `compiler` and `runtime` are then looked up inside the image.
+    #[serde(default)]
+    pub image: Option<ImageRef>,
+}
+
+impl LanguageSpec {
+    /// Returns the built-in spec for a language
+    pub fn builtin(language: ProgrammingLanguage) -> Self {
+        Self {
+            needs_compilation: language.needs_compilation(),
+            compiler: language.default_compiler().to_string(),
+            compile_flags: language.default_compile_flags(),
+            runtime: language.default_runtime(),
+            env: BTreeMap::new(),
+            image: None,
+        }
+    }
+}
+
+/// Partial spec as written in a TOML file; missing fields fall back to the built-in values
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LanguageSpecOverride {
+    needs_compilation: Option<bool>,
+    compiler: Option<String>,
+    compile_flags: Option<Vec<String>>,
+    runtime: Option<String>,
+    env: Option<BTreeMap<String, String>>,
+    image: Option<ImageRef>,
+}
+
+/// Errors raised while loading a language registry
+#[derive(Debug)]
+pub enum RegistryError {
+    /// The file could not be read
+    Io(std::io::Error),
+    /// The TOML document is malformed
+    Parse(String),
+    /// The table name does not match any supported language
+    UnknownLanguage(String),
+    /// A required command is empty
+    EmptyCommand {
+        language: ProgrammingLanguage,
+        field: &'static str,
+    },
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::Io(e) => write!(f, "failed to read language registry: {}", e),
+            RegistryError::Parse(e) => write!(f, "invalid language registry: {}", e),
+            RegistryError::UnknownLanguage(name) => write!(f, "unknown language: {}", name),
+            RegistryError::EmptyCommand { language, field } => {
+                write!(f, "{} for {} must not be empty", field, language.as_str())
+            }
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+/// Mapping from each language to its toolchain configuration
+///
+/// The TOML format uses one table per language, keyed by the variant name:
+///
+/// ```toml
+/// [Cpp17]
+/// compiler = "clang++"
+/// compile_flags = ["-O2", "-std=c++17"]
+///
+/// [Python3]
+/// runtime = "/usr/local/bin/python3.12"
+/// env = { PYTHONIOENCODING = "utf-8" }
+///
+/// [TypeScript]
+/// needs_compilation = false
+/// runtime = "ts-node"
+///
+/// [Cpp20]
+/// image = "axon/toolchain-gcc13:1.0"
+/// ```
+///
+/// Languages (and fields) not mentioned keep their built-in values. A language
+/// with an `image` runs its toolchain from that image (see [`crate::image`]), so
+/// the judger host doesn't need it installed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageRegistry {
+    specs: HashMap<ProgrammingLanguage, LanguageSpec>,
+}
+
+impl LanguageRegistry {
+    /// Creates a registry with the built-in toolchain for every language
+    ///
+    /// [`ProgrammingLanguage::Custom`] gets an empty placeholder spec; its toolchain
+    /// comes from the submission and cannot be configured here.
+    pub fn builtin() -> Self {
+        Self {
+            specs: ProgrammingLanguage::ALL
+                .iter()
+                .chain([&ProgrammingLanguage::Custom])
+                .map(|&language| (language, LanguageSpec::builtin(language)))
+                .collect(),
+        }
+    }
+
+    /// Parses a registry from a TOML document, layered over the built-in defaults
+    pub fn from_toml_str(s: &str) -> Result<Self, RegistryError> {
+        let overrides: BTreeMap<String, LanguageSpecOverride> =
+            toml::from_str(s).map_err(|e| RegistryError::Parse(e.to_string()))?;
+
+        let mut registry = Self::builtin();
+        for (name, spec_override) in overrides {
+            let language = parse_language(&name)?;
+            let spec = registry
+                .specs
+                .get_mut(&language)
+                .expect("built-in registry covers every language");
+            if let Some(needs_compilation) = spec_override.needs_compilation {
+                spec.needs_compilation = needs_compilation;
+            }
+            if let Some(compiler) = spec_override.compiler {
+                spec.compiler = compiler;
+            }
+            if let Some(compile_flags) = spec_override.compile_flags {
+                spec.compile_flags = compile_flags;
+            }
+            if let Some(runtime) = spec_override.runtime {
+                spec.runtime = runtime;
+            }
+            if let Some(env) = spec_override.env {
+                spec.env = env;
+            }
+            if let Some(image) = spec_override.image {
+                spec.image = Some(image);
+            }
+        }
+
+        registry.validate()?;
+        Ok(registry)
+    }
+
+    /// Reads and parses a registry from a TOML file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, RegistryError> {
+        let content = std::fs::read_to_string(path).map_err(RegistryError::Io)?;
+        Self::from_toml_str(&content)
+    }
+
+    /// Returns the toolchain configuration for a language
+    pub fn get(&self, language: &ProgrammingLanguage) -> &LanguageSpec {
+        &self.specs[language]
+    }
+
+    /// Returns the compiler argv for `language` using the configured toolchain
+    pub fn compile_command(
+        &self,
+        language: &ProgrammingLanguage,
+        source: &str,
+        output: &str,
+    ) -> Option<Vec<String>> {
+        let spec = self.get(language);
+        if !spec.needs_compilation {
+            return None;
+        }
+        language.compile_command_with(&spec.compiler, &spec.compile_flags, source, output)
+    }
+
+    /// Returns the run argv for `language` using the configured toolchain
+    pub fn run_command(&self, language: &ProgrammingLanguage, artifact: &str) -> Vec<String> {
+        language.run_command_with(&self.get(language).runtime, artifact)
+    }
+
+    /// Returns the precompile argv for `language` using the configured runtime
+    pub fn precompile_command(
+        &self,
+        language: &ProgrammingLanguage,
+        source: &str,
+    ) -> Option<Vec<String>> {
+        language.precompile_command_with(&self.get(language).runtime, source)
+    }
+
+    /// Checks that every language has non-empty commands
+    pub fn validate(&self) -> Result<(), RegistryError> {
+        for language in ProgrammingLanguage::ALL {
+            let spec = self.get(&language);
+            if spec.compiler.trim().is_empty() {
+                return Err(RegistryError::EmptyCommand {
+                    language,
+                    field: "compiler",
+                });
+            }
+            if spec.runtime.trim().is_empty() {
+                return Err(RegistryError::EmptyCommand {
+                    language,
+                    field: "runtime",
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Submission {
+    /// Returns whether this submission is compiled under `registry`
+    ///
+    /// Unlike [`needs_compilation`](Self::needs_compilation), this honors a language
+    /// configured to run its source directly, e.g. TypeScript through `ts-node`.
+    pub fn needs_compilation_in(&self, registry: &LanguageRegistry) -> bool {
+        match self.custom() {
+            Some(custom) => custom.needs_compilation,
+            None => registry.get(&self.language).needs_compilation,
+        }
+    }
+
+    /// Returns the file the compile step produces under `registry`, or `None` when
+    /// the source runs directly
+    pub fn artifact_name_in(&self, registry: &LanguageRegistry) -> Option<String> {
+        if !self.needs_compilation_in(registry) {
+            return None;
+        }
+        self.artifact_name()
+    }
+
+    /// Returns the compiler argv under `registry`, or `None` when the source runs directly
+    pub fn compile_command_in(&self, registry: &LanguageRegistry) -> Option<Vec<String>> {
+        if self.custom().is_some() {
+            return self.compile_command();
+        }
+        registry.compile_command(
+            &self.language,
+            &self.filename(),
+            &self.artifact_name_in(registry)?,
+        )
+    }
+
+    /// Returns the argv that runs this submission under `registry`
+    pub fn run_command_in(&self, registry: &LanguageRegistry) -> Vec<String> {
+        if self.custom().is_some() {
+            return self.run_command();
+        }
+        let artifact = match self.artifact_name_in(registry) {
+            Some(_) if self.language.is_native() => self.default_runtime(),
+            Some(artifact) => artifact,
+            None => self.filename(),
+        };
+        registry.run_command(&self.language, &artifact)
+    }
+}
+
+impl JudgeTask {
+    /// Creates a judge task whose compile step follows `registry`
+    ///
+    /// [`JudgeTask::new`] assumes the built-in toolchains; a language configured to
+    /// run its source directly gets no compile step here.
+    pub fn new_in(
+        submission: Submission,
+        test_cases: Vec<TestCase>,
+        registry: &LanguageRegistry,
+    ) -> Self {
+        let mut task = JudgeTask::new(submission, test_cases);
+        if !task.submission.needs_compilation_in(registry) {
+            task.needs_compilation = false;
+            task.compile_flags = None;
+        }
+        task
+    }
+}
+
+impl Default for LanguageRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+fn parse_language(name: &str) -> Result<ProgrammingLanguage, RegistryError> {
+    let deserializer: serde::de::value::StrDeserializer<'_, serde::de::value::Error> =
+        name.into_deserializer();
+    match ProgrammingLanguage::deserialize(deserializer) {
+        Ok(ProgrammingLanguage::Custom) | Err(_) => {
+            Err(RegistryError::UnknownLanguage(name.to_string()))
+        }
+        Ok(language) => Ok(language),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn test_builtin_matches_enum_defaults() {
+        let registry = LanguageRegistry::default();
+        for language in ProgrammingLanguage::ALL {
+            let spec = registry.get(&language);
+            assert_eq!(spec.compiler, language.default_compiler());
+            assert_eq!(spec.compile_flags, language.default_compile_flags());
+            assert_eq!(spec.runtime, language.default_runtime());
+            assert_eq!(spec.needs_compilation, language.needs_compilation());
+            assert!(spec.env.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_from_toml_str_overrides() {
+        let registry = LanguageRegistry::from_toml_str(
+            r#"
+            [Cpp17]
+            compiler = "clang++"
+            compile_flags = ["-O2", "-std=c++17"]
+
+            [Python3]
+            runtime = "/usr/local/bin/python3.12"
+            env = { PYTHONIOENCODING = "utf-8" }
+            "#,
+        )
+        .unwrap();
+
+        let cpp = registry.get(&ProgrammingLanguage::Cpp17);
+        assert_eq!(cpp.compiler, "clang++");
+        assert_eq!(cpp.compile_flags, vec!["-O2", "-std=c++17"]);
+        assert_eq!(cpp.runtime, "./a.out");
+
+        let python = registry.get(&ProgrammingLanguage::Python3);
+        assert_eq!(python.compiler, "python3");
+        assert_eq!(python.runtime, "/usr/local/bin/python3.12");
+        assert_eq!(python.env["PYTHONIOENCODING"], "utf-8");
+
+        assert_eq!(
+            registry.get(&ProgrammingLanguage::C),
+            &LanguageSpec::builtin(ProgrammingLanguage::C)
+        );
+        assert_eq!(cpp.image, None);
+    }
+
+    #[test]
+    fn test_toolchain_images() {
+        let registry = LanguageRegistry::from_toml_str(
+            r#"
+            [Cpp20]
+            image = "axon/toolchain-gcc13:1.0"
+            compiler = "/opt/gcc-13/bin/g++"
+            "#,
+        )
+        .unwrap();
+        let cpp = registry.get(&ProgrammingLanguage::Cpp20);
+        assert_eq!(cpp.image, Some("axon/toolchain-gcc13:1.0".parse().unwrap()));
+        assert_eq!(cpp.compiler, "/opt/gcc-13/bin/g++");
+        assert_eq!(registry.get(&ProgrammingLanguage::Cpp17).image, None);
+
+        let error = LanguageRegistry::from_toml_str("[Cpp20]\nimage = \"Axon/GCC\"\n").unwrap_err();
+        assert!(matches!(error, RegistryError::Parse(_)));
+        assert!(error.to_string().contains("invalid image repository"));
+    }
+
+    #[test]
+    fn test_commands_use_configured_toolchain() {
+        let registry = LanguageRegistry::from_toml_str(
+            "[Cpp17]\ncompiler = \"clang++\"\ncompile_flags = [\"-O3\"]\n\n[Python3]\nruntime = \"pypy3\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            registry.compile_command(&ProgrammingLanguage::Cpp17, "main.cpp", "main"),
+            Some(vec![
+                "clang++".to_string(),
+                "-O3".to_string(),
+                "main.cpp".to_string(),
+                "-o".to_string(),
+                "main".to_string(),
+            ])
+        );
+        assert_eq!(
+            registry.run_command(&ProgrammingLanguage::Python3, "main.py"),
+            vec!["pypy3", "main.py"]
+        );
+        assert_eq!(
+            LanguageRegistry::builtin().compile_command(
+                &ProgrammingLanguage::Go,
+                "main.go",
+                "main"
+            ),
+            ProgrammingLanguage::Go.compile_command("main.go", "main")
+        );
+    }
+
+    #[test]
+    fn test_precompile_uses_configured_runtime() {
+        let registry = LanguageRegistry::from_toml_str("[Python3]\nruntime = \"pypy3\"\n").unwrap();
+        assert_eq!(
+            registry.precompile_command(&ProgrammingLanguage::Python3, "main.py"),
+            Some(vec![
+                "pypy3".to_string(),
+                "-m".to_string(),
+                "py_compile".to_string(),
+                "main.py".to_string(),
+            ])
+        );
+        assert_eq!(
+            registry.precompile_command(&ProgrammingLanguage::Cpp17, "main.cpp"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_typescript_interpreted_fallback() {
+        let registry = LanguageRegistry::from_toml_str(
+            "[TypeScript]\nneeds_compilation = false\nruntime = \"ts-node\"\n",
+        )
+        .unwrap();
+        let submission = testing::submission(ProgrammingLanguage::TypeScript, "console.log(1)");
+        assert!(!submission.needs_compilation_in(&registry));
+        assert_eq!(submission.artifact_name_in(&registry), None);
+        assert_eq!(submission.compile_command_in(&registry), None);
+        assert_eq!(
+            submission.run_command_in(&registry),
+            vec!["ts-node", "main.ts"]
+        );
+        let task = JudgeTask::new_in(submission.clone(), vec![], &registry);
+        assert!(!task.needs_compilation);
+        assert_eq!(task.compile_flags, None);
+
+        let builtin = LanguageRegistry::builtin();
+        assert!(submission.needs_compilation_in(&builtin));
+        assert_eq!(
+            submission.artifact_name_in(&builtin).as_deref(),
+            Some("main.js")
+        );
+        assert!(submission.compile_command_in(&builtin).is_some());
+        assert_eq!(submission.run_command_in(&builtin), vec!["node", "main.js"]);
+        assert_eq!(
+            submission.run_command_in(&builtin),
+            submission.run_command()
+        );
+        assert!(JudgeTask::new_in(submission, vec![], &builtin).needs_compilation);
+
+        let cpp = testing::submission(ProgrammingLanguage::Cpp17, "int main() {}");
+        assert_eq!(cpp.run_command_in(&builtin), cpp.run_command());
+        assert_eq!(cpp.compile_command_in(&builtin), cpp.compile_command());
+    }
+
+    #[test]
+    fn test_rejects_unknown_language() {
+        let err = LanguageRegistry::from_toml_str("[Cobol]\ncompiler = \"cobc\"\n").unwrap_err();
+        assert!(matches!(err, RegistryError::UnknownLanguage(name) if name == "Cobol"));
+
+        let err = LanguageRegistry::from_toml_str("[Custom]\nruntime = \"ocaml\"\n").unwrap_err();
+        assert!(matches!(err, RegistryError::UnknownLanguage(name) if name == "Custom"));
+    }
+
+    #[test]
+    fn test_rejects_empty_commands() {
+        let err = LanguageRegistry::from_toml_str("[C]\ncompiler = \"\"\n").unwrap_err();
+        assert!(matches!(
+            err,
+            RegistryError::EmptyCommand {
+                language: ProgrammingLanguage::C,
+                field: "compiler"
+            }
+        ));
+
+        let err = LanguageRegistry::from_toml_str("[Go]\nruntime = \"  \"\n").unwrap_err();
+        assert!(matches!(
+            err,
+            RegistryError::EmptyCommand {
+                field: "runtime",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_rejects_malformed_toml() {
+        assert!(matches!(
+            LanguageRegistry::from_toml_str("[C]\nflags = 1\n"),
+            Err(RegistryError::Parse(_))
+        ));
+    }
+}
@@ -3,8 +3,94 @@ use serde::{Deserialize, Serialize};
 use std::fmt;
 use uuid::Uuid;
 
+pub mod batch;
+#[cfg(feature = "binary")]
+pub mod binary;
+pub mod builder;
+#[cfg(feature = "zip")]
+pub mod bundle;
+pub mod checker;
+pub mod compare;
+pub mod compat;
+pub mod compile_flags;
+pub mod compile_log;
+pub mod custom_language;
+pub mod diagnostics;
+pub mod env_policy;
+pub mod generator;
+pub mod hints;
+pub mod image;
+pub mod integrity;
+pub mod interactive;
+pub mod java;
+pub mod language_id;
+pub mod languages;
+pub mod limits;
+pub mod multi_case;
+pub mod ordering;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+pub mod preview;
+#[cfg(feature = "proto")]
+pub mod proto;
+pub mod queue;
+pub mod redaction;
+pub mod rejection;
+pub mod scoring;
+pub mod screening;
+pub mod signal;
+pub mod source_hash;
+pub mod subtask;
+pub mod summary;
+pub mod test_data;
+pub mod testcases;
+#[cfg(any(test, feature = "test-util"))]
+pub mod testing;
+pub mod validation;
+pub mod wire;
+
+pub use batch::{BatchInfo, MergeError};
+#[cfg(feature = "binary")]
+pub use binary::BinaryError;
+pub use builder::{BuildError, SubmissionBuilder};
+#[cfg(feature = "zip")]
+pub use bundle::{BundleError, TestCaseBundle};
+pub use checker::{Checker, CheckerError, OutputCheck};
+pub use compare::{ComparisonMode, OutputDiff, OutputVerdict};
+pub use compat::{Compat, CompatError, SCHEMA_VERSION};
+pub use compile_flags::{CompileFlags, CompileFlagsError, FlagPolicy};
+pub use compile_log::CompileLog;
+pub use custom_language::{CustomLanguage, CustomLanguageError};
+pub use diagnostics::{Diagnostic, Severity};
+pub use env_policy::{EnvError, EnvPolicy};
+pub use generator::{GeneratedTestCase, GeneratorError, ProgramSpec, TestCaseSource};
+pub use hints::{HintError, HintRules, HintSpec, HintVerdict};
+pub use image::{ImageRef, ParseImageRefError};
+pub use integrity::{DataPart, IntegrityError};
+pub use interactive::{ExecutionMode, InteractorError, InteractorProgram, InteractorSpec, TestIo};
+pub use languages::{LanguageRegistry, LanguageSpec, RegistryError};
+pub use limits::{
+    DEFAULT_COMPILE_MEMORY_LIMIT_KB, DEFAULT_COMPILE_TIME_LIMIT_MS, DEFAULT_OUTPUT_LIMIT_BYTES,
+    DEFAULT_STACK_LIMIT_KB, EffectiveLimits, LimitPolicy, LimitPolicyError, LimitScaling,
+    TestCaseLimits,
+};
+pub use multi_case::{MultiCaseError, MultiCaseSpec};
+pub use ordering::{FailureStats, TestOrder};
+#[cfg(feature = "proto")]
+pub use proto::ProtoError;
+pub use queue::{QueueKey, QueuedSubmission};
+pub use redaction::RedactionPolicy;
+pub use rejection::{RejectedSubmission, RejectionReason};
+pub use scoring::JudgeMode;
+pub use screening::{ScreeningRules, Violation};
+pub use source_hash::{ParseSourceHashError, SourceHash};
+pub use subtask::{Subtask, SubtaskError};
+pub use summary::JudgeSummary;
+pub use test_data::{TestData, TestDataError};
+pub use validation::{SubmissionPolicy, ValidationError};
+
 /// Programming languages supported by the judger
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ProgrammingLanguage {
     C,
     Cpp,
@@ -19,10 +105,104 @@ pub enum ProgrammingLanguage {
     Go,
     JavaScript,
     TypeScript,
+    Kotlin,
+    CSharp,
+    Ruby,
+    PHP,
+    /// Toolchain supplied with the submission, see [`CustomLanguage`]
+    Custom,
 }
 
 impl ProgrammingLanguage {
+    /// All built-in languages (everything except [`Custom`](Self::Custom))
+    pub const ALL: [ProgrammingLanguage; 17] = [
+        ProgrammingLanguage::C,
+        ProgrammingLanguage::Cpp,
+        ProgrammingLanguage::Cpp11,
+        ProgrammingLanguage::Cpp14,
+        ProgrammingLanguage::Cpp17,
+        ProgrammingLanguage::Cpp20,
+        ProgrammingLanguage::Python2,
+        ProgrammingLanguage::Python3,
+        ProgrammingLanguage::Java,
+        ProgrammingLanguage::Rust,
+        ProgrammingLanguage::Go,
+        ProgrammingLanguage::JavaScript,
+        ProgrammingLanguage::TypeScript,
+        ProgrammingLanguage::Kotlin,
+        ProgrammingLanguage::CSharp,
+        ProgrammingLanguage::Ruby,
+        ProgrammingLanguage::PHP,
+    ];
+
+    /// Returns the stable numeric id of this language
+    ///
+    /// These codes are persisted by external systems and must never change or be
+    /// reused; new languages get the next free number.
+    ///
+    /// | id | language   | id | language   |
+    /// |----|------------|----|------------|
+    /// | 1  | C          | 10 | Rust       |
+    /// | 2  | Cpp        | 11 | Go         |
+    /// | 3  | Cpp11      | 12 | JavaScript |
+    /// | 4  | Cpp14      | 13 | TypeScript |
+    /// | 5  | Cpp17      | 14 | Kotlin     |
+    /// | 6  | Cpp20      | 15 | CSharp     |
+    /// | 7  | Python2    | 16 | Ruby       |
+    /// | 8  | Python3    | 17 | PHP        |
+    /// | 9  | Java       | 18 | Custom     |
+    pub fn id(&self) -> u32 {
+        match self {
+            ProgrammingLanguage::C => 1,
+            ProgrammingLanguage::Cpp => 2,
+            ProgrammingLanguage::Cpp11 => 3,
+            ProgrammingLanguage::Cpp14 => 4,
+            ProgrammingLanguage::Cpp17 => 5,
+            ProgrammingLanguage::Cpp20 => 6,
+            ProgrammingLanguage::Python2 => 7,
+            ProgrammingLanguage::Python3 => 8,
+            ProgrammingLanguage::Java => 9,
+            ProgrammingLanguage::Rust => 10,
+            ProgrammingLanguage::Go => 11,
+            ProgrammingLanguage::JavaScript => 12,
+            ProgrammingLanguage::TypeScript => 13,
+            ProgrammingLanguage::Kotlin => 14,
+            ProgrammingLanguage::CSharp => 15,
+            ProgrammingLanguage::Ruby => 16,
+            ProgrammingLanguage::PHP => 17,
+            ProgrammingLanguage::Custom => 18,
+        }
+    }
+
+    /// Returns the language with the given stable numeric id
+    pub fn from_id(id: u32) -> Option<Self> {
+        match id {
+            1 => Some(ProgrammingLanguage::C),
+            2 => Some(ProgrammingLanguage::Cpp),
+            3 => Some(ProgrammingLanguage::Cpp11),
+            4 => Some(ProgrammingLanguage::Cpp14),
+            5 => Some(ProgrammingLanguage::Cpp17),
+            6 => Some(ProgrammingLanguage::Cpp20),
+            7 => Some(ProgrammingLanguage::Python2),
+            8 => Some(ProgrammingLanguage::Python3),
+            9 => Some(ProgrammingLanguage::Java),
+            10 => Some(ProgrammingLanguage::Rust),
+            11 => Some(ProgrammingLanguage::Go),
+            12 => Some(ProgrammingLanguage::JavaScript),
+            13 => Some(ProgrammingLanguage::TypeScript),
+            14 => Some(ProgrammingLanguage::Kotlin),
+            15 => Some(ProgrammingLanguage::CSharp),
+            16 => Some(ProgrammingLanguage::Ruby),
+            17 => Some(ProgrammingLanguage::PHP),
+            18 => Some(ProgrammingLanguage::Custom),
+            _ => None,
+        }
+    }
+
     /// Returns the file extension for this language
+    ///
+    /// The built-in tables below return placeholders for [`Custom`](Self::Custom);
+    /// the real values come from the submission's [`CustomLanguage`].
     pub fn file_extension(&self) -> &'static str {
         match self {
             ProgrammingLanguage::C => "c",
@@ -38,6 +218,11 @@ impl ProgrammingLanguage {
             ProgrammingLanguage::Go => "go",
             ProgrammingLanguage::JavaScript => "js",
             ProgrammingLanguage::TypeScript => "ts",
+            ProgrammingLanguage::Kotlin => "kt",
+            ProgrammingLanguage::CSharp => "cs",
+            ProgrammingLanguage::Ruby => "rb",
+            ProgrammingLanguage::PHP => "php",
+            ProgrammingLanguage::Custom => "txt",
         }
     }
 
@@ -56,7 +241,12 @@ impl ProgrammingLanguage {
             ProgrammingLanguage::Rust => "rustc",
             ProgrammingLanguage::Go => "go",
             ProgrammingLanguage::JavaScript => "node",
-            ProgrammingLanguage::TypeScript => "ts-node",
+            ProgrammingLanguage::TypeScript => "tsc",
+            ProgrammingLanguage::Kotlin => "kotlinc",
+            ProgrammingLanguage::CSharp => "mcs",
+            ProgrammingLanguage::Ruby => "ruby",
+            ProgrammingLanguage::PHP => "php",
+            ProgrammingLanguage::Custom => "",
         }
     }
 
@@ -71,11 +261,16 @@ impl ProgrammingLanguage {
             | ProgrammingLanguage::Cpp20
             | ProgrammingLanguage::Java
             | ProgrammingLanguage::Rust
-            | ProgrammingLanguage::Go => true,
+            | ProgrammingLanguage::Go
+            | ProgrammingLanguage::TypeScript
+            | ProgrammingLanguage::Kotlin
+            | ProgrammingLanguage::CSharp => true,
             ProgrammingLanguage::Python2
             | ProgrammingLanguage::Python3
             | ProgrammingLanguage::JavaScript
-            | ProgrammingLanguage::TypeScript => false,
+            | ProgrammingLanguage::Ruby
+            | ProgrammingLanguage::PHP
+            | ProgrammingLanguage::Custom => false,
         }
     }
 
@@ -111,29 +306,271 @@ impl ProgrammingLanguage {
             ProgrammingLanguage::Java => vec!["-Xlint:all".to_string()],
             ProgrammingLanguage::Rust => vec!["-O".to_string()],
             ProgrammingLanguage::Go => vec![],
+            ProgrammingLanguage::TypeScript => vec!["--target".to_string(), "es2020".to_string()],
+            ProgrammingLanguage::Kotlin => vec!["-include-runtime".to_string()],
+            ProgrammingLanguage::CSharp => vec!["-optimize+".to_string()],
             _ => vec![],
         }
     }
 
-    /// Returns the default runtime command for this language
-    pub fn default_runtime(&self) -> &'static str {
+    /// Returns the compilation flags for this language under a build profile
+    ///
+    /// The sanitize profile only affects C and C++: optimization flags are
+    /// dropped in favor of AddressSanitizer/UBSan instrumentation and debug info.
+    pub fn compile_flags_for(&self, profile: BuildProfile) -> Vec<String> {
+        let flags = self.default_compile_flags();
+        match (profile, self.is_c_family()) {
+            (BuildProfile::Sanitize, true) => flags
+                .into_iter()
+                .filter(|flag| !flag.starts_with("-O"))
+                .chain([
+                    "-fsanitize=address,undefined".to_string(),
+                    "-fno-omit-frame-pointer".to_string(),
+                    "-g".to_string(),
+                ])
+                .collect(),
+            _ => flags,
+        }
+    }
+
+    /// Returns the file the compile step produces, or `None` for interpreted languages
+    ///
+    /// [`compile_command`](Self::compile_command) is expected to be called with this name as
+    /// its output. Java is the exception: `javac` names class files after the public class,
+    /// so this is only the default `Main.class`; use [`Submission::artifact_name`] for the
+    /// actual name.
+    pub fn artifact_name(&self) -> Option<&'static str> {
         match self {
-            ProgrammingLanguage::C => "./a.out",
-            ProgrammingLanguage::Cpp => "./a.out",
-            ProgrammingLanguage::Cpp11 => "./a.out",
-            ProgrammingLanguage::Cpp14 => "./a.out",
-            ProgrammingLanguage::Cpp17 => "./a.out",
-            ProgrammingLanguage::Cpp20 => "./a.out",
+            ProgrammingLanguage::C
+            | ProgrammingLanguage::Cpp
+            | ProgrammingLanguage::Cpp11
+            | ProgrammingLanguage::Cpp14
+            | ProgrammingLanguage::Cpp17
+            | ProgrammingLanguage::Cpp20 => Some("a.out"),
+            ProgrammingLanguage::Rust | ProgrammingLanguage::Go => Some("main"),
+            ProgrammingLanguage::Java => Some("Main.class"),
+            ProgrammingLanguage::TypeScript => Some("main.js"),
+            ProgrammingLanguage::Kotlin => Some("main.jar"),
+            ProgrammingLanguage::CSharp => Some("main.exe"),
+            ProgrammingLanguage::Python2
+            | ProgrammingLanguage::Python3
+            | ProgrammingLanguage::JavaScript
+            | ProgrammingLanguage::Ruby
+            | ProgrammingLanguage::PHP
+            | ProgrammingLanguage::Custom => None,
+        }
+    }
+
+    /// Returns whether the compiled artifact is executed directly
+    pub fn is_native(&self) -> bool {
+        self.is_c_family() || matches!(self, ProgrammingLanguage::Rust | ProgrammingLanguage::Go)
+    }
+
+    /// Returns whether this language is C or a C++ dialect
+    pub fn is_c_family(&self) -> bool {
+        matches!(
+            self,
+            ProgrammingLanguage::C
+                | ProgrammingLanguage::Cpp
+                | ProgrammingLanguage::Cpp11
+                | ProgrammingLanguage::Cpp14
+                | ProgrammingLanguage::Cpp17
+                | ProgrammingLanguage::Cpp20
+        )
+    }
+
+    /// Returns the default runtime command for this language
+    ///
+    /// Native languages run their [`artifact_name`](Self::artifact_name) from the
+    /// working directory.
+    pub fn default_runtime(&self) -> String {
+        let runtime = match self {
+            ProgrammingLanguage::C
+            | ProgrammingLanguage::Cpp
+            | ProgrammingLanguage::Cpp11
+            | ProgrammingLanguage::Cpp14
+            | ProgrammingLanguage::Cpp17
+            | ProgrammingLanguage::Cpp20
+            | ProgrammingLanguage::Rust
+            | ProgrammingLanguage::Go => {
+                return format!("./{}", self.artifact_name().unwrap_or_default());
+            }
             ProgrammingLanguage::Python2 => "python2",
             ProgrammingLanguage::Python3 => "python3",
             ProgrammingLanguage::Java => "java",
-            ProgrammingLanguage::Rust => "./main",
-            ProgrammingLanguage::Go => "./main",
             ProgrammingLanguage::JavaScript => "node",
-            ProgrammingLanguage::TypeScript => "ts-node",
+            ProgrammingLanguage::TypeScript => "node",
+            ProgrammingLanguage::Kotlin => "java",
+            ProgrammingLanguage::CSharp => "mono",
+            ProgrammingLanguage::Ruby => "ruby",
+            ProgrammingLanguage::PHP => "php",
+            ProgrammingLanguage::Custom => "",
+        };
+        runtime.to_string()
+    }
+
+    /// Returns the default time limit multiplier for this language
+    pub fn time_multiplier(&self) -> f64 {
+        match self {
+            ProgrammingLanguage::Java | ProgrammingLanguage::Kotlin => 2.0,
+            ProgrammingLanguage::CSharp => 1.5,
+            ProgrammingLanguage::Python2
+            | ProgrammingLanguage::Python3
+            | ProgrammingLanguage::Ruby
+            | ProgrammingLanguage::PHP => 3.0,
+            ProgrammingLanguage::JavaScript | ProgrammingLanguage::TypeScript => 2.0,
+            _ => 1.0,
+        }
+    }
+
+    /// Returns the default extra memory (KB) granted to this language
+    pub fn memory_bonus_kb(&self) -> u64 {
+        match self {
+            ProgrammingLanguage::Java | ProgrammingLanguage::Kotlin => 256 * 1024,
+            ProgrammingLanguage::CSharp
+            | ProgrammingLanguage::JavaScript
+            | ProgrammingLanguage::TypeScript => 64 * 1024,
+            _ => 0,
+        }
+    }
+
+    /// Returns the default limit scaling for this language
+    pub fn limit_scaling(&self) -> LimitScaling {
+        LimitScaling {
+            time_multiplier: self.time_multiplier(),
+            memory_bonus_kb: self.memory_bonus_kb(),
         }
     }
 
+    /// Returns the full compiler argv for building `source` into `output`
+    ///
+    /// Returns `None` for interpreted languages. Java ignores `output`: `javac`
+    /// writes the class files next to the source, named after the public class.
+    /// Kotlin builds a self-contained jar, C# a Mono assembly and TypeScript a
+    /// single JavaScript file at `output`.
+    pub fn compile_command(&self, source: &str, output: &str) -> Option<Vec<String>> {
+        self.compile_command_with(
+            self.default_compiler(),
+            &self.default_compile_flags(),
+            source,
+            output,
+        )
+    }
+
+    /// Same as [`compile_command`](Self::compile_command) with a custom compiler and flags
+    pub fn compile_command_with(
+        &self,
+        compiler: &str,
+        flags: &[String],
+        source: &str,
+        output: &str,
+    ) -> Option<Vec<String>> {
+        if !self.needs_compilation() {
+            return None;
+        }
+
+        let mut argv = vec![compiler.to_string()];
+        match self {
+            ProgrammingLanguage::Java => {
+                argv.extend_from_slice(flags);
+                argv.push(source.to_string());
+            }
+            ProgrammingLanguage::Kotlin => {
+                argv.extend_from_slice(flags);
+                argv.extend([source.to_string(), "-d".to_string(), output.to_string()]);
+            }
+            ProgrammingLanguage::CSharp => {
+                argv.extend_from_slice(flags);
+                argv.extend([source.to_string(), format!("-out:{}", output)]);
+            }
+            ProgrammingLanguage::TypeScript => {
+                argv.extend_from_slice(flags);
+                argv.extend([
+                    "--outFile".to_string(),
+                    output.to_string(),
+                    source.to_string(),
+                ]);
+            }
+            ProgrammingLanguage::Go => {
+                argv.push("build".to_string());
+                argv.extend_from_slice(flags);
+                argv.extend(["-o".to_string(), output.to_string(), source.to_string()]);
+            }
+            _ => {
+                argv.extend_from_slice(flags);
+                argv.extend([source.to_string(), "-o".to_string(), output.to_string()]);
+            }
+        }
+        Some(argv)
+    }
+
+    /// Returns the full argv for running `artifact`
+    ///
+    /// `artifact` is the compiled binary for native languages, the class name
+    /// for Java, the jar for Kotlin, the assembly for C#, the emitted script for
+    /// TypeScript, and the source file for interpreted languages.
+    pub fn run_command(&self, artifact: &str) -> Vec<String> {
+        self.run_command_with(&self.default_runtime(), artifact)
+    }
+
+    /// Same as [`run_command`](Self::run_command) with a custom runtime
+    pub fn run_command_with(&self, runtime: &str, artifact: &str) -> Vec<String> {
+        match self {
+            ProgrammingLanguage::C
+            | ProgrammingLanguage::Cpp
+            | ProgrammingLanguage::Cpp11
+            | ProgrammingLanguage::Cpp14
+            | ProgrammingLanguage::Cpp17
+            | ProgrammingLanguage::Cpp20
+            | ProgrammingLanguage::Rust
+            | ProgrammingLanguage::Go => vec![artifact.to_string()],
+            ProgrammingLanguage::Java => {
+                let class_name = artifact.strip_suffix(".class").unwrap_or(artifact);
+                vec![runtime.to_string(), class_name.to_string()]
+            }
+            ProgrammingLanguage::Kotlin => {
+                vec![
+                    runtime.to_string(),
+                    "-jar".to_string(),
+                    artifact.to_string(),
+                ]
+            }
+            ProgrammingLanguage::CSharp
+            | ProgrammingLanguage::Python2
+            | ProgrammingLanguage::Python3
+            | ProgrammingLanguage::JavaScript
+            | ProgrammingLanguage::TypeScript
+            | ProgrammingLanguage::Ruby
+            | ProgrammingLanguage::PHP
+            | ProgrammingLanguage::Custom => vec![runtime.to_string(), artifact.to_string()],
+        }
+    }
+
+    /// Returns the argv for the optional precompile step of an interpreted language
+    ///
+    /// The step byte-compiles (Python) or syntax-checks (JavaScript, Ruby, PHP) the
+    /// source once before the test loop, so syntax errors surface as a compile error
+    /// and per-test timings don't include import/compile cost.
+    /// Returns `None` for languages without such a step.
+    pub fn precompile_command(&self, source: &str) -> Option<Vec<String>> {
+        self.precompile_command_with(&self.default_runtime(), source)
+    }
+
+    /// Same as [`precompile_command`](Self::precompile_command) with a custom runtime
+    pub fn precompile_command_with(&self, runtime: &str, source: &str) -> Option<Vec<String>> {
+        let args: &[&str] = match self {
+            ProgrammingLanguage::Python2 | ProgrammingLanguage::Python3 => &["-m", "py_compile"],
+            ProgrammingLanguage::JavaScript => &["--check"],
+            ProgrammingLanguage::Ruby => &["-c"],
+            ProgrammingLanguage::PHP => &["-l"],
+            _ => return None,
+        };
+        let mut argv = vec![runtime.to_string()];
+        argv.extend(args.iter().map(|arg| arg.to_string()));
+        argv.push(source.to_string());
+        Some(argv)
+    }
+
     /// Returns a string representation of the language
     pub fn as_str(&self) -> &'static str {
         match self {
@@ -150,6 +587,11 @@ impl ProgrammingLanguage {
             ProgrammingLanguage::Go => "Go",
             ProgrammingLanguage::JavaScript => "JavaScript",
             ProgrammingLanguage::TypeScript => "TypeScript",
+            ProgrammingLanguage::Kotlin => "Kotlin",
+            ProgrammingLanguage::CSharp => "C#",
+            ProgrammingLanguage::Ruby => "Ruby",
+            ProgrammingLanguage::PHP => "PHP",
+            ProgrammingLanguage::Custom => "Custom",
         }
     }
 }
@@ -177,10 +619,31 @@ pub struct Submission {
     pub priority: i32,
     /// Contest identifier if this is a contest submission
     pub contest_id: Option<Uuid>,
+    /// Toolchain for [`ProgrammingLanguage::Custom`] submissions
+    #[serde(default)]
+    pub custom_language: Option<CustomLanguage>,
+    /// Id of the original submission if this is a rejudge
+    #[serde(default)]
+    pub rejudge_of: Option<Uuid>,
+    /// Judging attempt, 1 for the original submission
+    #[serde(default = "first_attempt")]
+    pub attempt: u32,
+    /// Output limit in bytes ([`DEFAULT_OUTPUT_LIMIT_BYTES`] if not set)
+    #[serde(default)]
+    pub output_limit: Option<u64>,
+    /// Stack limit in kilobytes ([`DEFAULT_STACK_LIMIT_KB`] if not set)
+    #[serde(default)]
+    pub stack_limit: Option<u64>,
+}
+
+fn first_attempt() -> u32 {
+    1
 }
 
 impl Submission {
     /// Creates a new submission with default values
+    ///
+    /// Does not check invariants; prefer [`SubmissionBuilder`] for new code.
     pub fn new(
         problem_id: Uuid,
         user_id: Uuid,
@@ -189,21 +652,15 @@ impl Submission {
         time_limit: u64,
         memory_limit: u64,
     ) -> Self {
-        Self {
-            id: Uuid::new_v4(),
-            problem_id,
-            user_id,
-            language,
-            source_code,
-            created_at: Utc::now(),
-            time_limit,
-            memory_limit,
-            priority: 0,
-            contest_id: None,
-        }
+        SubmissionBuilder::new(problem_id, user_id, language, source_code)
+            .time_limit(time_limit)
+            .memory_limit(memory_limit)
+            .build_unchecked()
     }
 
     /// Creates a contest submission
+    ///
+    /// Does not check invariants; prefer [`SubmissionBuilder`] for new code.
     pub fn for_contest(
         problem_id: Uuid,
         user_id: Uuid,
@@ -213,28 +670,126 @@ impl Submission {
         time_limit: u64,
         memory_limit: u64,
     ) -> Self {
-        Self {
+        SubmissionBuilder::new(problem_id, user_id, language, source_code)
+            .time_limit(time_limit)
+            .memory_limit(memory_limit)
+            .contest(contest_id) // Higher priority for contest submissions
+            .build_unchecked()
+    }
+
+    /// Creates a rejudge of this submission
+    ///
+    /// The copy gets a new id, the next attempt number and [`builder::REJUDGE_PRIORITY`]
+    /// so rejudges don't starve live traffic. `rejudge_of` always points at the
+    /// original submission, even when rejudging a rejudge. The attempt number
+    /// saturates at `u32::MAX`.
+    pub fn rejudge(&self) -> Submission {
+        Submission {
             id: Uuid::new_v4(),
-            problem_id,
-            user_id,
-            language,
-            source_code,
-            created_at: Utc::now(),
-            time_limit,
-            memory_limit,
-            priority: 10, // Higher priority for contest submissions
-            contest_id: Some(contest_id),
+            rejudge_of: Some(self.rejudge_of.unwrap_or(self.id)),
+            attempt: self.attempt.saturating_add(1),
+            priority: builder::REJUDGE_PRIORITY.min(self.priority),
+            ..self.clone()
+        }
+    }
+
+    /// Returns the effective output limit in bytes (custom or default)
+    pub fn effective_output_limit(&self) -> u64 {
+        self.output_limit.unwrap_or(DEFAULT_OUTPUT_LIMIT_BYTES)
+    }
+
+    /// Returns the effective stack limit in kilobytes (custom or default)
+    ///
+    /// The stack counts against the memory limit, so it never exceeds it.
+    pub fn effective_stack_limit(&self) -> u64 {
+        self.stack_limit
+            .unwrap_or(DEFAULT_STACK_LIMIT_KB)
+            .min(self.memory_limit)
+    }
+
+    /// Returns whether this submission is a rejudge
+    pub fn is_rejudge(&self) -> bool {
+        self.rejudge_of.is_some()
+    }
+
+    /// Switches the submission to a custom language toolchain
+    pub fn with_custom_language(mut self, spec: CustomLanguage) -> Self {
+        self.language = ProgrammingLanguage::Custom;
+        self.custom_language = Some(spec);
+        self
+    }
+
+    /// Returns the custom toolchain if this is a [`ProgrammingLanguage::Custom`] submission
+    fn custom(&self) -> Option<&CustomLanguage> {
+        match self.language {
+            ProgrammingLanguage::Custom => self.custom_language.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Checks that a custom language submission carries a valid spec
+    ///
+    /// Built-in languages always pass; a stray `custom_language` on them is ignored.
+    pub fn validate_language(&self) -> Result<(), CustomLanguageError> {
+        match self.language {
+            ProgrammingLanguage::Custom => self
+                .custom_language
+                .as_ref()
+                .ok_or(CustomLanguageError::MissingSpec)?
+                .validate(),
+            _ => Ok(()),
         }
     }
 
     /// Returns the filename for this submission based on language
+    ///
+    /// Java sources are named after their public class, since javac requires it.
     pub fn filename(&self) -> String {
-        format!("main.{}", self.language.file_extension())
+        if let Some(custom) = self.custom() {
+            return custom.filename();
+        }
+        match self.main_class() {
+            Some(class_name) => format!("{}.{}", class_name, self.language.file_extension()),
+            None => format!("main.{}", self.language.file_extension()),
+        }
+    }
+
+    /// Returns the class to launch for Java submissions
+    ///
+    /// This is the public top-level class, or [`java::DEFAULT_MAIN_CLASS`] if none is declared.
+    /// Returns `None` for other languages.
+    pub fn main_class(&self) -> Option<String> {
+        match self.language {
+            ProgrammingLanguage::Java => Some(
+                java::main_class_name(&self.source_code)
+                    .unwrap_or_else(|| java::DEFAULT_MAIN_CLASS.to_string()),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Builds the error reported when compiling this submission failed
+    ///
+    /// A Java source without a public class was compiled as
+    /// [`java::DEFAULT_MAIN_CLASS`], so its error says that instead of repeating the
+    /// compiler's first message; the compiler output is kept as stderr either way.
+    pub fn compile_error(&self, compiler_output: &str) -> ErrorInfo {
+        if self.language == ProgrammingLanguage::Java
+            && java::main_class_name(&self.source_code).is_none()
+        {
+            let mut error = java::no_public_class_error();
+            error.stderr = Some(compiler_output.to_string());
+            return error;
+        }
+        ErrorInfo::from_compiler_output(self.language, compiler_output)
     }
 
     /// Returns whether this submission needs compilation
     pub fn needs_compilation(&self) -> bool {
-        self.language.needs_compilation()
+        match self.custom() {
+            Some(custom) => custom.needs_compilation,
+            None => self.language.needs_compilation(),
+        }
     }
 
     /// Returns the default compilation flags for this submission's language
@@ -242,15 +797,102 @@ impl Submission {
         self.language.default_compile_flags()
     }
 
-    /// Returns the default runtime command for this submission's language
-    pub fn default_runtime(&self) -> &'static str {
-        self.language.default_runtime()
+    /// Returns the runtime command for this submission
+    ///
+    /// Native languages run their [`artifact_name`](Self::artifact_name) from the working
+    /// directory; other languages use the language's runtime. Custom languages use the
+    /// first word of their expanded [`run_command`](Self::run_command).
+    pub fn default_runtime(&self) -> String {
+        if self.custom().is_some() {
+            return self.run_command().into_iter().next().unwrap_or_default();
+        }
+        match self.artifact_name() {
+            Some(artifact) if self.language.is_native() => format!("./{}", artifact),
+            _ => self.language.default_runtime(),
+        }
+    }
+
+    /// Returns the file the compile step produces, or `None` for interpreted languages
+    ///
+    /// Java produces `<MainClass>.class`; see [`main_class`](Self::main_class).
+    pub fn artifact_name(&self) -> Option<String> {
+        if let Some(custom) = self.custom() {
+            return custom.artifact_name().map(str::to_string);
+        }
+        match self.main_class() {
+            Some(class_name) => Some(format!("{}.class", class_name)),
+            None => self.language.artifact_name().map(str::to_string),
+        }
+    }
+
+    /// Returns the compiler argv for building this submission into its artifact
+    pub fn compile_command(&self) -> Option<Vec<String>> {
+        if let Some(custom) = self.custom() {
+            return custom.compile_command(&self.filename(), &self.artifact_name()?);
+        }
+        self.language
+            .compile_command(&self.filename(), &self.artifact_name()?)
+    }
+
+    /// Returns the argv for running this submission after the compile step
+    ///
+    /// Runs the [`artifact_name`](Self::artifact_name) for compiled languages and the
+    /// source file for interpreted ones. Java launches its [`main_class`](Self::main_class).
+    pub fn run_command(&self) -> Vec<String> {
+        if let Some(custom) = self.custom() {
+            let artifact = custom.artifact_name().unwrap_or_default();
+            return custom.run_command(&self.filename(), artifact);
+        }
+        let artifact = match self.artifact_name() {
+            Some(_) if self.language.is_native() => self.default_runtime(),
+            Some(artifact) => artifact,
+            None => self.filename(),
+        };
+        self.language.run_command(&artifact)
+    }
+}
+
+/// How a submission is built before running
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BuildProfile {
+    /// Optimized build used for regular judging
+    #[default]
+    Release,
+    /// Instrumented C/C++ build (ASan + UBSan) for diagnosing undefined behavior
+    Sanitize,
+}
+
+impl BuildProfile {
+    /// Factor applied to memory limits under the sanitize profile
+    ///
+    /// ASan's shadow memory and redzones typically need 2-3x the memory of an
+    /// uninstrumented run, so a stricter limit would just report MLE.
+    pub const SANITIZE_MEMORY_FACTOR: u64 = 4;
+
+    /// Relaxes a memory limit (KB) to account for instrumentation overhead
+    ///
+    /// Only C and C++ are instrumented (see [`ProgrammingLanguage::compile_flags_for`]);
+    /// other languages keep their limit.
+    pub fn relax_memory_limit(&self, language: ProgrammingLanguage, memory_limit: u64) -> u64 {
+        match self {
+            BuildProfile::Sanitize if language.is_c_family() => {
+                memory_limit.saturating_mul(Self::SANITIZE_MEMORY_FACTOR)
+            }
+            _ => memory_limit,
+        }
     }
 }
 
 /// Judge task for the judger service
+///
+/// Deserialization clamps `stack_limit` to the submission's memory limit, like
+/// [`JudgeTask::new`] does, so defaulted limits of old payloads stay consistent.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub struct JudgeTask {
+    /// Wire format version, see [`compat`]
+    #[serde(default = "compat::default_schema_version")]
+    pub schema_version: u32,
     /// Submission information
     pub submission: Submission,
     /// Test cases to run against the submission
@@ -258,17 +900,103 @@ pub struct JudgeTask {
     /// Whether to compile the code (true for compiled languages)
     pub needs_compilation: bool,
     /// Whether to run the code in a sandbox
+    ///
+    /// `false` selects the fast path for trusted internal jobs such as setters'
+    /// reference solutions: the program runs on the host with rlimits only. Judgers
+    /// reject such tasks unless explicitly configured to accept them.
     pub use_sandbox: bool,
     /// Additional compilation flags
     pub compile_flags: Option<Vec<String>>,
     /// Additional runtime arguments
     pub runtime_args: Option<Vec<String>>,
+    /// Environment variables for the run, checked against an [`EnvPolicy`]
+    #[serde(default)]
+    pub env: Vec<(String, String)>,
+    /// Per-language limit scaling policy (language defaults if not set)
+    #[serde(default)]
+    pub limit_policy: Option<LimitPolicy>,
+    /// Build profile used when compiling the submission
+    #[serde(default)]
+    pub build_profile: BuildProfile,
+    /// Whether to run the language's precompile step before the test loop
+    ///
+    /// Failures of this step are reported as [`JudgeStatus::CompileError`].
+    #[serde(default)]
+    pub precompile: bool,
+    /// Whether to skip static source screening, e.g. for problems that need `fork`
+    #[serde(default)]
+    pub skip_screening: bool,
+    /// Whether to stop running test cases after the first failure; `None` follows
+    /// `judge_mode`
+    ///
+    /// Payloads without the field predate the mode and keep running every test.
+    #[serde(default = "scoring::default_stop_on_first_failure")]
+    pub stop_on_first_failure: Option<bool>,
+    /// Order in which test cases are executed
+    #[serde(default)]
+    pub test_order: TestOrder,
+    /// Batch or interactive execution; in interactive mode the test case input goes
+    /// to the interactor (see [`ExecutionMode::test_io`])
+    #[serde(default)]
+    pub execution_mode: ExecutionMode,
+    /// How test case output is checked
+    #[serde(default)]
+    pub checker: Checker,
+    /// Comparison mode of test cases that don't set one (see
+    /// [`JudgeTask::comparison_for`])
+    #[serde(default)]
+    pub default_comparison: Option<ComparisonMode>,
+    /// Time limit of the compile phase in milliseconds
+    /// ([`DEFAULT_COMPILE_TIME_LIMIT_MS`] if not set)
+    #[serde(default)]
+    pub compile_time_limit: Option<u64>,
+    /// Memory limit of the compile phase in kilobytes
+    /// ([`DEFAULT_COMPILE_MEMORY_LIMIT_KB`] if not set)
+    #[serde(default)]
+    pub compile_memory_limit: Option<u64>,
+    /// Output limit in bytes, resolved from the submission
+    #[serde(default = "limits::default_output_limit")]
+    pub output_limit: u64,
+    /// Stack limit in kilobytes, resolved from the submission and at most its memory limit
+    #[serde(default = "limits::default_stack_limit")]
+    pub stack_limit: u64,
+    /// IOI-style subtasks; empty for tasks scored per test case
+    #[serde(default)]
+    pub subtasks: Vec<Subtask>,
+    /// Whether judging is ACM/ICPC (all-or-nothing) or OI (partial score)
+    #[serde(default)]
+    pub judge_mode: JudgeMode,
+    /// Set on the batches produced by [`JudgeTask::split_into_batches`]
+    #[serde(default)]
+    pub batch: Option<BatchInfo>,
+    /// Produces the input of generated test cases
+    #[serde(default)]
+    pub generator: Option<ProgramSpec>,
+    /// Produces the expected output of generated test cases
+    #[serde(default)]
+    pub reference_solution: Option<ProgramSpec>,
+}
+
+impl Serialize for JudgeTask {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        JudgeTask::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for JudgeTask {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut task = JudgeTask::deserialize(deserializer)?;
+        task.stack_limit = task.stack_limit.min(task.submission.memory_limit);
+        Ok(task)
+    }
 }
 
 impl JudgeTask {
     /// Creates a new judge task from a submission and test cases
     pub fn new(submission: Submission, test_cases: Vec<TestCase>) -> Self {
         let needs_compilation = submission.needs_compilation();
+        let output_limit = submission.effective_output_limit();
+        let stack_limit = submission.effective_stack_limit();
         let compile_flags = if needs_compilation {
             Some(submission.default_compile_flags())
         } else {
@@ -276,13 +1004,105 @@ impl JudgeTask {
         };
 
         Self {
+            schema_version: SCHEMA_VERSION,
             submission,
             test_cases,
             needs_compilation,
             use_sandbox: true, // Always use sandbox for security
             compile_flags,
             runtime_args: None,
+            env: Vec::new(),
+            limit_policy: None,
+            build_profile: BuildProfile::Release,
+            precompile: false,
+            skip_screening: false,
+            stop_on_first_failure: None,
+            test_order: TestOrder::Declared,
+            execution_mode: ExecutionMode::Batch,
+            checker: Checker::DefaultDiff,
+            default_comparison: None,
+            compile_time_limit: None,
+            compile_memory_limit: None,
+            output_limit,
+            stack_limit,
+            subtasks: Vec::new(),
+            judge_mode: JudgeMode::AcmIcpc,
+            batch: None,
+            generator: None,
+            reference_solution: None,
+        }
+    }
+
+    /// Switches the task to another build profile, regenerating the compile flags
+    ///
+    /// The previous profile's defaults are replaced by the new one's; flags the caller
+    /// added on top of them are kept.
+    pub fn with_build_profile(mut self, profile: BuildProfile) -> Self {
+        let language = self.submission.language;
+        let previous = language.compile_flags_for(self.build_profile);
+        self.build_profile = profile;
+        if self.needs_compilation {
+            let mut flags = language.compile_flags_for(profile);
+            let added: Vec<String> = self
+                .compile_flags
+                .take()
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|flag| !previous.contains(flag) && !flags.contains(flag))
+                .collect();
+            flags.extend(added);
+            self.compile_flags = Some(flags);
         }
+        self
+    }
+
+    /// Returns the precompile argv if the task requests it and the language supports it
+    pub fn precompile_command(&self) -> Option<Vec<String>> {
+        if !self.precompile {
+            return None;
+        }
+        self.submission
+            .language
+            .precompile_command(&self.submission.filename())
+    }
+
+    /// Returns the compile flags to use, validated against the language allowlist
+    ///
+    /// `compile_flags` is treated as overrides merged into the defaults of the task's
+    /// build profile. Untrusted callers get a [`CompileFlagsError`] instead of having
+    /// dangerous flags silently dropped.
+    pub fn sanitized_compile_flags(
+        &self,
+        policy: FlagPolicy,
+    ) -> Result<CompileFlags, CompileFlagsError> {
+        CompileFlags::merge(
+            self.submission.language,
+            self.build_profile,
+            self.compile_flags.as_deref().unwrap_or_default(),
+            policy,
+        )
+    }
+
+    /// Returns whether judging stops after the first failing test case
+    ///
+    /// An explicit `stop_on_first_failure` wins; otherwise the judge mode decides.
+    pub fn stops_on_first_failure(&self) -> bool {
+        self.stop_on_first_failure
+            .unwrap_or_else(|| self.judge_mode.stops_on_first_failure())
+    }
+
+    /// Returns the indices of the test cases in the order they should run
+    ///
+    /// [`TestOrder::FailFirst`] only applies when judging stops on the first failure,
+    /// since otherwise every test runs anyway. Report results in declared order
+    /// (see [`ordering::restore_declared_order`]).
+    pub fn execution_order(&self, stats: Option<&FailureStats>) -> Vec<usize> {
+        let order = if self.stops_on_first_failure() {
+            self.test_order
+        } else {
+            TestOrder::Declared
+        };
+        ordering::execution_order(&self.test_cases, order, stats)
     }
 
     /// Returns the number of test cases in this task
@@ -290,6 +1110,16 @@ impl JudgeTask {
         self.test_cases.len()
     }
 
+    /// Returns the sample test cases
+    pub fn sample_cases(&self) -> impl Iterator<Item = &TestCase> {
+        self.test_cases.iter().filter(|tc| tc.is_sample)
+    }
+
+    /// Returns the test cases that aren't samples
+    pub fn non_sample_cases(&self) -> impl Iterator<Item = &TestCase> {
+        self.test_cases.iter().filter(|tc| !tc.is_sample)
+    }
+
     /// Returns the total weight of all test cases
     pub fn total_weight(&self) -> f64 {
         self.test_cases.iter().map(|tc| tc.weight).sum()
@@ -304,6 +1134,26 @@ impl JudgeTask {
             .unwrap_or(self.submission.time_limit)
     }
 
+    /// Returns the compile phase time limit in milliseconds (custom or default)
+    ///
+    /// Applies to compilation and the precompile step; exceeding it is a
+    /// [`JudgeStatus::CompileError`] (see [`ErrorInfo::compile_time_limit_exceeded`]).
+    pub fn effective_compile_time_limit(&self) -> u64 {
+        self.compile_time_limit
+            .unwrap_or(DEFAULT_COMPILE_TIME_LIMIT_MS)
+    }
+
+    /// Returns the compile phase memory limit in kilobytes (custom or default)
+    pub fn effective_compile_memory_limit(&self) -> u64 {
+        self.compile_memory_limit
+            .unwrap_or(DEFAULT_COMPILE_MEMORY_LIMIT_KB)
+    }
+
+    /// Returns the output limit the sandbox enforces on every run, in bytes
+    pub fn max_output_limit(&self) -> u64 {
+        self.output_limit
+    }
+
     /// Returns the maximum memory limit among all test cases
     pub fn max_memory_limit(&self) -> u64 {
         self.test_cases
@@ -312,6 +1162,42 @@ impl JudgeTask {
             .max()
             .unwrap_or(self.submission.memory_limit)
     }
+
+    /// Returns the limit scaling for the submission's language under this task's policy
+    pub fn limit_scaling(&self) -> LimitScaling {
+        match &self.limit_policy {
+            Some(policy) => policy.scaling_for(self.submission.language),
+            None => self.submission.language.limit_scaling(),
+        }
+    }
+
+    /// Returns the submission and per-test-case limits scaled for the submission's language
+    ///
+    /// Per-test-case limits still take precedence over the submission limits; both are scaled.
+    /// Memory limits are further relaxed by the task's [`BuildProfile`].
+    pub fn effective_limits_for_language(&self) -> EffectiveLimits {
+        let scaling = self.limit_scaling();
+        let scale_memory = |limit: u64| {
+            self.build_profile
+                .relax_memory_limit(self.submission.language, scaling.scale_memory(limit))
+        };
+        EffectiveLimits {
+            time_limit: scaling.scale_time(self.submission.time_limit),
+            memory_limit: scale_memory(self.submission.memory_limit),
+            test_cases: self
+                .test_cases
+                .iter()
+                .map(|tc| TestCaseLimits {
+                    id: tc.id.clone(),
+                    time_limit: scaling
+                        .scale_time(tc.effective_time_limit(self.submission.time_limit)),
+                    memory_limit: scale_memory(
+                        tc.effective_memory_limit(self.submission.memory_limit),
+                    ),
+                })
+                .collect(),
+        }
+    }
 }
 
 /// Test case definition
@@ -320,9 +1206,9 @@ pub struct TestCase {
     /// Test case identifier
     pub id: String,
     /// Input data for the test case
-    pub input: String,
+    pub input: TestData,
     /// Expected output
-    pub expected_output: String,
+    pub expected_output: TestData,
     /// Time limit for this specific test case (overrides submission time limit)
     pub time_limit: Option<u64>,
     /// Memory limit for this specific test case (overrides submission memory limit)
@@ -331,54 +1217,139 @@ pub struct TestCase {
     pub is_hidden: bool,
     /// Weight of this test case in scoring
     pub weight: f64,
+    /// Other outputs accepted besides `expected_output`
+    #[serde(default)]
+    pub alternative_outputs: Vec<TestData>,
+    /// Output comparison for this test case (overrides the task default)
+    #[serde(default)]
+    pub comparison: Option<ComparisonMode>,
+    /// Lowercase hex SHA-256 of the input, see [`TestCase::verify`]
+    #[serde(default)]
+    pub input_sha256: Option<String>,
+    /// Lowercase hex SHA-256 of the expected output
+    #[serde(default)]
+    pub output_sha256: Option<String>,
+    /// Whether the data is static or generated (see [`generator`])
+    #[serde(default)]
+    pub source: TestCaseSource,
+    /// Whether this is a sample test shown in the problem statement
+    #[serde(default)]
+    pub is_sample: bool,
+    /// Environment variables for this test case's run, on top of
+    /// [`JudgeTask::env`] (see [`JudgeTask::env_for`])
+    #[serde(default)]
+    pub env: Option<Vec<(String, String)>>,
 }
 
 impl TestCase {
     /// Creates a new test case with default values
-    pub fn new(id: String, input: String, expected_output: String) -> Self {
+    pub fn new(
+        id: String,
+        input: impl Into<TestData>,
+        expected_output: impl Into<TestData>,
+    ) -> Self {
         Self {
             id,
-            input,
-            expected_output,
+            input: input.into(),
+            expected_output: expected_output.into(),
             time_limit: None,
             memory_limit: None,
             is_hidden: false,
             weight: 1.0,
+            alternative_outputs: Vec::new(),
+            comparison: None,
+            input_sha256: None,
+            output_sha256: None,
+            source: TestCaseSource::Static,
+            is_sample: false,
+            env: None,
         }
     }
 
     /// Creates a hidden test case
-    pub fn hidden(id: String, input: String, expected_output: String) -> Self {
+    pub fn hidden(
+        id: String,
+        input: impl Into<TestData>,
+        expected_output: impl Into<TestData>,
+    ) -> Self {
         Self {
             id,
-            input,
-            expected_output,
+            input: input.into(),
+            expected_output: expected_output.into(),
             time_limit: None,
             memory_limit: None,
             is_hidden: true,
             weight: 1.0,
+            alternative_outputs: Vec::new(),
+            comparison: None,
+            input_sha256: None,
+            output_sha256: None,
+            source: TestCaseSource::Static,
+            is_sample: false,
+            env: None,
+        }
+    }
+
+    /// Creates a sample test case
+    ///
+    /// Samples are published with the statement, so they have weight 0 and don't
+    /// contribute to the score.
+    pub fn sample(
+        id: String,
+        input: impl Into<TestData>,
+        expected_output: impl Into<TestData>,
+    ) -> Self {
+        Self {
+            weight: 0.0,
+            is_sample: true,
+            ..Self::new(id, input, expected_output)
         }
     }
 
     /// Creates a test case with custom limits
     pub fn with_limits(
         id: String,
-        input: String,
-        expected_output: String,
+        input: impl Into<TestData>,
+        expected_output: impl Into<TestData>,
         time_limit: u64,
         memory_limit: u64,
     ) -> Self {
         Self {
             id,
-            input,
-            expected_output,
+            input: input.into(),
+            expected_output: expected_output.into(),
             time_limit: Some(time_limit),
             memory_limit: Some(memory_limit),
             is_hidden: false,
             weight: 1.0,
+            alternative_outputs: Vec::new(),
+            comparison: None,
+            input_sha256: None,
+            output_sha256: None,
+            source: TestCaseSource::Static,
+            is_sample: false,
+            env: None,
         }
     }
 
+    /// Returns whether the test data may be shown to contestants
+    ///
+    /// Samples are public even if marked hidden, since the statement shows them.
+    pub fn is_public(&self) -> bool {
+        self.is_sample || !self.is_hidden
+    }
+
+    /// Adds an alternative accepted output
+    pub fn with_alternative_output(mut self, output: impl Into<TestData>) -> Self {
+        self.alternative_outputs.push(output.into());
+        self
+    }
+
+    /// Returns every accepted output, the primary expected output first
+    pub fn expected_outputs(&self) -> impl Iterator<Item = &TestData> {
+        std::iter::once(&self.expected_output).chain(&self.alternative_outputs)
+    }
+
     /// Returns the effective time limit (custom or default)
     pub fn effective_time_limit(&self, default_time_limit: u64) -> u64 {
         self.time_limit.unwrap_or(default_time_limit)
@@ -393,6 +1364,12 @@ impl TestCase {
 /// Detailed information about a judgment result
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct JudgeResult {
+    /// Wire format version, see [`compat`]
+    #[serde(default = "compat::default_schema_version")]
+    pub schema_version: u32,
+    /// Judging attempt of the submission this result belongs to
+    #[serde(default = "first_attempt")]
+    pub attempt: u32,
     /// The overall status of the judgment
     pub status: JudgeStatus,
     /// Time consumed in milliseconds
@@ -413,15 +1390,31 @@ pub struct JudgeResult {
     pub judged_at: DateTime<Utc>,
     /// Score achieved (0.0 to 100.0)
     pub score: f64,
+    /// Batch of a split task this result covers, see [`JudgeResult::merge`]
+    #[serde(default)]
+    pub batch: Option<BatchInfo>,
 }
 
 /// Represents the status of a code submission judgment with detailed variants
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+///
+/// Serializes as a flat string, see [`JudgeStatus::to_wire`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum JudgeStatus {
     /// The submission passed all test cases
     Accepted,
     /// The submission failed one or more test cases
     WrongAnswer,
+    /// The output has the right tokens but differs in whitespace or line breaks
+    ///
+    /// A kind of wrong answer rather than an error, see [`JudgeStatus::is_error`].
+    PresentationError,
+    /// Some subtasks passed and the score is strictly between 0 and 100
+    ///
+    /// A submission-level verdict, see [`JudgeResult::verdict_for`].
+    PartialAccepted,
+    /// The test case was not run because an earlier one, or a subtask it depends
+    /// on, failed
+    Skipped,
     /// The submission exceeded the time limit
     TimeLimitExceeded,
     /// The submission exceeded the memory limit
@@ -486,6 +1479,9 @@ pub struct ErrorInfo {
     pub exit_code: Option<i32>,
     /// Signal that terminated the process
     pub signal: Option<i32>,
+    /// Explanation of a common cause of the error, see [`hints`]
+    #[serde(default)]
+    pub hint: Option<String>,
 }
 
 /// Result for an individual test case
@@ -507,6 +1503,13 @@ pub struct TestCaseResult {
     pub actual_output: Option<String>,
     /// Error information if the test case failed
     pub error_info: Option<ErrorInfo>,
+    /// Whether the test case's data must not be shown to the submitter (see
+    /// [`TestCase::is_public`] and [`JudgeResult::redacted`])
+    #[serde(default)]
+    pub is_hidden: bool,
+    /// Where the output first differs from the expected output, for wrong answers
+    #[serde(default)]
+    pub diff: Option<OutputDiff>,
 }
 
 impl JudgeStatus {
@@ -520,7 +1523,10 @@ impl JudgeStatus {
         !matches!(self, JudgeStatus::Pending | JudgeStatus::Judging)
     }
 
-    /// Returns true if the status represents an error (not including WrongAnswer)
+    /// Returns true if the status represents an error
+    ///
+    /// WrongAnswer and PresentationError are not errors: the program ran fine and
+    /// its output was judged. Neither are PartialAccepted and Skipped.
     pub fn is_error(&self) -> bool {
         matches!(
             self,
@@ -544,16 +1550,16 @@ impl JudgeStatus {
         match self {
             JudgeStatus::Accepted => "Accepted",
             JudgeStatus::WrongAnswer => "Wrong Answer",
+            JudgeStatus::PresentationError => "Presentation Error",
+            JudgeStatus::PartialAccepted => "Partially Accepted",
+            JudgeStatus::Skipped => "Skipped",
             JudgeStatus::TimeLimitExceeded => "Time Limit Exceeded",
             JudgeStatus::MemoryLimitExceeded => "Memory Limit Exceeded",
             JudgeStatus::RuntimeError(_) => "Runtime Error",
             JudgeStatus::CompileError => "Compile Error",
             JudgeStatus::RestrictedOperation => "Restricted Operation",
             JudgeStatus::OutputLimitExceeded => "Output Limit Exceeded",
-            JudgeStatus::SystemError => {
-                "System
- Error"
-            }
+            JudgeStatus::SystemError => "System Error",
             JudgeStatus::Pending => "Pending",
             JudgeStatus::Judging => "Judging",
             JudgeStatus::Cancelled => "Cancelled",
@@ -565,6 +1571,9 @@ impl JudgeStatus {
         match self {
             JudgeStatus::Accepted => "AC",
             JudgeStatus::WrongAnswer => "WA",
+            JudgeStatus::PresentationError => "PE",
+            JudgeStatus::PartialAccepted => "PA",
+            JudgeStatus::Skipped => "SK",
             JudgeStatus::TimeLimitExceeded => "TLE",
             JudgeStatus::MemoryLimitExceeded => "MLE",
             JudgeStatus::RuntimeError(_) => "RE",
@@ -578,6 +1587,55 @@ impl JudgeStatus {
         }
     }
 
+    /// One value of every status, with [`RuntimeErrorType::Other`] standing in for
+    /// all runtime errors
+    pub const ALL: [JudgeStatus; 15] = [
+        JudgeStatus::Accepted,
+        JudgeStatus::WrongAnswer,
+        JudgeStatus::PresentationError,
+        JudgeStatus::PartialAccepted,
+        JudgeStatus::Skipped,
+        JudgeStatus::TimeLimitExceeded,
+        JudgeStatus::MemoryLimitExceeded,
+        JudgeStatus::RuntimeError(RuntimeErrorType::Other),
+        JudgeStatus::CompileError,
+        JudgeStatus::RestrictedOperation,
+        JudgeStatus::OutputLimitExceeded,
+        JudgeStatus::SystemError,
+        JudgeStatus::Pending,
+        JudgeStatus::Judging,
+        JudgeStatus::Cancelled,
+    ];
+
+    /// Returns how bad the status is when aggregating test case results
+    ///
+    /// Higher is worse: SystemError > Cancelled > CompileError > RestrictedOperation >
+    /// RuntimeError > TimeLimitExceeded > MemoryLimitExceeded > OutputLimitExceeded >
+    /// WrongAnswer > PresentationError > PartialAccepted > Judging > Pending > Skipped >
+    /// Accepted. A system error outranks everything because the other verdicts can't
+    /// be trusted when the judge itself failed, a presentation error gives way to any
+    /// real wrong answer, and a skipped test case never hides the failure that caused
+    /// the skip.
+    pub fn severity(&self) -> u8 {
+        match self {
+            JudgeStatus::Accepted => 0,
+            JudgeStatus::Skipped => 1,
+            JudgeStatus::Pending => 2,
+            JudgeStatus::Judging => 3,
+            JudgeStatus::PartialAccepted => 4,
+            JudgeStatus::PresentationError => 5,
+            JudgeStatus::WrongAnswer => 6,
+            JudgeStatus::OutputLimitExceeded => 7,
+            JudgeStatus::MemoryLimitExceeded => 8,
+            JudgeStatus::TimeLimitExceeded => 9,
+            JudgeStatus::RuntimeError(_) => 10,
+            JudgeStatus::RestrictedOperation => 11,
+            JudgeStatus::CompileError => 12,
+            JudgeStatus::Cancelled => 13,
+            JudgeStatus::SystemError => 14,
+        }
+    }
+
     /// Returns the runtime error type if applicable
     pub fn runtime_error_type(&self) -> Option<RuntimeErrorType> {
         match self {
@@ -588,6 +1646,19 @@ impl JudgeStatus {
 }
 
 impl RuntimeErrorType {
+    /// Every runtime error type
+    pub const ALL: [RuntimeErrorType; 9] = [
+        RuntimeErrorType::SegmentationFault,
+        RuntimeErrorType::FloatingPointException,
+        RuntimeErrorType::DivisionByZero,
+        RuntimeErrorType::AssertionFailed,
+        RuntimeErrorType::StackOverflow,
+        RuntimeErrorType::NullPointerDereference,
+        RuntimeErrorType::FileOperationError,
+        RuntimeErrorType::PermissionDenied,
+        RuntimeErrorType::Other,
+    ];
+
     /// Returns a string description of the runtime error
     pub fn as_str(&self) -> &'static str {
         match self {
@@ -614,6 +1685,8 @@ impl JudgeResult {
         user_id: Uuid,
     ) -> Self {
         Self {
+            schema_version: SCHEMA_VERSION,
+            attempt: 1,
             status: JudgeStatus::Accepted,
             time_used,
             memory_used,
@@ -624,6 +1697,7 @@ impl JudgeResult {
             user_id,
             judged_at: Utc::now(),
             score: 100.0,
+            batch: None,
         }
     }
 
@@ -638,6 +1712,8 @@ impl JudgeResult {
         user_id: Uuid,
     ) -> Self {
         Self {
+            schema_version: SCHEMA_VERSION,
+            attempt: 1,
             status,
             time_used,
             memory_used,
@@ -648,9 +1724,16 @@ impl JudgeResult {
             user_id,
             judged_at: Utc::now(),
             score: 0.0,
+            batch: None,
         }
     }
 
+    /// Sets the attempt number, normally the submission's `attempt`
+    pub fn with_attempt(mut self, attempt: u32) -> Self {
+        self.attempt = attempt;
+        self
+    }
+
     /// Adds a test case result to the judgment
     pub fn add_test_case(&mut self, test_case: TestCaseResult) {
         self.test_cases.push(test_case);
@@ -682,6 +1765,7 @@ impl ErrorInfo {
             stdout: None,
             exit_code: None,
             signal: None,
+            hint: None,
         }
     }
 
@@ -696,6 +1780,7 @@ impl ErrorInfo {
             stdout: None,
             exit_code: None,
             signal: None,
+            hint: None,
         }
     }
 
@@ -710,9 +1795,27 @@ impl ErrorInfo {
             stdout: None,
             exit_code: None,
             signal: None,
+            hint: None,
         }
     }
 
+    /// Creates error info for a compilation killed by the compile time limit
+    pub fn compile_time_limit_exceeded(limit_ms: u64) -> Self {
+        let mut error = Self::new(format!("Compilation timed out after {}ms", limit_ms));
+        error.code = Some("COMPILE_TIME_LIMIT_EXCEEDED".to_string());
+        error
+    }
+
+    /// Creates error info for a compilation killed by the compile memory limit
+    pub fn compile_memory_limit_exceeded(limit_kb: u64) -> Self {
+        let mut error = Self::new(format!(
+            "Compilation exceeded the memory limit of {}KB",
+            limit_kb
+        ));
+        error.code = Some("COMPILE_MEMORY_LIMIT_EXCEEDED".to_string());
+        error
+    }
+
     /// Creates error info for a runtime error with signal
     pub fn runtime_error(message: String, signal: i32, stderr: Option<String>) -> Self {
         Self {
@@ -724,6 +1827,7 @@ impl ErrorInfo {
             stdout: None,
             exit_code: None,
             signal: Some(signal),
+            hint: None,
         }
     }
 }
@@ -734,6 +1838,35 @@ impl fmt::Display for JudgeStatus {
     }
 }
 
+/// Error returned when parsing an unknown [`JudgeStatus`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseJudgeStatusError(pub String);
+
+impl fmt::Display for ParseJudgeStatusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown judge status {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseJudgeStatusError {}
+
+impl std::str::FromStr for JudgeStatus {
+    type Err = ParseJudgeStatusError;
+
+    /// Parses a long name (`"Wrong Answer"`) or short code (`"WA"`), ignoring case
+    ///
+    /// Runtime errors parse as [`RuntimeErrorType::Other`] since neither form encodes
+    /// the kind.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        JudgeStatus::ALL
+            .into_iter()
+            .find(|status| {
+                s.eq_ignore_ascii_case(status.as_str()) || s.eq_ignore_ascii_case(status.as_code())
+            })
+            .ok_or_else(|| ParseJudgeStatusError(s.to_string()))
+    }
+}
+
 impl fmt::Display for RuntimeErrorType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.as_str())
@@ -807,6 +1940,8 @@ mod tests {
             expected_output: Some("3".to_string()),
             actual_output: Some("3".to_string()),
             error_info: None,
+            is_hidden: false,
+            diff: None,
         });
         test_result.add_test_case(TestCaseResult {
             id: "test_2".to_string(),
@@ -817,6 +1952,8 @@ mod tests {
             expected_output: Some("12".to_string()),
             actual_output: Some("13".to_string()),
             error_info: None,
+            is_hidden: false,
+            diff: None,
         });
         assert_eq!(test_result.passed_test_cases(), 1);
         assert_eq!(test_result.total_test_cases(), 2);
@@ -833,6 +1970,73 @@ mod tests {
 
         assert!(!JudgeStatus::Pending.is_final());
         assert!(!JudgeStatus::Judging.is_final());
+
+        // A presentation error is a final verdict, but like a wrong answer not an error
+        assert!(JudgeStatus::PresentationError.is_final());
+        assert!(!JudgeStatus::PresentationError.is_error());
+        assert!(!JudgeStatus::PresentationError.is_accepted());
+        assert!(!JudgeStatus::WrongAnswer.is_error());
+
+        for status in [JudgeStatus::PartialAccepted, JudgeStatus::Skipped] {
+            assert!(status.is_final());
+            assert!(!status.is_error());
+            assert!(!status.is_accepted());
+        }
+    }
+
+    #[test]
+    fn test_judge_status_from_str() {
+        let mut statuses = JudgeStatus::ALL.to_vec();
+        statuses.push(JudgeStatus::RuntimeError(
+            RuntimeErrorType::SegmentationFault,
+        ));
+        for status in statuses {
+            let expected = match status {
+                JudgeStatus::RuntimeError(_) => JudgeStatus::RuntimeError(RuntimeErrorType::Other),
+                status => status,
+            };
+            assert_eq!(status.as_code().parse::<JudgeStatus>(), Ok(expected));
+            assert_eq!(status.as_str().parse::<JudgeStatus>(), Ok(expected));
+            assert_eq!(
+                status.as_str().to_lowercase().parse::<JudgeStatus>(),
+                Ok(expected)
+            );
+        }
+
+        assert_eq!("tle".parse(), Ok(JudgeStatus::TimeLimitExceeded));
+        assert_eq!(
+            "Accepted ".parse::<JudgeStatus>(),
+            Err(ParseJudgeStatusError("Accepted ".to_string()))
+        );
+        assert!("XX".parse::<JudgeStatus>().is_err());
+    }
+
+    #[test]
+    fn test_severity_order() {
+        let order = [
+            JudgeStatus::Accepted,
+            JudgeStatus::Skipped,
+            JudgeStatus::Pending,
+            JudgeStatus::Judging,
+            JudgeStatus::PartialAccepted,
+            JudgeStatus::PresentationError,
+            JudgeStatus::WrongAnswer,
+            JudgeStatus::OutputLimitExceeded,
+            JudgeStatus::MemoryLimitExceeded,
+            JudgeStatus::TimeLimitExceeded,
+            JudgeStatus::RuntimeError(RuntimeErrorType::Other),
+            JudgeStatus::RestrictedOperation,
+            JudgeStatus::CompileError,
+            JudgeStatus::Cancelled,
+            JudgeStatus::SystemError,
+        ];
+        for pair in order.windows(2) {
+            assert!(pair[0].severity() < pair[1].severity(), "{:?}", pair);
+        }
+        assert_eq!(
+            JudgeStatus::RuntimeError(RuntimeErrorType::SegmentationFault).severity(),
+            JudgeStatus::RuntimeError(RuntimeErrorType::Other).severity()
+        );
     }
 
     #[test]
@@ -862,6 +2066,818 @@ mod tests {
         assert_eq!(python.default_runtime(), "python3");
     }
 
+    #[test]
+    fn test_compile_command() {
+        fn argv(parts: &[&str]) -> Option<Vec<String>> {
+            Some(parts.iter().map(|s| s.to_string()).collect())
+        }
+
+        let cases = [
+            (
+                ProgrammingLanguage::C,
+                argv(&["gcc", "-O2", "-Wall", "main.c", "-o", "main"]),
+            ),
+            (
+                ProgrammingLanguage::Cpp,
+                argv(&[
+                    "g++",
+                    "-O2",
+                    "-Wall",
+                    "-std=c++11",
+                    "main.cpp",
+                    "-o",
+                    "main",
+                ]),
+            ),
+            (
+                ProgrammingLanguage::Cpp11,
+                argv(&[
+                    "g++",
+                    "-O2",
+                    "-Wall",
+                    "-std=c++11",
+                    "main.cpp",
+                    "-o",
+                    "main",
+                ]),
+            ),
+            (
+                ProgrammingLanguage::Cpp14,
+                argv(&[
+                    "g++",
+                    "-O2",
+                    "-Wall",
+                    "-std=c++14",
+                    "main.cpp",
+                    "-o",
+                    "main",
+                ]),
+            ),
+            (
+                ProgrammingLanguage::Cpp17,
+                argv(&[
+                    "g++",
+                    "-O2",
+                    "-Wall",
+                    "-std=c++17",
+                    "main.cpp",
+                    "-o",
+                    "main",
+                ]),
+            ),
+            (
+                ProgrammingLanguage::Cpp20,
+                argv(&[
+                    "g++",
+                    "-O2",
+                    "-Wall",
+                    "-std=c++20",
+                    "main.cpp",
+                    "-o",
+                    "main",
+                ]),
+            ),
+            (ProgrammingLanguage::Python2, None),
+            (ProgrammingLanguage::Python3, None),
+            (
+                ProgrammingLanguage::Java,
+                argv(&["javac", "-Xlint:all", "Main.java"]),
+            ),
+            (
+                ProgrammingLanguage::Rust,
+                argv(&["rustc", "-O", "main.rs", "-o", "main"]),
+            ),
+            (
+                ProgrammingLanguage::Go,
+                argv(&["go", "build", "-o", "main", "main.go"]),
+            ),
+            (ProgrammingLanguage::JavaScript, None),
+            (
+                ProgrammingLanguage::TypeScript,
+                argv(&["tsc", "--target", "es2020", "--outFile", "main", "main.ts"]),
+            ),
+            (
+                ProgrammingLanguage::Kotlin,
+                argv(&["kotlinc", "-include-runtime", "main.kt", "-d", "main"]),
+            ),
+            (
+                ProgrammingLanguage::CSharp,
+                argv(&["mcs", "-optimize+", "main.cs", "-out:main"]),
+            ),
+            (ProgrammingLanguage::Ruby, None),
+            (ProgrammingLanguage::PHP, None),
+        ];
+        assert_eq!(cases.len(), ProgrammingLanguage::ALL.len());
+
+        for (lang, expected) in cases {
+            let source = if lang == ProgrammingLanguage::Java {
+                "Main.java".to_string()
+            } else {
+                format!("main.{}", lang.file_extension())
+            };
+            assert_eq!(
+                lang.compile_command(&source, "main"),
+                expected,
+                "{:?}",
+                lang
+            );
+            assert_eq!(
+                lang.compile_command(&source, "main").is_some(),
+                lang.needs_compilation()
+            );
+        }
+    }
+
+    #[test]
+    fn test_run_command() {
+        let cases = [
+            (ProgrammingLanguage::C, "./main", vec!["./main"]),
+            (ProgrammingLanguage::Cpp, "./main", vec!["./main"]),
+            (ProgrammingLanguage::Cpp11, "./main", vec!["./main"]),
+            (ProgrammingLanguage::Cpp14, "./main", vec!["./main"]),
+            (ProgrammingLanguage::Cpp17, "./main", vec!["./main"]),
+            (ProgrammingLanguage::Cpp20, "./main", vec!["./main"]),
+            (
+                ProgrammingLanguage::Python2,
+                "main.py",
+                vec!["python2", "main.py"],
+            ),
+            (
+                ProgrammingLanguage::Python3,
+                "main.py",
+                vec!["python3", "main.py"],
+            ),
+            (
+                ProgrammingLanguage::Java,
+                "Main.class",
+                vec!["java", "Main"],
+            ),
+            (ProgrammingLanguage::Rust, "./main", vec!["./main"]),
+            (ProgrammingLanguage::Go, "./main", vec!["./main"]),
+            (
+                ProgrammingLanguage::JavaScript,
+                "main.js",
+                vec!["node", "main.js"],
+            ),
+            (
+                ProgrammingLanguage::TypeScript,
+                "main.js",
+                vec!["node", "main.js"],
+            ),
+            (
+                ProgrammingLanguage::Kotlin,
+                "main.jar",
+                vec!["java", "-jar", "main.jar"],
+            ),
+            (
+                ProgrammingLanguage::CSharp,
+                "main.exe",
+                vec!["mono", "main.exe"],
+            ),
+            (
+                ProgrammingLanguage::Ruby,
+                "main.rb",
+                vec!["ruby", "main.rb"],
+            ),
+            (
+                ProgrammingLanguage::PHP,
+                "main.php",
+                vec!["php", "main.php"],
+            ),
+        ];
+        assert_eq!(cases.len(), ProgrammingLanguage::ALL.len());
+
+        for (lang, artifact, expected) in cases {
+            assert_eq!(lang.run_command(artifact), expected, "{:?}", lang);
+        }
+        assert_eq!(
+            ProgrammingLanguage::Java.run_command("Main"),
+            vec!["java", "Main"]
+        );
+    }
+
+    #[test]
+    fn test_all_languages_consistent() {
+        for lang in ProgrammingLanguage::ALL {
+            assert!(!lang.file_extension().is_empty(), "{:?}", lang);
+            assert!(!lang.as_str().is_empty(), "{:?}", lang);
+
+            let source = format!("main.{}", lang.file_extension());
+            let compile = lang.compile_command(&source, "main");
+            assert_eq!(compile.is_some(), lang.needs_compilation(), "{:?}", lang);
+            if let Some(argv) = compile {
+                assert_eq!(argv[0], lang.default_compiler(), "{:?}", lang);
+                assert!(argv.contains(&source), "{:?}", lang);
+            }
+
+            let run = lang.run_command("main");
+            assert!(!run.is_empty(), "{:?}", lang);
+            if lang.is_native() {
+                assert_eq!(
+                    lang.default_runtime(),
+                    format!("./{}", lang.artifact_name().unwrap()),
+                    "{:?}",
+                    lang
+                );
+            }
+            if !lang.needs_compilation() {
+                assert_eq!(
+                    run,
+                    vec![lang.default_runtime(), "main".to_string()],
+                    "{:?}",
+                    lang
+                );
+            }
+
+            let json = serde_json::to_string(&lang).unwrap();
+            let deserialized: ProgrammingLanguage = serde_json::from_str(&json).unwrap();
+            assert_eq!(deserialized, lang);
+        }
+    }
+
+    #[test]
+    fn test_submission_commands() {
+        let submission = Submission::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            ProgrammingLanguage::Rust,
+            "fn main() {}".to_string(),
+            1000,
+            65536,
+        );
+        assert_eq!(
+            submission.compile_command(),
+            ProgrammingLanguage::Rust.compile_command("main.rs", "main")
+        );
+        assert_eq!(submission.run_command(), vec!["./main"]);
+        assert_eq!(submission.default_runtime(), "./main");
+    }
+
+    #[test]
+    fn test_custom_language_submission() {
+        let ocaml = CustomLanguage {
+            name: "OCaml".to_string(),
+            file_extension: "ml".to_string(),
+            needs_compilation: true,
+            compile_argv: ["ocamlfind", "ocamlopt", "{source}", "-o", "{artifact}"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            run_argv: vec!["./{artifact}".to_string()],
+        };
+        let submission = Submission::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            ProgrammingLanguage::Custom,
+            "let () = print_endline \"hi\"".to_string(),
+            1000,
+            65536,
+        );
+        assert_eq!(
+            submission.validate_language(),
+            Err(CustomLanguageError::MissingSpec)
+        );
+
+        let submission = submission.with_custom_language(ocaml);
+        assert!(submission.validate_language().is_ok());
+        assert_eq!(submission.filename(), "main.ml");
+        assert!(submission.needs_compilation());
+        assert_eq!(
+            submission.compile_command(),
+            Some(vec![
+                "ocamlfind".to_string(),
+                "ocamlopt".to_string(),
+                "main.ml".to_string(),
+                "-o".to_string(),
+                "main".to_string(),
+            ])
+        );
+        assert_eq!(submission.run_command(), vec!["./main"]);
+        assert_eq!(submission.default_runtime(), "./main");
+
+        let json = serde_json::to_string(&submission).unwrap();
+        let deserialized: Submission = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, submission);
+    }
+
+    #[test]
+    fn test_submission_without_custom_language_field() {
+        let submission = Submission::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            ProgrammingLanguage::C,
+            "int main() {}".to_string(),
+            1000,
+            65536,
+        );
+        let mut value = serde_json::to_value(&submission).unwrap();
+        value.as_object_mut().unwrap().remove("custom_language");
+        let deserialized: Submission = serde_json::from_value(value).unwrap();
+        assert_eq!(deserialized.custom_language, None);
+        assert_eq!(deserialized.run_command(), vec!["./a.out"]);
+    }
+
+    #[test]
+    fn test_rejudge() {
+        let original = Submission::for_contest(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            ProgrammingLanguage::Cpp17,
+            "int main() {}".to_string(),
+            1000,
+            65536,
+        );
+        assert!(!original.is_rejudge());
+        assert_eq!(original.attempt, 1);
+
+        let rejudge = original.rejudge();
+        assert_ne!(rejudge.id, original.id);
+        assert_eq!(rejudge.rejudge_of, Some(original.id));
+        assert_eq!(rejudge.attempt, 2);
+        assert_eq!(rejudge.priority, builder::REJUDGE_PRIORITY);
+        assert_eq!(rejudge.source_code, original.source_code);
+        assert_eq!(rejudge.contest_id, original.contest_id);
+        assert_eq!(rejudge.created_at, original.created_at);
+
+        let again = rejudge.rejudge();
+        assert_eq!(again.rejudge_of, Some(original.id));
+        assert_eq!(again.attempt, 3);
+
+        let result = JudgeResult::accepted(0, 0, again.id, again.problem_id, again.user_id)
+            .with_attempt(again.attempt);
+        assert_eq!(result.attempt, 3);
+
+        let mut last = again.clone();
+        last.attempt = u32::MAX;
+        assert_eq!(last.rejudge().attempt, u32::MAX);
+    }
+
+    #[test]
+    fn test_output_and_stack_limits() {
+        let submission = SubmissionBuilder::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            ProgrammingLanguage::Cpp17,
+            "int main() {}",
+        )
+        .memory_limit(32 * 1024)
+        .build()
+        .unwrap();
+        assert_eq!(
+            submission.effective_output_limit(),
+            DEFAULT_OUTPUT_LIMIT_BYTES
+        );
+        // The default stack is capped by the smaller memory limit
+        assert_eq!(submission.effective_stack_limit(), 32 * 1024);
+
+        let submission = SubmissionBuilder::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            ProgrammingLanguage::Cpp17,
+            "int main() {}",
+        )
+        .output_limit(1024)
+        .stack_limit(8 * 1024)
+        .build()
+        .unwrap();
+        let task = JudgeTask::new(submission, vec![]);
+        assert_eq!(task.max_output_limit(), 1024);
+        assert_eq!(task.stack_limit, 8 * 1024);
+
+        let mut value = serde_json::to_value(&task).unwrap();
+        for field in ["output_limit", "stack_limit"] {
+            value.as_object_mut().unwrap().remove(field);
+            value["submission"].as_object_mut().unwrap().remove(field);
+        }
+        let deserialized: JudgeTask = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(deserialized.submission.output_limit, None);
+        assert_eq!(deserialized.max_output_limit(), DEFAULT_OUTPUT_LIMIT_BYTES);
+        assert_eq!(deserialized.stack_limit, DEFAULT_STACK_LIMIT_KB);
+
+        // The defaulted stack is capped by the memory limit, as in `JudgeTask::new`
+        value["submission"]["memory_limit"] = (32 * 1024).into();
+        let deserialized: JudgeTask = serde_json::from_value(value).unwrap();
+        assert_eq!(deserialized.stack_limit, 32 * 1024);
+    }
+
+    #[test]
+    fn test_compile_limits() {
+        let submission = Submission::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            ProgrammingLanguage::Cpp17,
+            "int main() {}".to_string(),
+            1000,
+            65536,
+        );
+        let mut task = JudgeTask::new(submission, vec![]);
+        assert_eq!(
+            task.effective_compile_time_limit(),
+            DEFAULT_COMPILE_TIME_LIMIT_MS
+        );
+        assert_eq!(
+            task.effective_compile_memory_limit(),
+            DEFAULT_COMPILE_MEMORY_LIMIT_KB
+        );
+
+        task.compile_time_limit = Some(30_000);
+        task.compile_memory_limit = Some(2 * 1024 * 1024);
+        assert_eq!(task.effective_compile_time_limit(), 30_000);
+        assert_eq!(task.effective_compile_memory_limit(), 2 * 1024 * 1024);
+        // Run limits are unaffected
+        assert_eq!(task.max_time_limit(), 1000);
+
+        let mut value = serde_json::to_value(&task).unwrap();
+        value.as_object_mut().unwrap().remove("compile_time_limit");
+        value
+            .as_object_mut()
+            .unwrap()
+            .remove("compile_memory_limit");
+        let deserialized: JudgeTask = serde_json::from_value(value).unwrap();
+        assert_eq!(deserialized.compile_time_limit, None);
+
+        let error = ErrorInfo::compile_time_limit_exceeded(20_000);
+        assert_eq!(error.message, "Compilation timed out after 20000ms");
+        assert_eq!(error.code.as_deref(), Some("COMPILE_TIME_LIMIT_EXCEEDED"));
+    }
+
+    #[test]
+    fn test_submission_without_rejudge_fields() {
+        let submission = Submission::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            ProgrammingLanguage::C,
+            "int main() {}".to_string(),
+            1000,
+            65536,
+        );
+        let mut value = serde_json::to_value(&submission).unwrap();
+        value.as_object_mut().unwrap().remove("rejudge_of");
+        value.as_object_mut().unwrap().remove("attempt");
+        let deserialized: Submission = serde_json::from_value(value).unwrap();
+        assert_eq!(deserialized.rejudge_of, None);
+        assert_eq!(deserialized.attempt, 1);
+    }
+
+    #[test]
+    fn test_compile_output_matches_run_artifact() {
+        for lang in ProgrammingLanguage::ALL {
+            let submission = Submission::new(
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                lang,
+                String::new(),
+                1000,
+                65536,
+            );
+            assert_eq!(submission.default_runtime(), lang.default_runtime());
+
+            let Some(compile) = submission.compile_command() else {
+                assert!(!lang.needs_compilation(), "{:?}", lang);
+                assert_eq!(
+                    submission.run_command(),
+                    vec![lang.default_runtime(), submission.filename()]
+                );
+                continue;
+            };
+            let run = submission.run_command();
+            if lang == ProgrammingLanguage::Java {
+                // javac writes Main.class next to the source; java takes the class name
+                assert_eq!(run, vec!["java", "Main"]);
+                continue;
+            }
+
+            let artifact = submission.artifact_name().unwrap();
+            let output = compile
+                .iter()
+                .find_map(|arg| arg.strip_prefix("-out:"))
+                .map(str::to_string)
+                .or_else(|| {
+                    compile
+                        .windows(2)
+                        .find(|w| ["-o", "-d", "--outFile"].contains(&w[0].as_str()))
+                        .map(|w| w[1].clone())
+                });
+            assert_eq!(output.as_ref(), Some(&artifact), "{:?}", lang);
+            let executable = run.last().unwrap();
+            assert_eq!(executable.trim_start_matches("./"), artifact, "{:?}", lang);
+        }
+    }
+
+    #[test]
+    fn test_java_submission_uses_public_class() {
+        let mut submission = Submission::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            ProgrammingLanguage::Java,
+            "public class Solution { public static void main(String[] a) {} }".to_string(),
+            1000,
+            65536,
+        );
+        assert_eq!(submission.filename(), "Solution.java");
+        assert_eq!(
+            submission.compile_command(),
+            Some(vec![
+                "javac".to_string(),
+                "-Xlint:all".to_string(),
+                "Solution.java".to_string()
+            ])
+        );
+        assert_eq!(
+            submission.artifact_name().as_deref(),
+            Some("Solution.class")
+        );
+        assert_eq!(submission.run_command(), vec!["java", "Solution"]);
+
+        submission.source_code = "class Helper {}".to_string();
+        assert_eq!(submission.filename(), "Main.java");
+        assert_eq!(submission.run_command(), vec!["java", "Main"]);
+    }
+
+    #[test]
+    fn test_compile_error_without_public_class() {
+        let output = "Solution.java:1: error: ';' expected\n";
+        let mut submission =
+            testing::submission(ProgrammingLanguage::Java, "public class Solution { int x }");
+        let error = submission.compile_error(output);
+        assert_eq!(error.message, "';' expected");
+        assert_eq!(error.stderr.as_deref(), Some(output));
+
+        submission.source_code = "class Solution { int x }".to_string();
+        let error = submission.compile_error(output);
+        assert_eq!(error.code.as_deref(), Some("JAVA_NO_PUBLIC_CLASS"));
+        assert!(error.message.contains("Main.java"));
+        assert_eq!(error.stderr.as_deref(), Some(output));
+
+        let cpp = testing::submission(ProgrammingLanguage::Cpp17, "int main() {");
+        assert_eq!(
+            cpp.compile_error("main.cpp:1:13: error: expected '}'"),
+            ErrorInfo::from_compiler_output(
+                ProgrammingLanguage::Cpp17,
+                "main.cpp:1:13: error: expected '}'"
+            )
+        );
+    }
+
+    #[test]
+    fn test_effective_limits_for_language() {
+        let submission = Submission::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            ProgrammingLanguage::Java,
+            "class Main {}".to_string(),
+            1000,
+            65536,
+        );
+        let task = JudgeTask::new(
+            submission,
+            vec![
+                TestCase::new("1".to_string(), "".to_string(), "".to_string()),
+                TestCase::with_limits(
+                    "2".to_string(),
+                    "".to_string(),
+                    "".to_string(),
+                    3000,
+                    131072,
+                ),
+            ],
+        );
+
+        let limits = task.effective_limits_for_language();
+        assert_eq!(limits.time_limit, 2000);
+        assert_eq!(limits.memory_limit, 65536 + 262144);
+        assert_eq!(limits.test_cases[0].time_limit, 2000);
+        assert_eq!(limits.test_cases[0].memory_limit, 65536 + 262144);
+        // The per-test-case override still wins over the submission limit after scaling
+        assert_eq!(limits.test_cases[1].time_limit, 6000);
+        assert_eq!(limits.test_cases[1].memory_limit, 131072 + 262144);
+    }
+
+    #[test]
+    fn test_effective_limits_with_policy() {
+        let submission = Submission::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            ProgrammingLanguage::Python3,
+            "print(1)".to_string(),
+            1000,
+            65536,
+        );
+        let mut task = JudgeTask::new(
+            submission,
+            vec![TestCase::with_limits(
+                "1".to_string(),
+                "".to_string(),
+                "".to_string(),
+                500,
+                1024,
+            )],
+        );
+        assert_eq!(task.effective_limits_for_language().time_limit, 3000);
+
+        let mut policy = LimitPolicy::default();
+        policy.overrides.insert(
+            ProgrammingLanguage::Python3,
+            LimitScaling {
+                time_multiplier: 5.0,
+                memory_bonus_kb: 1024,
+            },
+        );
+        task.limit_policy = Some(policy);
+        let limits = task.effective_limits_for_language();
+        assert_eq!(limits.time_limit, 5000);
+        assert_eq!(limits.memory_limit, 66560);
+        assert_eq!(limits.test_cases[0].time_limit, 2500);
+        assert_eq!(limits.test_cases[0].memory_limit, 2048);
+
+        task.limit_policy = Some(LimitPolicy::unscaled());
+        let limits = task.effective_limits_for_language();
+        assert_eq!(limits.time_limit, 1000);
+        assert_eq!(limits.test_cases[0].time_limit, 500);
+    }
+
+    #[test]
+    fn test_sanitize_build_profile() {
+        let flags = ProgrammingLanguage::Cpp17.compile_flags_for(BuildProfile::Sanitize);
+        assert!(!flags.iter().any(|f| f == "-O2"));
+        assert!(flags.contains(&"-std=c++17".to_string()));
+        assert!(flags.contains(&"-fsanitize=address,undefined".to_string()));
+        assert!(flags.contains(&"-g".to_string()));
+
+        assert_eq!(
+            ProgrammingLanguage::Cpp17.compile_flags_for(BuildProfile::Release),
+            ProgrammingLanguage::Cpp17.default_compile_flags()
+        );
+        assert_eq!(
+            ProgrammingLanguage::Rust.compile_flags_for(BuildProfile::Sanitize),
+            ProgrammingLanguage::Rust.default_compile_flags()
+        );
+
+        let submission = Submission::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            ProgrammingLanguage::C,
+            "int main() {}".to_string(),
+            1000,
+            262144,
+        );
+        let task = JudgeTask::new(submission, vec![]);
+        assert_eq!(task.build_profile, BuildProfile::Release);
+        assert_eq!(task.effective_limits_for_language().memory_limit, 262144);
+
+        let task = task.with_build_profile(BuildProfile::Sanitize);
+        assert_eq!(
+            task.compile_flags,
+            Some(ProgrammingLanguage::C.compile_flags_for(BuildProfile::Sanitize))
+        );
+        assert_eq!(
+            task.effective_limits_for_language().memory_limit,
+            262144 * 4
+        );
+
+        // Flags added by the caller survive a profile switch; `-O2` does not
+        let mut release = JudgeTask::new(task.submission.clone(), vec![]);
+        release
+            .compile_flags
+            .as_mut()
+            .unwrap()
+            .push("-DONLINE_JUDGE".to_string());
+        let sanitize = release.with_build_profile(BuildProfile::Sanitize);
+        let mut expected = ProgrammingLanguage::C.compile_flags_for(BuildProfile::Sanitize);
+        expected.push("-DONLINE_JUDGE".to_string());
+        assert_eq!(sanitize.compile_flags, Some(expected));
+
+        let json = serde_json::to_string(&task).unwrap();
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        value.as_object_mut().unwrap().remove("build_profile");
+        let deserialized: JudgeTask = serde_json::from_value(value).unwrap();
+        assert_eq!(deserialized.build_profile, BuildProfile::Release);
+
+        // Only C and C++ are instrumented, so only they get the extra memory
+        let mut python = task.clone();
+        python.submission.language = ProgrammingLanguage::Python3;
+        assert_eq!(
+            python.effective_limits_for_language().memory_limit,
+            python
+                .limit_scaling()
+                .scale_memory(python.submission.memory_limit)
+        );
+        assert_eq!(
+            BuildProfile::Sanitize.relax_memory_limit(ProgrammingLanguage::Java, 1024),
+            1024
+        );
+    }
+
+    #[test]
+    fn test_sanitized_compile_flags() {
+        let submission = Submission::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            ProgrammingLanguage::Cpp17,
+            "int main() {}".to_string(),
+            1000,
+            262144,
+        );
+        let mut task = JudgeTask::new(submission, vec![]);
+        assert_eq!(
+            task.sanitized_compile_flags(FlagPolicy::Restricted)
+                .unwrap()
+                .into_vec(),
+            ProgrammingLanguage::Cpp17.default_compile_flags()
+        );
+
+        task.compile_flags = Some(vec!["-o".to_string(), "/etc/cron.d/x".to_string()]);
+        assert!(matches!(
+            task.sanitized_compile_flags(FlagPolicy::Restricted),
+            Err(CompileFlagsError::NotAllowed { .. })
+        ));
+        assert!(task.sanitized_compile_flags(FlagPolicy::Trusted).is_ok());
+    }
+
+    #[test]
+    fn test_judge_task_without_checker_field() {
+        let submission = Submission::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            ProgrammingLanguage::C,
+            "int main() {}".to_string(),
+            1000,
+            65536,
+        );
+        let mut value = serde_json::to_value(JudgeTask::new(submission, vec![])).unwrap();
+        value.as_object_mut().unwrap().remove("checker");
+        let deserialized: JudgeTask = serde_json::from_value(value).unwrap();
+        assert_eq!(deserialized.checker, Checker::DefaultDiff);
+    }
+
+    #[test]
+    fn test_sample_cases() {
+        let submission = Submission::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            ProgrammingLanguage::C,
+            "int main() {}".to_string(),
+            1000,
+            65536,
+        );
+        let sample = TestCase::sample("1".to_string(), "1\n", "1\n");
+        assert!(sample.is_sample);
+        assert_eq!(sample.weight, 0.0);
+        assert!(sample.is_public());
+        assert!(!TestCase::hidden("2".to_string(), "", "").is_public());
+
+        let task = JudgeTask::new(
+            submission,
+            vec![
+                sample,
+                TestCase::new("2".to_string(), "2\n", "4\n"),
+                TestCase::hidden("3".to_string(), "3\n", "9\n"),
+            ],
+        );
+        let ids = |cases: Vec<&TestCase>| cases.iter().map(|tc| tc.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(task.sample_cases().collect()), vec!["1"]);
+        assert_eq!(ids(task.non_sample_cases().collect()), vec!["2", "3"]);
+        assert_eq!(task.total_weight(), 2.0);
+
+        let mut value = serde_json::to_value(&task.test_cases[0]).unwrap();
+        value.as_object_mut().unwrap().remove("is_sample");
+        let old: TestCase = serde_json::from_value(value).unwrap();
+        assert!(!old.is_sample);
+    }
+
+    #[test]
+    fn test_execution_order_requires_stop_on_first_failure() {
+        let submission = Submission::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            ProgrammingLanguage::C,
+            "int main() {}".to_string(),
+            1000,
+            65536,
+        );
+        let test_cases = vec![
+            TestCase::new("1".to_string(), String::new(), String::new()),
+            TestCase::hidden("2".to_string(), String::new(), String::new()),
+        ];
+        let mut stats = FailureStats::default();
+        stats.failures.insert("2".to_string(), 5);
+
+        let mut task = JudgeTask::new(submission, test_cases);
+        task.test_order = TestOrder::FailFirst;
+        assert_eq!(task.execution_order(Some(&stats)), vec![1, 0]);
+
+        task.judge_mode = JudgeMode::OiPartial;
+        assert_eq!(task.execution_order(Some(&stats)), vec![0, 1]);
+
+        task.stop_on_first_failure = Some(true);
+        assert_eq!(task.execution_order(Some(&stats)), vec![1, 0]);
+
+        task.judge_mode = JudgeMode::AcmIcpc;
+        task.stop_on_first_failure = Some(false);
+        assert_eq!(task.execution_order(Some(&stats)), vec![0, 1]);
+    }
+
     #[test]
     fn test_judge_result() {
         let submission_id = Uuid::new_v4();
@@ -0,0 +1,237 @@
+use crate::{JudgeTask, ProgrammingLanguage};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Output limit used when a submission sets none, in bytes
+pub const DEFAULT_OUTPUT_LIMIT_BYTES: u64 = 64 * 1024 * 1024;
+/// Stack limit used when a submission sets none, in kilobytes
+pub const DEFAULT_STACK_LIMIT_KB: u64 = 64 * 1024;
+/// Compile time limit used when a task sets none, in milliseconds
+pub const DEFAULT_COMPILE_TIME_LIMIT_MS: u64 = 20_000;
+/// Compile memory limit used when a task sets none, in kilobytes
+pub const DEFAULT_COMPILE_MEMORY_LIMIT_KB: u64 = 1024 * 1024;
+
+pub(crate) fn default_output_limit() -> u64 {
+    DEFAULT_OUTPUT_LIMIT_BYTES
+}
+
+pub(crate) fn default_stack_limit() -> u64 {
+    DEFAULT_STACK_LIMIT_KB
+}
+
+/// How a language's limits are scaled relative to the problem's (C++-oriented) limits
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LimitScaling {
+    /// Factor applied to time limits
+    pub time_multiplier: f64,
+    /// Extra memory added to memory limits, in kilobytes
+    pub memory_bonus_kb: u64,
+}
+
+/// Why a limit policy is unusable
+#[derive(Debug, Clone, PartialEq)]
+pub enum LimitPolicyError {
+    /// A time multiplier is not a finite number of at least 1.0
+    InvalidTimeMultiplier {
+        language: ProgrammingLanguage,
+        multiplier: f64,
+    },
+}
+
+impl fmt::Display for LimitPolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LimitPolicyError::InvalidTimeMultiplier {
+                language,
+                multiplier,
+            } => write!(
+                f,
+                "time multiplier {} for {} must be a finite number of at least 1.0",
+                multiplier,
+                language.as_str()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LimitPolicyError {}
+
+impl LimitScaling {
+    /// Scaling that leaves limits untouched
+    pub const IDENTITY: LimitScaling = LimitScaling {
+        time_multiplier: 1.0,
+        memory_bonus_kb: 0,
+    };
+
+    /// Returns whether the time multiplier is finite and at least 1.0
+    ///
+    /// Scaling only ever relaxes limits; anything else would turn into a 0ms or
+    /// saturated limit in [`scale_time`](Self::scale_time).
+    pub fn is_valid(&self) -> bool {
+        self.time_multiplier.is_finite() && self.time_multiplier >= 1.0
+    }
+
+    /// Applies the multiplier to a time limit in milliseconds, rounding up
+    pub fn scale_time(&self, time_limit: u64) -> u64 {
+        (time_limit as f64 * self.time_multiplier).ceil() as u64
+    }
+
+    /// Applies the bonus to a memory limit in kilobytes
+    pub fn scale_memory(&self, memory_limit: u64) -> u64 {
+        memory_limit.saturating_add(self.memory_bonus_kb)
+    }
+}
+
+impl Default for LimitScaling {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// Per-language limit scaling policy, e.g. set by a contest
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LimitPolicy {
+    /// Scaling per language; languages not listed use their built-in defaults
+    #[serde(default)]
+    pub overrides: HashMap<ProgrammingLanguage, LimitScaling>,
+}
+
+impl LimitPolicy {
+    /// Creates a policy that disables scaling for every language
+    pub fn unscaled() -> Self {
+        Self {
+            overrides: ProgrammingLanguage::ALL
+                .iter()
+                .map(|&language| (language, LimitScaling::IDENTITY))
+                .collect(),
+        }
+    }
+
+    /// Returns the scaling applied to a language under this policy
+    pub fn scaling_for(&self, language: ProgrammingLanguage) -> LimitScaling {
+        self.overrides
+            .get(&language)
+            .copied()
+            .unwrap_or_else(|| language.limit_scaling())
+    }
+
+    /// Checks every override's time multiplier
+    pub fn validate(&self) -> Result<(), LimitPolicyError> {
+        match self
+            .overrides
+            .iter()
+            .filter(|(_, scaling)| !scaling.is_valid())
+            .min_by_key(|(language, _)| language.as_str())
+        {
+            Some((&language, scaling)) => Err(LimitPolicyError::InvalidTimeMultiplier {
+                language,
+                multiplier: scaling.time_multiplier,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl JudgeTask {
+    /// Checks the task's limit policy, if any
+    pub fn validate_limit_policy(&self) -> Result<(), LimitPolicyError> {
+        self.limit_policy
+            .as_ref()
+            .map_or(Ok(()), LimitPolicy::validate)
+    }
+}
+
+/// Limits of a single test case after language scaling
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestCaseLimits {
+    /// Test case identifier
+    pub id: String,
+    /// Time limit in milliseconds
+    pub time_limit: u64,
+    /// Memory limit in kilobytes
+    pub memory_limit: u64,
+}
+
+/// Limits of a judge task after language scaling
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EffectiveLimits {
+    /// Submission-level time limit in milliseconds
+    pub time_limit: u64,
+    /// Submission-level memory limit in kilobytes
+    pub memory_limit: u64,
+    /// Per test case limits, in the same order as the task's test cases
+    pub test_cases: Vec<TestCaseLimits>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scaling() {
+        let scaling = LimitScaling {
+            time_multiplier: 1.5,
+            memory_bonus_kb: 1024,
+        };
+        assert_eq!(scaling.scale_time(1000), 1500);
+        assert_eq!(scaling.scale_time(333), 500);
+        assert_eq!(scaling.scale_memory(2048), 3072);
+        assert_eq!(LimitScaling::IDENTITY.scale_time(1234), 1234);
+        assert_eq!(LimitScaling::IDENTITY.scale_memory(u64::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn test_policy_validation() {
+        assert_eq!(LimitPolicy::unscaled().validate(), Ok(()));
+        for language in ProgrammingLanguage::ALL {
+            assert!(language.limit_scaling().is_valid());
+        }
+
+        for multiplier in [f64::NAN, f64::INFINITY, -1.0, 0.0, 0.5] {
+            let mut policy = LimitPolicy::unscaled();
+            policy.overrides.insert(
+                ProgrammingLanguage::Python3,
+                LimitScaling {
+                    time_multiplier: multiplier,
+                    memory_bonus_kb: 0,
+                },
+            );
+            match policy.validate() {
+                Err(LimitPolicyError::InvalidTimeMultiplier {
+                    language: ProgrammingLanguage::Python3,
+                    multiplier: reported,
+                }) => assert!(reported.to_bits() == multiplier.to_bits()),
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_policy_falls_back_to_language_defaults() {
+        let mut policy = LimitPolicy::default();
+        policy
+            .overrides
+            .insert(ProgrammingLanguage::Java, LimitScaling::IDENTITY);
+
+        assert_eq!(
+            policy.scaling_for(ProgrammingLanguage::Java),
+            LimitScaling::IDENTITY
+        );
+        assert_eq!(
+            policy.scaling_for(ProgrammingLanguage::Python3),
+            ProgrammingLanguage::Python3.limit_scaling()
+        );
+    }
+
+    #[test]
+    fn test_policy_serialization() {
+        let policy = LimitPolicy::unscaled();
+        let json = serde_json::to_string(&policy).unwrap();
+        let deserialized: LimitPolicy = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, policy);
+
+        let empty: LimitPolicy = serde_json::from_str("{}").unwrap();
+        assert!(empty.overrides.is_empty());
+    }
+}
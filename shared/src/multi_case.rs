@@ -0,0 +1,408 @@
+//! Judging every test case in a single run
+//!
+//! Some problems read `T` cases from one input. In
+//! [`ExecutionMode::SingleRunMultiCase`](crate::ExecutionMode::SingleRunMultiCase) the
+//! judger concatenates the test case inputs (optionally behind a count line), runs the
+//! submission once, splits its output back into one piece per test case and scores
+//! each piece like a separate test case.
+
+use crate::compare::{DEFAULT_DIFF_FRAGMENT_CHARS, first_difference};
+use crate::{JudgeStatus, JudgeTask, OutputCheck, TestCase, TestCaseResult, TestData};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::{self, Read};
+
+/// How inputs are combined and the output is split
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultiCaseSpec {
+    /// Whether the combined input starts with a line holding the number of cases
+    #[serde(default = "default_count_header")]
+    pub count_header: bool,
+    /// Line separating the outputs of consecutive cases
+    ///
+    /// Without a delimiter each case's output is expected to have as many lines as
+    /// its expected output.
+    #[serde(default)]
+    pub delimiter: Option<String>,
+}
+
+fn default_count_header() -> bool {
+    true
+}
+
+impl Default for MultiCaseSpec {
+    fn default() -> Self {
+        Self {
+            count_header: true,
+            delimiter: None,
+        }
+    }
+}
+
+/// Why a multi-case spec is rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MultiCaseError {
+    /// The delimiter is empty or only whitespace
+    EmptyDelimiter,
+    /// The delimiter spans several lines
+    MultiLineDelimiter,
+    /// The task has a custom checker, which can't judge the pieces of a single run
+    CustomChecker,
+}
+
+impl fmt::Display for MultiCaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MultiCaseError::EmptyDelimiter => write!(f, "output delimiter must not be empty"),
+            MultiCaseError::MultiLineDelimiter => {
+                write!(f, "output delimiter must be a single line")
+            }
+            MultiCaseError::CustomChecker => write!(
+                f,
+                "custom checkers are not supported when all test cases run at once"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MultiCaseError {}
+
+impl MultiCaseSpec {
+    /// Checks that the delimiter can be matched against output lines
+    pub fn validate(&self) -> Result<(), MultiCaseError> {
+        match &self.delimiter {
+            Some(delimiter) if delimiter.trim().is_empty() => Err(MultiCaseError::EmptyDelimiter),
+            Some(delimiter) if delimiter.contains(['\n', '\r']) => {
+                Err(MultiCaseError::MultiLineDelimiter)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the spec together with the task it judges
+    ///
+    /// Output pieces are only compared under built-in modes, so a custom checker
+    /// would be silently ignored; it is rejected instead.
+    pub fn validate_for(&self, task: &JudgeTask) -> Result<(), MultiCaseError> {
+        self.validate()?;
+        if task.checker.is_custom() {
+            return Err(MultiCaseError::CustomChecker);
+        }
+        Ok(())
+    }
+
+    /// Builds the stdin of the single run
+    pub fn combine_inputs(&self, test_cases: &[TestCase]) -> io::Result<String> {
+        let mut combined = String::new();
+        if self.count_header {
+            combined.push_str(&format!("{}\n", test_cases.len()));
+        }
+        for test_case in test_cases {
+            let input = read_to_string(&test_case.input)?;
+            combined.push_str(&input);
+            if !input.is_empty() && !input.ends_with('\n') {
+                combined.push('\n');
+            }
+        }
+        Ok(combined)
+    }
+
+    /// Splits the output of the single run into one piece per expected output
+    ///
+    /// Returns fewer pieces than expected outputs if the output ends early, and more
+    /// if it has trailing content.
+    pub fn split_output(&self, output: &str, expected: &[&str]) -> Vec<String> {
+        match &self.delimiter {
+            Some(delimiter) => {
+                let mut pieces = vec![String::new()];
+                for line in output.lines() {
+                    if line.trim_end() == delimiter.trim_end() {
+                        pieces.push(String::new());
+                    } else {
+                        let piece = pieces.last_mut().expect("pieces is never empty");
+                        piece.push_str(line);
+                        piece.push('\n');
+                    }
+                }
+                // Output ending with a delimiter doesn't start another case
+                if pieces.last().is_some_and(String::is_empty) {
+                    pieces.pop();
+                }
+                pieces
+            }
+            None => {
+                let mut lines = output.lines();
+                let mut pieces: Vec<String> = expected
+                    .iter()
+                    .map_while(|expected| {
+                        let count = content_lines(expected);
+                        let piece: Vec<&str> = lines.by_ref().take(count).collect();
+                        (piece.len() == count || !piece.is_empty())
+                            .then(|| piece.iter().map(|line| format!("{}\n", line)).collect())
+                    })
+                    .collect();
+                let rest: String = lines.map(|line| format!("{}\n", line)).collect();
+                if !rest.trim().is_empty() {
+                    pieces.push(rest);
+                }
+                pieces
+            }
+        }
+    }
+
+    /// Maps the single run back to one result per test case
+    ///
+    /// If the run itself failed (time limit, runtime error, ...) every test case gets
+    /// that status. Otherwise each output piece is compared under the test case's
+    /// comparison mode (see [`JudgeTask::comparison_for`]); pieces with the right
+    /// tokens but different whitespace are presentation errors, missing pieces and
+    /// trailing extra output are wrong answers. Every test case reports the time and
+    /// memory of the whole run.
+    pub fn results_for(
+        &self,
+        task: &JudgeTask,
+        run_status: JudgeStatus,
+        time_used: u64,
+        memory_used: u64,
+        output: &str,
+    ) -> io::Result<Vec<TestCaseResult>> {
+        let result = |test_case: &TestCase, status| TestCaseResult {
+            id: test_case.id.clone(),
+            status,
+            time_used,
+            memory_used,
+            input: None,
+            expected_output: None,
+            actual_output: None,
+            error_info: None,
+            is_hidden: !test_case.is_public(),
+            diff: None,
+        };
+        if !run_status.is_accepted() {
+            return Ok(task
+                .test_cases
+                .iter()
+                .map(|tc| result(tc, run_status))
+                .collect());
+        }
+
+        let expected = task
+            .test_cases
+            .iter()
+            .map(|tc| read_to_string(&tc.expected_output))
+            .collect::<io::Result<Vec<String>>>()?;
+        let expected: Vec<&str> = expected.iter().map(String::as_str).collect();
+        let pieces = self.split_output(output, &expected);
+        let extra_output = pieces.len() > task.test_cases.len();
+
+        let mut results = Vec::with_capacity(task.test_cases.len());
+        for (i, test_case) in task.test_cases.iter().enumerate() {
+            let last = i + 1 == task.test_cases.len();
+            let status = match pieces.get(i) {
+                Some(piece) if !(last && extra_output) => {
+                    let alternatives = test_case
+                        .alternative_outputs
+                        .iter()
+                        .map(read_to_string)
+                        .collect::<io::Result<Vec<String>>>()?;
+                    let accepted: Vec<&str> = std::iter::once(expected[i])
+                        .chain(alternatives.iter().map(String::as_str))
+                        .collect();
+                    match task.checker.check_output(
+                        task.comparison_for(test_case),
+                        &accepted,
+                        piece,
+                    ) {
+                        OutputCheck::Accepted => JudgeStatus::Accepted,
+                        OutputCheck::WrongAnswer => JudgeStatus::WrongAnswer,
+                        OutputCheck::PresentationError => JudgeStatus::PresentationError,
+                        // Rejected by `validate_for`
+                        OutputCheck::NeedsChecker => JudgeStatus::SystemError,
+                    }
+                }
+                _ => JudgeStatus::WrongAnswer,
+            };
+            let mut test_result = result(test_case, status);
+            test_result.actual_output = pieces.get(i).cloned();
+            if !status.is_accepted() {
+                test_result.diff = first_difference(
+                    expected[i],
+                    pieces.get(i).map_or("", String::as_str),
+                    task.comparison_for(test_case),
+                    DEFAULT_DIFF_FRAGMENT_CHARS,
+                );
+            }
+            results.push(test_result);
+        }
+        Ok(results)
+    }
+}
+
+fn read_to_string(data: &TestData) -> io::Result<String> {
+    if let Some(text) = data.as_inline() {
+        return Ok(text.to_string());
+    }
+    let mut text = String::new();
+    data.open_reader()?.read_to_string(&mut text)?;
+    Ok(text)
+}
+
+/// Number of lines up to the last non-blank one
+fn content_lines(text: &str) -> usize {
+    let lines: Vec<&str> = text.lines().collect();
+    lines
+        .iter()
+        .rposition(|line| !line.trim().is_empty())
+        .map_or(0, |i| i + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use crate::{ExecutionMode, ProgrammingLanguage};
+
+    fn task(spec: MultiCaseSpec) -> JudgeTask {
+        let mut task = testing::task(vec![
+            TestCase::new("1".to_string(), "1 2\n", "3\n"),
+            TestCase::new("2".to_string(), "2 2", "4\n"),
+            TestCase::new("3".to_string(), "3\n1 2 3\n", "1\n2\n3\n"),
+        ]);
+        task.execution_mode = ExecutionMode::SingleRunMultiCase(spec);
+        task
+    }
+
+    fn statuses(results: &[TestCaseResult]) -> Vec<JudgeStatus> {
+        results.iter().map(|r| r.status).collect()
+    }
+
+    #[test]
+    fn test_combine_inputs() {
+        let task = task(MultiCaseSpec::default());
+        let spec = MultiCaseSpec::default();
+        assert_eq!(
+            spec.combine_inputs(&task.test_cases).unwrap(),
+            "3\n1 2\n2 2\n3\n1 2 3\n"
+        );
+        let spec = MultiCaseSpec {
+            count_header: false,
+            delimiter: None,
+        };
+        assert_eq!(spec.combine_inputs(&task.test_cases[..1]).unwrap(), "1 2\n");
+    }
+
+    #[test]
+    fn test_split_by_expected_lines() {
+        let spec = MultiCaseSpec::default();
+        let expected = ["3\n", "4\n", "1\n2\n3\n"];
+        assert_eq!(
+            spec.split_output("3\n4\n1\n2\n3\n", &expected),
+            vec!["3\n", "4\n", "1\n2\n3\n"]
+        );
+        assert_eq!(spec.split_output("3\n4", &expected), vec!["3\n", "4\n"]);
+        assert_eq!(spec.split_output("3\n4\n1\n2\n3\n9\n", &expected).len(), 4);
+    }
+
+    #[test]
+    fn test_split_by_delimiter() {
+        let spec = MultiCaseSpec {
+            count_header: true,
+            delimiter: Some("---".to_string()),
+        };
+        assert_eq!(
+            spec.split_output("3\n---\n4\n---  \n1\n2\n3\n---\n", &[]),
+            vec!["3\n", "4\n", "1\n2\n3\n"]
+        );
+        assert_eq!(spec.validate(), Ok(()));
+        let spec = MultiCaseSpec {
+            count_header: true,
+            delimiter: Some(" ".to_string()),
+        };
+        assert_eq!(spec.validate(), Err(MultiCaseError::EmptyDelimiter));
+    }
+
+    #[test]
+    fn test_custom_checker_rejected() {
+        let spec = MultiCaseSpec::default();
+        let mut task = task(spec.clone());
+        assert_eq!(spec.validate_for(&task), Ok(()));
+
+        task.checker = crate::Checker::Custom {
+            language: ProgrammingLanguage::Cpp17,
+            source: "int main() {}".to_string(),
+        };
+        assert_eq!(spec.validate_for(&task), Err(MultiCaseError::CustomChecker));
+
+        let spec = MultiCaseSpec {
+            count_header: true,
+            delimiter: Some(String::new()),
+        };
+        assert_eq!(
+            spec.validate_for(&task),
+            Err(MultiCaseError::EmptyDelimiter)
+        );
+    }
+
+    #[test]
+    fn test_results_for() {
+        let spec = MultiCaseSpec::default();
+        let task = task(spec.clone());
+
+        let results = spec
+            .results_for(&task, JudgeStatus::Accepted, 120, 2048, "3\n5\n1\n2\n3\n")
+            .unwrap();
+        assert_eq!(
+            statuses(&results),
+            vec![
+                JudgeStatus::Accepted,
+                JudgeStatus::WrongAnswer,
+                JudgeStatus::Accepted
+            ]
+        );
+        assert_eq!(results[1].actual_output.as_deref(), Some("5\n"));
+        let diff = results[1].diff.as_ref().unwrap();
+        assert_eq!((diff.line, diff.column), (1, 1));
+        assert_eq!(diff.expected_fragment, "4");
+        assert_eq!(diff.actual_fragment, "5");
+        assert_eq!(results[0].diff, None);
+        assert!(results.iter().all(|r| r.time_used == 120));
+
+        let results = spec
+            .results_for(&task, JudgeStatus::Accepted, 0, 0, "3\n4\n")
+            .unwrap();
+        assert_eq!(results[2].status, JudgeStatus::WrongAnswer);
+        assert_eq!(results[2].actual_output, None);
+
+        let results = spec
+            .results_for(&task, JudgeStatus::Accepted, 0, 0, "3\n4\n1\n2\n3\nextra\n")
+            .unwrap();
+        assert_eq!(results[2].status, JudgeStatus::WrongAnswer);
+
+        let results = spec
+            .results_for(&task, JudgeStatus::Accepted, 0, 0, "3\n 4\n1 2\n3\n")
+            .unwrap();
+        assert_eq!(
+            statuses(&results),
+            vec![
+                JudgeStatus::Accepted,
+                JudgeStatus::PresentationError,
+                JudgeStatus::PresentationError
+            ]
+        );
+        assert!(results[1].diff.is_some());
+
+        let results = spec
+            .results_for(&task, JudgeStatus::TimeLimitExceeded, 1000, 0, "3\n")
+            .unwrap();
+        assert_eq!(statuses(&results), vec![JudgeStatus::TimeLimitExceeded; 3]);
+    }
+
+    #[test]
+    fn test_serde_defaults() {
+        let spec: MultiCaseSpec = serde_json::from_str("{}").unwrap();
+        assert_eq!(spec, MultiCaseSpec::default());
+        let mode = ExecutionMode::SingleRunMultiCase(spec);
+        let json = serde_json::to_string(&mode).unwrap();
+        assert_eq!(serde_json::from_str::<ExecutionMode>(&json).unwrap(), mode);
+    }
+}
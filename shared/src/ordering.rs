@@ -0,0 +1,160 @@
+//! Test case execution order
+//!
+//! When judging stops at the first failure, running the tests that fail most often
+//! first shortens the average judge time. The order only affects execution: results
+//! are always reported in the task's declared order, so the reordering never reveals
+//! which hidden tests are the hard ones.
+
+use crate::{JudgeResult, JudgeStatus, TestCase, TestCaseResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Strategy for ordering test case execution
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TestOrder {
+    /// Run tests in the order they are declared
+    #[default]
+    Declared,
+    /// Run the historically most-failed tests first (only with stop-on-first-failure)
+    FailFirst,
+}
+
+/// Per-problem failure counts by test case id
+///
+/// Kept by the judging side only; it is never part of a user-facing result.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FailureStats {
+    /// Number of judgments in which each test case failed
+    pub failures: HashMap<String, u64>,
+}
+
+impl FailureStats {
+    /// Records the failed test cases of a judgment
+    ///
+    /// Pending, cancelled and system-error test cases are not the submission's
+    /// failures and are not counted.
+    pub fn record(&mut self, result: &JudgeResult) {
+        for test_case in &result.test_cases {
+            let status = test_case.status;
+            let counts = status.is_final()
+                && !status.is_accepted()
+                && !matches!(status, JudgeStatus::Cancelled | JudgeStatus::SystemError);
+            if counts {
+                *self.failures.entry(test_case.id.clone()).or_default() += 1;
+            }
+        }
+    }
+
+    /// Returns the failure count of a test case
+    pub fn failures_of(&self, id: &str) -> u64 {
+        self.failures.get(id).copied().unwrap_or(0)
+    }
+}
+
+/// Returns the indices of `test_cases` in execution order
+///
+/// Ties (and tests without statistics) keep their declared order.
+pub fn execution_order(
+    test_cases: &[TestCase],
+    order: TestOrder,
+    stats: Option<&FailureStats>,
+) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..test_cases.len()).collect();
+    if let (TestOrder::FailFirst, Some(stats)) = (order, stats) {
+        // Stable sort keeps the declared order among equals
+        indices.sort_by_key(|&i| std::cmp::Reverse(stats.failures_of(&test_cases[i].id)));
+    }
+    indices
+}
+
+/// Sorts test case results back into the declared order of `test_cases`
+pub fn restore_declared_order(test_cases: &[TestCase], results: &mut [TestCaseResult]) {
+    let position: HashMap<&str, usize> = test_cases
+        .iter()
+        .enumerate()
+        .map(|(i, tc)| (tc.id.as_str(), i))
+        .collect();
+    results.sort_by_key(|r| position.get(r.id.as_str()).copied().unwrap_or(usize::MAX));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn test_cases(ids: &[&str]) -> Vec<TestCase> {
+        ids.iter()
+            .map(|id| TestCase::new(id.to_string(), String::new(), String::new()))
+            .collect()
+    }
+
+    fn result(id: &str, status: JudgeStatus) -> TestCaseResult {
+        TestCaseResult {
+            id: id.to_string(),
+            status,
+            time_used: 0,
+            memory_used: 0,
+            input: None,
+            expected_output: None,
+            actual_output: None,
+            error_info: None,
+            is_hidden: false,
+            diff: None,
+        }
+    }
+
+    #[test]
+    fn test_fail_first_order() {
+        let cases = test_cases(&["1", "2", "3", "4"]);
+        let mut stats = FailureStats::default();
+        stats.failures.insert("3".to_string(), 10);
+        stats.failures.insert("2".to_string(), 2);
+        stats.failures.insert("4".to_string(), 2);
+
+        assert_eq!(
+            execution_order(&cases, TestOrder::FailFirst, Some(&stats)),
+            vec![2, 1, 3, 0]
+        );
+        assert_eq!(
+            execution_order(&cases, TestOrder::Declared, Some(&stats)),
+            vec![0, 1, 2, 3]
+        );
+        assert_eq!(
+            execution_order(&cases, TestOrder::FailFirst, None),
+            vec![0, 1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn test_record_failures() {
+        let mut judge_result =
+            JudgeResult::accepted(0, 0, Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        judge_result.test_cases = vec![
+            result("1", JudgeStatus::Accepted),
+            result("2", JudgeStatus::WrongAnswer),
+            result("3", JudgeStatus::Pending),
+            result("4", JudgeStatus::SystemError),
+        ];
+
+        let mut stats = FailureStats::default();
+        stats.record(&judge_result);
+        stats.record(&judge_result);
+        assert_eq!(stats.failures_of("1"), 0);
+        assert_eq!(stats.failures_of("2"), 2);
+        assert_eq!(stats.failures_of("3"), 0);
+        assert_eq!(stats.failures_of("4"), 0);
+    }
+
+    #[test]
+    fn test_restore_declared_order() {
+        let cases = test_cases(&["a", "b", "c"]);
+        let mut results = vec![
+            result("c", JudgeStatus::WrongAnswer),
+            result("a", JudgeStatus::Accepted),
+            result("b", JudgeStatus::Accepted),
+        ];
+        restore_declared_order(&cases, &mut results);
+        let ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b", "c"]);
+    }
+}
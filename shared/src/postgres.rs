@@ -0,0 +1,202 @@
+//! sqlx support for storing the shared types in Postgres
+//!
+//! Enabled by the `postgres` feature, so the judger and other consumers don't pull
+//! in sqlx. Enums map to scalar columns:
+//!
+//! * [`ProgrammingLanguage`]: `INT4`, its stable [`id`](ProgrammingLanguage::id)
+//! * [`JudgeStatus`]: `TEXT`, its [wire string](JudgeStatus::to_wire) (`"AC"`,
+//!   `"RE:SegmentationFault"`, ...)
+//! * [`RuntimeErrorType`]: `TEXT`, its [wire name](RuntimeErrorType::wire_name)
+//!   (`"SegmentationFault"`)
+//!
+//! [`Submission`] implements `FromRow` for this column layout:
+//!
+//! | column            | type          |
+//! |-------------------|---------------|
+//! | `id`              | `UUID`        |
+//! | `problem_id`      | `UUID`        |
+//! | `user_id`         | `UUID`        |
+//! | `language`        | `INT4`        |
+//! | `source_code`     | `TEXT`        |
+//! | `created_at`      | `TIMESTAMPTZ` |
+//! | `time_limit`      | `INT8`        |
+//! | `memory_limit`    | `INT8`        |
+//! | `priority`        | `INT4`        |
+//! | `contest_id`      | `UUID NULL`   |
+//! | `custom_language` | `JSONB NULL`  |
+//! | `rejudge_of`      | `UUID NULL`   |
+//! | `attempt`         | `INT4`        |
+//! | `output_limit`    | `INT8 NULL`   |
+//! | `stack_limit`     | `INT8 NULL`   |
+
+use crate::{CustomLanguage, JudgeStatus, ProgrammingLanguage, RuntimeErrorType, Submission};
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::postgres::{PgArgumentBuffer, PgRow, PgTypeInfo, PgValueRef};
+use sqlx::types::Json;
+use sqlx::{Decode, Encode, FromRow, Postgres, Row, Type};
+
+impl Type<Postgres> for ProgrammingLanguage {
+    fn type_info() -> PgTypeInfo {
+        <i32 as Type<Postgres>>::type_info()
+    }
+}
+
+impl Encode<'_, Postgres> for ProgrammingLanguage {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        <i32 as Encode<Postgres>>::encode_by_ref(&(self.id() as i32), buf)
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for ProgrammingLanguage {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        language_from_column(<i32 as Decode<Postgres>>::decode(value)?)
+    }
+}
+
+fn language_from_column(id: i32) -> Result<ProgrammingLanguage, BoxDynError> {
+    u32::try_from(id)
+        .ok()
+        .and_then(ProgrammingLanguage::from_id)
+        .ok_or_else(|| format!("unknown language id {}", id).into())
+}
+
+impl Type<Postgres> for JudgeStatus {
+    fn type_info() -> PgTypeInfo {
+        <&str as Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <&str as Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl Encode<'_, Postgres> for JudgeStatus {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        <&str as Encode<Postgres>>::encode_by_ref(&self.to_wire(), buf)
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for JudgeStatus {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        Ok(JudgeStatus::from_wire(<&str as Decode<Postgres>>::decode(
+            value,
+        )?)?)
+    }
+}
+
+impl Type<Postgres> for RuntimeErrorType {
+    fn type_info() -> PgTypeInfo {
+        <&str as Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <&str as Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl Encode<'_, Postgres> for RuntimeErrorType {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        <&str as Encode<Postgres>>::encode_by_ref(&self.wire_name(), buf)
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for RuntimeErrorType {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        let name = <&str as Decode<Postgres>>::decode(value)?;
+        RuntimeErrorType::from_wire_name(name)
+            .ok_or_else(|| format!("unknown runtime error type {:?}", name).into())
+    }
+}
+
+impl<'r> FromRow<'r, PgRow> for Submission {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        let unsigned = |column: &str| -> Result<u64, sqlx::Error> {
+            let value: i64 = row.try_get(column)?;
+            u64::try_from(value).map_err(|e| sqlx::Error::ColumnDecode {
+                index: column.to_string(),
+                source: Box::new(e),
+            })
+        };
+        let optional_unsigned = |column: &str| -> Result<Option<u64>, sqlx::Error> {
+            let value: Option<i64> = row.try_get(column)?;
+            value
+                .map(u64::try_from)
+                .transpose()
+                .map_err(|e| sqlx::Error::ColumnDecode {
+                    index: column.to_string(),
+                    source: Box::new(e),
+                })
+        };
+        let attempt: i32 = row.try_get("attempt")?;
+        let custom_language: Option<Json<CustomLanguage>> = row.try_get("custom_language")?;
+
+        Ok(Submission {
+            id: row.try_get("id")?,
+            problem_id: row.try_get("problem_id")?,
+            user_id: row.try_get("user_id")?,
+            language: row.try_get("language")?,
+            source_code: row.try_get("source_code")?,
+            created_at: row.try_get("created_at")?,
+            time_limit: unsigned("time_limit")?,
+            memory_limit: unsigned("memory_limit")?,
+            priority: row.try_get("priority")?,
+            contest_id: row.try_get("contest_id")?,
+            custom_language: custom_language.map(|json| json.0),
+            rejudge_of: row.try_get("rejudge_of")?,
+            attempt: u32::try_from(attempt).map_err(|e| sqlx::Error::ColumnDecode {
+                index: "attempt".to_string(),
+                source: Box::new(e),
+            })?,
+            output_limit: optional_unsigned("output_limit")?,
+            stack_limit: optional_unsigned("stack_limit")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded<'q, T: Encode<'q, Postgres>>(value: T) -> Vec<u8> {
+        let mut buf = PgArgumentBuffer::default();
+        let _ = value.encode_by_ref(&mut buf).unwrap();
+        buf.to_vec()
+    }
+
+    #[test]
+    fn test_language_round_trip() {
+        for language in ProgrammingLanguage::ALL {
+            let bytes = encoded(language);
+            let id = i32::from_be_bytes(bytes.try_into().unwrap());
+            assert_eq!(language_from_column(id).unwrap(), language);
+        }
+        assert!(language_from_column(-1).is_err());
+        assert!(language_from_column(10_000).is_err());
+        assert_eq!(
+            <ProgrammingLanguage as Type<Postgres>>::type_info(),
+            <i32 as Type<Postgres>>::type_info()
+        );
+    }
+
+    #[test]
+    fn test_status_round_trip() {
+        let statuses = JudgeStatus::ALL
+            .into_iter()
+            .chain(RuntimeErrorType::ALL.map(JudgeStatus::RuntimeError));
+        for status in statuses {
+            let text = String::from_utf8(encoded(status)).unwrap();
+            assert_eq!(text, status.to_wire());
+            assert_eq!(JudgeStatus::from_wire(&text).unwrap(), status);
+        }
+    }
+
+    #[test]
+    fn test_runtime_error_round_trip() {
+        for error_type in RuntimeErrorType::ALL {
+            let name = String::from_utf8(encoded(error_type)).unwrap();
+            assert_eq!(name, error_type.wire_name());
+            assert_eq!(RuntimeErrorType::from_wire_name(&name), Some(error_type));
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//! Truncated previews of test data and outputs
+//!
+//! Failing test cases show the first bytes of their input, expected output and actual
+//! output. Previews are cut on a UTF-8 character boundary and end with a marker that
+//! says how much was left out, e.g. `1 2 3… (+1024 bytes)`.
+
+use crate::{TestCase, TestCaseResult, TestData};
+use std::io::{self, Read};
+
+/// Truncates `text` to at most `max_bytes` on a character boundary, appending a
+/// marker with the number of bytes left out
+///
+/// Text that fits is returned unchanged.
+pub fn truncate_preview(text: &str, max_bytes: usize) -> String {
+    if text.len() <= max_bytes {
+        return text.to_string();
+    }
+    let cut = floor_char_boundary(text, max_bytes);
+    with_marker(&text[..cut], text.len() - cut)
+}
+
+impl TestData {
+    /// Returns a preview of at most `max_bytes` of the content (plus the marker)
+    ///
+    /// File-backed data is read only up to the preview size; remote data must be
+    /// fetched first. Bytes that aren't valid UTF-8 are shown as replacement
+    /// characters.
+    pub fn preview(&self, max_bytes: usize) -> io::Result<String> {
+        let total = match self {
+            TestData::Inline(text) => return Ok(truncate_preview(text, max_bytes)),
+            TestData::File(path) => std::fs::metadata(path)?.len(),
+            TestData::Remote { size, .. } => *size,
+        };
+        let mut bytes = Vec::with_capacity(max_bytes.min(total as usize));
+        self.open_reader()?
+            .take(max_bytes as u64)
+            .read_to_end(&mut bytes)?;
+        let (text, consumed) = decode_prefix(&bytes, max_bytes, (bytes.len() as u64) < total);
+        let omitted = total.saturating_sub(consumed as u64);
+        if omitted == 0 {
+            Ok(text)
+        } else {
+            Ok(with_marker(&text, omitted as usize))
+        }
+    }
+}
+
+impl TestCase {
+    /// Returns a preview of the input
+    pub fn input_preview(&self, max_bytes: usize) -> io::Result<String> {
+        self.input.preview(max_bytes)
+    }
+
+    /// Returns a preview of the expected output
+    pub fn expected_preview(&self, max_bytes: usize) -> io::Result<String> {
+        self.expected_output.preview(max_bytes)
+    }
+}
+
+impl TestCaseResult {
+    /// Returns a preview of the submission's output, if it was recorded
+    pub fn actual_preview(&self, max_bytes: usize) -> Option<String> {
+        self.actual_output
+            .as_deref()
+            .map(|output| truncate_preview(output, max_bytes))
+    }
+
+    /// Returns a copy with input, expected and actual output truncated, safe to send
+    /// to browsers
+    pub fn with_truncated_io(&self, max_bytes: usize) -> Self {
+        let truncate = |text: &Option<String>| {
+            text.as_deref()
+                .map(|text| truncate_preview(text, max_bytes))
+        };
+        Self {
+            input: truncate(&self.input),
+            expected_output: truncate(&self.expected_output),
+            actual_output: truncate(&self.actual_output),
+            ..self.clone()
+        }
+    }
+}
+
+fn with_marker(kept: &str, omitted: usize) -> String {
+    format!("{}… (+{} bytes)", kept, omitted)
+}
+
+/// Returns the largest character boundary of `text` not above `index`
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    (0..=index.min(text.len()))
+        .rev()
+        .find(|&i| text.is_char_boundary(i))
+        .unwrap_or(0)
+}
+
+/// Decodes a byte prefix into at most `max_bytes` of text, returning it with the
+/// number of source bytes it covers
+///
+/// Invalid bytes become replacement characters, which take three bytes each, so
+/// binary data may cover fewer source bytes than it renders. A character cut off at
+/// the end of the prefix is dropped if `more` data follows.
+fn decode_prefix(bytes: &[u8], max_bytes: usize, more: bool) -> (String, usize) {
+    let mut text = String::new();
+    let mut consumed = 0;
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            if text.len() + c.len_utf8() > max_bytes {
+                return (text, consumed);
+            }
+            text.push(c);
+            consumed += c.len_utf8();
+        }
+        let invalid = chunk.invalid();
+        if invalid.is_empty() {
+            continue;
+        }
+        let incomplete = std::str::from_utf8(invalid).is_err_and(|e| e.error_len().is_none());
+        if (more && incomplete && consumed + invalid.len() == bytes.len())
+            || text.len() + char::REPLACEMENT_CHARACTER.len_utf8() > max_bytes
+        {
+            return (text, consumed);
+        }
+        text.push(char::REPLACEMENT_CHARACTER);
+        consumed += invalid.len();
+    }
+    (text, consumed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JudgeStatus;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_truncate_preview() {
+        assert_eq!(truncate_preview("1 2 3\n", 256), "1 2 3\n");
+        assert_eq!(truncate_preview("1 2 3\n", 6), "1 2 3\n");
+        assert_eq!(truncate_preview("1 2 3\n", 3), "1 2… (+3 bytes)");
+        assert_eq!(truncate_preview("abc", 0), "… (+3 bytes)");
+    }
+
+    #[test]
+    fn test_multibyte_at_cut_point() {
+        // "é" is two bytes at 1..3, so cutting at 2 falls inside it
+        assert_eq!(truncate_preview("aé", 2), "a… (+2 bytes)");
+        assert_eq!(truncate_preview("aéb", 3), "aé… (+1 bytes)");
+        // Four-byte emoji
+        assert_eq!(truncate_preview("🙂🙂", 5), "🙂… (+4 bytes)");
+    }
+
+    #[test]
+    fn test_file_preview() {
+        let path = std::env::temp_dir().join(format!("oj-preview-{}", Uuid::new_v4()));
+        std::fs::write(&path, "aé".repeat(100)).unwrap();
+        let data = TestData::File(path.clone());
+        assert_eq!(data.preview(2).unwrap(), "a… (+299 bytes)");
+        assert_eq!(data.preview(1000).unwrap(), "aé".repeat(100));
+        std::fs::remove_file(&path).unwrap();
+        assert!(data.preview(2).is_err());
+    }
+
+    #[test]
+    fn test_binary_file_preview() {
+        let path = std::env::temp_dir().join(format!("oj-preview-{}", Uuid::new_v4()));
+        std::fs::write(&path, [0xff; 10]).unwrap();
+        let data = TestData::File(path.clone());
+        // Each invalid byte renders as a three-byte replacement character
+        assert_eq!(data.preview(4).unwrap(), "\u{fffd}… (+9 bytes)");
+        assert_eq!(
+            data.preview(10).unwrap(),
+            format!("{}… (+7 bytes)", "\u{fffd}".repeat(3))
+        );
+        assert_eq!(data.preview(2).unwrap(), "… (+10 bytes)");
+        assert_eq!(data.preview(30).unwrap(), "\u{fffd}".repeat(10));
+
+        // A truncated multi-byte character at the real end of the data is invalid,
+        // not cut off
+        std::fs::write(&path, b"ab\xc3").unwrap();
+        assert_eq!(data.preview(16).unwrap(), "ab\u{fffd}");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_test_case_previews() {
+        let test_case = TestCase::new("1".to_string(), "x".repeat(300), "42\n");
+        assert_eq!(
+            test_case.input_preview(256).unwrap(),
+            format!("{}… (+44 bytes)", "x".repeat(256))
+        );
+        assert_eq!(test_case.expected_preview(256).unwrap(), "42\n");
+
+        let result = TestCaseResult {
+            id: "1".to_string(),
+            status: JudgeStatus::WrongAnswer,
+            time_used: 0,
+            memory_used: 0,
+            input: Some("x".repeat(300)),
+            expected_output: Some("42\n".to_string()),
+            actual_output: Some("ü".repeat(200)),
+            error_info: None,
+            is_hidden: false,
+            diff: None,
+        };
+        assert_eq!(
+            result.actual_preview(3).unwrap(),
+            format!("ü… (+{} bytes)", 398)
+        );
+
+        let truncated = result.with_truncated_io(8);
+        assert_eq!(truncated.input.as_deref(), Some("xxxxxxxx… (+292 bytes)"));
+        assert_eq!(truncated.expected_output.as_deref(), Some("42\n"));
+        assert_eq!(
+            truncated.actual_output.as_deref(),
+            Some("üüüü… (+392 bytes)")
+        );
+        assert_eq!(truncated.status, JudgeStatus::WrongAnswer);
+    }
+}
@@ -0,0 +1,1347 @@
+//! Protobuf representation of the shared types
+//!
+//! Enabled by the `proto` feature. [`v1`] holds the prost types generated from
+//! `proto/axon/judge/v1/judge.proto`; this module converts them to and from the
+//! serde types without losing information:
+//!
+//! * Messages convert with `From<T> for v1::T` and `TryFrom<v1::T> for T`, failing
+//!   with a [`ProtoError`] on a missing required field, a malformed UUID or
+//!   timestamp, or a oneof without a variant this build knows.
+//! * Enum values unknown to this build (sent by a newer peer) and `UNSPECIFIED`
+//!   decode to a fallback instead: languages to [`ProgrammingLanguage::Custom`],
+//!   which validation rejects without a toolchain, statuses to
+//!   [`JudgeStatus::SystemError`], runtime errors to [`RuntimeErrorType::Other`] and
+//!   the others to their default.
+//! * A missing `checker` or `execution_mode` message decodes to the default, like a
+//!   missing JSON field.
+//!
+//! ```
+//! use oj_shared::{ErrorInfo, proto::v1};
+//! use prost::Message;
+//!
+//! let error = ErrorInfo::killed_by_signal(11, None);
+//! let bytes = v1::ErrorInfo::from(error.clone()).encode_to_vec();
+//! let decoded = v1::ErrorInfo::decode(bytes.as_slice()).unwrap();
+//! assert_eq!(ErrorInfo::from(decoded), error);
+//! ```
+
+use crate::{
+    BatchInfo, BuildProfile, Checker, ComparisonMode, CustomLanguage, ErrorInfo, ExecutionMode,
+    GeneratedTestCase, InteractorProgram, InteractorSpec, JudgeMode, JudgeResult, JudgeStatus,
+    JudgeTask, LimitPolicy, LimitScaling, MultiCaseSpec, OutputDiff, ProgramSpec,
+    ProgrammingLanguage, RuntimeErrorType, Submission, Subtask, TestCase, TestCaseResult,
+    TestCaseSource, TestData, TestOrder,
+};
+use chrono::{DateTime, Utc};
+use std::ffi::OsStr;
+use std::fmt;
+use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+/// Types generated from `proto/axon/judge/v1/judge.proto`
+pub mod v1 {
+    include!(concat!(env!("OUT_DIR"), "/axon.judge.v1.rs"));
+}
+
+/// Why a protobuf message can't be converted to the shared type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProtoError {
+    /// A required message field is not set
+    MissingField(&'static str),
+    /// A oneof is not set, or set to a variant this build doesn't know
+    UnknownVariant(&'static str),
+    /// A UUID field doesn't hold exactly 16 bytes
+    InvalidUuid(&'static str),
+    /// A timestamp is outside the range `DateTime<Utc>` can represent
+    InvalidTimestamp(&'static str),
+    /// A number doesn't fit the field's Rust type
+    OutOfRange(&'static str),
+}
+
+impl fmt::Display for ProtoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtoError::MissingField(field) => write!(f, "{} is not set", field),
+            ProtoError::UnknownVariant(field) => write!(f, "{} has no known variant", field),
+            ProtoError::InvalidUuid(field) => write!(f, "{} is not a 16-byte UUID", field),
+            ProtoError::InvalidTimestamp(field) => write!(f, "{} is not a valid timestamp", field),
+            ProtoError::OutOfRange(field) => write!(f, "{} is out of range", field),
+        }
+    }
+}
+
+impl std::error::Error for ProtoError {}
+
+fn uuid_to_proto(id: Uuid) -> Vec<u8> {
+    id.as_bytes().to_vec()
+}
+
+fn uuid_from_proto(bytes: &[u8], field: &'static str) -> Result<Uuid, ProtoError> {
+    Uuid::from_slice(bytes).map_err(|_| ProtoError::InvalidUuid(field))
+}
+
+fn optional_uuid_from_proto(
+    bytes: Option<Vec<u8>>,
+    field: &'static str,
+) -> Result<Option<Uuid>, ProtoError> {
+    bytes
+        .map(|bytes| uuid_from_proto(&bytes, field))
+        .transpose()
+}
+
+fn timestamp_to_proto(time: DateTime<Utc>) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: time.timestamp(),
+        // Above 999_999_999 only during a leap second, which chrono also accepts back
+        nanos: time.timestamp_subsec_nanos() as i32,
+    }
+}
+
+fn timestamp_from_proto(
+    timestamp: Option<prost_types::Timestamp>,
+    field: &'static str,
+) -> Result<DateTime<Utc>, ProtoError> {
+    let timestamp = timestamp.ok_or(ProtoError::MissingField(field))?;
+    let nanos = u32::try_from(timestamp.nanos).map_err(|_| ProtoError::InvalidTimestamp(field))?;
+    DateTime::from_timestamp(timestamp.seconds, nanos).ok_or(ProtoError::InvalidTimestamp(field))
+}
+
+fn usize_from_proto(value: u64, field: &'static str) -> Result<usize, ProtoError> {
+    usize::try_from(value).map_err(|_| ProtoError::OutOfRange(field))
+}
+
+fn language_to_proto(language: ProgrammingLanguage) -> i32 {
+    // The proto enum numbers are the stable language ids
+    language.id() as i32
+}
+
+fn language_from_proto(value: i32) -> ProgrammingLanguage {
+    u32::try_from(value)
+        .ok()
+        .and_then(ProgrammingLanguage::from_id)
+        .unwrap_or(ProgrammingLanguage::Custom)
+}
+
+/// Returns the proto status and runtime error type of a status
+fn status_to_proto(status: JudgeStatus) -> (i32, i32) {
+    let proto = match status {
+        JudgeStatus::Accepted => v1::JudgeStatus::Accepted,
+        JudgeStatus::WrongAnswer => v1::JudgeStatus::WrongAnswer,
+        JudgeStatus::PresentationError => v1::JudgeStatus::PresentationError,
+        JudgeStatus::PartialAccepted => v1::JudgeStatus::PartialAccepted,
+        JudgeStatus::Skipped => v1::JudgeStatus::Skipped,
+        JudgeStatus::TimeLimitExceeded => v1::JudgeStatus::TimeLimitExceeded,
+        JudgeStatus::MemoryLimitExceeded => v1::JudgeStatus::MemoryLimitExceeded,
+        JudgeStatus::RuntimeError(_) => v1::JudgeStatus::RuntimeError,
+        JudgeStatus::CompileError => v1::JudgeStatus::CompileError,
+        JudgeStatus::RestrictedOperation => v1::JudgeStatus::RestrictedOperation,
+        JudgeStatus::OutputLimitExceeded => v1::JudgeStatus::OutputLimitExceeded,
+        JudgeStatus::SystemError => v1::JudgeStatus::SystemError,
+        JudgeStatus::Pending => v1::JudgeStatus::Pending,
+        JudgeStatus::Judging => v1::JudgeStatus::Judging,
+        JudgeStatus::Cancelled => v1::JudgeStatus::Cancelled,
+    };
+    let runtime_error = status
+        .runtime_error_type()
+        .map_or(v1::RuntimeErrorType::Unspecified, Into::into);
+    (proto as i32, runtime_error as i32)
+}
+
+fn status_from_proto(status: i32, runtime_error: i32) -> JudgeStatus {
+    match v1::JudgeStatus::try_from(status) {
+        Ok(v1::JudgeStatus::Accepted) => JudgeStatus::Accepted,
+        Ok(v1::JudgeStatus::WrongAnswer) => JudgeStatus::WrongAnswer,
+        Ok(v1::JudgeStatus::PresentationError) => JudgeStatus::PresentationError,
+        Ok(v1::JudgeStatus::PartialAccepted) => JudgeStatus::PartialAccepted,
+        Ok(v1::JudgeStatus::Skipped) => JudgeStatus::Skipped,
+        Ok(v1::JudgeStatus::TimeLimitExceeded) => JudgeStatus::TimeLimitExceeded,
+        Ok(v1::JudgeStatus::MemoryLimitExceeded) => JudgeStatus::MemoryLimitExceeded,
+        Ok(v1::JudgeStatus::RuntimeError) => JudgeStatus::RuntimeError(
+            v1::RuntimeErrorType::try_from(runtime_error)
+                .map_or(RuntimeErrorType::Other, Into::into),
+        ),
+        Ok(v1::JudgeStatus::CompileError) => JudgeStatus::CompileError,
+        Ok(v1::JudgeStatus::RestrictedOperation) => JudgeStatus::RestrictedOperation,
+        Ok(v1::JudgeStatus::OutputLimitExceeded) => JudgeStatus::OutputLimitExceeded,
+        Ok(v1::JudgeStatus::Pending) => JudgeStatus::Pending,
+        Ok(v1::JudgeStatus::Judging) => JudgeStatus::Judging,
+        Ok(v1::JudgeStatus::Cancelled) => JudgeStatus::Cancelled,
+        Ok(v1::JudgeStatus::SystemError | v1::JudgeStatus::Unspecified) | Err(_) => {
+            JudgeStatus::SystemError
+        }
+    }
+}
+
+impl From<RuntimeErrorType> for v1::RuntimeErrorType {
+    fn from(error_type: RuntimeErrorType) -> Self {
+        match error_type {
+            RuntimeErrorType::SegmentationFault => v1::RuntimeErrorType::SegmentationFault,
+            RuntimeErrorType::FloatingPointException => {
+                v1::RuntimeErrorType::FloatingPointException
+            }
+            RuntimeErrorType::DivisionByZero => v1::RuntimeErrorType::DivisionByZero,
+            RuntimeErrorType::AssertionFailed => v1::RuntimeErrorType::AssertionFailed,
+            RuntimeErrorType::StackOverflow => v1::RuntimeErrorType::StackOverflow,
+            RuntimeErrorType::NullPointerDereference => {
+                v1::RuntimeErrorType::NullPointerDereference
+            }
+            RuntimeErrorType::FileOperationError => v1::RuntimeErrorType::FileOperationError,
+            RuntimeErrorType::PermissionDenied => v1::RuntimeErrorType::PermissionDenied,
+            RuntimeErrorType::Other => v1::RuntimeErrorType::Other,
+        }
+    }
+}
+
+impl From<v1::RuntimeErrorType> for RuntimeErrorType {
+    fn from(error_type: v1::RuntimeErrorType) -> Self {
+        match error_type {
+            v1::RuntimeErrorType::SegmentationFault => RuntimeErrorType::SegmentationFault,
+            v1::RuntimeErrorType::FloatingPointException => {
+                RuntimeErrorType::FloatingPointException
+            }
+            v1::RuntimeErrorType::DivisionByZero => RuntimeErrorType::DivisionByZero,
+            v1::RuntimeErrorType::AssertionFailed => RuntimeErrorType::AssertionFailed,
+            v1::RuntimeErrorType::StackOverflow => RuntimeErrorType::StackOverflow,
+            v1::RuntimeErrorType::NullPointerDereference => {
+                RuntimeErrorType::NullPointerDereference
+            }
+            v1::RuntimeErrorType::FileOperationError => RuntimeErrorType::FileOperationError,
+            v1::RuntimeErrorType::PermissionDenied => RuntimeErrorType::PermissionDenied,
+            v1::RuntimeErrorType::Other | v1::RuntimeErrorType::Unspecified => {
+                RuntimeErrorType::Other
+            }
+        }
+    }
+}
+
+fn judge_mode_to_proto(mode: JudgeMode) -> i32 {
+    match mode {
+        JudgeMode::AcmIcpc => v1::JudgeMode::AcmIcpc as i32,
+        JudgeMode::OiPartial => v1::JudgeMode::OiPartial as i32,
+    }
+}
+
+fn judge_mode_from_proto(value: i32) -> JudgeMode {
+    match v1::JudgeMode::try_from(value) {
+        Ok(v1::JudgeMode::OiPartial) => JudgeMode::OiPartial,
+        Ok(v1::JudgeMode::AcmIcpc | v1::JudgeMode::Unspecified) | Err(_) => JudgeMode::AcmIcpc,
+    }
+}
+
+fn build_profile_to_proto(profile: BuildProfile) -> i32 {
+    match profile {
+        BuildProfile::Release => v1::BuildProfile::Release as i32,
+        BuildProfile::Sanitize => v1::BuildProfile::Sanitize as i32,
+    }
+}
+
+fn build_profile_from_proto(value: i32) -> BuildProfile {
+    match v1::BuildProfile::try_from(value) {
+        Ok(v1::BuildProfile::Sanitize) => BuildProfile::Sanitize,
+        Ok(v1::BuildProfile::Release | v1::BuildProfile::Unspecified) | Err(_) => {
+            BuildProfile::Release
+        }
+    }
+}
+
+fn test_order_to_proto(order: TestOrder) -> i32 {
+    match order {
+        TestOrder::Declared => v1::TestOrder::Declared as i32,
+        TestOrder::FailFirst => v1::TestOrder::FailFirst as i32,
+    }
+}
+
+fn test_order_from_proto(value: i32) -> TestOrder {
+    match v1::TestOrder::try_from(value) {
+        Ok(v1::TestOrder::FailFirst) => TestOrder::FailFirst,
+        Ok(v1::TestOrder::Declared | v1::TestOrder::Unspecified) | Err(_) => TestOrder::Declared,
+    }
+}
+
+impl From<CustomLanguage> for v1::CustomLanguage {
+    fn from(language: CustomLanguage) -> Self {
+        Self {
+            name: language.name,
+            file_extension: language.file_extension,
+            needs_compilation: language.needs_compilation,
+            compile_argv: language.compile_argv,
+            run_argv: language.run_argv,
+        }
+    }
+}
+
+impl From<v1::CustomLanguage> for CustomLanguage {
+    fn from(language: v1::CustomLanguage) -> Self {
+        Self {
+            name: language.name,
+            file_extension: language.file_extension,
+            needs_compilation: language.needs_compilation,
+            compile_argv: language.compile_argv,
+            run_argv: language.run_argv,
+        }
+    }
+}
+
+impl From<Submission> for v1::Submission {
+    fn from(submission: Submission) -> Self {
+        Self {
+            id: uuid_to_proto(submission.id),
+            problem_id: uuid_to_proto(submission.problem_id),
+            user_id: uuid_to_proto(submission.user_id),
+            language: language_to_proto(submission.language),
+            source_code: submission.source_code,
+            created_at: Some(timestamp_to_proto(submission.created_at)),
+            time_limit: submission.time_limit,
+            memory_limit: submission.memory_limit,
+            priority: submission.priority,
+            contest_id: submission.contest_id.map(uuid_to_proto),
+            custom_language: submission.custom_language.map(Into::into),
+            rejudge_of: submission.rejudge_of.map(uuid_to_proto),
+            attempt: submission.attempt,
+            output_limit: submission.output_limit,
+            stack_limit: submission.stack_limit,
+        }
+    }
+}
+
+impl TryFrom<v1::Submission> for Submission {
+    type Error = ProtoError;
+
+    fn try_from(submission: v1::Submission) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: uuid_from_proto(&submission.id, "Submission.id")?,
+            problem_id: uuid_from_proto(&submission.problem_id, "Submission.problem_id")?,
+            user_id: uuid_from_proto(&submission.user_id, "Submission.user_id")?,
+            language: language_from_proto(submission.language),
+            source_code: submission.source_code,
+            created_at: timestamp_from_proto(submission.created_at, "Submission.created_at")?,
+            time_limit: submission.time_limit,
+            memory_limit: submission.memory_limit,
+            priority: submission.priority,
+            contest_id: optional_uuid_from_proto(submission.contest_id, "Submission.contest_id")?,
+            custom_language: submission.custom_language.map(Into::into),
+            rejudge_of: optional_uuid_from_proto(submission.rejudge_of, "Submission.rejudge_of")?,
+            attempt: submission.attempt,
+            output_limit: submission.output_limit,
+            stack_limit: submission.stack_limit,
+        })
+    }
+}
+
+impl From<TestData> for v1::TestData {
+    fn from(data: TestData) -> Self {
+        let data = match data {
+            TestData::Inline(text) => v1::test_data::Data::Inline(text),
+            TestData::File(path) => v1::test_data::Data::File(path.as_os_str().as_bytes().to_vec()),
+            TestData::Remote { url, sha256, size } => {
+                v1::test_data::Data::Remote(v1::RemoteTestData { url, sha256, size })
+            }
+        };
+        Self { data: Some(data) }
+    }
+}
+
+impl TryFrom<v1::TestData> for TestData {
+    type Error = ProtoError;
+
+    fn try_from(data: v1::TestData) -> Result<Self, Self::Error> {
+        Ok(match data.data {
+            Some(v1::test_data::Data::Inline(text)) => TestData::Inline(text),
+            Some(v1::test_data::Data::File(path)) => {
+                TestData::File(PathBuf::from(OsStr::from_bytes(&path)))
+            }
+            Some(v1::test_data::Data::Remote(remote)) => TestData::Remote {
+                url: remote.url,
+                sha256: remote.sha256,
+                size: remote.size,
+            },
+            None => return Err(ProtoError::UnknownVariant("TestData.data")),
+        })
+    }
+}
+
+fn required_test_data(
+    data: Option<v1::TestData>,
+    field: &'static str,
+) -> Result<TestData, ProtoError> {
+    data.ok_or(ProtoError::MissingField(field))?.try_into()
+}
+
+impl From<ComparisonMode> for v1::ComparisonMode {
+    fn from(mode: ComparisonMode) -> Self {
+        use v1::comparison_mode::Mode;
+        let mode = match mode {
+            ComparisonMode::Exact => Mode::Exact(v1::Empty {}),
+            ComparisonMode::TrimWhitespace => Mode::TrimWhitespace(v1::Empty {}),
+            ComparisonMode::TokenEquality => Mode::TokenEquality(v1::Empty {}),
+            ComparisonMode::FloatEpsilon { abs, rel } => {
+                Mode::FloatEpsilon(v1::FloatEpsilon { abs, rel })
+            }
+        };
+        Self { mode: Some(mode) }
+    }
+}
+
+impl TryFrom<v1::ComparisonMode> for ComparisonMode {
+    type Error = ProtoError;
+
+    fn try_from(mode: v1::ComparisonMode) -> Result<Self, Self::Error> {
+        use v1::comparison_mode::Mode;
+        Ok(match mode.mode {
+            Some(Mode::Exact(_)) => ComparisonMode::Exact,
+            Some(Mode::TrimWhitespace(_)) => ComparisonMode::TrimWhitespace,
+            Some(Mode::TokenEquality(_)) => ComparisonMode::TokenEquality,
+            Some(Mode::FloatEpsilon(v1::FloatEpsilon { abs, rel })) => {
+                ComparisonMode::FloatEpsilon { abs, rel }
+            }
+            None => return Err(ProtoError::UnknownVariant("ComparisonMode.mode")),
+        })
+    }
+}
+
+impl From<GeneratedTestCase> for v1::GeneratedTestCase {
+    fn from(generated: GeneratedTestCase) -> Self {
+        Self {
+            generator_args: generated.generator_args,
+            seed: generated.seed,
+        }
+    }
+}
+
+impl From<v1::GeneratedTestCase> for GeneratedTestCase {
+    fn from(generated: v1::GeneratedTestCase) -> Self {
+        Self {
+            generator_args: generated.generator_args,
+            seed: generated.seed,
+        }
+    }
+}
+
+impl From<TestCase> for v1::TestCase {
+    fn from(test_case: TestCase) -> Self {
+        Self {
+            id: test_case.id,
+            input: Some(test_case.input.into()),
+            expected_output: Some(test_case.expected_output.into()),
+            time_limit: test_case.time_limit,
+            memory_limit: test_case.memory_limit,
+            is_hidden: test_case.is_hidden,
+            weight: test_case.weight,
+            alternative_outputs: test_case
+                .alternative_outputs
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            comparison: test_case.comparison.map(Into::into),
+            input_sha256: test_case.input_sha256,
+            output_sha256: test_case.output_sha256,
+            generated: match test_case.source {
+                TestCaseSource::Static => None,
+                TestCaseSource::Generated(generated) => Some(generated.into()),
+            },
+            is_sample: test_case.is_sample,
+            env: test_case.env.map(|env| v1::EnvVarList {
+                values: env_to_proto(env),
+            }),
+        }
+    }
+}
+
+impl TryFrom<v1::TestCase> for TestCase {
+    type Error = ProtoError;
+
+    fn try_from(test_case: v1::TestCase) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: test_case.id,
+            input: required_test_data(test_case.input, "TestCase.input")?,
+            expected_output: required_test_data(
+                test_case.expected_output,
+                "TestCase.expected_output",
+            )?,
+            time_limit: test_case.time_limit,
+            memory_limit: test_case.memory_limit,
+            is_hidden: test_case.is_hidden,
+            weight: test_case.weight,
+            alternative_outputs: test_case
+                .alternative_outputs
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            comparison: test_case.comparison.map(TryInto::try_into).transpose()?,
+            input_sha256: test_case.input_sha256,
+            output_sha256: test_case.output_sha256,
+            source: match test_case.generated {
+                Some(generated) => TestCaseSource::Generated(generated.into()),
+                None => TestCaseSource::Static,
+            },
+            is_sample: test_case.is_sample,
+            env: test_case.env.map(|env| env_from_proto(env.values)),
+        })
+    }
+}
+
+fn env_to_proto(env: Vec<(String, String)>) -> Vec<v1::EnvVar> {
+    env.into_iter()
+        .map(|(name, value)| v1::EnvVar { name, value })
+        .collect()
+}
+
+fn env_from_proto(env: Vec<v1::EnvVar>) -> Vec<(String, String)> {
+    env.into_iter().map(|var| (var.name, var.value)).collect()
+}
+
+impl From<LimitScaling> for v1::LimitScaling {
+    fn from(scaling: LimitScaling) -> Self {
+        Self {
+            time_multiplier: scaling.time_multiplier,
+            memory_bonus_kb: scaling.memory_bonus_kb,
+        }
+    }
+}
+
+impl From<v1::LimitScaling> for LimitScaling {
+    fn from(scaling: v1::LimitScaling) -> Self {
+        Self {
+            time_multiplier: scaling.time_multiplier,
+            memory_bonus_kb: scaling.memory_bonus_kb,
+        }
+    }
+}
+
+impl From<LimitPolicy> for v1::LimitPolicy {
+    fn from(policy: LimitPolicy) -> Self {
+        let mut overrides: Vec<_> = policy
+            .overrides
+            .into_iter()
+            .map(|(language, scaling)| v1::LanguageLimitScaling {
+                language: language_to_proto(language),
+                scaling: Some(scaling.into()),
+            })
+            .collect();
+        // Deterministic bytes for the same policy
+        overrides.sort_by_key(|entry| entry.language);
+        Self { overrides }
+    }
+}
+
+impl TryFrom<v1::LimitPolicy> for LimitPolicy {
+    type Error = ProtoError;
+
+    fn try_from(policy: v1::LimitPolicy) -> Result<Self, Self::Error> {
+        let mut overrides = std::collections::HashMap::new();
+        for entry in policy.overrides {
+            let scaling = entry
+                .scaling
+                .ok_or(ProtoError::MissingField("LanguageLimitScaling.scaling"))?;
+            // A policy for a language this build doesn't have can't apply to anything
+            let language = u32::try_from(entry.language)
+                .ok()
+                .and_then(ProgrammingLanguage::from_id);
+            if let Some(language) = language {
+                overrides.insert(language, scaling.into());
+            }
+        }
+        Ok(Self { overrides })
+    }
+}
+
+impl From<ProgramSpec> for v1::ProgramSpec {
+    fn from(spec: ProgramSpec) -> Self {
+        Self {
+            language: language_to_proto(spec.language),
+            source: spec.source,
+        }
+    }
+}
+
+impl From<v1::ProgramSpec> for ProgramSpec {
+    fn from(spec: v1::ProgramSpec) -> Self {
+        Self {
+            language: language_from_proto(spec.language),
+            source: spec.source,
+        }
+    }
+}
+
+impl From<InteractorSpec> for v1::InteractorSpec {
+    fn from(spec: InteractorSpec) -> Self {
+        Self {
+            language: language_to_proto(spec.language),
+            program: Some(match spec.program {
+                InteractorProgram::Source(source) => v1::interactor_spec::Program::Source(source),
+                InteractorProgram::Binary(path) => v1::interactor_spec::Program::Binary(path),
+            }),
+            test_args: spec
+                .test_args
+                .into_iter()
+                .map(|(id, values)| (id, v1::StringList { values }))
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<v1::InteractorSpec> for InteractorSpec {
+    type Error = ProtoError;
+
+    fn try_from(spec: v1::InteractorSpec) -> Result<Self, Self::Error> {
+        Ok(Self {
+            language: language_from_proto(spec.language),
+            program: match spec.program {
+                Some(v1::interactor_spec::Program::Source(source)) => {
+                    InteractorProgram::Source(source)
+                }
+                Some(v1::interactor_spec::Program::Binary(path)) => InteractorProgram::Binary(path),
+                None => return Err(ProtoError::UnknownVariant("InteractorSpec.program")),
+            },
+            test_args: spec
+                .test_args
+                .into_iter()
+                .map(|(id, args)| (id, args.values))
+                .collect(),
+        })
+    }
+}
+
+impl From<MultiCaseSpec> for v1::MultiCaseSpec {
+    fn from(spec: MultiCaseSpec) -> Self {
+        Self {
+            count_header: spec.count_header,
+            delimiter: spec.delimiter,
+        }
+    }
+}
+
+impl From<v1::MultiCaseSpec> for MultiCaseSpec {
+    fn from(spec: v1::MultiCaseSpec) -> Self {
+        Self {
+            count_header: spec.count_header,
+            delimiter: spec.delimiter,
+        }
+    }
+}
+
+impl From<ExecutionMode> for v1::ExecutionMode {
+    fn from(mode: ExecutionMode) -> Self {
+        use v1::execution_mode::Mode;
+        let mode = match mode {
+            ExecutionMode::Batch => Mode::Batch(v1::Empty {}),
+            ExecutionMode::Interactive(spec) => Mode::Interactive(spec.into()),
+            ExecutionMode::SingleRunMultiCase(spec) => Mode::SingleRunMultiCase(spec.into()),
+        };
+        Self { mode: Some(mode) }
+    }
+}
+
+impl TryFrom<v1::ExecutionMode> for ExecutionMode {
+    type Error = ProtoError;
+
+    fn try_from(mode: v1::ExecutionMode) -> Result<Self, Self::Error> {
+        use v1::execution_mode::Mode;
+        Ok(match mode.mode {
+            Some(Mode::Batch(_)) => ExecutionMode::Batch,
+            Some(Mode::Interactive(spec)) => ExecutionMode::Interactive(spec.try_into()?),
+            Some(Mode::SingleRunMultiCase(spec)) => ExecutionMode::SingleRunMultiCase(spec.into()),
+            None => return Err(ProtoError::UnknownVariant("ExecutionMode.mode")),
+        })
+    }
+}
+
+impl From<Checker> for v1::Checker {
+    fn from(checker: Checker) -> Self {
+        use v1::checker::Checker as Kind;
+        let checker = match checker {
+            Checker::DefaultDiff => Kind::DefaultDiff(v1::Empty {}),
+            Checker::TokenCompare => Kind::TokenCompare(v1::Empty {}),
+            Checker::FloatCompare { epsilon } => Kind::FloatCompare(v1::FloatCompare { epsilon }),
+            Checker::Custom { language, source } => {
+                Kind::Custom(ProgramSpec { language, source }.into())
+            }
+        };
+        Self {
+            checker: Some(checker),
+        }
+    }
+}
+
+impl TryFrom<v1::Checker> for Checker {
+    type Error = ProtoError;
+
+    fn try_from(checker: v1::Checker) -> Result<Self, Self::Error> {
+        use v1::checker::Checker as Kind;
+        Ok(match checker.checker {
+            Some(Kind::DefaultDiff(_)) => Checker::DefaultDiff,
+            Some(Kind::TokenCompare(_)) => Checker::TokenCompare,
+            Some(Kind::FloatCompare(v1::FloatCompare { epsilon })) => {
+                Checker::FloatCompare { epsilon }
+            }
+            Some(Kind::Custom(spec)) => {
+                let spec = ProgramSpec::from(spec);
+                Checker::Custom {
+                    language: spec.language,
+                    source: spec.source,
+                }
+            }
+            None => return Err(ProtoError::UnknownVariant("Checker.checker")),
+        })
+    }
+}
+
+impl From<Subtask> for v1::Subtask {
+    fn from(subtask: Subtask) -> Self {
+        Self {
+            id: subtask.id,
+            score: subtask.score,
+            test_case_ids: subtask.test_case_ids,
+            depends_on: subtask.depends_on,
+        }
+    }
+}
+
+impl From<v1::Subtask> for Subtask {
+    fn from(subtask: v1::Subtask) -> Self {
+        Self {
+            id: subtask.id,
+            score: subtask.score,
+            test_case_ids: subtask.test_case_ids,
+            depends_on: subtask.depends_on,
+        }
+    }
+}
+
+impl From<BatchInfo> for v1::BatchInfo {
+    fn from(batch: BatchInfo) -> Self {
+        Self {
+            index: batch.index as u64,
+            total: batch.total as u64,
+            parent_task_id: uuid_to_proto(batch.parent_task_id),
+            score_share: batch.score_share,
+            judge_mode: judge_mode_to_proto(batch.judge_mode),
+        }
+    }
+}
+
+impl TryFrom<v1::BatchInfo> for BatchInfo {
+    type Error = ProtoError;
+
+    fn try_from(batch: v1::BatchInfo) -> Result<Self, Self::Error> {
+        Ok(Self {
+            index: usize_from_proto(batch.index, "BatchInfo.index")?,
+            total: usize_from_proto(batch.total, "BatchInfo.total")?,
+            parent_task_id: uuid_from_proto(&batch.parent_task_id, "BatchInfo.parent_task_id")?,
+            score_share: batch.score_share,
+            judge_mode: judge_mode_from_proto(batch.judge_mode),
+        })
+    }
+}
+
+impl From<JudgeTask> for v1::JudgeTask {
+    fn from(task: JudgeTask) -> Self {
+        Self {
+            schema_version: task.schema_version,
+            submission: Some(task.submission.into()),
+            test_cases: task.test_cases.into_iter().map(Into::into).collect(),
+            needs_compilation: task.needs_compilation,
+            use_sandbox: task.use_sandbox,
+            compile_flags: task.compile_flags.map(|values| v1::StringList { values }),
+            runtime_args: task.runtime_args.map(|values| v1::StringList { values }),
+            env: env_to_proto(task.env),
+            limit_policy: task.limit_policy.map(Into::into),
+            build_profile: build_profile_to_proto(task.build_profile),
+            precompile: task.precompile,
+            skip_screening: task.skip_screening,
+            stop_on_first_failure: task.stop_on_first_failure,
+            test_order: test_order_to_proto(task.test_order),
+            execution_mode: Some(task.execution_mode.into()),
+            checker: Some(task.checker.into()),
+            default_comparison: task.default_comparison.map(Into::into),
+            compile_time_limit: task.compile_time_limit,
+            compile_memory_limit: task.compile_memory_limit,
+            output_limit: task.output_limit,
+            stack_limit: task.stack_limit,
+            subtasks: task.subtasks.into_iter().map(Into::into).collect(),
+            judge_mode: judge_mode_to_proto(task.judge_mode),
+            batch: task.batch.map(Into::into),
+            generator: task.generator.map(Into::into),
+            reference_solution: task.reference_solution.map(Into::into),
+        }
+    }
+}
+
+impl TryFrom<v1::JudgeTask> for JudgeTask {
+    type Error = ProtoError;
+
+    fn try_from(task: v1::JudgeTask) -> Result<Self, Self::Error> {
+        Ok(Self {
+            schema_version: task.schema_version,
+            submission: task
+                .submission
+                .ok_or(ProtoError::MissingField("JudgeTask.submission"))?
+                .try_into()?,
+            test_cases: task
+                .test_cases
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            needs_compilation: task.needs_compilation,
+            use_sandbox: task.use_sandbox,
+            compile_flags: task.compile_flags.map(|flags| flags.values),
+            runtime_args: task.runtime_args.map(|args| args.values),
+            env: env_from_proto(task.env),
+            limit_policy: task.limit_policy.map(TryInto::try_into).transpose()?,
+            build_profile: build_profile_from_proto(task.build_profile),
+            precompile: task.precompile,
+            skip_screening: task.skip_screening,
+            stop_on_first_failure: task.stop_on_first_failure,
+            test_order: test_order_from_proto(task.test_order),
+            execution_mode: task
+                .execution_mode
+                .map(TryInto::try_into)
+                .transpose()?
+                .unwrap_or_default(),
+            checker: task
+                .checker
+                .map(TryInto::try_into)
+                .transpose()?
+                .unwrap_or_default(),
+            default_comparison: task.default_comparison.map(TryInto::try_into).transpose()?,
+            compile_time_limit: task.compile_time_limit,
+            compile_memory_limit: task.compile_memory_limit,
+            output_limit: task.output_limit,
+            stack_limit: task.stack_limit,
+            subtasks: task.subtasks.into_iter().map(Into::into).collect(),
+            judge_mode: judge_mode_from_proto(task.judge_mode),
+            batch: task.batch.map(TryInto::try_into).transpose()?,
+            generator: task.generator.map(Into::into),
+            reference_solution: task.reference_solution.map(Into::into),
+        })
+    }
+}
+
+impl From<ErrorInfo> for v1::ErrorInfo {
+    fn from(error: ErrorInfo) -> Self {
+        Self {
+            message: error.message,
+            code: error.code,
+            line: error.line,
+            column: error.column,
+            stderr: error.stderr,
+            stdout: error.stdout,
+            exit_code: error.exit_code,
+            signal: error.signal,
+            hint: error.hint,
+        }
+    }
+}
+
+impl From<v1::ErrorInfo> for ErrorInfo {
+    fn from(error: v1::ErrorInfo) -> Self {
+        Self {
+            message: error.message,
+            code: error.code,
+            line: error.line,
+            column: error.column,
+            stderr: error.stderr,
+            stdout: error.stdout,
+            exit_code: error.exit_code,
+            signal: error.signal,
+            hint: error.hint,
+        }
+    }
+}
+
+impl From<OutputDiff> for v1::OutputDiff {
+    fn from(diff: OutputDiff) -> Self {
+        Self {
+            line: diff.line,
+            column: diff.column,
+            expected_fragment: diff.expected_fragment,
+            actual_fragment: diff.actual_fragment,
+        }
+    }
+}
+
+impl From<v1::OutputDiff> for OutputDiff {
+    fn from(diff: v1::OutputDiff) -> Self {
+        Self {
+            line: diff.line,
+            column: diff.column,
+            expected_fragment: diff.expected_fragment,
+            actual_fragment: diff.actual_fragment,
+        }
+    }
+}
+
+impl From<TestCaseResult> for v1::TestCaseResult {
+    fn from(result: TestCaseResult) -> Self {
+        let (status, runtime_error) = status_to_proto(result.status);
+        Self {
+            id: result.id,
+            status,
+            runtime_error,
+            time_used: result.time_used,
+            memory_used: result.memory_used,
+            input: result.input,
+            expected_output: result.expected_output,
+            actual_output: result.actual_output,
+            error_info: result.error_info.map(Into::into),
+            is_hidden: result.is_hidden,
+            diff: result.diff.map(Into::into),
+        }
+    }
+}
+
+impl From<v1::TestCaseResult> for TestCaseResult {
+    fn from(result: v1::TestCaseResult) -> Self {
+        Self {
+            id: result.id,
+            status: status_from_proto(result.status, result.runtime_error),
+            time_used: result.time_used,
+            memory_used: result.memory_used,
+            input: result.input,
+            expected_output: result.expected_output,
+            actual_output: result.actual_output,
+            error_info: result.error_info.map(Into::into),
+            is_hidden: result.is_hidden,
+            diff: result.diff.map(Into::into),
+        }
+    }
+}
+
+impl From<JudgeResult> for v1::JudgeResult {
+    fn from(result: JudgeResult) -> Self {
+        let (status, runtime_error) = status_to_proto(result.status);
+        Self {
+            schema_version: result.schema_version,
+            attempt: result.attempt,
+            status,
+            runtime_error,
+            time_used: result.time_used,
+            memory_used: result.memory_used,
+            error_info: result.error_info.map(Into::into),
+            test_cases: result.test_cases.into_iter().map(Into::into).collect(),
+            submission_id: uuid_to_proto(result.submission_id),
+            problem_id: uuid_to_proto(result.problem_id),
+            user_id: uuid_to_proto(result.user_id),
+            judged_at: Some(timestamp_to_proto(result.judged_at)),
+            score: result.score,
+            batch: result.batch.map(Into::into),
+        }
+    }
+}
+
+impl TryFrom<v1::JudgeResult> for JudgeResult {
+    type Error = ProtoError;
+
+    fn try_from(result: v1::JudgeResult) -> Result<Self, Self::Error> {
+        Ok(Self {
+            schema_version: result.schema_version,
+            attempt: result.attempt,
+            status: status_from_proto(result.status, result.runtime_error),
+            time_used: result.time_used,
+            memory_used: result.memory_used,
+            error_info: result.error_info.map(Into::into),
+            test_cases: result.test_cases.into_iter().map(Into::into).collect(),
+            submission_id: uuid_from_proto(&result.submission_id, "JudgeResult.submission_id")?,
+            problem_id: uuid_from_proto(&result.problem_id, "JudgeResult.problem_id")?,
+            user_id: uuid_from_proto(&result.user_id, "JudgeResult.user_id")?,
+            judged_at: timestamp_from_proto(result.judged_at, "JudgeResult.judged_at")?,
+            score: result.score,
+            batch: result.batch.map(TryInto::try_into).transpose()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JudgeMode;
+    use crate::testing;
+    use chrono::TimeZone;
+    use prost::Message;
+    use std::collections::HashMap;
+
+    /// Converts to protobuf, through the wire and back
+    fn round_trip<T, P>(value: T) -> T
+    where
+        T: Clone + TryFrom<P>,
+        <T as TryFrom<P>>::Error: fmt::Debug,
+        P: From<T> + Message + Default,
+    {
+        let bytes = P::from(value).encode_to_vec();
+        T::try_from(P::decode(bytes.as_slice()).unwrap()).unwrap()
+    }
+
+    fn submission() -> Submission {
+        let mut submission =
+            testing::submission(ProgrammingLanguage::Custom, "let () = print_endline \"hi\"")
+                .with_custom_language(CustomLanguage {
+                    name: "OCaml".to_string(),
+                    file_extension: "ml".to_string(),
+                    needs_compilation: true,
+                    compile_argv: vec![
+                        "ocamlfind".to_string(),
+                        "{source}".to_string(),
+                        "-o".to_string(),
+                        "{artifact}".to_string(),
+                    ],
+                    run_argv: vec!["{artifact}".to_string()],
+                });
+        // Sub-microsecond precision survives
+        submission.created_at = Utc.timestamp_opt(1_700_000_000, 123_456_789).unwrap();
+        submission.priority = -3;
+        submission.contest_id = Some(Uuid::new_v4());
+        submission.rejudge_of = Some(Uuid::new_v4());
+        submission.attempt = 4;
+        submission.output_limit = Some(0);
+        submission.stack_limit = Some(65536);
+        submission
+    }
+
+    fn test_case() -> TestCase {
+        TestCase {
+            id: "7".to_string(),
+            input: TestData::Inline("1 2\n".to_string()),
+            expected_output: TestData::File(PathBuf::from("data/7.out")),
+            time_limit: Some(500),
+            memory_limit: Some(0),
+            is_hidden: true,
+            weight: 2.5,
+            alternative_outputs: vec![TestData::Remote {
+                url: "https://example.com/7.alt".to_string(),
+                sha256: "ab".repeat(32),
+                size: 42,
+            }],
+            comparison: Some(ComparisonMode::FloatEpsilon {
+                abs: 1e-6,
+                rel: 1e-9,
+            }),
+            input_sha256: Some("cd".repeat(32)),
+            output_sha256: None,
+            source: TestCaseSource::Generated(GeneratedTestCase {
+                generator_args: vec!["--n".to_string(), "100".to_string()],
+                seed: u64::MAX,
+            }),
+            is_sample: true,
+            env: Some(vec![("OMP_NUM_THREADS".to_string(), "2".to_string())]),
+        }
+    }
+
+    fn task() -> JudgeTask {
+        let mut task = JudgeTask::new(
+            submission(),
+            vec![test_case(), TestCase::new("8".to_string(), "", "")],
+        );
+        task.needs_compilation = true;
+        task.compile_flags = Some(Vec::new());
+        task.runtime_args = Some(vec!["--fast".to_string()]);
+        task.env = vec![("LANG".to_string(), "C".to_string())];
+        task.limit_policy = Some(LimitPolicy::unscaled());
+        task.build_profile = BuildProfile::Sanitize;
+        task.precompile = true;
+        task.skip_screening = true;
+        task.stop_on_first_failure = Some(true);
+        task.test_order = TestOrder::FailFirst;
+        task.execution_mode = ExecutionMode::Interactive(InteractorSpec {
+            language: ProgrammingLanguage::Cpp17,
+            program: InteractorProgram::Binary("interactor".to_string()),
+            test_args: HashMap::from([("7".to_string(), vec!["--strict".to_string()])]),
+        });
+        task.checker = Checker::Custom {
+            language: ProgrammingLanguage::Cpp20,
+            source: "#include \"testlib.h\"".to_string(),
+        };
+        task.default_comparison = Some(ComparisonMode::Exact);
+        task.compile_time_limit = Some(30000);
+        task.compile_memory_limit = None;
+        task.subtasks = vec![Subtask {
+            id: "all".to_string(),
+            score: 100.0,
+            test_case_ids: vec!["7".to_string(), "8".to_string()],
+            depends_on: vec!["samples".to_string()],
+        }];
+        task.judge_mode = JudgeMode::OiPartial;
+        task.batch = Some(BatchInfo {
+            index: 1,
+            total: 3,
+            parent_task_id: task.submission.id,
+            score_share: 0.25,
+            judge_mode: JudgeMode::OiPartial,
+        });
+        task.generator = Some(ProgramSpec {
+            language: ProgrammingLanguage::Python3,
+            source: "print(1)".to_string(),
+        });
+        task.reference_solution = Some(ProgramSpec {
+            language: ProgrammingLanguage::Rust,
+            source: "fn main() {}".to_string(),
+        });
+        task
+    }
+
+    fn error_info() -> ErrorInfo {
+        ErrorInfo {
+            message: "killed by SIGSEGV (11)".to_string(),
+            code: Some("E0308".to_string()),
+            line: Some(0),
+            column: Some(12),
+            stderr: Some(String::new()),
+            stdout: Some("partial".to_string()),
+            exit_code: Some(-1),
+            signal: Some(11),
+            hint: Some("Check array bounds".to_string()),
+        }
+    }
+
+    fn test_case_result(status: JudgeStatus) -> TestCaseResult {
+        TestCaseResult {
+            id: "7".to_string(),
+            status,
+            time_used: 312,
+            memory_used: 43008,
+            input: Some("1 2\n".to_string()),
+            expected_output: Some("3\n".to_string()),
+            actual_output: Some(String::new()),
+            error_info: Some(error_info()),
+            is_hidden: true,
+            diff: Some(OutputDiff {
+                line: 1,
+                column: 1,
+                expected_fragment: "3".to_string(),
+                actual_fragment: String::new(),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_submission_round_trip() {
+        let submission = submission();
+        assert_eq!(
+            round_trip::<Submission, v1::Submission>(submission.clone()),
+            submission
+        );
+        for language in ProgrammingLanguage::ALL {
+            let mut submission = submission.clone();
+            submission.language = language;
+            submission.custom_language = None;
+            submission.contest_id = None;
+            submission.output_limit = None;
+            assert_eq!(
+                round_trip::<Submission, v1::Submission>(submission.clone()),
+                submission
+            );
+        }
+    }
+
+    #[test]
+    fn test_test_case_round_trip() {
+        let test_case = test_case();
+        assert_eq!(
+            round_trip::<TestCase, v1::TestCase>(test_case.clone()),
+            test_case
+        );
+        let mut plain = TestCase::new("1".to_string(), "", "\n");
+        assert_eq!(round_trip::<TestCase, v1::TestCase>(plain.clone()), plain);
+        // An empty environment stays apart from none
+        plain.env = Some(Vec::new());
+        assert_eq!(round_trip::<TestCase, v1::TestCase>(plain.clone()), plain);
+    }
+
+    #[test]
+    fn test_judge_task_round_trip() {
+        let task = task();
+        assert_eq!(round_trip::<JudgeTask, v1::JudgeTask>(task.clone()), task);
+
+        let modes = [
+            ExecutionMode::Batch,
+            ExecutionMode::SingleRunMultiCase(MultiCaseSpec {
+                count_header: false,
+                delimiter: Some("---".to_string()),
+            }),
+            ExecutionMode::Interactive(InteractorSpec {
+                language: ProgrammingLanguage::C,
+                program: InteractorProgram::Source("int main() {}".to_string()),
+                test_args: HashMap::new(),
+            }),
+        ];
+        let checkers = [
+            Checker::DefaultDiff,
+            Checker::TokenCompare,
+            Checker::FloatCompare { epsilon: 1e-4 },
+        ];
+        for (execution_mode, checker) in modes.into_iter().zip(checkers) {
+            let mut task = task.clone();
+            task.execution_mode = execution_mode;
+            task.checker = checker;
+            task.default_comparison = Some(ComparisonMode::TokenEquality);
+            task.compile_flags = None;
+            task.limit_policy = Some(LimitPolicy::default());
+            task.batch = None;
+            assert_eq!(round_trip::<JudgeTask, v1::JudgeTask>(task.clone()), task);
+        }
+    }
+
+    #[test]
+    fn test_judge_result_round_trip() {
+        let submission = submission();
+        let mut statuses: Vec<JudgeStatus> = JudgeStatus::ALL.to_vec();
+        statuses.push(JudgeStatus::RuntimeError(RuntimeErrorType::StackOverflow));
+        for status in statuses {
+            let case = test_case_result(status);
+            assert_eq!(
+                round_trip::<TestCaseResult, v1::TestCaseResult>(case.clone()),
+                case
+            );
+
+            let mut result = JudgeResult::from_test_cases(
+                &submission,
+                vec![case, test_case_result(JudgeStatus::Accepted)],
+                &[1.0, 3.0],
+                JudgeMode::OiPartial,
+            )
+            .with_attempt(2);
+            result.judged_at = Utc.timestamp_opt(1_700_000_123, 999_999_999).unwrap();
+            result.batch = task().batch;
+            assert_eq!(
+                round_trip::<JudgeResult, v1::JudgeResult>(result.clone()),
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn test_error_info_round_trip() {
+        let error = error_info();
+        assert_eq!(round_trip::<ErrorInfo, v1::ErrorInfo>(error.clone()), error);
+        let bare = ErrorInfo::new(String::new());
+        assert_eq!(round_trip::<ErrorInfo, v1::ErrorInfo>(bare.clone()), bare);
+    }
+
+    #[test]
+    fn test_enum_round_trip() {
+        for language in ProgrammingLanguage::ALL
+            .into_iter()
+            .chain([ProgrammingLanguage::Custom])
+        {
+            assert_eq!(language_from_proto(language_to_proto(language)), language);
+        }
+        for error_type in [
+            RuntimeErrorType::SegmentationFault,
+            RuntimeErrorType::FloatingPointException,
+            RuntimeErrorType::DivisionByZero,
+            RuntimeErrorType::AssertionFailed,
+            RuntimeErrorType::StackOverflow,
+            RuntimeErrorType::NullPointerDereference,
+            RuntimeErrorType::FileOperationError,
+            RuntimeErrorType::PermissionDenied,
+            RuntimeErrorType::Other,
+        ] {
+            let status = JudgeStatus::RuntimeError(error_type);
+            let (code, runtime_error) = status_to_proto(status);
+            assert_eq!(status_from_proto(code, runtime_error), status);
+        }
+        for status in JudgeStatus::ALL {
+            let (code, runtime_error) = status_to_proto(status);
+            assert_eq!(status_from_proto(code, runtime_error), status);
+        }
+        for mode in [JudgeMode::AcmIcpc, JudgeMode::OiPartial] {
+            assert_eq!(judge_mode_from_proto(judge_mode_to_proto(mode)), mode);
+        }
+        for profile in [BuildProfile::Release, BuildProfile::Sanitize] {
+            assert_eq!(
+                build_profile_from_proto(build_profile_to_proto(profile)),
+                profile
+            );
+        }
+        for order in [TestOrder::Declared, TestOrder::FailFirst] {
+            assert_eq!(test_order_from_proto(test_order_to_proto(order)), order);
+        }
+    }
+
+    #[test]
+    fn test_unknown_enum_values_fall_back() {
+        let mut proto = v1::TestCaseResult::from(test_case_result(JudgeStatus::Accepted));
+        proto.status = 99;
+        assert_eq!(
+            TestCaseResult::from(proto.clone()).status,
+            JudgeStatus::SystemError
+        );
+        proto.status = v1::JudgeStatus::RuntimeError as i32;
+        proto.runtime_error = 99;
+        assert_eq!(
+            TestCaseResult::from(proto.clone()).status,
+            JudgeStatus::RuntimeError(RuntimeErrorType::Other)
+        );
+        // A peer that doesn't set the kind
+        proto.runtime_error = 0;
+        assert_eq!(
+            TestCaseResult::from(proto).status,
+            JudgeStatus::RuntimeError(RuntimeErrorType::Other)
+        );
+
+        let mut proto = v1::JudgeTask::from(task());
+        proto.submission.as_mut().unwrap().language = 99;
+        proto.build_profile = 99;
+        proto.test_order = 99;
+        proto.judge_mode = 99;
+        proto.limit_policy.as_mut().unwrap().overrides[0].language = 99;
+        let task = JudgeTask::try_from(proto).unwrap();
+        assert_eq!(task.submission.language, ProgrammingLanguage::Custom);
+        assert_eq!(task.build_profile, BuildProfile::Release);
+        assert_eq!(task.test_order, TestOrder::Declared);
+        assert_eq!(task.judge_mode, JudgeMode::AcmIcpc);
+        // The entry for the unknown language is dropped
+        assert_eq!(
+            task.limit_policy.unwrap().overrides.len(),
+            ProgrammingLanguage::ALL.len() - 1
+        );
+    }
+
+    #[test]
+    fn test_invalid_messages() {
+        let mut proto = v1::JudgeTask::from(task());
+        proto.submission.as_mut().unwrap().user_id.pop();
+        assert_eq!(
+            JudgeTask::try_from(proto).unwrap_err(),
+            ProtoError::InvalidUuid("Submission.user_id")
+        );
+
+        let mut proto = v1::JudgeTask::from(task());
+        proto.submission.as_mut().unwrap().created_at = None;
+        assert_eq!(
+            JudgeTask::try_from(proto).unwrap_err(),
+            ProtoError::MissingField("Submission.created_at")
+        );
+
+        let mut proto = v1::JudgeTask::from(task());
+        proto.test_cases[0].input = Some(v1::TestData { data: None });
+        assert_eq!(
+            JudgeTask::try_from(proto).unwrap_err(),
+            ProtoError::UnknownVariant("TestData.data")
+        );
+
+        // Missing messages with a default decode like missing JSON fields
+        let mut proto = v1::JudgeTask::from(task());
+        proto.checker = None;
+        proto.execution_mode = None;
+        let task = JudgeTask::try_from(proto).unwrap();
+        assert_eq!(task.checker, Checker::DefaultDiff);
+        assert_eq!(task.execution_mode, ExecutionMode::Batch);
+
+        let mut proto = v1::JudgeResult::from(JudgeResult::accepted(
+            1,
+            1,
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        ));
+        proto.judged_at = Some(prost_types::Timestamp {
+            seconds: 0,
+            nanos: -1,
+        });
+        assert_eq!(
+            JudgeResult::try_from(proto).unwrap_err(),
+            ProtoError::InvalidTimestamp("JudgeResult.judged_at")
+        );
+    }
+}
@@ -0,0 +1,106 @@
+//! Dequeue ordering for submissions waiting to be judged
+//!
+//! Submissions with a higher `priority` are judged first; contest submissions are
+//! created with priority 10 and regular ones with 0, so contest traffic overtakes
+//! practice traffic. Equal priorities are served first-come first-served by
+//! `created_at`, and the `id` breaks any remaining tie so the order is deterministic.
+
+use crate::Submission;
+use chrono::{DateTime, Utc};
+use std::cmp::{Ordering, Reverse};
+use uuid::Uuid;
+
+/// Sort key of a submission; sorting ascending yields the dequeue order
+pub type QueueKey = (Reverse<i32>, DateTime<Utc>, Uuid);
+
+impl Submission {
+    /// Returns the key that orders this submission in the judge queue
+    pub fn queue_key(&self) -> QueueKey {
+        (Reverse(self.priority), self.created_at, self.id)
+    }
+}
+
+/// Submission wrapper whose ordering makes `BinaryHeap` pop the next one to judge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueuedSubmission(pub Submission);
+
+impl Ord for QueuedSubmission {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // BinaryHeap is a max-heap, so the submission to judge first must compare greatest
+        other.0.queue_key().cmp(&self.0.queue_key())
+    }
+}
+
+impl PartialOrd for QueuedSubmission {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProgrammingLanguage;
+    use crate::testing;
+    use chrono::TimeZone;
+    use std::collections::BinaryHeap;
+
+    fn submission(priority: i32, second: u32, id: u128) -> Submission {
+        let mut submission = testing::submission(ProgrammingLanguage::C, "int main() {}");
+        submission.id = Uuid::from_u128(id);
+        submission.priority = priority;
+        submission.created_at = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, second).unwrap();
+        submission
+    }
+
+    fn drain(heap: BinaryHeap<QueuedSubmission>) -> Vec<u128> {
+        heap.into_sorted_vec()
+            .into_iter()
+            .rev()
+            .map(|queued| queued.0.id.as_u128())
+            .collect()
+    }
+
+    #[test]
+    fn test_dequeue_order() {
+        let heap: BinaryHeap<_> = [
+            submission(0, 1, 1),
+            submission(10, 5, 2),
+            submission(0, 0, 3),
+            submission(10, 2, 4),
+        ]
+        .into_iter()
+        .map(QueuedSubmission)
+        .collect();
+
+        // Contest priority first, then oldest first
+        assert_eq!(drain(heap), vec![4, 2, 3, 1]);
+    }
+
+    #[test]
+    fn test_equal_timestamps_are_deterministic() {
+        let submissions = [
+            submission(0, 0, 7),
+            submission(0, 0, 3),
+            submission(0, 0, 5),
+        ];
+        for rotation in 0..submissions.len() {
+            let mut rotated = submissions.to_vec();
+            rotated.rotate_left(rotation);
+            let heap: BinaryHeap<_> = rotated.into_iter().map(QueuedSubmission).collect();
+            assert_eq!(drain(heap), vec![3, 5, 7]);
+        }
+    }
+
+    #[test]
+    fn test_queue_key_sorts_in_dequeue_order() {
+        let mut submissions = [
+            submission(0, 0, 1),
+            submission(5, 9, 2),
+            submission(0, 0, 0),
+        ];
+        submissions.sort_by_key(Submission::queue_key);
+        let ids: Vec<u128> = submissions.iter().map(|s| s.id.as_u128()).collect();
+        assert_eq!(ids, vec![2, 0, 1]);
+    }
+}
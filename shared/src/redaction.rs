@@ -0,0 +1,250 @@
+//! User-facing views of judge results
+//!
+//! A [`JudgeResult`] straight from the judger carries the input, expected output and
+//! actual output of every test case, including hidden ones. [`JudgeResult::redacted`]
+//! produces the copy that may be sent to the contestant: hidden test data never
+//! survives, and the policy decides how much of the rest is shown.
+
+use crate::preview::truncate_preview;
+use crate::{ErrorInfo, JudgeResult, TestCaseResult};
+use serde::{Deserialize, Serialize};
+
+/// Message shown instead of process output that was used as an error message
+pub const HIDDEN_OUTPUT_MESSAGE: &str = "Output of hidden test cases is not shown";
+
+/// What a redacted result keeps besides statuses, times and scores
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedactionPolicy {
+    /// Maximum bytes kept of each input, expected output and actual output of
+    /// non-hidden test cases (see [`truncate_preview`]); `None` keeps them whole
+    pub max_io_bytes: Option<usize>,
+    /// Whether `stderr` and `stdout` of [`ErrorInfo`] are kept (never for hidden
+    /// test cases)
+    pub include_process_output: bool,
+}
+
+impl Default for RedactionPolicy {
+    fn default() -> Self {
+        Self {
+            max_io_bytes: Some(1024),
+            include_process_output: true,
+        }
+    }
+}
+
+impl JudgeResult {
+    /// Returns a copy safe to show to the submitter
+    ///
+    /// Hidden test cases lose their input, expected output, actual output, output
+    /// diff and process output regardless of the policy, including an error message that
+    /// repeats the process output. The overall error info loses its
+    /// process output when it was copied from a hidden test case (see
+    /// [`JudgeResult::from_test_cases`]). Statuses, times, memory and scores are
+    /// never changed.
+    pub fn redacted(&self, policy: RedactionPolicy) -> JudgeResult {
+        let from_hidden_case = self.error_info.is_some()
+            && self
+                .test_cases
+                .iter()
+                .any(|tc| tc.is_hidden && tc.error_info == self.error_info);
+        let error_info = self
+            .error_info
+            .as_ref()
+            .map(|error| redact_error(error, &policy, from_hidden_case));
+        JudgeResult {
+            error_info,
+            test_cases: self
+                .test_cases
+                .iter()
+                .map(|tc| tc.redacted(&policy))
+                .collect(),
+            ..self.clone()
+        }
+    }
+}
+
+impl TestCaseResult {
+    /// Returns a copy safe to show to the submitter, see [`JudgeResult::redacted`]
+    pub fn redacted(&self, policy: &RedactionPolicy) -> TestCaseResult {
+        let io = |text: &Option<String>| {
+            if self.is_hidden {
+                return None;
+            }
+            match policy.max_io_bytes {
+                Some(max) => text.as_deref().map(|text| truncate_preview(text, max)),
+                None => text.clone(),
+            }
+        };
+        TestCaseResult {
+            input: io(&self.input),
+            expected_output: io(&self.expected_output),
+            actual_output: io(&self.actual_output),
+            diff: self.diff.clone().filter(|_| !self.is_hidden),
+            error_info: self
+                .error_info
+                .as_ref()
+                .map(|error| redact_error(error, policy, self.is_hidden)),
+            ..self.clone()
+        }
+    }
+}
+
+fn redact_error(error: &ErrorInfo, policy: &RedactionPolicy, hidden: bool) -> ErrorInfo {
+    let output = |text: &Option<String>| {
+        if hidden || !policy.include_process_output {
+            return None;
+        }
+        match policy.max_io_bytes {
+            Some(max) => text.as_deref().map(|text| truncate_preview(text, max)),
+            None => text.clone(),
+        }
+    };
+    // `ErrorInfo::from_stderr` copies the process output into the message
+    let message_is_output = [&error.stderr, &error.stdout]
+        .into_iter()
+        .any(|output| output.as_deref() == Some(error.message.as_str()));
+    let message = if hidden && message_is_output {
+        HIDDEN_OUTPUT_MESSAGE.to_string()
+    } else {
+        error.message.clone()
+    };
+    ErrorInfo {
+        message,
+        stderr: output(&error.stderr),
+        stdout: output(&error.stdout),
+        ..error.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        JudgeMode, JudgeStatus, OutputDiff, ProgrammingLanguage, RuntimeErrorType, Submission,
+    };
+    use uuid::Uuid;
+
+    const SECRET: &str = "hidden-secret-data";
+
+    fn case(id: &str, status: JudgeStatus, hidden: bool) -> TestCaseResult {
+        let mut error = ErrorInfo::new("killed by SIGSEGV (11)".to_string());
+        error.stderr = Some(format!("{} stderr", SECRET));
+        error.stdout = Some(format!("{} stdout", SECRET));
+        TestCaseResult {
+            id: id.to_string(),
+            status,
+            time_used: 10,
+            memory_used: 1024,
+            input: Some(format!("{} input", SECRET)),
+            expected_output: Some(format!("{} expected", SECRET)),
+            actual_output: Some(format!("{} actual", SECRET)),
+            error_info: Some(error),
+            is_hidden: hidden,
+            diff: Some(OutputDiff {
+                line: 1,
+                column: 1,
+                expected_fragment: format!("{} expected", SECRET),
+                actual_fragment: format!("{} actual", SECRET),
+            }),
+        }
+    }
+
+    fn result() -> JudgeResult {
+        let submission = Submission::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            ProgrammingLanguage::Cpp17,
+            "int main() {}".to_string(),
+            1000,
+            262144,
+        );
+        JudgeResult::from_test_cases(
+            &submission,
+            vec![
+                case("1", JudgeStatus::Accepted, false),
+                case("2", JudgeStatus::WrongAnswer, true),
+                case("3", JudgeStatus::Accepted, true),
+            ],
+            &[1.0, 1.0, 1.0],
+            JudgeMode::OiPartial,
+        )
+    }
+
+    #[test]
+    fn test_hidden_io_never_survives() {
+        let result = result();
+        let policies = [
+            RedactionPolicy::default(),
+            RedactionPolicy {
+                max_io_bytes: None,
+                include_process_output: true,
+            },
+            RedactionPolicy {
+                max_io_bytes: Some(0),
+                include_process_output: false,
+            },
+        ];
+        for policy in policies {
+            let redacted = result.redacted(policy.clone());
+            for tc in redacted.test_cases.iter().filter(|tc| tc.is_hidden) {
+                let json = serde_json::to_string(tc).unwrap();
+                assert!(!json.contains(SECRET), "{:?}: {}", policy, json);
+            }
+            // The overall error info was copied from hidden case 2
+            let json = serde_json::to_string(&redacted.error_info).unwrap();
+            assert!(!json.contains(SECRET), "{:?}: {}", policy, json);
+        }
+
+        let mut hidden = case(
+            "4",
+            JudgeStatus::RuntimeError(RuntimeErrorType::Other),
+            true,
+        );
+        hidden.error_info = Some(ErrorInfo::from_stderr(SECRET.to_string()));
+        let error = hidden
+            .redacted(&RedactionPolicy::default())
+            .error_info
+            .unwrap();
+        assert_eq!(error.message, HIDDEN_OUTPUT_MESSAGE);
+        assert_eq!(error.stderr, None);
+    }
+
+    #[test]
+    fn test_visible_io_follows_policy() {
+        let result = result();
+        let redacted = result.redacted(RedactionPolicy {
+            max_io_bytes: Some(6),
+            include_process_output: false,
+        });
+        let visible = &redacted.test_cases[0];
+        assert_eq!(visible.input.as_deref(), Some("hidden… (+18 bytes)"));
+        assert_eq!(visible.error_info.as_ref().unwrap().stderr, None);
+
+        let redacted = result.redacted(RedactionPolicy {
+            max_io_bytes: None,
+            include_process_output: true,
+        });
+        assert_eq!(redacted.test_cases[0], result.test_cases[0]);
+    }
+
+    #[test]
+    fn test_verdict_is_unchanged() {
+        let result = result();
+        let redacted = result.redacted(RedactionPolicy::default());
+        assert_eq!(redacted.status, result.status);
+        assert_eq!(redacted.score, result.score);
+        assert_eq!(redacted.time_used, result.time_used);
+        assert_eq!(redacted.memory_used, result.memory_used);
+        let statuses = |r: &JudgeResult| -> Vec<_> {
+            r.test_cases
+                .iter()
+                .map(|tc| (tc.id.clone(), tc.status, tc.time_used, tc.memory_used))
+                .collect()
+        };
+        assert_eq!(statuses(&redacted), statuses(&result));
+        assert_eq!(
+            redacted.error_info.as_ref().unwrap().message,
+            result.error_info.as_ref().unwrap().message
+        );
+    }
+}
@@ -0,0 +1,181 @@
+//! Reasons a submission is refused before it reaches the judge queue
+//!
+//! The backend answers a refused submission with a [`RejectionReason`] instead of a
+//! free-form message, and keeps a [`RejectedSubmission`] record of the attempt so
+//! organizers can spot suspicious patterns (for example a user hammering the rate
+//! limit during a contest). Rejected submissions are never judged.
+
+use crate::{ProgrammingLanguage, Submission, ValidationError};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use uuid::Uuid;
+
+/// Why a submission was refused
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum RejectionReason {
+    /// The user submitted too often; they may retry after the given number of seconds
+    RateLimited { retry_after_secs: u64 },
+    /// The language is not allowed for this problem or contest
+    LanguageNotAllowed { language: ProgrammingLanguage },
+    /// The user used up their submission quota
+    QuotaExceeded { used: u32, limit: u32 },
+    /// The submission violates the deployment policy
+    Invalid { errors: Vec<ValidationError> },
+}
+
+impl RejectionReason {
+    /// Returns the machine-readable code, matching the serialized `code` tag
+    pub fn code(&self) -> &'static str {
+        match self {
+            RejectionReason::RateLimited { .. } => "rate_limited",
+            RejectionReason::LanguageNotAllowed { .. } => "language_not_allowed",
+            RejectionReason::QuotaExceeded { .. } => "quota_exceeded",
+            RejectionReason::Invalid { .. } => "invalid",
+        }
+    }
+
+    /// Whether the same submission may succeed if sent again later
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, RejectionReason::RateLimited { .. })
+    }
+}
+
+impl fmt::Display for RejectionReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RejectionReason::RateLimited { retry_after_secs } => write!(
+                f,
+                "too many submissions, retry in {} seconds",
+                retry_after_secs
+            ),
+            RejectionReason::LanguageNotAllowed { language } => {
+                write!(f, "{} is not allowed here", language.as_str())
+            }
+            RejectionReason::QuotaExceeded { used, limit } => {
+                write!(f, "submission quota used up ({}/{})", used, limit)
+            }
+            RejectionReason::Invalid { errors } => {
+                let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+                write!(f, "invalid submission: {}", messages.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for RejectionReason {}
+
+impl From<Vec<ValidationError>> for RejectionReason {
+    fn from(errors: Vec<ValidationError>) -> Self {
+        RejectionReason::Invalid { errors }
+    }
+}
+
+/// A refused submission attempt, kept for organizers
+///
+/// The source code is not stored; its size is enough to tell probing from honest
+/// mistakes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RejectedSubmission {
+    /// Id the submission would have had
+    pub submission_id: Uuid,
+    /// Problem identifier
+    pub problem_id: Uuid,
+    /// User identifier who submitted the code
+    pub user_id: Uuid,
+    /// Contest identifier if this was a contest submission
+    pub contest_id: Option<Uuid>,
+    /// Programming language of the submission
+    pub language: ProgrammingLanguage,
+    /// Source code size in bytes
+    pub source_bytes: usize,
+    /// Why the submission was refused
+    pub reason: RejectionReason,
+    /// Time when the submission was refused
+    pub rejected_at: DateTime<Utc>,
+}
+
+impl Submission {
+    /// Records this submission as refused for `reason`
+    pub fn reject(&self, reason: RejectionReason) -> RejectedSubmission {
+        RejectedSubmission {
+            submission_id: self.id,
+            problem_id: self.problem_id,
+            user_id: self.user_id,
+            contest_id: self.contest_id,
+            language: self.language,
+            source_bytes: self.source_code.len(),
+            reason,
+            rejected_at: Utc::now(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SubmissionPolicy;
+    use crate::testing;
+
+    fn submission(source: &str) -> Submission {
+        testing::submission(ProgrammingLanguage::Python3, source)
+    }
+
+    #[test]
+    fn test_wire_format() {
+        let reason = RejectionReason::RateLimited {
+            retry_after_secs: 30,
+        };
+        let json = serde_json::to_value(&reason).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"code": "rate_limited", "retry_after_secs": 30})
+        );
+        assert_eq!(json["code"], reason.code());
+        assert!(reason.is_retryable());
+
+        let reason = RejectionReason::QuotaExceeded {
+            used: 50,
+            limit: 50,
+        };
+        assert_eq!(
+            serde_json::to_value(&reason).unwrap()["code"],
+            reason.code()
+        );
+        assert!(!reason.is_retryable());
+        assert_eq!(reason.to_string(), "submission quota used up (50/50)");
+    }
+
+    #[test]
+    fn test_from_validation_errors() {
+        let submission = submission(" ");
+        let errors = submission
+            .validate(&SubmissionPolicy::default())
+            .unwrap_err();
+        let reason = RejectionReason::from(errors);
+        assert_eq!(reason.code(), "invalid");
+        assert_eq!(
+            reason.to_string(),
+            "invalid submission: source code is empty"
+        );
+
+        let json = serde_json::to_string(&reason).unwrap();
+        assert_eq!(
+            serde_json::from_str::<RejectionReason>(&json).unwrap(),
+            reason
+        );
+    }
+
+    #[test]
+    fn test_reject_records_attempt() {
+        let submission = submission("print(1)\n");
+        let record = submission.reject(RejectionReason::LanguageNotAllowed {
+            language: ProgrammingLanguage::Python3,
+        });
+        assert_eq!(record.submission_id, submission.id);
+        assert_eq!(record.user_id, submission.user_id);
+        assert_eq!(record.source_bytes, 9);
+        assert_eq!(record.reason.code(), "language_not_allowed");
+    }
+}
@@ -0,0 +1,561 @@
+//! Judge mode and score aggregation
+//!
+//! ACM/ICPC problems are all-or-nothing and judging stops at the first failing test.
+//! OI problems run every test and award partial score: by subtask when the task has
+//! subtasks, otherwise by test case weight. Test cases that aren't run report
+//! [`JudgeStatus::Skipped`].
+
+use crate::testcases::natural_cmp;
+use crate::{JudgeResult, JudgeStatus, JudgeTask, Submission, TestCase, TestCaseResult};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Judging and scoring semantics of a task
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum JudgeMode {
+    /// Stop at the first failing test; score 100 only if every test passes
+    #[default]
+    AcmIcpc,
+    /// Run every test and award partial score
+    OiPartial,
+}
+
+impl JudgeMode {
+    /// Returns whether judging stops after the first failing test case
+    pub fn stops_on_first_failure(&self) -> bool {
+        matches!(self, JudgeMode::AcmIcpc)
+    }
+
+    /// Returns whether passing some of the tests earns part of the score
+    pub fn scores_partially(&self) -> bool {
+        matches!(self, JudgeMode::OiPartial)
+    }
+}
+
+/// Serde default for `stop_on_first_failure`: tasks serialized before the field
+/// existed ran every test, whatever their mode defaults to
+pub(crate) fn default_stop_on_first_failure() -> Option<bool> {
+    Some(false)
+}
+
+impl JudgeResult {
+    /// Computes the score (0.0 to 100.0) of these test case results under the task's mode
+    ///
+    /// Test cases without a result count as failed, so a run stopped early never
+    /// scores more than it proved.
+    pub fn score_for(&self, task: &JudgeTask) -> f64 {
+        let passed: HashSet<&str> = self
+            .test_cases
+            .iter()
+            .filter(|tc| tc.status.is_accepted())
+            .map(|tc| tc.id.as_str())
+            .collect();
+        let all_passed = task
+            .test_cases
+            .iter()
+            .all(|tc| passed.contains(tc.id.as_str()));
+
+        if !task.judge_mode.scores_partially() {
+            return if all_passed { 100.0 } else { 0.0 };
+        }
+        if !task.subtasks.is_empty() {
+            return subtask_score(task, &passed);
+        }
+
+        let total = task.total_weight();
+        if total <= 0.0 {
+            return if all_passed { 100.0 } else { 0.0 };
+        }
+        let earned: f64 = task
+            .test_cases
+            .iter()
+            .filter(|tc| passed.contains(tc.id.as_str()))
+            .map(|tc| tc.weight)
+            .sum();
+        earned / total * 100.0
+    }
+
+    /// Builds the overall result of a submission from its test case results
+    ///
+    /// The status is `Accepted` only if every test case is accepted; otherwise it is
+    /// the most severe test case status (see [`JudgeStatus::severity`]), ties going
+    /// to the test case whose id sorts first. Time and memory are the maxima over
+    /// the test cases. `weights` lines up with `results` (missing weights count as
+    /// 1.0); in [`JudgeMode::OiPartial`] the score is the accepted share of the total
+    /// weight, otherwise 100 or 0. The outcome doesn't depend on the order of
+    /// `results`.
+    pub fn from_test_cases(
+        submission: &Submission,
+        results: Vec<TestCaseResult>,
+        weights: &[f64],
+        mode: JudgeMode,
+    ) -> JudgeResult {
+        let worst = results
+            .iter()
+            .filter(|tc| !tc.status.is_accepted())
+            .min_by(|a, b| {
+                b.status
+                    .severity()
+                    .cmp(&a.status.severity())
+                    .then_with(|| natural_cmp(&a.id, &b.id))
+            });
+        let (status, error_info) = match worst {
+            Some(tc) => (tc.status, tc.error_info.clone()),
+            None => (JudgeStatus::Accepted, None),
+        };
+
+        let weight = |i: usize| weights.get(i).copied().unwrap_or(1.0);
+        let total: f64 = (0..results.len()).map(weight).sum();
+        let earned: f64 = results
+            .iter()
+            .enumerate()
+            .filter(|(_, tc)| tc.status.is_accepted())
+            .map(|(i, _)| weight(i))
+            .sum();
+        let score = if status.is_accepted() {
+            100.0
+        } else if mode.scores_partially() && total > 0.0 {
+            earned / total * 100.0
+        } else {
+            0.0
+        };
+
+        JudgeResult {
+            schema_version: crate::SCHEMA_VERSION,
+            attempt: submission.attempt,
+            status,
+            time_used: results.iter().map(|tc| tc.time_used).max().unwrap_or(0),
+            memory_used: results.iter().map(|tc| tc.memory_used).max().unwrap_or(0),
+            error_info,
+            test_cases: results,
+            submission_id: submission.id,
+            problem_id: submission.problem_id,
+            user_id: submission.user_id,
+            judged_at: Utc::now(),
+            score,
+            batch: None,
+        }
+    }
+
+    /// Returns the submission-level verdict under the task's mode
+    ///
+    /// An OI task with subtasks that scores strictly between 0 and 100 is
+    /// [`JudgeStatus::PartialAccepted`]. Otherwise, and always for system errors and
+    /// cancellations, this is `status`.
+    pub fn verdict_for(&self, task: &JudgeTask) -> JudgeStatus {
+        let partial = self.status.is_final()
+            && !matches!(
+                self.status,
+                JudgeStatus::Accepted | JudgeStatus::SystemError | JudgeStatus::Cancelled
+            )
+            && task.judge_mode.scores_partially()
+            && !task.subtasks.is_empty();
+        if partial {
+            let score = self.score_for(task);
+            if score > 0.0 && score < 100.0 {
+                return JudgeStatus::PartialAccepted;
+            }
+        }
+        self.status
+    }
+
+    /// Adds a test case result and recomputes `score` under the task's mode
+    pub fn add_scored_test_case(&mut self, task: &JudgeTask, test_case: TestCaseResult) {
+        self.add_test_case(test_case);
+        self.score = self.score_for(task);
+    }
+}
+
+impl TestCaseResult {
+    /// Creates the result of a test case that was not run
+    ///
+    /// Carries no time, memory or I/O, so it doesn't count towards the maxima of
+    /// [`JudgeResult::from_test_cases`].
+    pub fn skipped(test_case: &TestCase) -> Self {
+        TestCaseResult {
+            id: test_case.id.clone(),
+            status: JudgeStatus::Skipped,
+            time_used: 0,
+            memory_used: 0,
+            input: None,
+            expected_output: None,
+            actual_output: None,
+            error_info: None,
+            is_hidden: !test_case.is_public(),
+            diff: None,
+        }
+    }
+}
+
+fn subtask_score(task: &JudgeTask, passed: &HashSet<&str>) -> f64 {
+    let total = task.subtask_score_total();
+    let Ok(order) = task.subtask_order() else {
+        return 0.0;
+    };
+    if total <= 0.0 {
+        return 0.0;
+    }
+
+    let mut passed_subtasks: HashSet<&str> = HashSet::new();
+    let mut earned = 0.0;
+    for subtask in order {
+        let ok = subtask
+            .test_case_ids
+            .iter()
+            .all(|id| passed.contains(id.as_str()))
+            && subtask
+                .depends_on
+                .iter()
+                .all(|dep| passed_subtasks.contains(dep.as_str()));
+        if ok {
+            passed_subtasks.insert(&subtask.id);
+            earned += subtask.score;
+        }
+    }
+    earned / total * 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use crate::{RuntimeErrorType, Subtask};
+    use uuid::Uuid;
+
+    fn task(mode: JudgeMode) -> JudgeTask {
+        let mut task = testing::task(testing::blank_test_cases(["1", "2", "3"]));
+        task.test_cases[2].weight = 2.0;
+        task.judge_mode = mode;
+        task
+    }
+
+    fn case(id: &str, status: JudgeStatus, time_used: u64, memory_used: u64) -> TestCaseResult {
+        TestCaseResult {
+            id: id.to_string(),
+            status,
+            time_used,
+            memory_used,
+            input: None,
+            expected_output: None,
+            actual_output: None,
+            error_info: None,
+            is_hidden: false,
+            diff: None,
+        }
+    }
+
+    fn result(statuses: &[(&str, JudgeStatus)]) -> JudgeResult {
+        let mut result =
+            JudgeResult::accepted(0, 0, Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        for (id, status) in statuses {
+            result.add_test_case(TestCaseResult {
+                id: id.to_string(),
+                status: *status,
+                time_used: 0,
+                memory_used: 0,
+                input: None,
+                expected_output: None,
+                actual_output: None,
+                error_info: None,
+                is_hidden: false,
+                diff: None,
+            });
+        }
+        result
+    }
+
+    #[test]
+    fn test_mode_helpers() {
+        assert_eq!(JudgeMode::default(), JudgeMode::AcmIcpc);
+        assert!(JudgeMode::AcmIcpc.stops_on_first_failure());
+        assert!(!JudgeMode::AcmIcpc.scores_partially());
+        assert!(!JudgeMode::OiPartial.stops_on_first_failure());
+        assert!(JudgeMode::OiPartial.scores_partially());
+    }
+
+    #[test]
+    fn test_acm_is_all_or_nothing() {
+        let task = task(JudgeMode::AcmIcpc);
+        let all = result(&[
+            ("1", JudgeStatus::Accepted),
+            ("2", JudgeStatus::Accepted),
+            ("3", JudgeStatus::Accepted),
+        ]);
+        assert_eq!(all.score_for(&task), 100.0);
+
+        let stopped = result(&[
+            ("1", JudgeStatus::Accepted),
+            ("2", JudgeStatus::WrongAnswer),
+        ]);
+        assert_eq!(stopped.score_for(&task), 0.0);
+    }
+
+    #[test]
+    fn test_oi_is_weighted() {
+        let task = task(JudgeMode::OiPartial);
+        let partial = result(&[
+            ("1", JudgeStatus::Accepted),
+            ("2", JudgeStatus::WrongAnswer),
+            ("3", JudgeStatus::Accepted),
+        ]);
+        assert_eq!(partial.score_for(&task), 75.0);
+
+        let mut incremental = result(&[]);
+        incremental.add_scored_test_case(&task, partial.test_cases[0].clone());
+        assert_eq!(incremental.score, 25.0);
+    }
+
+    #[test]
+    fn test_oi_scores_by_subtask() {
+        let mut task = task(JudgeMode::OiPartial);
+        task.subtasks = vec![
+            Subtask {
+                id: "small".to_string(),
+                score: 40.0,
+                test_case_ids: vec!["1".to_string()],
+                depends_on: vec![],
+            },
+            Subtask {
+                id: "large".to_string(),
+                score: 60.0,
+                test_case_ids: vec!["2".to_string(), "3".to_string()],
+                depends_on: vec!["small".to_string()],
+            },
+        ];
+
+        let large_only = result(&[
+            ("1", JudgeStatus::WrongAnswer),
+            ("2", JudgeStatus::Accepted),
+            ("3", JudgeStatus::Accepted),
+        ]);
+        // "large" depends on the failed "small"
+        assert_eq!(large_only.score_for(&task), 0.0);
+
+        let small_only = result(&[
+            ("1", JudgeStatus::Accepted),
+            ("2", JudgeStatus::Accepted),
+            ("3", JudgeStatus::TimeLimitExceeded),
+        ]);
+        assert_eq!(small_only.score_for(&task), 40.0);
+    }
+
+    /// Deterministic Fisher-Yates shuffles driven by a small LCG
+    fn shuffles<T: Clone>(items: &[T], count: usize) -> Vec<Vec<T>> {
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        (0..count)
+            .map(|_| {
+                let mut items = items.to_vec();
+                for i in (1..items.len()).rev() {
+                    state = state
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    items.swap(i, (state >> 33) as usize % (i + 1));
+                }
+                items
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_from_test_cases() {
+        let submission = task(JudgeMode::OiPartial).submission;
+        let results = vec![
+            case("1", JudgeStatus::Accepted, 10, 1000),
+            case("2", JudgeStatus::WrongAnswer, 312, 500),
+            case("3", JudgeStatus::TimeLimitExceeded, 1000, 42000),
+            case("4", JudgeStatus::Accepted, 5, 100),
+        ];
+        let result = JudgeResult::from_test_cases(
+            &submission,
+            results,
+            &[1.0, 1.0, 1.0, 2.0],
+            JudgeMode::OiPartial,
+        );
+        assert_eq!(result.status, JudgeStatus::TimeLimitExceeded);
+        assert_eq!(result.time_used, 1000);
+        assert_eq!(result.memory_used, 42000);
+        assert_eq!(result.score, 60.0);
+        assert_eq!(result.submission_id, submission.id);
+        assert_eq!(result.total_test_cases(), 4);
+
+        let results = vec![
+            case("1", JudgeStatus::Accepted, 10, 1000),
+            case("2", JudgeStatus::WrongAnswer, 0, 0),
+        ];
+        let acm = JudgeResult::from_test_cases(&submission, results, &[], JudgeMode::AcmIcpc);
+        assert_eq!(acm.score, 0.0);
+
+        let accepted = JudgeResult::from_test_cases(
+            &submission,
+            vec![case("1", JudgeStatus::Accepted, 10, 1000)],
+            &[],
+            JudgeMode::AcmIcpc,
+        );
+        assert_eq!(accepted.status, JudgeStatus::Accepted);
+        assert_eq!(accepted.score, 100.0);
+
+        // A presentation error gives way to a real wrong answer, but fails the run
+        let results = vec![
+            case("1", JudgeStatus::PresentationError, 10, 1000),
+            case("2", JudgeStatus::WrongAnswer, 0, 0),
+        ];
+        let result = JudgeResult::from_test_cases(&submission, results, &[], JudgeMode::OiPartial);
+        assert_eq!(result.status, JudgeStatus::WrongAnswer);
+        let presentation = JudgeResult::from_test_cases(
+            &submission,
+            vec![
+                case("1", JudgeStatus::Accepted, 10, 1000),
+                case("2", JudgeStatus::PresentationError, 10, 1000),
+            ],
+            &[],
+            JudgeMode::OiPartial,
+        );
+        assert_eq!(presentation.status, JudgeStatus::PresentationError);
+        assert_eq!(presentation.score, 50.0);
+    }
+
+    #[test]
+    fn test_skipped_never_dominates() {
+        let mut task = task(JudgeMode::AcmIcpc);
+        task.test_cases[2].is_hidden = true;
+        let skipped = TestCaseResult::skipped(&task.test_cases[2]);
+        assert_eq!(skipped.status, JudgeStatus::Skipped);
+        assert_eq!((skipped.time_used, skipped.memory_used), (0, 0));
+        assert_eq!(skipped.input, None);
+        assert_eq!(skipped.actual_output, None);
+        assert!(skipped.is_hidden);
+
+        let results = vec![
+            case("1", JudgeStatus::Accepted, 10, 1000),
+            case("2", JudgeStatus::PresentationError, 20, 2000),
+            skipped,
+        ];
+        let result =
+            JudgeResult::from_test_cases(&task.submission, results, &[], JudgeMode::AcmIcpc);
+        assert_eq!(result.status, JudgeStatus::PresentationError);
+        assert_eq!(result.time_used, 20);
+        assert_eq!(result.passed_test_cases(), 1);
+        assert_eq!(result.score, 0.0);
+    }
+
+    #[test]
+    fn test_verdict_for_subtasks() {
+        let mut task = task(JudgeMode::OiPartial);
+        task.subtasks = vec![
+            Subtask {
+                id: "small".to_string(),
+                score: 40.0,
+                test_case_ids: vec!["1".to_string()],
+                depends_on: vec![],
+            },
+            Subtask {
+                id: "large".to_string(),
+                score: 60.0,
+                test_case_ids: vec!["2".to_string(), "3".to_string()],
+                depends_on: vec!["small".to_string()],
+            },
+        ];
+
+        let mut partial = result(&[
+            ("1", JudgeStatus::Accepted),
+            ("2", JudgeStatus::Accepted),
+            ("3", JudgeStatus::TimeLimitExceeded),
+        ]);
+        partial.status = JudgeStatus::TimeLimitExceeded;
+        assert_eq!(partial.verdict_for(&task), JudgeStatus::PartialAccepted);
+        partial.status = JudgeStatus::SystemError;
+        assert_eq!(partial.verdict_for(&task), JudgeStatus::SystemError);
+
+        let mut none = result(&[
+            ("1", JudgeStatus::WrongAnswer),
+            ("2", JudgeStatus::Skipped),
+            ("3", JudgeStatus::Skipped),
+        ]);
+        none.status = JudgeStatus::WrongAnswer;
+        assert_eq!(none.verdict_for(&task), JudgeStatus::WrongAnswer);
+
+        // Without subtasks the worst test case status stands
+        partial.status = JudgeStatus::TimeLimitExceeded;
+        task.subtasks.clear();
+        assert_eq!(partial.verdict_for(&task), JudgeStatus::TimeLimitExceeded);
+    }
+
+    #[test]
+    fn test_from_test_cases_is_order_independent() {
+        let submission = task(JudgeMode::OiPartial).submission;
+        let cases = vec![
+            (case("1", JudgeStatus::Accepted, 10, 100), 1.0),
+            (case("2", JudgeStatus::WrongAnswer, 20, 300), 2.0),
+            (
+                case(
+                    "3",
+                    JudgeStatus::RuntimeError(RuntimeErrorType::StackOverflow),
+                    30,
+                    200,
+                ),
+                1.5,
+            ),
+            (
+                case(
+                    "10",
+                    JudgeStatus::RuntimeError(RuntimeErrorType::SegmentationFault),
+                    5,
+                    50,
+                ),
+                1.0,
+            ),
+            (case("5", JudgeStatus::Accepted, 40, 10), 0.5),
+            (case("6", JudgeStatus::MemoryLimitExceeded, 1, 900), 1.0),
+        ];
+
+        for mode in [JudgeMode::AcmIcpc, JudgeMode::OiPartial] {
+            for shuffled in shuffles(&cases, 50) {
+                let (results, weights): (Vec<_>, Vec<_>) = shuffled.into_iter().unzip();
+                let result = JudgeResult::from_test_cases(&submission, results, &weights, mode);
+                // Both runtime errors are equally severe; test "3" sorts before "10"
+                assert_eq!(
+                    result.status,
+                    JudgeStatus::RuntimeError(RuntimeErrorType::StackOverflow)
+                );
+                assert_eq!(result.time_used, 40);
+                assert_eq!(result.memory_used, 900);
+                let expected_score = if mode == JudgeMode::OiPartial {
+                    1.5 / 7.0 * 100.0
+                } else {
+                    0.0
+                };
+                assert!((result.score - expected_score).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_judge_mode_defaults_for_old_payloads() {
+        let mut value = serde_json::to_value(task(JudgeMode::OiPartial)).unwrap();
+        value.as_object_mut().unwrap().remove("judge_mode");
+        value
+            .as_object_mut()
+            .unwrap()
+            .remove("stop_on_first_failure");
+        let deserialized: JudgeTask = serde_json::from_value(value).unwrap();
+        assert_eq!(deserialized.judge_mode, JudgeMode::AcmIcpc);
+        // Old tasks ran every test; the default mode must not change that
+        assert!(!deserialized.stops_on_first_failure());
+    }
+
+    #[test]
+    fn test_stop_on_first_failure_follows_mode() {
+        let mut acm = task(JudgeMode::AcmIcpc);
+        assert!(acm.stops_on_first_failure());
+        let json = serde_json::to_string(&acm).unwrap();
+        let deserialized: JudgeTask = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.stop_on_first_failure, None);
+        assert!(deserialized.stops_on_first_failure());
+
+        acm.judge_mode = JudgeMode::OiPartial;
+        assert!(!acm.stops_on_first_failure());
+        acm.stop_on_first_failure = Some(true);
+        assert!(acm.stops_on_first_failure());
+    }
+}
@@ -0,0 +1,531 @@
+//! Cheap static screening of submissions before they reach the sandbox
+//!
+//! Rules are data: each one has an id, names the languages it applies to, a substring
+//! or regex pattern, and a human-readable reason. Contest admins can replace the
+//! built-in set with a TOML file:
+//!
+//! ```toml
+//! [[rules]]
+//! id = "c-ptrace"
+//! languages = ["C", "Cpp17"]
+//! pattern = "sys/ptrace.h"
+//! reason = "ptrace is not allowed"
+//!
+//! [[rules]]
+//! id = "python-os-system"
+//! languages = ["Python3"]
+//! pattern = '\bos\.system\s*\('
+//! regex = true
+//! reason = "os.system is not allowed for this problem"
+//! warn_only = true
+//! ```
+//!
+//! Warn-only rules are reported but never reject a submission, which lets admins
+//! trial a new rule before enforcing it.
+//!
+//! Patterns are matched against the raw source text, line by line. Comments and
+//! string literals are **not** skipped, so a banned include mentioned in a comment
+//! is still reported; screening errs on the side of rejecting.
+
+use crate::{ErrorInfo, ProgrammingLanguage, Submission};
+use regex::Regex;
+use serde::Deserialize;
+use std::fmt;
+use std::path::Path;
+
+/// A screening rule as written in a rules file
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleSpec {
+    /// Identifier reported with violations, e.g. "c-ptrace"
+    pub id: String,
+    /// Languages the rule applies to; empty means every language
+    #[serde(default)]
+    pub languages: Vec<ProgrammingLanguage>,
+    /// Substring (or regex, see `regex`) that must not appear in the source
+    pub pattern: String,
+    /// Whether `pattern` is a regular expression
+    #[serde(default)]
+    pub regex: bool,
+    /// Reason shown to the user when the rule matches
+    pub reason: String,
+    /// Whether a match is only reported instead of rejecting the submission
+    #[serde(default)]
+    pub warn_only: bool,
+}
+
+/// A rule that matched a submission
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// Id of the matching rule
+    pub rule: String,
+    /// Reason of the matching rule
+    pub reason: String,
+    /// 1-based line of the match
+    pub line: u32,
+    /// Whether the matching rule is warn-only
+    pub warn_only: bool,
+}
+
+impl Violation {
+    /// Returns whether this violation rejects the submission
+    pub fn is_blocking(&self) -> bool {
+        !self.warn_only
+    }
+}
+
+/// Errors raised while loading screening rules
+#[derive(Debug)]
+pub enum ScreeningError {
+    /// The file could not be read
+    Io(std::io::Error),
+    /// The TOML document is malformed
+    Parse(String),
+    /// A regex rule does not compile
+    InvalidPattern { pattern: String, message: String },
+}
+
+impl fmt::Display for ScreeningError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScreeningError::Io(e) => write!(f, "failed to read screening rules: {}", e),
+            ScreeningError::Parse(e) => write!(f, "invalid screening rules: {}", e),
+            ScreeningError::InvalidPattern { pattern, message } => {
+                write!(f, "invalid screening pattern {:?}: {}", pattern, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ScreeningError {}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Substring(String),
+    Regex(Regex),
+}
+
+impl Matcher {
+    fn is_match(&self, line: &str) -> bool {
+        match self {
+            Matcher::Substring(s) => line.contains(s.as_str()),
+            Matcher::Regex(re) => re.is_match(line),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    id: String,
+    languages: Vec<ProgrammingLanguage>,
+    matcher: Matcher,
+    reason: String,
+    warn_only: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    #[serde(default)]
+    rules: Vec<RuleSpec>,
+}
+
+/// Compiled set of screening rules
+#[derive(Debug, Clone)]
+pub struct ScreeningRules {
+    rules: Vec<Rule>,
+}
+
+const C_FAMILY: &[ProgrammingLanguage] = &[
+    ProgrammingLanguage::C,
+    ProgrammingLanguage::Cpp,
+    ProgrammingLanguage::Cpp11,
+    ProgrammingLanguage::Cpp14,
+    ProgrammingLanguage::Cpp17,
+    ProgrammingLanguage::Cpp20,
+];
+
+const PYTHON: &[ProgrammingLanguage] =
+    &[ProgrammingLanguage::Python2, ProgrammingLanguage::Python3];
+
+/// Built-in rules: `(id, languages, pattern, is_regex, reason)`
+const BUILTIN_RULES: &[(&str, &[ProgrammingLanguage], &str, bool, &str)] = &[
+    (
+        "c-ptrace",
+        C_FAMILY,
+        "sys/ptrace.h",
+        false,
+        "ptrace is not allowed",
+    ),
+    (
+        "c-socket",
+        C_FAMILY,
+        "sys/socket.h",
+        false,
+        "Network access is not allowed",
+    ),
+    (
+        "c-spawn",
+        C_FAMILY,
+        r"\b(fork|vfork|system|popen|exec[lv]p?e?)\s*\(",
+        true,
+        "Spawning processes is not allowed",
+    ),
+    (
+        "python-os-spawn",
+        PYTHON,
+        r"\bos\.(system|popen|fork|exec\w*|spawn\w*)\s*\(",
+        true,
+        "Spawning processes is not allowed",
+    ),
+    (
+        "python-import",
+        PYTHON,
+        r"^\s*(import|from)\s+(subprocess|socket|ctypes)\b",
+        true,
+        "Importing this module is not allowed",
+    ),
+    (
+        "jvm-runtime-exec",
+        &[ProgrammingLanguage::Java, ProgrammingLanguage::Kotlin],
+        r"Runtime\s*\.\s*getRuntime\s*\(\s*\)\s*\.\s*exec",
+        true,
+        "Spawning processes is not allowed",
+    ),
+    (
+        "jvm-process-builder",
+        &[ProgrammingLanguage::Java, ProgrammingLanguage::Kotlin],
+        "ProcessBuilder",
+        false,
+        "Spawning processes is not allowed",
+    ),
+    (
+        "rust-command",
+        &[ProgrammingLanguage::Rust],
+        "std::process::Command",
+        false,
+        "Spawning processes is not allowed",
+    ),
+    (
+        "go-exec",
+        &[ProgrammingLanguage::Go],
+        "\"os/exec\"",
+        false,
+        "Spawning processes is not allowed",
+    ),
+    (
+        "js-child-process",
+        &[
+            ProgrammingLanguage::JavaScript,
+            ProgrammingLanguage::TypeScript,
+        ],
+        "child_process",
+        false,
+        "Spawning processes is not allowed",
+    ),
+];
+
+impl ScreeningRules {
+    /// Returns the built-in rule set
+    pub fn builtin() -> Self {
+        let specs = BUILTIN_RULES
+            .iter()
+            .map(|(id, languages, pattern, regex, reason)| RuleSpec {
+                id: id.to_string(),
+                languages: languages.to_vec(),
+                pattern: pattern.to_string(),
+                regex: *regex,
+                reason: reason.to_string(),
+                warn_only: false,
+            })
+            .collect();
+        Self::from_specs(specs).expect("built-in screening rules are valid")
+    }
+
+    /// Returns a rule set that never reports violations
+    pub fn empty() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Compiles rules from their specs
+    pub fn from_specs(specs: Vec<RuleSpec>) -> Result<Self, ScreeningError> {
+        let rules = specs
+            .into_iter()
+            .map(|spec| {
+                let matcher = if spec.regex {
+                    Regex::new(&spec.pattern).map(Matcher::Regex).map_err(|e| {
+                        ScreeningError::InvalidPattern {
+                            pattern: spec.pattern.clone(),
+                            message: e.to_string(),
+                        }
+                    })?
+                } else {
+                    Matcher::Substring(spec.pattern)
+                };
+                Ok(Rule {
+                    id: spec.id,
+                    languages: spec.languages,
+                    matcher,
+                    reason: spec.reason,
+                    warn_only: spec.warn_only,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Parses rules from a TOML document; it replaces the built-in set entirely
+    pub fn from_toml_str(s: &str) -> Result<Self, ScreeningError> {
+        let file: RulesFile =
+            toml::from_str(s).map_err(|e| ScreeningError::Parse(e.to_string()))?;
+        Self::from_specs(file.rules)
+    }
+
+    /// Reads and parses rules from a TOML file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ScreeningError> {
+        let content = std::fs::read_to_string(path).map_err(ScreeningError::Io)?;
+        Self::from_toml_str(&content)
+    }
+
+    /// Returns every rule violation in the submission, in source order
+    ///
+    /// Each rule is reported at most once, at its first matching line. Warn-only
+    /// matches are included; use [`Violation::is_blocking`] to tell them apart.
+    pub fn screen(&self, submission: &Submission) -> Vec<Violation> {
+        let mut violations: Vec<Violation> = self
+            .rules
+            .iter()
+            .filter(|rule| {
+                rule.languages.is_empty() || rule.languages.contains(&submission.language)
+            })
+            .filter_map(|rule| {
+                let index = submission
+                    .source_code
+                    .lines()
+                    .position(|line| rule.matcher.is_match(line))?;
+                Some(Violation {
+                    rule: rule.id.clone(),
+                    reason: rule.reason.clone(),
+                    line: index as u32 + 1,
+                    warn_only: rule.warn_only,
+                })
+            })
+            .collect();
+        violations.sort_by_key(|v| v.line);
+        violations
+    }
+}
+
+impl Default for ScreeningRules {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+/// Builds the error reported for a submission rejected by screening
+///
+/// Every reason is listed in the message together with its rule id; `line` points
+/// at the first violation.
+pub fn violations_error(violations: &[Violation]) -> ErrorInfo {
+    let reasons: Vec<String> = violations
+        .iter()
+        .map(|v| format!("line {}: {} [{}]", v.line, v.reason, v.rule))
+        .collect();
+    let mut error = ErrorInfo::new(format!(
+        "Submission rejected by source screening: {}",
+        reasons.join("; ")
+    ));
+    error.code = Some("SCREENING_VIOLATION".to_string());
+    error.line = violations.first().map(|v| v.line);
+    error
+}
+
+/// Screens a submission against the built-in rules
+pub fn screen(submission: &Submission) -> Vec<Violation> {
+    ScreeningRules::builtin().screen(submission)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::submission;
+
+    #[test]
+    fn test_cpp_snippets() {
+        let bad = "#include <bits/stdc++.h>\n#include <sys/ptrace.h>\n\nint main() {\n    if (fork() == 0) { return 0; }\n}\n";
+        let violations = screen(&submission(ProgrammingLanguage::Cpp17, bad));
+        assert_eq!(
+            violations,
+            vec![
+                Violation {
+                    rule: "c-ptrace".to_string(),
+                    reason: "ptrace is not allowed".to_string(),
+                    line: 2,
+                    warn_only: false,
+                },
+                Violation {
+                    rule: "c-spawn".to_string(),
+                    reason: "Spawning processes is not allowed".to_string(),
+                    line: 5,
+                    warn_only: false,
+                },
+            ]
+        );
+
+        let good = "#include <cstdio>\nint main() {\n    int forks = 0;\n    printf(\"%d\\n\", forks);\n}\n";
+        assert!(screen(&submission(ProgrammingLanguage::Cpp17, good)).is_empty());
+    }
+
+    #[test]
+    fn test_python_snippets() {
+        let bad = "import sys\nimport os\n\nos.system('rm -rf /')\n";
+        let violations = screen(&submission(ProgrammingLanguage::Python3, bad));
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].line, 4);
+
+        let bad = "from subprocess import run\nrun(['ls'])\n";
+        assert_eq!(
+            screen(&submission(ProgrammingLanguage::Python3, bad))[0].reason,
+            "Importing this module is not allowed"
+        );
+
+        let good = "import sys\nn = int(sys.stdin.readline())\nprint(n * 2)\n";
+        assert!(screen(&submission(ProgrammingLanguage::Python3, good)).is_empty());
+    }
+
+    #[test]
+    fn test_java_snippets() {
+        let bad = "public class Main {\n    public static void main(String[] args) throws Exception {\n        Runtime.getRuntime().exec(\"sh\");\n    }\n}\n";
+        let violations = screen(&submission(ProgrammingLanguage::Java, bad));
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].line, 3);
+
+        let good = "import java.util.Scanner;\npublic class Main {\n    public static void main(String[] a) {\n        System.out.println(Runtime.getRuntime().availableProcessors());\n    }\n}\n";
+        assert!(screen(&submission(ProgrammingLanguage::Java, good)).is_empty());
+    }
+
+    #[test]
+    fn test_comments_and_strings_are_matched() {
+        // Documented behavior: screening does not parse the source
+        let source = "// #include <sys/ptrace.h> is banned here\nint main() {}\n";
+        assert_eq!(screen(&submission(ProgrammingLanguage::C, source)).len(), 1);
+    }
+
+    #[test]
+    fn test_rules_only_apply_to_their_languages() {
+        let source = "ProcessBuilder is just a word here\n";
+        assert!(screen(&submission(ProgrammingLanguage::Python3, source)).is_empty());
+    }
+
+    #[test]
+    fn test_violations_error() {
+        let error = violations_error(&[
+            Violation {
+                rule: "c-ptrace".to_string(),
+                reason: "ptrace is not allowed".to_string(),
+                line: 2,
+                warn_only: false,
+            },
+            Violation {
+                rule: "c-spawn".to_string(),
+                reason: "Spawning processes is not allowed".to_string(),
+                line: 5,
+                warn_only: false,
+            },
+        ]);
+        assert_eq!(error.line, Some(2));
+        assert_eq!(
+            error.message,
+            "Submission rejected by source screening: line 2: ptrace is not allowed [c-ptrace]; line 5: Spawning processes is not allowed [c-spawn]"
+        );
+    }
+
+    #[test]
+    fn test_from_toml_str() {
+        let rules = ScreeningRules::from_toml_str(
+            r#"
+            [[rules]]
+            id = "goto"
+            pattern = "goto"
+            reason = "goto is banned in this contest"
+
+            [[rules]]
+            id = "numpy"
+            languages = ["Python3"]
+            pattern = '^\s*import\s+numpy'
+            regex = true
+            reason = "numpy is not available"
+            "#,
+        )
+        .unwrap();
+
+        let violations = rules.screen(&submission(ProgrammingLanguage::C, "goto end;\n"));
+        assert_eq!(violations[0].rule, "goto");
+        assert_eq!(violations[0].reason, "goto is banned in this contest");
+        assert_eq!(
+            rules
+                .screen(&submission(
+                    ProgrammingLanguage::Python3,
+                    "import numpy as np\n"
+                ))
+                .len(),
+            1
+        );
+        // Custom rules replace the built-in ones
+        assert!(
+            rules
+                .screen(&submission(
+                    ProgrammingLanguage::C,
+                    "#include <sys/ptrace.h>\n"
+                ))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        assert!(matches!(
+            ScreeningRules::from_toml_str(
+                "[[rules]]\nid = \"x\"\npattern = \"(\"\nregex = true\nreason = \"x\"\n"
+            ),
+            Err(ScreeningError::InvalidPattern { .. })
+        ));
+        assert!(matches!(
+            ScreeningRules::from_toml_str(
+                "[[rules]]\nid = \"x\"\nlanguages = [\"Cobol\"]\npattern = \"x\"\nreason = \"x\"\n"
+            ),
+            Err(ScreeningError::Parse(_))
+        ));
+        // Every rule needs an id so violations can name it
+        assert!(matches!(
+            ScreeningRules::from_toml_str("[[rules]]\npattern = \"x\"\nreason = \"x\"\n"),
+            Err(ScreeningError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_warn_only_rules() {
+        let rules = ScreeningRules::from_toml_str(
+            r#"
+            [[rules]]
+            id = "python-os-system"
+            languages = ["Python3"]
+            pattern = "os.system"
+            reason = "os.system is not allowed for this problem"
+            warn_only = true
+            "#,
+        )
+        .unwrap();
+
+        let violations = rules.screen(&submission(
+            ProgrammingLanguage::Python3,
+            "import os
+os.system('ls')
+",
+        ));
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].warn_only);
+        assert!(!violations[0].is_blocking());
+        assert_eq!(violations[0].rule, "python-os-system");
+    }
+}
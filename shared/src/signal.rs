@@ -0,0 +1,146 @@
+//! Classification of the signals that kill a submission
+//!
+//! The sandbox reports the raw signal number of a killed process. Numbers follow
+//! Linux on x86-64 and arm64, the only platforms the judger runs on.
+
+use crate::{ErrorInfo, JudgeStatus, RuntimeErrorType};
+
+pub const SIGABRT: i32 = 6;
+pub const SIGBUS: i32 = 7;
+pub const SIGFPE: i32 = 8;
+pub const SIGKILL: i32 = 9;
+pub const SIGSEGV: i32 = 11;
+pub const SIGXFSZ: i32 = 25;
+pub const SIGSYS: i32 = 31;
+
+/// Returns the name of a signal, e.g. `"SIGSEGV"` for 11
+pub fn signal_name(signal: i32) -> &'static str {
+    match signal {
+        1 => "SIGHUP",
+        2 => "SIGINT",
+        3 => "SIGQUIT",
+        4 => "SIGILL",
+        5 => "SIGTRAP",
+        SIGABRT => "SIGABRT",
+        SIGBUS => "SIGBUS",
+        SIGFPE => "SIGFPE",
+        SIGKILL => "SIGKILL",
+        10 => "SIGUSR1",
+        SIGSEGV => "SIGSEGV",
+        12 => "SIGUSR2",
+        13 => "SIGPIPE",
+        14 => "SIGALRM",
+        15 => "SIGTERM",
+        16 => "SIGSTKFLT",
+        17 => "SIGCHLD",
+        18 => "SIGCONT",
+        19 => "SIGSTOP",
+        20 => "SIGTSTP",
+        21 => "SIGTTIN",
+        22 => "SIGTTOU",
+        23 => "SIGURG",
+        24 => "SIGXCPU",
+        SIGXFSZ => "SIGXFSZ",
+        26 => "SIGVTALRM",
+        27 => "SIGPROF",
+        28 => "SIGWINCH",
+        29 => "SIGIO",
+        30 => "SIGPWR",
+        SIGSYS => "SIGSYS",
+        _ => "unknown signal",
+    }
+}
+
+impl RuntimeErrorType {
+    /// Classifies the signal that killed a submission
+    ///
+    /// Integer division by zero arrives as SIGFPE, so it is reported as a
+    /// floating point exception; stack overflows arrive as SIGSEGV. SIGKILL carries
+    /// no information by itself: the sandbox sends it when a time or memory limit is
+    /// hit, so callers should check the limits first and only fall back to
+    /// [`RuntimeErrorType::Other`] here.
+    pub fn from_signal(signal: i32) -> RuntimeErrorType {
+        match signal {
+            SIGSEGV | SIGBUS => RuntimeErrorType::SegmentationFault,
+            SIGFPE => RuntimeErrorType::FloatingPointException,
+            SIGABRT => RuntimeErrorType::AssertionFailed,
+            SIGXFSZ => RuntimeErrorType::FileOperationError,
+            // Raised by seccomp for a forbidden system call
+            SIGSYS => RuntimeErrorType::PermissionDenied,
+            _ => RuntimeErrorType::Other,
+        }
+    }
+}
+
+impl JudgeStatus {
+    /// Returns the runtime error verdict for a submission killed by `signal`
+    pub fn runtime_error_from_signal(signal: i32) -> JudgeStatus {
+        JudgeStatus::RuntimeError(RuntimeErrorType::from_signal(signal))
+    }
+}
+
+impl ErrorInfo {
+    /// Creates error info for a submission killed by a signal, e.g.
+    /// "killed by SIGSEGV (11)"
+    pub fn killed_by_signal(signal: i32, stderr: Option<String>) -> Self {
+        Self::runtime_error(
+            format!("killed by {} ({})", signal_name(signal), signal),
+            signal,
+            stderr,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_signal() {
+        let table = [
+            (SIGSEGV, RuntimeErrorType::SegmentationFault),
+            (SIGBUS, RuntimeErrorType::SegmentationFault),
+            (SIGFPE, RuntimeErrorType::FloatingPointException),
+            (SIGABRT, RuntimeErrorType::AssertionFailed),
+            (SIGXFSZ, RuntimeErrorType::FileOperationError),
+            (SIGSYS, RuntimeErrorType::PermissionDenied),
+            (SIGKILL, RuntimeErrorType::Other),
+            (15, RuntimeErrorType::Other),
+        ];
+        for (signal, expected) in table {
+            assert_eq!(
+                RuntimeErrorType::from_signal(signal),
+                expected,
+                "{}",
+                signal
+            );
+            assert_eq!(
+                JudgeStatus::runtime_error_from_signal(signal),
+                JudgeStatus::RuntimeError(expected)
+            );
+        }
+    }
+
+    #[test]
+    fn test_unknown_signals() {
+        for signal in [0, -1, 32, 64, i32::MAX] {
+            assert_eq!(
+                RuntimeErrorType::from_signal(signal),
+                RuntimeErrorType::Other
+            );
+            assert_eq!(signal_name(signal), "unknown signal");
+        }
+    }
+
+    #[test]
+    fn test_signal_names() {
+        assert_eq!(signal_name(SIGSEGV), "SIGSEGV");
+        assert_eq!(signal_name(SIGKILL), "SIGKILL");
+        assert_eq!(signal_name(13), "SIGPIPE");
+        assert!((1..=31).all(|signal| signal_name(signal).starts_with("SIG")));
+
+        let error = ErrorInfo::killed_by_signal(SIGSEGV, None);
+        assert_eq!(error.message, "killed by SIGSEGV (11)");
+        assert_eq!(error.signal, Some(11));
+    }
+}
@@ -0,0 +1,198 @@
+//! Stable content hash of a submission's source
+//!
+//! Used by the backend to detect duplicate submissions and by the judger as a compile
+//! cache key, so both sides must agree on it exactly. The hash covers the language id
+//! and the normalized source: a leading UTF-8 BOM is dropped and CRLF or lone CR line
+//! endings become LF, so the same file saved on Windows and Unix hashes identically.
+
+use crate::Submission;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+
+/// SHA-256 of a submission's language and normalized source, shown as lowercase hex
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SourceHash([u8; 32]);
+
+impl SourceHash {
+    /// Returns the raw digest
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl fmt::Display for SourceHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// Error returned when parsing a [`SourceHash`] from a string that isn't 64 hex digits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseSourceHashError;
+
+impl fmt::Display for ParseSourceHashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "source hash must be 64 hexadecimal digits")
+    }
+}
+
+impl std::error::Error for ParseSourceHashError {}
+
+impl FromStr for SourceHash {
+    type Err = ParseSourceHashError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 64 || !s.is_ascii() {
+            return Err(ParseSourceHashError);
+        }
+        let mut bytes = [0u8; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte =
+                u8::from_str_radix(&s[i * 2..i * 2 + 2], 16).map_err(|_| ParseSourceHashError)?;
+        }
+        Ok(Self(bytes))
+    }
+}
+
+impl Serialize for SourceHash {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for SourceHash {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl Submission {
+    /// Returns the stable hash of the language and normalized source
+    ///
+    /// Custom-language submissions also hash their toolchain spec, since the same
+    /// source compiled by a different toolchain is a different program.
+    pub fn source_hash(&self) -> SourceHash {
+        let mut hasher = Sha256::new();
+        hasher.update(self.language.id().to_le_bytes());
+        if let Some(spec) = &self.custom_language {
+            let spec = serde_json::to_vec(spec).expect("custom language spec serializes");
+            hasher.update((spec.len() as u64).to_le_bytes());
+            hasher.update(spec);
+        }
+
+        let source = self
+            .source_code
+            .strip_prefix('\u{feff}')
+            .unwrap_or(&self.source_code);
+        let mut chars = source.chars().peekable();
+        let mut buf = [0u8; 4];
+        while let Some(c) = chars.next() {
+            let c = if c == '\r' {
+                chars.next_if_eq(&'\n');
+                '\n'
+            } else {
+                c
+            };
+            hasher.update(c.encode_utf8(&mut buf).as_bytes());
+        }
+        SourceHash(hasher.finalize().into())
+    }
+
+    /// Returns the size of the source code in bytes, as stored
+    pub fn source_len_bytes(&self) -> usize {
+        self.source_code.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::submission;
+    use crate::{CustomLanguage, ProgrammingLanguage};
+
+    #[test]
+    fn test_line_endings_and_bom_are_normalized() {
+        let lf = submission(
+            ProgrammingLanguage::Cpp17,
+            "int main() {\n    return 0;\n}\n",
+        );
+        let crlf = submission(
+            ProgrammingLanguage::Cpp17,
+            "int main() {\r\n    return 0;\r\n}\r\n",
+        );
+        let cr = submission(
+            ProgrammingLanguage::Cpp17,
+            "int main() {\r    return 0;\r}\r",
+        );
+        let bom = submission(
+            ProgrammingLanguage::Cpp17,
+            "\u{feff}int main() {\r\n    return 0;\r\n}\r\n",
+        );
+        assert_eq!(lf.source_hash(), crlf.source_hash());
+        assert_eq!(lf.source_hash(), cr.source_hash());
+        assert_eq!(lf.source_hash(), bom.source_hash());
+        assert_ne!(lf.source_len_bytes(), crlf.source_len_bytes());
+    }
+
+    #[test]
+    fn test_hash_depends_on_language_and_content() {
+        let source = "print(1)\n";
+        let python = submission(ProgrammingLanguage::Python3, source);
+        assert_ne!(
+            python.source_hash(),
+            submission(ProgrammingLanguage::Python2, source).source_hash()
+        );
+        assert_ne!(
+            python.source_hash(),
+            submission(ProgrammingLanguage::Python3, "print(2)\n").source_hash()
+        );
+        // Blank lines are not collapsed
+        assert_ne!(
+            python.source_hash(),
+            submission(ProgrammingLanguage::Python3, "print(1)\n\n").source_hash()
+        );
+
+        let lua = |run: &str| {
+            submission(ProgrammingLanguage::Custom, source).with_custom_language(CustomLanguage {
+                name: "Lua".to_string(),
+                file_extension: "lua".to_string(),
+                needs_compilation: false,
+                compile_argv: vec![],
+                run_argv: vec![run.to_string(), "{source}".to_string()],
+            })
+        };
+        assert_ne!(lua("lua5.4").source_hash(), lua("luajit").source_hash());
+    }
+
+    #[test]
+    fn test_known_digest() {
+        // Pinned so any change to the hashing scheme is a deliberate, visible one
+        let hash = submission(ProgrammingLanguage::C, "").source_hash();
+        let mut expected = Sha256::new();
+        expected.update(ProgrammingLanguage::C.id().to_le_bytes());
+        let expected: [u8; 32] = expected.finalize().into();
+        assert_eq!(hash.as_bytes(), &expected);
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let hash = submission(ProgrammingLanguage::Rust, "fn main() {}\n").source_hash();
+        let hex = hash.to_string();
+        assert_eq!(hex.len(), 64);
+        assert!(hex.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f')));
+        assert_eq!(hex.parse::<SourceHash>(), Ok(hash));
+
+        let json = serde_json::to_string(&hash).unwrap();
+        assert_eq!(json, format!("\"{}\"", hex));
+        assert_eq!(serde_json::from_str::<SourceHash>(&json).unwrap(), hash);
+
+        assert_eq!("abc".parse::<SourceHash>(), Err(ParseSourceHashError));
+        assert!(serde_json::from_str::<SourceHash>(&format!("\"{}\"", "g".repeat(64))).is_err());
+    }
+}
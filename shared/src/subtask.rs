@@ -0,0 +1,321 @@
+//! IOI-style subtasks
+//!
+//! A subtask groups test cases and awards its score only when every one of them
+//! passes. A subtask may depend on others, in which case it scores 0 (and its tests
+//! need not run) unless all its dependencies pass as well.
+
+use crate::{JudgeTask, TestCase};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+
+/// Group of test cases scored together
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Subtask {
+    /// Subtask identifier, unique within the task
+    pub id: String,
+    /// Score awarded when every test case (and every dependency) passes
+    pub score: f64,
+    /// Ids of the test cases in this subtask
+    pub test_case_ids: Vec<String>,
+    /// Ids of the subtasks that must also pass
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+}
+
+/// Why a task's subtasks are inconsistent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubtaskError {
+    /// Two subtasks share an id
+    DuplicateId(String),
+    /// A subtask references a test case that isn't in the task
+    UnknownTestCase { subtask: String, test_case: String },
+    /// A subtask depends on a subtask that doesn't exist
+    UnknownDependency { subtask: String, dependency: String },
+    /// The dependencies form a cycle through this subtask
+    Cycle(String),
+}
+
+impl fmt::Display for SubtaskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SubtaskError::DuplicateId(id) => write!(f, "duplicate subtask id {:?}", id),
+            SubtaskError::UnknownTestCase { subtask, test_case } => write!(
+                f,
+                "subtask {:?} references unknown test case {:?}",
+                subtask, test_case
+            ),
+            SubtaskError::UnknownDependency {
+                subtask,
+                dependency,
+            } => write!(
+                f,
+                "subtask {:?} depends on unknown subtask {:?}",
+                subtask, dependency
+            ),
+            SubtaskError::Cycle(id) => write!(f, "subtask {:?} is part of a dependency cycle", id),
+        }
+    }
+}
+
+impl std::error::Error for SubtaskError {}
+
+impl JudgeTask {
+    /// Checks that subtask ids are unique, reference existing test cases and have
+    /// acyclic dependencies
+    ///
+    /// Scores are not required to sum to 100; see [`JudgeTask::subtask_score_total`].
+    pub fn validate_subtasks(&self) -> Result<(), SubtaskError> {
+        let mut ids = HashSet::new();
+        for subtask in &self.subtasks {
+            if !ids.insert(subtask.id.as_str()) {
+                return Err(SubtaskError::DuplicateId(subtask.id.clone()));
+            }
+        }
+        for subtask in &self.subtasks {
+            if let Some(test_case) = subtask
+                .test_case_ids
+                .iter()
+                .find(|id| !self.test_cases.iter().any(|tc| &&tc.id == id))
+            {
+                return Err(SubtaskError::UnknownTestCase {
+                    subtask: subtask.id.clone(),
+                    test_case: test_case.clone(),
+                });
+            }
+            if let Some(dependency) = subtask
+                .depends_on
+                .iter()
+                .find(|id| !ids.contains(id.as_str()))
+            {
+                return Err(SubtaskError::UnknownDependency {
+                    subtask: subtask.id.clone(),
+                    dependency: dependency.clone(),
+                });
+            }
+        }
+        self.subtask_order().map(|_| ())
+    }
+
+    /// Returns the sum of all subtask scores
+    pub fn subtask_score_total(&self) -> f64 {
+        self.subtasks.iter().map(|s| s.score).sum()
+    }
+
+    /// Returns the first subtask containing a test case
+    pub fn subtask_for(&self, test_case_id: &str) -> Option<&Subtask> {
+        self.subtasks
+            .iter()
+            .find(|s| s.test_case_ids.iter().any(|id| id == test_case_id))
+    }
+
+    /// Returns the subtasks so that every subtask comes after its dependencies
+    ///
+    /// Among subtasks whose dependencies are satisfied, the declared order is kept.
+    /// Unknown dependencies are ignored here; [`JudgeTask::validate_subtasks`] reports them.
+    pub fn subtask_order(&self) -> Result<Vec<&Subtask>, SubtaskError> {
+        let mut done: HashSet<&str> = HashSet::new();
+        let mut order = Vec::with_capacity(self.subtasks.len());
+        while order.len() < self.subtasks.len() {
+            let next = self.subtasks.iter().find(|s| {
+                !done.contains(s.id.as_str())
+                    && s.depends_on.iter().all(|dep| {
+                        done.contains(dep.as_str()) || !self.subtasks.iter().any(|s| &s.id == dep)
+                    })
+            });
+            match next {
+                Some(subtask) => {
+                    done.insert(&subtask.id);
+                    order.push(subtask);
+                }
+                None => {
+                    let stuck = self
+                        .subtasks
+                        .iter()
+                        .find(|s| !done.contains(s.id.as_str()))
+                        .expect("an unordered subtask remains");
+                    return Err(SubtaskError::Cycle(stuck.id.clone()));
+                }
+            }
+        }
+        Ok(order)
+    }
+
+    /// Returns the ids of the subtasks that score 0 because `failed` did,
+    /// directly or transitively, in declared order
+    pub fn dependents_of(&self, failed: &str) -> Vec<&str> {
+        let mut blocked: HashSet<&str> = HashSet::from([failed]);
+        loop {
+            let before = blocked.len();
+            for subtask in &self.subtasks {
+                if subtask
+                    .depends_on
+                    .iter()
+                    .any(|dep| blocked.contains(dep.as_str()))
+                {
+                    blocked.insert(&subtask.id);
+                }
+            }
+            if blocked.len() == before {
+                break;
+            }
+        }
+        self.subtasks
+            .iter()
+            .map(|s| s.id.as_str())
+            .filter(|id| *id != failed && blocked.contains(id))
+            .collect()
+    }
+
+    /// Returns the test cases that need not run because subtask `failed` did,
+    /// in declared order
+    ///
+    /// These are the test cases of its dependents that no subtask still able to
+    /// score needs; they should be reported as
+    /// [`TestCaseResult::skipped`](crate::TestCaseResult::skipped).
+    pub fn skippable_after(&self, failed: &str) -> Vec<&TestCase> {
+        let blocked = self.dependents_of(failed);
+        let needed: HashSet<&str> = self
+            .subtasks
+            .iter()
+            .filter(|s| s.id != failed && !blocked.contains(&s.id.as_str()))
+            .flat_map(|s| s.test_case_ids.iter().map(String::as_str))
+            .collect();
+        let skippable: HashSet<&str> = self
+            .subtasks
+            .iter()
+            .filter(|s| blocked.contains(&s.id.as_str()))
+            .flat_map(|s| s.test_case_ids.iter().map(String::as_str))
+            .filter(|id| !needed.contains(id))
+            .collect();
+        self.test_cases
+            .iter()
+            .filter(|tc| skippable.contains(tc.id.as_str()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn subtask(id: &str, score: f64, tests: &[&str], depends_on: &[&str]) -> Subtask {
+        Subtask {
+            id: id.to_string(),
+            score,
+            test_case_ids: tests.iter().map(|s| s.to_string()).collect(),
+            depends_on: depends_on.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    fn task(subtasks: Vec<Subtask>) -> JudgeTask {
+        let mut task = testing::task(testing::blank_test_cases(["1", "2", "3", "4"]));
+        task.subtasks = subtasks;
+        task
+    }
+
+    fn ioi_task() -> JudgeTask {
+        task(vec![
+            subtask("full", 60.0, &["3", "4"], &["small", "medium"]),
+            subtask("medium", 30.0, &["2"], &["small"]),
+            subtask("small", 10.0, &["1"], &[]),
+        ])
+    }
+
+    #[test]
+    fn test_valid_subtasks() {
+        let task = ioi_task();
+        assert_eq!(task.validate_subtasks(), Ok(()));
+        assert_eq!(task.subtask_score_total(), 100.0);
+        assert_eq!(task.subtask_for("4").unwrap().id, "full");
+        assert!(task.subtask_for("9").is_none());
+    }
+
+    #[test]
+    fn test_subtask_order_respects_dependencies() {
+        let task = ioi_task();
+        let ids: Vec<&str> = task
+            .subtask_order()
+            .unwrap()
+            .iter()
+            .map(|s| s.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["small", "medium", "full"]);
+    }
+
+    #[test]
+    fn test_dependents_of() {
+        let task = ioi_task();
+        assert_eq!(task.dependents_of("small"), vec!["full", "medium"]);
+        assert_eq!(task.dependents_of("medium"), vec!["full"]);
+        assert!(task.dependents_of("full").is_empty());
+    }
+
+    #[test]
+    fn test_skippable_after() {
+        let ids = |test_cases: Vec<&TestCase>| -> Vec<String> {
+            test_cases.iter().map(|tc| tc.id.clone()).collect()
+        };
+        let ioi = ioi_task();
+        assert_eq!(ids(ioi.skippable_after("small")), vec!["2", "3", "4"]);
+        assert_eq!(ids(ioi.skippable_after("medium")), vec!["3", "4"]);
+        assert!(ioi.skippable_after("full").is_empty());
+
+        // Test 3 still counts for "other", which doesn't depend on "small"
+        let shared = task(vec![
+            subtask("small", 20.0, &["1"], &[]),
+            subtask("large", 40.0, &["3", "4"], &["small"]),
+            subtask("other", 40.0, &["2", "3"], &[]),
+        ]);
+        assert_eq!(ids(shared.skippable_after("small")), vec!["4"]);
+    }
+
+    #[test]
+    fn test_invalid_subtasks() {
+        let cyclic = task(vec![
+            subtask("a", 50.0, &["1"], &["b"]),
+            subtask("b", 50.0, &["2"], &["a"]),
+        ]);
+        assert_eq!(
+            cyclic.validate_subtasks(),
+            Err(SubtaskError::Cycle("a".to_string()))
+        );
+
+        let unknown_test = task(vec![subtask("a", 100.0, &["1", "9"], &[])]);
+        assert_eq!(
+            unknown_test.validate_subtasks(),
+            Err(SubtaskError::UnknownTestCase {
+                subtask: "a".to_string(),
+                test_case: "9".to_string(),
+            })
+        );
+
+        let unknown_dependency = task(vec![subtask("a", 100.0, &["1"], &["z"])]);
+        assert_eq!(
+            unknown_dependency.validate_subtasks(),
+            Err(SubtaskError::UnknownDependency {
+                subtask: "a".to_string(),
+                dependency: "z".to_string(),
+            })
+        );
+
+        let duplicate = task(vec![
+            subtask("a", 50.0, &["1"], &[]),
+            subtask("a", 50.0, &["2"], &[]),
+        ]);
+        assert_eq!(
+            duplicate.validate_subtasks(),
+            Err(SubtaskError::DuplicateId("a".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_plain_tasks_have_no_subtasks() {
+        let mut value = serde_json::to_value(task(vec![])).unwrap();
+        value.as_object_mut().unwrap().remove("subtasks");
+        let deserialized: JudgeTask = serde_json::from_value(value).unwrap();
+        assert!(deserialized.subtasks.is_empty());
+        assert_eq!(deserialized.validate_subtasks(), Ok(()));
+    }
+}
@@ -0,0 +1,202 @@
+//! Compact statistics of a judge result
+//!
+//! List endpoints and notifications need "passed 17/20, max time 312ms, first failed
+//! test: 14" without shipping every test case. [`JudgeResult::summary`] computes
+//! that once.
+
+use crate::testcases::natural_cmp;
+use crate::{JudgeResult, JudgeStatus};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Statistics of a [`JudgeResult`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JudgeSummary {
+    /// Overall verdict
+    pub status: JudgeStatus,
+    /// Number of accepted test cases
+    pub passed: usize,
+    /// Number of test cases
+    pub total: usize,
+    /// Number of test cases per verdict, keyed by [`JudgeStatus::as_code`]
+    pub verdict_counts: BTreeMap<String, usize>,
+    /// Largest time used in milliseconds
+    pub max_time_ms: u64,
+    /// Average time used in milliseconds over the test cases that ran
+    pub avg_time_ms: f64,
+    /// Largest memory used in kilobytes
+    pub max_memory_kb: u64,
+    /// Average memory used in kilobytes over the test cases that ran
+    pub avg_memory_kb: f64,
+    /// Id of the first test case (in natural id order) that ran and wasn't accepted
+    pub first_failed: Option<String>,
+    /// Score as a fraction between 0.0 and 1.0
+    pub score_fraction: f64,
+}
+
+impl JudgeResult {
+    /// Summarizes the result
+    ///
+    /// Results without test cases (compile errors, or results that only carry
+    /// aggregate numbers) report zero test cases and take time and memory from the
+    /// result itself.
+    pub fn summary(&self) -> JudgeSummary {
+        let mut verdict_counts = BTreeMap::new();
+        for tc in &self.test_cases {
+            *verdict_counts
+                .entry(tc.status.as_code().to_string())
+                .or_insert(0) += 1;
+        }
+
+        let total = self.test_cases.len();
+        let (max_time_ms, avg_time_ms, max_memory_kb, avg_memory_kb) = if total == 0 {
+            (
+                self.time_used,
+                self.time_used as f64,
+                self.memory_used,
+                self.memory_used as f64,
+            )
+        } else {
+            // Skipped test cases never ran and would pull the averages down
+            let ran: Vec<_> = self
+                .test_cases
+                .iter()
+                .filter(|tc| tc.status != JudgeStatus::Skipped)
+                .collect();
+            let count = ran.len().max(1) as f64;
+            let times = ran.iter().map(|tc| tc.time_used);
+            let memories = ran.iter().map(|tc| tc.memory_used);
+            (
+                times.clone().max().unwrap_or(0),
+                times.sum::<u64>() as f64 / count,
+                memories.clone().max().unwrap_or(0),
+                memories.sum::<u64>() as f64 / count,
+            )
+        };
+
+        JudgeSummary {
+            status: self.status,
+            passed: self.passed_test_cases(),
+            total,
+            verdict_counts,
+            max_time_ms,
+            avg_time_ms,
+            max_memory_kb,
+            avg_memory_kb,
+            first_failed: self
+                .test_cases
+                .iter()
+                .filter(|tc| !tc.status.is_accepted() && tc.status != JudgeStatus::Skipped)
+                .min_by(|a, b| natural_cmp(&a.id, &b.id))
+                .map(|tc| tc.id.clone()),
+            score_fraction: (self.score / 100.0).clamp(0.0, 1.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use crate::{ErrorInfo, JudgeMode, ProgrammingLanguage, Submission, TestCaseResult};
+
+    fn submission() -> Submission {
+        testing::submission(ProgrammingLanguage::Cpp17, "int main() {}")
+    }
+
+    fn case(id: &str, status: JudgeStatus, time_used: u64, memory_used: u64) -> TestCaseResult {
+        TestCaseResult {
+            id: id.to_string(),
+            status,
+            time_used,
+            memory_used,
+            input: None,
+            expected_output: None,
+            actual_output: None,
+            error_info: None,
+            is_hidden: false,
+            diff: None,
+        }
+    }
+
+    #[test]
+    fn test_summary() {
+        let result = JudgeResult::from_test_cases(
+            &submission(),
+            vec![
+                case("10", JudgeStatus::WrongAnswer, 300, 4096),
+                case("2", JudgeStatus::Accepted, 100, 1024),
+                case("9", JudgeStatus::TimeLimitExceeded, 1000, 2048),
+                case("1", JudgeStatus::Accepted, 200, 1024),
+            ],
+            &[1.0; 4],
+            JudgeMode::OiPartial,
+        );
+        let summary = result.summary();
+        assert_eq!(summary.status, JudgeStatus::TimeLimitExceeded);
+        assert_eq!((summary.passed, summary.total), (2, 4));
+        assert_eq!(summary.verdict_counts["AC"], 2);
+        assert_eq!(summary.verdict_counts["WA"], 1);
+        assert_eq!(summary.verdict_counts["TLE"], 1);
+        assert_eq!(summary.max_time_ms, 1000);
+        assert_eq!(summary.avg_time_ms, 400.0);
+        assert_eq!(summary.max_memory_kb, 4096);
+        assert_eq!(summary.avg_memory_kb, 2048.0);
+        // Natural order: 9 comes before 10
+        assert_eq!(summary.first_failed.as_deref(), Some("9"));
+        assert_eq!(summary.score_fraction, 0.5);
+
+        let json = serde_json::to_string(&summary).unwrap();
+        assert_eq!(
+            serde_json::from_str::<JudgeSummary>(&json).unwrap(),
+            summary
+        );
+
+        // A skipped test case didn't fail
+        let result = JudgeResult::from_test_cases(
+            &submission(),
+            vec![
+                case("1", JudgeStatus::Skipped, 0, 0),
+                case("2", JudgeStatus::WrongAnswer, 300, 4096),
+            ],
+            &[],
+            JudgeMode::AcmIcpc,
+        );
+        let summary = result.summary();
+        assert_eq!(summary.first_failed.as_deref(), Some("2"));
+        assert_eq!(summary.verdict_counts["SK"], 1);
+        assert_eq!(summary.avg_time_ms, 300.0);
+        assert_eq!(summary.avg_memory_kb, 4096.0);
+    }
+
+    #[test]
+    fn test_summary_without_test_cases() {
+        let submission = submission();
+        let result = JudgeResult::with_error(
+            JudgeStatus::CompileError,
+            0,
+            0,
+            ErrorInfo::new("error".to_string()),
+            submission.id,
+            submission.problem_id,
+            submission.user_id,
+        );
+        let summary = result.summary();
+        assert_eq!((summary.passed, summary.total), (0, 0));
+        assert!(summary.verdict_counts.is_empty());
+        assert_eq!(summary.first_failed, None);
+        assert_eq!(summary.score_fraction, 0.0);
+
+        let result = JudgeResult::accepted(
+            312,
+            43008,
+            submission.id,
+            submission.problem_id,
+            submission.user_id,
+        );
+        let summary = result.summary();
+        assert_eq!(summary.max_time_ms, 312);
+        assert_eq!(summary.avg_memory_kb, 43008.0);
+        assert_eq!(summary.score_fraction, 1.0);
+    }
+}
@@ -0,0 +1,427 @@
+//! Test case data by value or by reference
+//!
+//! Small test data is carried inline in the [`JudgeTask`]. Large data stays on disk or
+//! in object storage and is referenced instead, so a task doesn't have to carry (and
+//! the judger doesn't have to hold) hundreds of megabytes in memory. The judger
+//! downloads [`TestData::Remote`] through its fetch layer and verifies the hash.
+//!
+//! For compatibility, inline data serializes as a bare string, exactly as test cases
+//! did before references existed:
+//!
+//! ```
+//! use oj_shared::TestData;
+//!
+//! let inline: TestData = serde_json::from_str(r#""1 2\n""#).unwrap();
+//! assert_eq!(inline, TestData::Inline("1 2\n".to_string()));
+//!
+//! let file: TestData = serde_json::from_str(r#"{"File": "/data/42/1.in"}"#).unwrap();
+//! assert_eq!(file, TestData::File("/data/42/1.in".into()));
+//! ```
+
+use crate::JudgeTask;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+
+/// Input or expected output of a test case
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TestData {
+    /// The data itself
+    Inline(String),
+    /// A file under the judger's data root, relative to it or absolute
+    File(PathBuf),
+    /// An object the judger downloads and verifies
+    Remote {
+        url: String,
+        /// Lowercase hex SHA-256 of the content
+        sha256: String,
+        /// Content size in bytes
+        size: u64,
+    },
+}
+
+/// Why a test data reference is unusable
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TestDataError {
+    /// A file reference has an empty path
+    EmptyPath,
+    /// A remote reference has an empty URL
+    EmptyUrl,
+    /// A remote reference's hash is not 64 hex digits
+    InvalidHash(String),
+    /// A file reference resolves outside the data root
+    OutsideDataRoot(PathBuf),
+}
+
+impl fmt::Display for TestDataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TestDataError::EmptyPath => write!(f, "test data file path is empty"),
+            TestDataError::EmptyUrl => write!(f, "remote test data URL is empty"),
+            TestDataError::InvalidHash(hash) => {
+                write!(f, "remote test data sha256 {:?} is not 64 hex digits", hash)
+            }
+            TestDataError::OutsideDataRoot(path) => {
+                write!(
+                    f,
+                    "test data file {} is outside the data root",
+                    path.display()
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for TestDataError {}
+
+impl TestData {
+    /// Returns the data if it is inline
+    pub fn as_inline(&self) -> Option<&str> {
+        match self {
+            TestData::Inline(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    /// Returns the size in bytes when known without touching the filesystem or network
+    pub fn byte_len(&self) -> Option<u64> {
+        match self {
+            TestData::Inline(data) => Some(data.len() as u64),
+            TestData::File(_) => None,
+            TestData::Remote { size, .. } => Some(*size),
+        }
+    }
+
+    /// Returns whether this is known to be empty inline data
+    pub fn is_empty_inline(&self) -> bool {
+        self.as_inline().is_some_and(str::is_empty)
+    }
+
+    /// Opens a reader over local data
+    ///
+    /// Remote data must be fetched by the judger first; it yields
+    /// [`io::ErrorKind::Unsupported`].
+    pub fn open_reader(&self) -> io::Result<Box<dyn Read + '_>> {
+        match self {
+            TestData::Inline(data) => Ok(Box::new(data.as_bytes())),
+            TestData::File(path) => Ok(Box::new(std::fs::File::open(path)?)),
+            TestData::Remote { url, .. } => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("remote test data {} must be fetched first", url),
+            )),
+        }
+    }
+
+    /// Resolves a file reference against `root` in place
+    ///
+    /// Relative paths are taken relative to `root` and absolute paths must already lie
+    /// under it. `..` is resolved lexically, and a path that exists is also checked
+    /// after following symlinks, so a task can't read arbitrary host files. Inline and
+    /// remote data are left unchanged.
+    pub fn resolve_within(&mut self, root: &Path) -> Result<(), TestDataError> {
+        let TestData::File(path) = self else {
+            return Ok(());
+        };
+        let resolved =
+            resolve_path(root, path).ok_or_else(|| TestDataError::OutsideDataRoot(path.clone()))?;
+        if let (Ok(real), Ok(real_root)) = (resolved.canonicalize(), root.canonicalize())
+            && !real.starts_with(real_root)
+        {
+            return Err(TestDataError::OutsideDataRoot(path.clone()));
+        }
+        *path = resolved;
+        Ok(())
+    }
+
+    /// Checks that a reference can be resolved
+    pub fn validate(&self) -> Result<(), TestDataError> {
+        match self {
+            TestData::Inline(_) => Ok(()),
+            TestData::File(path) => {
+                if path.as_os_str().is_empty() {
+                    Err(TestDataError::EmptyPath)
+                } else {
+                    Ok(())
+                }
+            }
+            TestData::Remote { url, sha256, .. } => {
+                if url.trim().is_empty() {
+                    Err(TestDataError::EmptyUrl)
+                } else if sha256.len() != 64 || !sha256.chars().all(|c| c.is_ascii_hexdigit()) {
+                    Err(TestDataError::InvalidHash(sha256.clone()))
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+/// Joins `path` onto `root` without leaving it, or `None` if it would
+fn resolve_path(root: &Path, path: &Path) -> Option<PathBuf> {
+    let relative = if path.is_absolute() {
+        path.strip_prefix(root).ok()?
+    } else {
+        path
+    };
+    let mut resolved = PathBuf::new();
+    for component in relative.components() {
+        match component {
+            Component::Normal(part) => resolved.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !resolved.pop() {
+                    return None;
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(root.join(resolved))
+}
+
+impl Default for TestData {
+    fn default() -> Self {
+        TestData::Inline(String::new())
+    }
+}
+
+impl From<String> for TestData {
+    fn from(data: String) -> Self {
+        TestData::Inline(data)
+    }
+}
+
+impl From<&str> for TestData {
+    fn from(data: &str) -> Self {
+        TestData::Inline(data.to_string())
+    }
+}
+
+/// Externally tagged form of every variant
+#[derive(Serialize, Deserialize)]
+enum Tagged {
+    Inline(String),
+    File(PathBuf),
+    Remote {
+        url: String,
+        sha256: String,
+        size: u64,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Repr {
+    Bare(String),
+    Tagged(Tagged),
+}
+
+impl Serialize for TestData {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            TestData::Inline(data) => serializer.serialize_str(data),
+            TestData::File(path) => Tagged::File(path.clone()).serialize(serializer),
+            TestData::Remote { url, sha256, size } => Tagged::Remote {
+                url: url.clone(),
+                sha256: sha256.clone(),
+                size: *size,
+            }
+            .serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TestData {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::Bare(data) | Repr::Tagged(Tagged::Inline(data)) => TestData::Inline(data),
+            Repr::Tagged(Tagged::File(path)) => TestData::File(path),
+            Repr::Tagged(Tagged::Remote { url, sha256, size }) => {
+                TestData::Remote { url, sha256, size }
+            }
+        })
+    }
+}
+
+impl JudgeTask {
+    /// Checks every test case's data references
+    pub fn validate_test_data(&self) -> Result<(), TestDataError> {
+        self.test_cases
+            .iter()
+            .flat_map(|tc| std::iter::once(&tc.input).chain(tc.expected_outputs()))
+            .try_for_each(TestData::validate)
+    }
+
+    /// Resolves every test case's file references against the data root `root`
+    pub fn resolve_test_data(&mut self, root: &Path) -> Result<(), TestDataError> {
+        self.test_cases.iter_mut().try_for_each(|tc| {
+            tc.input.resolve_within(root)?;
+            tc.expected_output.resolve_within(root)?;
+            tc.alternative_outputs
+                .iter_mut()
+                .try_for_each(|output| output.resolve_within(root))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TestCase;
+
+    fn remote() -> TestData {
+        TestData::Remote {
+            url: "https://data.example.com/42/1.in".to_string(),
+            sha256: "ab".repeat(32),
+            size: 200 * 1024 * 1024,
+        }
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        for data in [
+            TestData::Inline("3\n1 2 3\n".to_string()),
+            TestData::File("/data/42/1.ans".into()),
+            remote(),
+        ] {
+            let json = serde_json::to_string(&data).unwrap();
+            assert_eq!(serde_json::from_str::<TestData>(&json).unwrap(), data);
+        }
+        assert_eq!(
+            serde_json::to_string(&TestData::Inline("1\n".to_string())).unwrap(),
+            r#""1\n""#
+        );
+        assert_eq!(
+            serde_json::from_str::<TestData>(r#"{"Inline": "1\n"}"#).unwrap(),
+            TestData::Inline("1\n".to_string())
+        );
+        assert!(serde_json::from_str::<TestData>(r#"{"Ftp": "x"}"#).is_err());
+    }
+
+    #[test]
+    fn test_legacy_test_case_payload() {
+        let json = r#"{
+            "id": "1",
+            "input": "1 2\n",
+            "expected_output": "3\n",
+            "time_limit": null,
+            "memory_limit": null,
+            "is_hidden": false,
+            "weight": 1.0
+        }"#;
+        let test_case: TestCase = serde_json::from_str(json).unwrap();
+        assert_eq!(test_case.input, TestData::Inline("1 2\n".to_string()));
+        assert_eq!(test_case.expected_output.as_inline(), Some("3\n"));
+        assert!(test_case.alternative_outputs.is_empty());
+    }
+
+    #[test]
+    fn test_accessors() {
+        let inline = TestData::from("hello");
+        assert_eq!(inline.byte_len(), Some(5));
+        let mut content = String::new();
+        inline
+            .open_reader()
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "hello");
+
+        assert_eq!(remote().byte_len(), Some(200 * 1024 * 1024));
+        assert_eq!(
+            remote().open_reader().err().unwrap().kind(),
+            io::ErrorKind::Unsupported
+        );
+
+        let path = std::env::temp_dir().join(format!("oj-test-data-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "from disk").unwrap();
+        let file = TestData::File(path.clone());
+        assert_eq!(file.byte_len(), None);
+        let mut content = String::new();
+        file.open_reader()
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "from disk");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(remote().validate(), Ok(()));
+        assert_eq!(
+            TestData::File(PathBuf::new()).validate(),
+            Err(TestDataError::EmptyPath)
+        );
+        assert_eq!(
+            TestData::Remote {
+                url: "https://data.example.com/1.in".to_string(),
+                sha256: String::new(),
+                size: 1,
+            }
+            .validate(),
+            Err(TestDataError::InvalidHash(String::new()))
+        );
+        assert_eq!(
+            TestData::Remote {
+                url: " ".to_string(),
+                sha256: "0".repeat(64),
+                size: 1,
+            }
+            .validate(),
+            Err(TestDataError::EmptyUrl)
+        );
+    }
+
+    #[test]
+    fn test_resolve_within() {
+        let root = Path::new("/data");
+        let resolve = |path: &str| {
+            let mut data = TestData::File(path.into());
+            data.resolve_within(root).map(|()| data)
+        };
+        assert_eq!(
+            resolve("42/1.in"),
+            Ok(TestData::File("/data/42/1.in".into()))
+        );
+        assert_eq!(
+            resolve("/data/42/./1.in"),
+            Ok(TestData::File("/data/42/1.in".into()))
+        );
+        assert_eq!(
+            resolve("42/../43/1.in"),
+            Ok(TestData::File("/data/43/1.in".into()))
+        );
+        for escaping in [
+            "/etc/passwd",
+            "../etc/passwd",
+            "42/../../etc/passwd",
+            "/database/1.in",
+        ] {
+            assert_eq!(
+                resolve(escaping),
+                Err(TestDataError::OutsideDataRoot(escaping.into()))
+            );
+        }
+
+        let mut inline = TestData::from("/etc/passwd");
+        inline.resolve_within(root).unwrap();
+        assert_eq!(inline, TestData::from("/etc/passwd"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_rejects_symlink_escape() {
+        let root = std::env::temp_dir().join(format!("oj-data-root-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&root).unwrap();
+        std::os::unix::fs::symlink("/etc/passwd", root.join("1.in")).unwrap();
+        let mut data = TestData::File("1.in".into());
+        assert_eq!(
+            data.resolve_within(&root),
+            Err(TestDataError::OutsideDataRoot("1.in".into()))
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,423 @@
+//! Loading test cases from the `N.in` / `N.out` directory convention
+//!
+//! Every input `<stem>.in` is paired with an expected output `<stem>.out` (or
+//! `<stem>.ans`); the stem becomes the test case id. Test cases are ordered
+//! naturally, so `10` comes after `9`, not after `1`. An optional `meta.toml`
+//! overrides per-case settings:
+//!
+//! ```toml
+//! [cases.1]
+//! sample = true
+//!
+//! [cases.10]
+//! time_limit = 2000
+//! memory_limit = 524288
+//! weight = 3.0
+//! hidden = true
+//! ```
+//!
+//! Files without a recognized extension are ignored.
+
+use crate::{TestCase, TestData};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Name of the per-directory metadata file
+pub const META_FILE: &str = "meta.toml";
+
+/// Files up to this size are read into memory; larger ones are referenced by path
+pub const INLINE_LIMIT_BYTES: u64 = 1024 * 1024;
+
+/// Per-case settings in `meta.toml`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CaseMeta {
+    /// Time limit override in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_limit: Option<u64>,
+    /// Memory limit override in kilobytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_limit: Option<u64>,
+    /// Scoring weight (1.0 if not set)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<f64>,
+    /// Whether the case is hidden (false if not set)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hidden: Option<bool>,
+    /// Whether the case is a statement sample (false if not set); samples weigh 0
+    /// unless `weight` is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample: Option<bool>,
+}
+
+/// Contents of `meta.toml`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Meta {
+    /// Settings by test case id
+    #[serde(default)]
+    pub cases: BTreeMap<String, CaseMeta>,
+}
+
+impl Meta {
+    /// Records the settings of a test case, omitting defaults
+    pub fn from_test_cases(test_cases: &[TestCase]) -> Self {
+        let cases = test_cases
+            .iter()
+            .map(|tc| {
+                let default_weight = if tc.is_sample { 0.0 } else { 1.0 };
+                let meta = CaseMeta {
+                    time_limit: tc.time_limit,
+                    memory_limit: tc.memory_limit,
+                    weight: (tc.weight != default_weight).then_some(tc.weight),
+                    hidden: tc.is_hidden.then_some(true),
+                    sample: tc.is_sample.then_some(true),
+                };
+                (tc.id.clone(), meta)
+            })
+            .filter(|(_, meta)| meta != &CaseMeta::default())
+            .collect();
+        Self { cases }
+    }
+}
+
+/// Errors raised while loading test cases
+#[derive(Debug)]
+pub enum LoadError {
+    /// A file or directory could not be read
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// An input has no expected output
+    MissingOutput(String),
+    /// An expected output has no input
+    MissingInput(String),
+    /// A test case has more than one `.in` file, e.g. in different directories of a
+    /// bundle
+    DuplicateInput(String),
+    /// A test case has more than one expected output, e.g. both a `.out` and a `.ans`
+    /// file
+    DuplicateOutput(String),
+    /// `meta.toml` is malformed
+    Meta(String),
+    /// `meta.toml` configures a test case that doesn't exist
+    UnknownMetaCase(String),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io { path, source } => {
+                write!(f, "failed to read {}: {}", path.display(), source)
+            }
+            LoadError::MissingOutput(stem) => {
+                write!(
+                    f,
+                    "{}.in has no matching {}.out or {}.ans",
+                    stem, stem, stem
+                )
+            }
+            LoadError::MissingInput(stem) => write!(f, "output for {:?} has no {}.in", stem, stem),
+            LoadError::DuplicateInput(stem) => write!(f, "{}.in exists more than once", stem),
+            LoadError::DuplicateOutput(stem) => write!(
+                f,
+                "test case {:?} has more than one {}.out or {}.ans",
+                stem, stem, stem
+            ),
+            LoadError::Meta(e) => write!(f, "invalid {}: {}", META_FILE, e),
+            LoadError::UnknownMetaCase(stem) => {
+                write!(f, "{} configures unknown test case {:?}", META_FILE, stem)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+/// Role of a file in the convention
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Role {
+    Input,
+    Output,
+}
+
+/// Splits a file name into its stem and role, `None` for unrelated files
+pub(crate) fn classify(name: &str) -> Option<(&str, Role)> {
+    let (stem, ext) = name.rsplit_once('.')?;
+    if stem.is_empty() {
+        return None;
+    }
+    match ext {
+        "in" => Some((stem, Role::Input)),
+        "out" | "ans" => Some((stem, Role::Output)),
+        _ => None,
+    }
+}
+
+/// Compares ids so that digit runs compare by value (`2` < `10`)
+pub(crate) fn natural_cmp(a: &str, b: &str) -> Ordering {
+    fn chunks(s: &str) -> Vec<&str> {
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        for (i, c) in s.char_indices().skip(1) {
+            let prev = s[..i].chars().next_back().unwrap();
+            if prev.is_ascii_digit() != c.is_ascii_digit() {
+                chunks.push(&s[start..i]);
+                start = i;
+            }
+        }
+        chunks.push(&s[start..]);
+        chunks
+    }
+    for (x, y) in chunks(a).into_iter().zip(chunks(b)) {
+        let ordering = if x.starts_with(|c: char| c.is_ascii_digit())
+            && y.starts_with(|c: char| c.is_ascii_digit())
+        {
+            let (x, y) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+            x.len().cmp(&y.len()).then_with(|| x.cmp(y))
+        } else {
+            x.cmp(y)
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+/// Pairs named files into test cases and applies `meta.toml`
+///
+/// Shared by the directory loader and the zip bundle importer.
+pub(crate) fn assemble(
+    files: Vec<(String, TestData)>,
+    meta: Option<&str>,
+) -> Result<Vec<TestCase>, LoadError> {
+    let mut inputs: BTreeMap<String, TestData> = BTreeMap::new();
+    let mut outputs: BTreeMap<String, TestData> = BTreeMap::new();
+    for (name, data) in files {
+        let Some((stem, role)) = classify(&name) else {
+            continue;
+        };
+        let (target, duplicate): (_, fn(String) -> LoadError) = match role {
+            Role::Input => (&mut inputs, LoadError::DuplicateInput),
+            Role::Output => (&mut outputs, LoadError::DuplicateOutput),
+        };
+        // Names are flattened, so bundles can repeat an input in another directory
+        if target.insert(stem.to_string(), data).is_some() {
+            return Err(duplicate(stem.to_string()));
+        }
+    }
+    if let Some(stem) = outputs.keys().find(|stem| !inputs.contains_key(*stem)) {
+        return Err(LoadError::MissingInput(stem.clone()));
+    }
+
+    let meta: Meta = match meta {
+        Some(meta) => toml::from_str(meta).map_err(|e| LoadError::Meta(e.to_string()))?,
+        None => Meta::default(),
+    };
+    if let Some(stem) = meta.cases.keys().find(|stem| !inputs.contains_key(*stem)) {
+        return Err(LoadError::UnknownMetaCase(stem.clone()));
+    }
+
+    let mut test_cases = Vec::with_capacity(inputs.len());
+    for (stem, input) in inputs {
+        let output = outputs
+            .remove(&stem)
+            .ok_or_else(|| LoadError::MissingOutput(stem.clone()))?;
+        let mut test_case = TestCase::new(stem, input, output);
+        if let Some(case) = meta.cases.get(&test_case.id) {
+            test_case.time_limit = case.time_limit;
+            test_case.memory_limit = case.memory_limit;
+            test_case.is_sample = case.sample.unwrap_or(false);
+            let default_weight = if test_case.is_sample { 0.0 } else { 1.0 };
+            test_case.weight = case.weight.unwrap_or(default_weight);
+            test_case.is_hidden = case.hidden.unwrap_or(false);
+        }
+        test_cases.push(test_case);
+    }
+    test_cases.sort_by(|a, b| natural_cmp(&a.id, &b.id));
+    Ok(test_cases)
+}
+
+/// Loads the test cases of a directory
+///
+/// Files up to [`INLINE_LIMIT_BYTES`] of valid UTF-8 are read inline; anything else
+/// becomes a [`TestData::File`] reference so large data is never held in memory.
+pub fn load_from_dir(path: impl AsRef<Path>) -> Result<Vec<TestCase>, LoadError> {
+    let dir = path.as_ref();
+    let io_error = |path: &Path| {
+        let path = path.to_path_buf();
+        move |source| LoadError::Io { path, source }
+    };
+
+    let mut files = Vec::new();
+    let mut meta = None;
+    for entry in std::fs::read_dir(dir).map_err(io_error(dir))? {
+        let entry = entry.map_err(io_error(dir))?;
+        let path = entry.path();
+        let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        if name == META_FILE {
+            meta = Some(std::fs::read_to_string(&path).map_err(io_error(&path))?);
+            continue;
+        }
+        if classify(&name).is_none() {
+            continue;
+        }
+        let metadata = entry.metadata().map_err(io_error(&path))?;
+        if !metadata.is_file() {
+            continue;
+        }
+        let data = if metadata.len() <= INLINE_LIMIT_BYTES {
+            let bytes = std::fs::read(&path).map_err(io_error(&path))?;
+            match String::from_utf8(bytes) {
+                Ok(content) => TestData::Inline(content),
+                Err(_) => TestData::File(path),
+            }
+        } else {
+            TestData::File(path)
+        };
+        files.push((name, data));
+    }
+    assemble(files, meta.as_deref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(files: &[(&str, &str)]) -> Self {
+            let dir = std::env::temp_dir().join(format!("oj-testcases-{}", Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+            for (name, content) in files {
+                std::fs::write(dir.join(name), content).unwrap();
+            }
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn ids(test_cases: &[TestCase]) -> Vec<&str> {
+        test_cases.iter().map(|tc| tc.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_natural_order_and_mixed_naming() {
+        let dir = TempDir::new(&[
+            ("1.in", "1\n"),
+            ("1.out", "1\n"),
+            ("2.in", "2\n"),
+            ("2.ans", "4\n"),
+            ("10.in", "10\n"),
+            ("10.out", "100\n"),
+            ("9.in", "9\n"),
+            ("9.out", "81\n"),
+            ("sample1.in", "3\n"),
+            ("sample1.out", "9\n"),
+            ("README.md", "squares"),
+        ]);
+        let test_cases = load_from_dir(&dir.0).unwrap();
+        assert_eq!(ids(&test_cases), vec!["1", "2", "9", "10", "sample1"]);
+        assert_eq!(test_cases[1].expected_output.as_inline(), Some("4\n"));
+        assert_eq!(test_cases[3].input.as_inline(), Some("10\n"));
+        assert!(
+            test_cases
+                .iter()
+                .all(|tc| !tc.is_hidden && tc.weight == 1.0)
+        );
+    }
+
+    #[test]
+    fn test_meta_overrides() {
+        let dir = TempDir::new(&[
+            ("1.in", ""),
+            ("1.out", ""),
+            ("2.in", ""),
+            ("2.out", ""),
+            (
+                "meta.toml",
+                "[cases.1]\nsample = true\n[cases.2]\ntime_limit = 2000\nmemory_limit = 524288\nweight = 3.0\nhidden = true\n",
+            ),
+        ]);
+        let test_cases = load_from_dir(&dir.0).unwrap();
+        assert_eq!(test_cases[0].time_limit, None);
+        assert!(test_cases[0].is_sample);
+        assert_eq!(test_cases[0].weight, 0.0);
+        assert_eq!(test_cases[1].time_limit, Some(2000));
+        assert_eq!(test_cases[1].memory_limit, Some(524288));
+        assert_eq!(test_cases[1].weight, 3.0);
+        assert!(test_cases[1].is_hidden);
+        assert_eq!(Meta::from_test_cases(&test_cases).cases.len(), 2);
+    }
+
+    #[test]
+    fn test_unpaired_files() {
+        let dir = TempDir::new(&[("1.in", "1"), ("1.out", "1"), ("2.in", "2")]);
+        let error = load_from_dir(&dir.0).unwrap_err();
+        assert!(matches!(&error, LoadError::MissingOutput(stem) if stem == "2"));
+        assert_eq!(error.to_string(), "2.in has no matching 2.out or 2.ans");
+
+        let dir = TempDir::new(&[("1.out", "1")]);
+        assert!(matches!(
+            load_from_dir(&dir.0),
+            Err(LoadError::MissingInput(stem)) if stem == "1"
+        ));
+
+        let dir = TempDir::new(&[("1.in", "1"), ("1.out", "1"), ("1.ans", "1")]);
+        assert!(matches!(
+            load_from_dir(&dir.0),
+            Err(LoadError::DuplicateOutput(stem)) if stem == "1"
+        ));
+    }
+
+    #[test]
+    fn test_meta_errors() {
+        let dir = TempDir::new(&[("1.in", ""), ("1.out", ""), ("meta.toml", "[cases.7]\n")]);
+        assert!(matches!(
+            load_from_dir(&dir.0),
+            Err(LoadError::UnknownMetaCase(stem)) if stem == "7"
+        ));
+
+        let dir = TempDir::new(&[
+            ("1.in", ""),
+            ("1.out", ""),
+            ("meta.toml", "[cases.1]\ntimelimit = 5\n"),
+        ]);
+        assert!(matches!(load_from_dir(&dir.0), Err(LoadError::Meta(_))));
+
+        assert!(matches!(
+            load_from_dir(std::env::temp_dir().join("oj-testcases-missing")),
+            Err(LoadError::Io { .. })
+        ));
+    }
+
+    #[test]
+    fn test_large_files_are_referenced() {
+        let large = "7\n".repeat(INLINE_LIMIT_BYTES as usize);
+        let dir = TempDir::new(&[("1.in", &large), ("1.out", "7\n")]);
+        let test_cases = load_from_dir(&dir.0).unwrap();
+        assert_eq!(test_cases[0].input, TestData::File(dir.0.join("1.in")));
+        assert_eq!(test_cases[0].expected_output.as_inline(), Some("7\n"));
+    }
+
+    #[test]
+    fn test_natural_cmp() {
+        let mut ids = vec!["10", "9", "1", "a10", "a2", "002", "b"];
+        ids.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(ids, vec!["1", "002", "9", "10", "a2", "a10", "b"]);
+    }
+}
@@ -0,0 +1,41 @@
+//! Fixtures for unit tests
+//!
+//! Built for this crate's tests and, through the `test-util` feature, for the tests
+//! of crates that depend on it. Ids are fresh on every call, so fixtures never
+//! collide.
+
+use crate::{JudgeTask, ProgrammingLanguage, Submission, TestCase};
+use uuid::Uuid;
+
+/// Time limit of fixture submissions in milliseconds
+pub const TIME_LIMIT: u64 = 1000;
+
+/// Memory limit of fixture submissions in kilobytes
+pub const MEMORY_LIMIT: u64 = 65536;
+
+/// Returns a submission with fresh ids and the fixture limits
+pub fn submission(language: ProgrammingLanguage, source: &str) -> Submission {
+    Submission::new(
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+        language,
+        source.to_string(),
+        TIME_LIMIT,
+        MEMORY_LIMIT,
+    )
+}
+
+/// Returns a task for an empty C++17 program
+pub fn task(test_cases: Vec<TestCase>) -> JudgeTask {
+    JudgeTask::new(
+        submission(ProgrammingLanguage::Cpp17, "int main() {}"),
+        test_cases,
+    )
+}
+
+/// Returns test cases with the given ids and empty data
+pub fn blank_test_cases<I: ToString>(ids: impl IntoIterator<Item = I>) -> Vec<TestCase> {
+    ids.into_iter()
+        .map(|id| TestCase::new(id.to_string(), String::new(), String::new()))
+        .collect()
+}
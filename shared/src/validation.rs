@@ -0,0 +1,247 @@
+//! Bounds checking of submissions against a deployment policy
+//!
+//! The backend validates incoming submissions and returns the errors as a structured
+//! 400 response; the judger re-checks them before creating a sandbox.
+
+use crate::{ProgrammingLanguage, Submission};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Limits a submission must respect
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubmissionPolicy {
+    /// Maximum source size in bytes
+    pub max_source_bytes: usize,
+    /// Minimum time limit in milliseconds
+    pub min_time_limit: u64,
+    /// Maximum time limit in milliseconds
+    pub max_time_limit: u64,
+    /// Minimum memory limit in kilobytes
+    pub min_memory_limit: u64,
+    /// Maximum memory limit in kilobytes
+    pub max_memory_limit: u64,
+    /// Languages accepted; `None` accepts every language
+    pub allowed_languages: Option<Vec<ProgrammingLanguage>>,
+}
+
+impl Default for SubmissionPolicy {
+    fn default() -> Self {
+        Self {
+            max_source_bytes: 64 * 1024,
+            min_time_limit: 100,
+            max_time_limit: 30_000,
+            min_memory_limit: 16 * 1024,
+            max_memory_limit: 2 * 1024 * 1024,
+            allowed_languages: None,
+        }
+    }
+}
+
+/// A single way in which a submission violates the policy
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum ValidationError {
+    /// The source code is empty or only whitespace
+    EmptySource,
+    /// The source code exceeds the size cap
+    SourceTooLarge { size: usize, max: usize },
+    /// The source contains control characters other than tab, CR and LF
+    ControlCharacters { line: u32 },
+    /// The time limit is outside the allowed range
+    TimeLimitOutOfRange { value: u64, min: u64, max: u64 },
+    /// The memory limit is outside the allowed range
+    MemoryLimitOutOfRange { value: u64, min: u64, max: u64 },
+    /// The language is not enabled
+    LanguageNotAllowed { language: ProgrammingLanguage },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::EmptySource => write!(f, "source code is empty"),
+            ValidationError::SourceTooLarge { size, max } => {
+                write!(f, "source code is {} bytes, the maximum is {}", size, max)
+            }
+            ValidationError::ControlCharacters { line } => {
+                write!(
+                    f,
+                    "source code contains control characters on line {}",
+                    line
+                )
+            }
+            ValidationError::TimeLimitOutOfRange { value, min, max } => {
+                write!(f, "time limit {}ms is outside {}..={}ms", value, min, max)
+            }
+            ValidationError::MemoryLimitOutOfRange { value, min, max } => {
+                write!(f, "memory limit {}KB is outside {}..={}KB", value, min, max)
+            }
+            ValidationError::LanguageNotAllowed { language } => {
+                write!(f, "{} is not enabled", language.as_str())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl Submission {
+    /// Checks the submission against a policy, returning every violation found
+    pub fn validate(&self, policy: &SubmissionPolicy) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        let size = self.source_code.len();
+        if self.source_code.trim().is_empty() {
+            errors.push(ValidationError::EmptySource);
+        } else if size > policy.max_source_bytes {
+            errors.push(ValidationError::SourceTooLarge {
+                size,
+                max: policy.max_source_bytes,
+            });
+        }
+
+        let control_line = self.source_code.lines().position(|line| {
+            line.chars()
+                .any(|c| c.is_control() && !matches!(c, '\t' | '\r'))
+        });
+        if let Some(index) = control_line {
+            errors.push(ValidationError::ControlCharacters {
+                line: index as u32 + 1,
+            });
+        }
+
+        if !(policy.min_time_limit..=policy.max_time_limit).contains(&self.time_limit) {
+            errors.push(ValidationError::TimeLimitOutOfRange {
+                value: self.time_limit,
+                min: policy.min_time_limit,
+                max: policy.max_time_limit,
+            });
+        }
+        if !(policy.min_memory_limit..=policy.max_memory_limit).contains(&self.memory_limit) {
+            errors.push(ValidationError::MemoryLimitOutOfRange {
+                value: self.memory_limit,
+                min: policy.min_memory_limit,
+                max: policy.max_memory_limit,
+            });
+        }
+
+        if let Some(allowed) = &policy.allowed_languages
+            && !allowed.contains(&self.language)
+        {
+            errors.push(ValidationError::LanguageNotAllowed {
+                language: self.language,
+            });
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn submission(source: &str, time_limit: u64, memory_limit: u64) -> Submission {
+        let mut submission = testing::submission(ProgrammingLanguage::Cpp17, source);
+        submission.time_limit = time_limit;
+        submission.memory_limit = memory_limit;
+        submission
+    }
+
+    #[test]
+    fn test_valid_submission() {
+        let policy = SubmissionPolicy::default();
+        assert_eq!(
+            submission("int main() {}\n", 1000, 262144).validate(&policy),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_source_size_boundary() {
+        let policy = SubmissionPolicy {
+            max_source_bytes: 10,
+            ..SubmissionPolicy::default()
+        };
+        assert_eq!(
+            submission(&"a".repeat(10), 1000, 262144).validate(&policy),
+            Ok(())
+        );
+        assert_eq!(
+            submission(&"a".repeat(11), 1000, 262144).validate(&policy),
+            Err(vec![ValidationError::SourceTooLarge { size: 11, max: 10 }])
+        );
+        assert_eq!(
+            submission("", 1000, 262144).validate(&policy),
+            Err(vec![ValidationError::EmptySource])
+        );
+    }
+
+    #[test]
+    fn test_limit_boundaries() {
+        let policy = SubmissionPolicy::default();
+        assert_eq!(
+            submission("x", policy.max_time_limit, policy.min_memory_limit).validate(&policy),
+            Ok(())
+        );
+        assert_eq!(
+            submission("x", 0, 0).validate(&policy),
+            Err(vec![
+                ValidationError::TimeLimitOutOfRange {
+                    value: 0,
+                    min: policy.min_time_limit,
+                    max: policy.max_time_limit,
+                },
+                ValidationError::MemoryLimitOutOfRange {
+                    value: 0,
+                    min: policy.min_memory_limit,
+                    max: policy.max_memory_limit,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_control_characters() {
+        let policy = SubmissionPolicy::default();
+        assert_eq!(
+            submission("int main() {\r\n\treturn 0;\r\n}\n", 1000, 262144).validate(&policy),
+            Ok(())
+        );
+        assert_eq!(
+            submission("int main() {\n  return 0;\u{0}\n}", 1000, 262144).validate(&policy),
+            Err(vec![ValidationError::ControlCharacters { line: 2 }])
+        );
+    }
+
+    #[test]
+    fn test_allowed_languages() {
+        let policy = SubmissionPolicy {
+            allowed_languages: Some(vec![ProgrammingLanguage::Python3]),
+            ..SubmissionPolicy::default()
+        };
+        assert_eq!(
+            submission("x", 1000, 262144).validate(&policy),
+            Err(vec![ValidationError::LanguageNotAllowed {
+                language: ProgrammingLanguage::Cpp17
+            }])
+        );
+    }
+
+    #[test]
+    fn test_errors_serialize_with_code() {
+        let json =
+            serde_json::to_value(ValidationError::SourceTooLarge { size: 11, max: 10 }).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"code": "source_too_large", "size": 11, "max": 10})
+        );
+        let json = serde_json::to_string(&ValidationError::EmptySource).unwrap();
+        let deserialized: ValidationError = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, ValidationError::EmptySource);
+    }
+}
@@ -0,0 +1,250 @@
+//! Flat string representation of [`JudgeStatus`]
+//!
+//! Statuses are written as their short code, and runtime errors carry their kind
+//! after a colon:
+//!
+//! | status                                   | wire                     |
+//! |------------------------------------------|--------------------------|
+//! | `Accepted`                               | `"AC"`                   |
+//! | `WrongAnswer`                            | `"WA"`                   |
+//! | `RuntimeError(SegmentationFault)`        | `"RE:SegmentationFault"` |
+//! | `RuntimeError(Other)`                    | `"RE:Other"`             |
+//!
+//! The kind is the [`RuntimeErrorType`] variant name, as serde writes it. Serde,
+//! SQL and logs all go through [`JudgeStatus::to_wire`] and
+//! [`JudgeStatus::from_wire`], so the strings match everywhere. Deserialization
+//! also accepts the externally tagged form written before schema version 3
+//! (`"Accepted"`, `{"RuntimeError": "SegmentationFault"}`).
+
+use crate::{JudgeStatus, ParseJudgeStatusError, RuntimeErrorType};
+use serde::de::{self, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+/// Separates the runtime error code from its kind
+const KIND_SEPARATOR: char = ':';
+
+impl RuntimeErrorType {
+    /// Returns the variant name used on the wire, e.g. `"SegmentationFault"`
+    pub fn wire_name(&self) -> &'static str {
+        match self {
+            RuntimeErrorType::SegmentationFault => "SegmentationFault",
+            RuntimeErrorType::FloatingPointException => "FloatingPointException",
+            RuntimeErrorType::DivisionByZero => "DivisionByZero",
+            RuntimeErrorType::AssertionFailed => "AssertionFailed",
+            RuntimeErrorType::StackOverflow => "StackOverflow",
+            RuntimeErrorType::NullPointerDereference => "NullPointerDereference",
+            RuntimeErrorType::FileOperationError => "FileOperationError",
+            RuntimeErrorType::PermissionDenied => "PermissionDenied",
+            RuntimeErrorType::Other => "Other",
+        }
+    }
+
+    /// Returns the runtime error type with the given [`wire_name`](Self::wire_name)
+    pub fn from_wire_name(name: &str) -> Option<Self> {
+        RuntimeErrorType::ALL
+            .into_iter()
+            .find(|error_type| error_type.wire_name() == name)
+    }
+}
+
+impl JudgeStatus {
+    /// Returns the flat wire string, e.g. `"WA"` or `"RE:SegmentationFault"`
+    pub fn to_wire(&self) -> &'static str {
+        match self {
+            JudgeStatus::RuntimeError(error_type) => match error_type {
+                RuntimeErrorType::SegmentationFault => "RE:SegmentationFault",
+                RuntimeErrorType::FloatingPointException => "RE:FloatingPointException",
+                RuntimeErrorType::DivisionByZero => "RE:DivisionByZero",
+                RuntimeErrorType::AssertionFailed => "RE:AssertionFailed",
+                RuntimeErrorType::StackOverflow => "RE:StackOverflow",
+                RuntimeErrorType::NullPointerDereference => "RE:NullPointerDereference",
+                RuntimeErrorType::FileOperationError => "RE:FileOperationError",
+                RuntimeErrorType::PermissionDenied => "RE:PermissionDenied",
+                RuntimeErrorType::Other => "RE:Other",
+            },
+            status => status.as_code(),
+        }
+    }
+
+    /// Parses a string written by [`to_wire`](Self::to_wire)
+    ///
+    /// Unlike [`FromStr`](std::str::FromStr) this is exact: case matters, long names
+    /// are refused and a runtime error must name its kind.
+    pub fn from_wire(s: &str) -> Result<Self, ParseJudgeStatusError> {
+        let error = || ParseJudgeStatusError(s.to_string());
+        if let Some((code, kind)) = s.split_once(KIND_SEPARATOR) {
+            if code != JudgeStatus::RuntimeError(RuntimeErrorType::Other).as_code() {
+                return Err(error());
+            }
+            return RuntimeErrorType::from_wire_name(kind)
+                .map(JudgeStatus::RuntimeError)
+                .ok_or_else(error);
+        }
+        JudgeStatus::ALL
+            .into_iter()
+            .filter(|status| !status.is_runtime_error())
+            .find(|status| status.as_code() == s)
+            .ok_or_else(error)
+    }
+
+    /// Variant name of a status without data, as written before the flat form
+    fn legacy_name(&self) -> Option<&'static str> {
+        Some(match self {
+            JudgeStatus::Accepted => "Accepted",
+            JudgeStatus::WrongAnswer => "WrongAnswer",
+            JudgeStatus::PresentationError => "PresentationError",
+            JudgeStatus::PartialAccepted => "PartialAccepted",
+            JudgeStatus::Skipped => "Skipped",
+            JudgeStatus::TimeLimitExceeded => "TimeLimitExceeded",
+            JudgeStatus::MemoryLimitExceeded => "MemoryLimitExceeded",
+            JudgeStatus::RuntimeError(_) => return None,
+            JudgeStatus::CompileError => "CompileError",
+            JudgeStatus::RestrictedOperation => "RestrictedOperation",
+            JudgeStatus::OutputLimitExceeded => "OutputLimitExceeded",
+            JudgeStatus::SystemError => "SystemError",
+            JudgeStatus::Pending => "Pending",
+            JudgeStatus::Judging => "Judging",
+            JudgeStatus::Cancelled => "Cancelled",
+        })
+    }
+}
+
+impl Serialize for JudgeStatus {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.to_wire())
+    }
+}
+
+struct JudgeStatusVisitor;
+
+impl<'de> Visitor<'de> for JudgeStatusVisitor {
+    type Value = JudgeStatus;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "a judge status such as \"WA\" or \"RE:SegmentationFault\""
+        )
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<JudgeStatus, E> {
+        JudgeStatus::from_wire(s)
+            .ok()
+            .or_else(|| {
+                JudgeStatus::ALL
+                    .into_iter()
+                    .find(|status| status.legacy_name() == Some(s))
+            })
+            .ok_or_else(|| E::invalid_value(de::Unexpected::Str(s), &self))
+    }
+
+    /// The legacy `{"RuntimeError": kind}` form
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<JudgeStatus, A::Error> {
+        let key: String = map
+            .next_key()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        if key != "RuntimeError" {
+            return Err(de::Error::unknown_variant(&key, &["RuntimeError"]));
+        }
+        let error_type: RuntimeErrorType = map.next_value()?;
+        if map.next_key::<de::IgnoredAny>()?.is_some() {
+            return Err(de::Error::invalid_length(2, &self));
+        }
+        Ok(JudgeStatus::RuntimeError(error_type))
+    }
+}
+
+impl<'de> Deserialize<'de> for JudgeStatus {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(JudgeStatusVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every status, with every runtime error kind
+    fn all_statuses() -> Vec<JudgeStatus> {
+        JudgeStatus::ALL
+            .into_iter()
+            .filter(|status| !status.is_runtime_error())
+            .chain(RuntimeErrorType::ALL.map(JudgeStatus::RuntimeError))
+            .collect()
+    }
+
+    #[test]
+    fn test_wire_round_trip() {
+        let statuses = all_statuses();
+        assert_eq!(statuses.len(), 14 + 9);
+        for status in statuses {
+            let wire = status.to_wire();
+            assert_eq!(JudgeStatus::from_wire(wire), Ok(status), "{}", wire);
+            let json = serde_json::to_string(&status).unwrap();
+            assert_eq!(json, format!("\"{}\"", wire));
+            assert_eq!(serde_json::from_str::<JudgeStatus>(&json).unwrap(), status);
+            if let JudgeStatus::RuntimeError(error_type) = status {
+                assert_eq!(wire, format!("RE:{}", error_type.wire_name()));
+                // The kind matches how serde writes the type itself
+                assert_eq!(
+                    serde_json::to_string(&error_type).unwrap(),
+                    format!("\"{}\"", error_type.wire_name())
+                );
+            } else {
+                assert_eq!(wire, status.as_code());
+            }
+        }
+        assert_eq!(
+            JudgeStatus::RuntimeError(RuntimeErrorType::SegmentationFault).to_wire(),
+            "RE:SegmentationFault"
+        );
+    }
+
+    #[test]
+    fn test_legacy_form_reads() {
+        for status in all_statuses() {
+            let legacy = match status {
+                JudgeStatus::RuntimeError(error_type) => {
+                    serde_json::json!({ "RuntimeError": error_type })
+                }
+                status => serde_json::json!(status.legacy_name().unwrap()),
+            };
+            assert_eq!(
+                serde_json::from_value::<JudgeStatus>(legacy).unwrap(),
+                status
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_wire_strings() {
+        for s in [
+            "",
+            "RE",
+            "re:Other",
+            "RE:Segfault",
+            "AC:Other",
+            "ac",
+            "Accepted",
+            "Wrong Answer",
+            "RE:Other:Other",
+        ] {
+            assert!(JudgeStatus::from_wire(s).is_err(), "{}", s);
+        }
+        for json in [
+            "\"RE\"",
+            "\"Wrong Answer\"",
+            "{\"WrongAnswer\": null}",
+            "{\"RuntimeError\": \"Other\", \"Accepted\": null}",
+            "{}",
+            "3",
+        ] {
+            assert!(
+                serde_json::from_str::<JudgeStatus>(json).is_err(),
+                "{}",
+                json
+            );
+        }
+    }
+}